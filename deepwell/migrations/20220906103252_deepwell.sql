@@ -77,6 +77,13 @@ CREATE TABLE user_bot_owner (
 -- Site
 --
 
+CREATE TYPE site_join_policy AS ENUM (
+    'open',
+    'password',
+    'application',
+    'invite'
+);
+
 CREATE TABLE site (
     site_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
@@ -91,8 +98,12 @@ CREATE TABLE site (
     default_page TEXT NOT NULL DEFAULT 'start',
     custom_domain TEXT,  -- Dependency cycle, add foreign key constraint after
     layout TEXT,  -- Default page layout for the site
+    default_license JSON,  -- Default license for uploaded files, see services/file/licensing.rs
+    join_policy site_join_policy NOT NULL DEFAULT 'application',
+    join_password TEXT,  -- Password hash, for the 'password' join policy
 
-    UNIQUE (slug, deleted_at)
+    UNIQUE (slug, deleted_at),
+    CHECK (join_policy != 'password' OR join_password IS NOT NULL)
 );
 
 CREATE TABLE site_domain (
@@ -170,6 +181,7 @@ CREATE TABLE relation (
 
 CREATE TABLE session (
     session_token TEXT PRIMARY KEY CHECK (length(session_token) > 48),
+    session_id BIGSERIAL NOT NULL UNIQUE,
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (expires_at > created_at),
@@ -178,6 +190,37 @@ CREATE TABLE session (
     restricted BOOLEAN NOT NULL
 );
 
+CREATE TYPE login_attempt_result AS ENUM (
+    'success',
+    'bad_password',
+    'bad_mfa'
+);
+
+-- Only attempts for existing users are recorded, for showing login history.
+CREATE TABLE login_attempt (
+    login_attempt_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    result login_attempt_result NOT NULL,
+    ip_address INET NOT NULL,
+    user_agent TEXT NOT NULL,
+    new_device BOOLEAN NOT NULL DEFAULT false
+);
+
+CREATE INDEX login_attempt_user_idx ON login_attempt (user_id, login_attempt_id);
+
+CREATE TABLE user_webauthn_credential (
+    credential_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    name TEXT NOT NULL CHECK (length(name) > 0),
+    external_id BYTEA NOT NULL UNIQUE,  -- credential ID as issued by the authenticator
+    passkey JSON NOT NULL,              -- serialized passkey, see services/webauthn
+
+    UNIQUE (user_id, name)
+);
+
 --
 -- Page
 --
@@ -186,9 +229,15 @@ CREATE TABLE page_category (
     category_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     updated_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
     site_id BIGINT NOT NULL REFERENCES site(site_id),
     slug TEXT NOT NULL,
     layout TEXT, -- category-specific override for DOM layout
+    default_license JSON, -- category-specific override for the site's default license
+    scorer TEXT, -- score type used for pages in this category, see ScoreType
+    template_slug TEXT, -- page slug to use as the template, instead of _template
+    use_templates BOOLEAN NOT NULL DEFAULT true,
+    permissions JSON,
 
     UNIQUE (site_id, slug)
 );
@@ -398,6 +447,97 @@ CREATE TABLE page_connection_missing (
     PRIMARY KEY (from_page_id, to_site_id, to_page_slug, connection_type)
 );
 
+-- A redirect left at a page's old slug after it was moved.
+--
+-- Links to the old slug are recorded as 'redirect' connections to the target page.
+CREATE TABLE page_redirect (
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    slug TEXT NOT NULL,
+    page_id BIGINT NOT NULL REFERENCES page(page_id),  -- The page being redirected to
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+
+    PRIMARY KEY (site_id, slug)
+);
+
+CREATE INDEX page_redirect_page_idx ON page_redirect (page_id);
+
+-- An autosaved draft of a user's in-progress edit to a page.
+CREATE TABLE page_draft (
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    revision_id BIGINT NOT NULL REFERENCES page_revision(revision_id),  -- The revision being edited
+    title TEXT NOT NULL,
+    alt_title TEXT,
+    wikitext_hash BYTEA NOT NULL REFERENCES text(hash),
+
+    PRIMARY KEY (page_id, user_id)
+);
+
+--
+-- Page tags
+--
+
+-- A group of related tags, with limits on how many a page may have.
+--
+-- For instance, an "object class" group with min_tags = 1 and max_tags = 1
+-- requires that each page have exactly one tag from that group.
+CREATE TABLE site_tag_group (
+    tag_group_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    min_tags INTEGER NOT NULL DEFAULT 0 CHECK (min_tags >= 0),
+    max_tags INTEGER CHECK (max_tags >= min_tags),
+
+    UNIQUE (site_id, name)
+);
+
+-- A tag which may be used on pages in a site.
+--
+-- If a site has any tags defined, then pages may only use those tags.
+CREATE TABLE site_tag (
+    tag_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    tag_group_id BIGINT REFERENCES site_tag_group(tag_group_id),
+
+    UNIQUE (site_id, name)
+);
+
+--
+-- Bulk page operations
+--
+
+-- A single operation (e.g. retagging or deleting) applied to many pages by a job.
+--
+-- The selected pages are resolved when the operation is created, and are then
+-- processed in order in batches, with position tracking how many are done.
+CREATE TABLE page_bulk_operation (
+    bulk_operation_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    canceled_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    operation JSON NOT NULL,  -- See BulkPageOperation in services/bulk_operation/structs.rs
+    revision_comments TEXT NOT NULL,
+    page_ids BIGINT[] NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    succeeded INTEGER NOT NULL DEFAULT 0,
+    failures JSON NOT NULL DEFAULT '[]',  -- List of BulkPageFailure
+
+    CHECK (completed_at IS NULL OR canceled_at IS NULL)
+);
+
 --
 -- Page votes
 --
@@ -417,6 +557,48 @@ CREATE TABLE page_vote (
     CHECK ((disabled_at IS NULL) = (disabled_by IS NULL))
 );
 
+-- A record of a page's score at some point in time.
+--
+-- These are taken whenever a vote on the page changes, and periodically
+-- by a job, so that scores can be graphed over time.
+CREATE TABLE page_score_snapshot (
+    snapshot_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    score DOUBLE PRECISION NOT NULL,
+    vote_count BIGINT NOT NULL,
+    upvotes BIGINT NOT NULL,
+    downvotes BIGINT NOT NULL
+);
+
+CREATE INDEX page_score_snapshot_page_idx ON page_score_snapshot (page_id, snapshot_id);
+CREATE INDEX page_score_snapshot_site_idx ON page_score_snapshot (site_id, created_at);
+
+-- Suspected sockpuppet voting found by the vote fraud detection job.
+--
+-- Each report is a cluster of recently-created accounts which share
+-- an IP address or user agent and voted together on the same pages.
+-- Confirming a report disables the votes listed in it.
+CREATE TABLE vote_fraud_report (
+    report_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_ids BIGINT[] NOT NULL,
+    page_ids BIGINT[] NOT NULL,
+    vote_ids BIGINT[] NOT NULL,  -- The page_vote rows flagged by this report
+    ip_addresses TEXT[] NOT NULL,
+    user_agents TEXT[] NOT NULL,
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    reviewed_by BIGINT REFERENCES "user"(user_id),
+    confirmed BOOLEAN,
+
+    CHECK ((reviewed_at IS NULL) = (reviewed_by IS NULL)),
+    CHECK ((reviewed_at IS NULL) = (confirmed IS NULL))
+);
+
+CREATE INDEX vote_fraud_report_site_idx ON vote_fraud_report (site_id, report_id);
+
 --
 -- Blobs
 --
@@ -444,6 +626,22 @@ CREATE TABLE blob_blacklist (
     created_by BIGINT NOT NULL REFERENCES "user"(user_id)
 );
 
+-- Manages blobs which were produced from another, such as image thumbnails
+CREATE TABLE blob_derivative (
+    source_hash BYTEA NOT NULL,
+    size INTEGER NOT NULL,  -- Longest side in pixels, or 0 for the full-size image with metadata removed
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    s3_hash BYTEA NOT NULL,
+    mime TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+
+    PRIMARY KEY (source_hash, size),
+    CHECK (length(source_hash) = 64),  -- SHA-512 hash size
+    CHECK (length(s3_hash) = 64),      -- SHA-512 hash size
+    CHECK (size >= 0 AND width > 0 AND height > 0)
+);
+
 --
 -- Files
 --
@@ -637,6 +835,70 @@ CREATE TABLE message_report (
     PRIMARY KEY (message_id, reported_to_site_id)
 );
 
+--
+-- Notifications
+--
+
+CREATE TYPE notification_type AS ENUM (
+    'page_edit',
+    'page_move',
+    'page_delete',
+    'page_create',
+    'message',
+    'site_invitation',
+    'site_application_accepted',
+    'site_application_declined'
+);
+
+-- A notification delivered to a particular user.
+--
+-- These are produced by jobs which fan out a single event (such as a page edit)
+-- to all interested users (such as the page's watchers).
+CREATE TABLE notification (
+    notification_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    read_at TIMESTAMP WITH TIME ZONE,
+    dismissed_at TIMESTAMP WITH TIME ZONE,
+    emailed_at TIMESTAMP WITH TIME ZONE,
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),  -- The user receiving the notification
+    notification_type notification_type NOT NULL,
+    actor_id BIGINT REFERENCES "user"(user_id),  -- The user who caused the notification, if any
+    site_id BIGINT REFERENCES site(site_id),
+    page_id BIGINT REFERENCES page(page_id),
+    revision_id BIGINT REFERENCES page_revision(revision_id),
+    message_record_id TEXT REFERENCES message_record(external_id),
+
+    -- Ensure the referenced object is present for each notification type
+    CHECK (
+        notification_type NOT IN ('page_edit', 'page_move', 'page_delete', 'page_create') OR
+        page_id IS NOT NULL
+    ),
+    CHECK (notification_type != 'message' OR message_record_id IS NOT NULL),
+    CHECK (
+        notification_type NOT IN (
+            'site_invitation',
+            'site_application_accepted',
+            'site_application_declined'
+        ) OR
+        site_id IS NOT NULL
+    )
+);
+
+CREATE INDEX notification_user_idx ON notification (user_id, notification_id);
+
+-- Per-user notification settings. If a user has no row, the defaults are used.
+CREATE TABLE notification_preference (
+    user_id BIGINT PRIMARY KEY REFERENCES "user"(user_id),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    notify_page_edit BOOLEAN NOT NULL DEFAULT true,
+    notify_page_move BOOLEAN NOT NULL DEFAULT true,
+    notify_page_delete BOOLEAN NOT NULL DEFAULT true,
+    notify_page_create BOOLEAN NOT NULL DEFAULT true,
+    notify_message BOOLEAN NOT NULL DEFAULT true,
+    notify_site_membership BOOLEAN NOT NULL DEFAULT true,
+    email_digest BOOLEAN NOT NULL DEFAULT false
+);
+
 --
 -- Filters
 --
@@ -663,3 +925,108 @@ CREATE TABLE filter (
 
     UNIQUE (site_id, regex, deleted_at)
 );
+
+--
+-- Imports
+--
+
+CREATE TYPE import_stage AS ENUM (
+    'users',
+    'site',
+    'pages',
+    'files',
+    'done'
+);
+
+-- Tracks an import of a Wikidot backup, in the format produced by wikicomma.
+--
+-- Imports are run in small steps by jobs. This row records where the next step
+-- picks up from, and is updated in the same transaction as each step, so an
+-- interrupted import can be resumed without repeating or skipping work.
+CREATE TABLE import_progress (
+    import_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    directory TEXT NOT NULL,
+    locale TEXT NOT NULL,
+    sites JSON NOT NULL,  -- List of WikicommaSite, see services/import/structs.rs
+    step INTEGER NOT NULL DEFAULT 0,  -- Number of steps run, to detect duplicate jobs
+    stage import_stage NOT NULL DEFAULT 'users',
+    site_index INTEGER NOT NULL DEFAULT 0,
+    position INTEGER NOT NULL DEFAULT 0,
+    total INTEGER,  -- Number of items in the current stage, if known
+    users_imported INTEGER NOT NULL DEFAULT 0,
+    pages_imported INTEGER NOT NULL DEFAULT 0,
+    revisions_imported INTEGER NOT NULL DEFAULT 0,
+    votes_imported INTEGER NOT NULL DEFAULT 0,
+    files_imported INTEGER NOT NULL DEFAULT 0,
+
+    CHECK ((stage = 'done') = (completed_at IS NOT NULL))
+);
+
+-- Tracks an import of a site archive, see services/site_archive.
+--
+-- The archive's text and blob entries are stored in batches by jobs, with
+-- position counting how many entries are done. Once all are stored
+-- (extracted_at), a final step creates the site and its pages and files.
+CREATE TABLE site_import (
+    import_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    extracted_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    failed_at TIMESTAMP WITH TIME ZONE,
+    s3_hash BYTEA NOT NULL,
+    slug TEXT NOT NULL,
+    name TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
+    site_id BIGINT REFERENCES site(site_id),
+    unmapped_users TEXT[] NOT NULL DEFAULT '{}',
+    skipped_aliases TEXT[] NOT NULL DEFAULT '{}',
+    error_code INTEGER,
+    error_message TEXT,
+
+    CHECK (length(s3_hash) = 64),  -- SHA-512 hash size
+    CHECK ((completed_at IS NOT NULL) = (site_id IS NOT NULL)),
+    CHECK ((failed_at IS NOT NULL) = (error_code IS NOT NULL)),
+    CHECK (completed_at IS NULL OR failed_at IS NULL)
+);
+
+CREATE TABLE site_export (
+    export_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    failed_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    s3_hash BYTEA,
+    size BIGINT,
+    error_code INTEGER,
+    error_message TEXT,
+
+    CHECK (s3_hash IS NULL OR length(s3_hash) = 64),  -- SHA-512 hash size
+    CHECK ((completed_at IS NOT NULL) = (s3_hash IS NOT NULL)),
+    CHECK ((completed_at IS NOT NULL) = (size IS NOT NULL)),
+    CHECK ((failed_at IS NOT NULL) = (error_code IS NOT NULL)),
+    CHECK (completed_at IS NULL OR failed_at IS NULL)
+);
+
+--
+-- Audit log
+--
+
+-- A record of an administrative or membership action.
+--
+-- The data column holds details particular to each event,
+-- see services/audit/structs.rs.
+CREATE TABLE audit_log (
+    audit_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    site_id BIGINT REFERENCES site(site_id),
+    actor_id BIGINT NOT NULL REFERENCES "user"(user_id),  -- The user who performed the action
+    user_id BIGINT REFERENCES "user"(user_id),  -- The user affected by the action, if any
+    event TEXT NOT NULL,  -- check enum value in runtime
+    data JSON NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX audit_log_site_idx ON audit_log (site_id, audit_id);
@@ -28,24 +28,33 @@
 
 use crate::config::{Config, Secrets};
 use crate::endpoints::{
-    auth::*, blob::*, category::*, domain::*, email::*, file::*, file_revision::*,
-    info::*, link::*, locale::*, message::*, misc::*, page::*, page_revision::*,
-    parent::*, site::*, site_member::*, text::*, user::*, user_bot::*, view::*, vote::*,
+    auth::*, blob::*, bulk_operation::*, category::*, domain::*, edit_session::*,
+    email::*, file::*, file_revision::*, import::*, info::*, link::*, locale::*,
+    message::*, misc::*, notification::*, page::*, page_attribution::*, page_revision::*,
+    page_star::*, page_watch::*, parent::*, recent_changes::*, redirect::*, site::*,
+    site_member::*, tag::*, text::*, user::*, user_bot::*, user_follow::*, view::*,
+    vote::*,
 };
 use crate::locales::Localizations;
-use crate::services::blob::MimeAnalyzer;
+use crate::metrics::{Metrics, MetricsLayer};
+use crate::services::blob::{
+    BlobStorage, BlobStorageType, LocalStorage, LocalStorageLayer, MimeAnalyzer,
+    S3Storage,
+};
 use crate::services::job::JobWorker;
-use crate::services::{into_rpc_error, ServiceContext};
+use crate::services::{
+    into_rpc_error, PendingInvalidations, RateLimitService, ServiceContext,
+};
 use crate::utils::debug_pointer;
 use crate::{database, redis as redis_db};
 use jsonrpsee::server::{RpcModule, Server, ServerHandle};
 use jsonrpsee::types::error::ErrorObjectOwned;
 use rsmq_async::PooledRsmq;
-use s3::bucket::Bucket;
 use sea_orm::{DatabaseConnection, TransactionTrait};
 use std::fmt::{self, Debug};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::Instant;
 
 pub type ServerState = Arc<ServerStateInner>;
 
@@ -56,7 +65,8 @@ pub struct ServerStateInner {
     pub rsmq: PooledRsmq,
     pub localizations: Localizations,
     pub mime_analyzer: MimeAnalyzer,
-    pub s3_bucket: Box<Bucket>,
+    pub blob_storage: Box<dyn BlobStorage>,
+    pub metrics: Metrics,
 }
 
 impl Debug for ServerStateInner {
@@ -68,7 +78,8 @@ impl Debug for ServerStateInner {
             .field("rsmq", &debug_pointer(&self.rsmq))
             .field("localizations", &self.localizations)
             .field("mime_analyzer", &self.mime_analyzer)
-            .field("s3_bucket", &self.s3_bucket)
+            .field("blob_storage", &self.blob_storage)
+            .field("metrics", &self.metrics)
             .finish()
     }
 }
@@ -91,22 +102,35 @@ pub async fn build_server_state(
     // Load magic data and start MIME thread
     let mime_analyzer = MimeAnalyzer::spawn();
 
-    // Create S3 bucket
-    info!("Opening S3 bucket");
-
-    let s3_bucket = {
-        let mut bucket = Bucket::new(
-            &secrets.s3_bucket,
-            secrets.s3_region.clone(),
-            secrets.s3_credentials.clone(),
-        )?;
-
-        if secrets.s3_path_style {
-            bucket = bucket.with_path_style();
+    // Set up blob storage
+    let blob_storage: Box<dyn BlobStorage> = match config.blob_storage {
+        BlobStorageType::S3 => {
+            info!("Opening S3 bucket");
+
+            let s3 = secrets
+                .s3
+                .as_ref()
+                .expect("S3 secrets not loaded for S3 blob storage");
+
+            Box::new(S3Storage::new(
+                &s3.bucket,
+                s3.region.clone(),
+                s3.credentials.clone(),
+                s3.path_style,
+            )?)
+        }
+        BlobStorageType::Local => {
+            info!(
+                "Using local blob storage at {}",
+                config.local_storage_path.display(),
+            );
+
+            Box::new(LocalStorage::new(
+                config.local_storage_path.clone(),
+                &config.local_storage_url,
+                usize::try_from(config.maximum_blob_size)?,
+            ))
         }
-
-        bucket.request_timeout = Some(Duration::from_millis(500));
-        bucket
     };
 
     // Build server state
@@ -117,7 +141,8 @@ pub async fn build_server_state(
         rsmq,
         localizations,
         mime_analyzer,
-        s3_bucket,
+        blob_storage,
+        metrics: Metrics::new(),
     });
 
     // Start workers listening to the job queue (requires ServerState)
@@ -129,12 +154,45 @@ pub async fn build_server_state(
 
 pub async fn build_server(app_state: ServerState) -> anyhow::Result<ServerHandle> {
     let socket_address = app_state.config.address;
-    let server = Server::builder().build(socket_address).await?;
+
+    // Serve metrics on their own listener, if enabled
+    if let Some(metrics_address) = app_state.config.metrics_address {
+        let metrics_handle = build_metrics_server(&app_state, metrics_address).await?;
+        tokio::spawn(metrics_handle.stopped());
+    }
+
+    // Serve presigned URLs if using local blob storage
+    let local_storage = app_state.blob_storage.as_local().cloned();
+    let http_middleware =
+        tower::ServiceBuilder::new().layer(LocalStorageLayer::new(local_storage));
+
+    let server = Server::builder()
+        .set_http_middleware(http_middleware)
+        .build(socket_address)
+        .await?;
     let module = build_module(app_state).await?;
     let handle = server.start(module);
     Ok(handle)
 }
 
+/// Builds the server which only serves `/metrics`.
+///
+/// It has no RPC methods, all other requests are rejected by `MetricsLayer`.
+async fn build_metrics_server(
+    app_state: &ServerState,
+    socket_address: SocketAddr,
+) -> anyhow::Result<ServerHandle> {
+    let http_middleware =
+        tower::ServiceBuilder::new().layer(MetricsLayer::new(app_state));
+
+    let server = Server::builder()
+        .set_http_middleware(http_middleware)
+        .build(socket_address)
+        .await?;
+
+    Ok(server.start(RpcModule::new(())))
+}
+
 async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<ServerState>> {
     let mut module = RpcModule::new(app_state);
 
@@ -155,19 +213,36 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
                 // automatically based on whether the Result is Ok or Err.
                 //
                 // At this level, we take the database-or-RPC error and make it just an RPC error.
+                //
+                // Cache invalidations are collected during the call, and
+                // only run once the transaction has committed.
                 let db_state = Arc::clone(&state);
-                db_state
+                let invalidations = PendingInvalidations::default();
+                let txn_invalidations = invalidations.clone();
+                let result = db_state
                     .database
                     .transaction(move |txn| {
                         Box::pin(async move {
-                            // Run the endpoint's implementation, and convert from
-                            // ServiceError to an RPC error.
-                            let ctx = ServiceContext::new(&state, &txn);
-                            $method(&ctx, params).await.map_err(ErrorObjectOwned::from)
+                            // Check the rate limit, run the endpoint's implementation,
+                            // record metrics, and convert from ServiceError to an RPC error.
+                            let ctx = ServiceContext::with_invalidations(&state, &txn, txn_invalidations);
+                            let start = Instant::now();
+                            let result = match RateLimitService::check(&ctx, $name, &params).await {
+                                Ok(()) => $method(&ctx, params).await,
+                                Err(error) => Err(error),
+                            };
+                            ctx.metrics().record_rpc($name, start.elapsed(), result.as_ref().err());
+                            result.map_err(ErrorObjectOwned::from)
                         })
                     })
                     .await
-                    .map_err(into_rpc_error)
+                    .map_err(into_rpc_error);
+
+                if result.is_ok() {
+                    invalidations.run(&db_state).await;
+                }
+
+                result
             })?;
         }};
     }
@@ -196,18 +271,38 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("logout", auth_logout);
     register!("session_get", auth_session_get);
     register!("session_get_others", auth_session_get_others);
+    register!("session_invalidate", auth_session_invalidate);
     register!("session_invalidate_others", auth_session_invalidate_others);
     register!("session_renew", auth_session_renew);
     register!("mfa_verify", auth_mfa_verify);
     register!("mfa_setup", auth_mfa_setup);
     register!("mfa_disable", auth_mfa_disable);
     register!("mfa_reset_recovery", auth_mfa_reset_recovery);
+    register!("mfa_webauthn_start", auth_mfa_webauthn_start);
+    register!("mfa_webauthn_verify", auth_mfa_webauthn_verify);
+    register!("login_webauthn_start", auth_webauthn_login_start);
+    register!("login_webauthn", auth_webauthn_login);
+    register!("webauthn_register_start", auth_webauthn_register_start);
+    register!("webauthn_register_finish", auth_webauthn_register_finish);
+    register!("webauthn_list", auth_webauthn_list);
+    register!("webauthn_remove", auth_webauthn_remove);
+    register!("login_history", auth_login_history);
 
     // Site
     register!("site_create", site_create);
     register!("site_get", site_get);
     register!("site_update", site_update);
+    register!("site_delete", site_delete);
+    register!("site_restore", site_restore);
+    register!("site_clone", site_clone);
     register!("site_from_domain", site_get_from_domain);
+    register!("site_export", site_export);
+    register!("site_export_get", site_export_get);
+    register!("site_import", site_import);
+    register!("site_import_get", site_import_get);
+    register!("import_wikicomma_start", import_wikicomma_start);
+    register!("import_wikicomma_resume", import_wikicomma_resume);
+    register!("import_progress_get", import_progress_get);
 
     // Site custom domain
     register!("custom_domain_create", site_custom_domain_create);
@@ -218,10 +313,35 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("member_set", membership_set);
     register!("member_get", membership_get);
     register!("member_delete", membership_delete);
+    register!("member_join", membership_join);
+
+    // Site membership applications
+    register!("application_get", application_get);
+    register!("application_list", application_list);
+    register!("application_submit", application_submit);
+    register!("application_withdraw", application_withdraw);
+    register!("application_accept", application_accept);
+    register!("application_decline", application_decline);
+
+    // Site membership invitations
+    register!("invitation_get", invitation_get);
+    register!("invitation_list", invitation_list);
+    register!("invitation_list_user", invitation_list_user);
+    register!("invitation_send", invitation_send);
+    register!("invitation_accept", invitation_accept);
+    register!("invitation_decline", invitation_decline);
+    register!("invitation_revoke", invitation_revoke);
+
+    // Audit log
+    register!("audit_log_get", audit_log_get);
 
     // Category
     register!("category_get", category_get);
     register!("category_get_all", category_get_all);
+    register!("category_create", category_create);
+    register!("category_update", category_update);
+    register!("category_delete", category_delete);
+    register!("category_move_pages", category_move_pages);
 
     // Page
     register!("page_create", page_create);
@@ -244,6 +364,10 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("page_revision_count", page_revision_count);
     register!("page_revision_range", page_revision_range);
 
+    // Recent changes
+    register!("recent_changes", recent_changes);
+    register!("recent_changes_feed", recent_changes_feed);
+
     // Page links
     register!("page_get_links_from", page_links_from_get);
     register!("page_get_links_to", page_links_to_get);
@@ -251,6 +375,64 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("page_get_urls_from", page_links_external_from);
     register!("page_get_urls_to", page_links_external_to);
 
+    // Page watching
+    register!("page_watch_set", page_watch_set);
+    register!("page_watch_get", page_watch_get);
+    register!("page_watch_remove", page_watch_remove);
+    register!("page_watch_list", page_watch_list);
+    register!("page_watch_list_count", page_watch_list_count);
+
+    // Page stars
+    register!("page_star_set", page_star_set);
+    register!("page_star_get", page_star_get);
+    register!("page_star_remove", page_star_remove);
+    register!("page_star_list", page_star_list);
+    register!("page_star_list_count", page_star_list_count);
+    register!("page_star_count", page_star_count);
+
+    // Page attributions
+    register!("page_attribution_get", page_attribution_get);
+    register!("page_attribution_set", page_attribution_set);
+    register!("page_attribution_remove", page_attribution_remove);
+    register!("page_attribution_import", page_attribution_import);
+
+    // Page edit sessions and drafts
+    register!("edit_session_start", edit_session_start);
+    register!("edit_session_heartbeat", edit_session_heartbeat);
+    register!("edit_session_release", edit_session_release);
+    register!("edit_session_force_release", edit_session_force_release);
+    register!("edit_session_get", edit_session_get);
+    register!("page_draft_save", page_draft_save);
+    register!("page_draft_get", page_draft_get);
+    register!("page_draft_delete", page_draft_delete);
+
+    // Page redirects
+    register!("redirect_get", redirect_get);
+    register!("redirect_list", redirect_list);
+    register!("redirect_delete", redirect_delete);
+    register!("backlink_rewrite_preview", backlink_rewrite_preview);
+    register!("backlink_rewrite", backlink_rewrite);
+
+    // Tags
+    register!("tag_create", tag_create);
+    register!("tag_get", tag_get);
+    register!("tag_get_all", tag_get_all);
+    register!("tag_update", tag_update);
+    register!("tag_delete", tag_delete);
+    register!("tag_rename", tag_rename);
+    register!("tag_usage", tag_usage);
+    register!("tag_group_create", tag_group_create);
+    register!("tag_group_get", tag_group_get);
+    register!("tag_group_get_all", tag_group_get_all);
+    register!("tag_group_update", tag_group_update);
+    register!("tag_group_delete", tag_group_delete);
+
+    // Bulk page operations
+    register!("bulk_operation_create", bulk_operation_create);
+    register!("bulk_operation_get", bulk_operation_get);
+    register!("bulk_operation_get_all", bulk_operation_get_all);
+    register!("bulk_operation_cancel", bulk_operation_cancel);
+
     // Page parents
     register!("parent_set", parent_set);
     register!("parent_get", parent_get);
@@ -258,6 +440,9 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("parent_relationships_get", parent_relationships_get);
     register!("parent_get_all", parent_get_all);
     register!("parent_update", parent_update);
+    register!("parent_ancestors_get", parent_ancestors_get);
+    register!("parent_descendants_get", parent_descendants_get);
+    register!("parent_move_subtree", parent_move_subtree);
 
     // Blob data
     register!("blob_get", blob_get);
@@ -281,6 +466,7 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("file_move", file_move);
     register!("file_restore", file_restore);
     register!("file_rollback", file_rollback);
+    register!("file_attribution", file_attribution);
 
     // File revisions
     register!("file_revision_get", file_revision_get);
@@ -300,6 +486,13 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("user_delete", user_delete);
     register!("user_add_name_change", user_add_name_change);
 
+    // User following
+    register!("user_follow_set", user_follow_set);
+    register!("user_follow_get", user_follow_get);
+    register!("user_follow_remove", user_follow_remove);
+    register!("user_followers", user_followers_get);
+    register!("user_following", user_following_get);
+
     // Bot user
     register!("bot_user_create", bot_user_create);
     register!("bot_user_get", bot_user_get);
@@ -312,6 +505,16 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("message_draft_delete", message_draft_delete);
     register!("message_draft_send", message_draft_send);
 
+    // Notifications
+    register!("notification_list", notification_list);
+    register!("notification_count_unread", notification_count_unread);
+    register!("notification_read", notification_read);
+    register!("notification_unread", notification_unread);
+    register!("notification_read_batch", notification_read_batch);
+    register!("notification_dismiss", notification_dismiss);
+    register!("notification_preferences_get", notification_preferences_get);
+    register!("notification_preferences_set", notification_preferences_set);
+
     // Email
     register!("email_validate", validate_email);
 
@@ -322,6 +525,12 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("vote_action", vote_action);
     register!("vote_list", vote_list_get);
     register!("vote_list_count", vote_list_count);
+    register!("vote_fraud_report_get", vote_fraud_report_get);
+    register!("vote_fraud_report_list", vote_fraud_report_list);
+    register!("vote_fraud_report_review", vote_fraud_report_review);
+    register!("score_timeline", score_timeline);
+    register!("score_daily_votes", score_daily_votes);
+    register!("score_drops", score_drops);
 
     // Return
     Ok(module)
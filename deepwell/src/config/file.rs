@@ -19,9 +19,12 @@
  */
 
 use super::Config;
+use crate::services::blob::BlobStorageType;
+use crate::services::rate_limit::RateLimitRule;
 use anyhow::Result;
 use femme::LevelFilter;
 use ftml::layout::Layout;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs::File;
 use std::io::Read;
@@ -55,6 +58,11 @@ pub struct ConfigFile {
     user: User,
     file: FileSection,
     message: Message,
+    site: SiteSection,
+    page: Page,
+    vote: Vote,
+    rate_limit: RateLimit,
+    cache: Cache,
 }
 
 /// Structure containing extra fields not found in `ConfigFile`.
@@ -78,6 +86,7 @@ struct Logger {
 #[serde(rename_all = "kebab-case")]
 struct Server {
     address: SocketAddr,
+    metrics_address: Option<SocketAddr>,
     pid_file: Option<PathBuf>,
 }
 
@@ -112,6 +121,8 @@ struct Mfa {
     recovery_code_length: usize,
     time_step: u64,
     time_skew: i64,
+    webauthn_relying_party_name: String,
+    webauthn_challenge_lifetime_secs: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -126,6 +137,15 @@ struct Job {
     prune_text_secs: u64,
     name_change_refill_secs: u64,
     lift_expired_punishments_secs: u64,
+    notification_digest_secs: u64,
+    bulk_operation_batch_size: NonZeroU16,
+    site_import_batch_size: NonZeroU16,
+    backlink_rewrite_batch_size: NonZeroU16,
+    tag_rename_batch_size: NonZeroU16,
+    score_snapshot_batch_size: NonZeroU16,
+    purge_deleted_sites_secs: u64,
+    detect_vote_fraud_secs: u64,
+    score_snapshot_secs: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -186,10 +206,15 @@ struct User {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 struct FileSection {
+    storage: BlobStorageType,
+    local_storage_path: PathBuf,
+    local_storage_url: String,
     presigned_path_length: usize,
     presigned_expiration_minutes: u32,
     maximum_blob_size_kb: i64,
     maximum_avatar_size_kb: i64,
+    thumbnail_sizes: Vec<u32>,
+    maximum_thumbnail_source_kb: i64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -200,6 +225,56 @@ struct Message {
     maximum_recipients: usize,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct SiteSection {
+    deletion_grace_period_days: u32,
+    maximum_archive_size_mb: u64,
+    maximum_archive_entries: u32,
+    import_path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Page {
+    edit_session_secs: u64,
+    maximum_hierarchy_depth: u32,
+    maximum_recent_changes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Vote {
+    fraud_account_age_days: u32,
+    fraud_lookback_days: u32,
+    fraud_creation_window_mins: u32,
+    fraud_minimum_cluster_size: usize,
+    fraud_minimum_shared_pages: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct RateLimit {
+    enable: bool,
+    methods: HashMap<String, RateLimitMethod>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct RateLimitMethod {
+    requests: u32,
+    period_secs: u64,
+
+    #[serde(default)]
+    bypass_bots: Vec<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Cache {
+    page_view_secs: u64,
+}
+
 impl ConfigFile {
     pub fn load(path: PathBuf) -> Result<(Self, ExtraConfig)> {
         // Read TOML
@@ -244,6 +319,7 @@ impl ConfigFile {
             server:
                 Server {
                     address,
+                    metrics_address,
                     mut pid_file,
                 },
             database:
@@ -267,6 +343,8 @@ impl ConfigFile {
                             recovery_code_length,
                             time_step,
                             time_skew,
+                            webauthn_relying_party_name,
+                            webauthn_challenge_lifetime_secs,
                         },
                 },
             domain:
@@ -285,6 +363,15 @@ impl ConfigFile {
                     prune_text_secs: job_prune_text_secs,
                     name_change_refill_secs: job_name_change_refill_secs,
                     lift_expired_punishments_secs: job_lift_expired_punishments_secs,
+                    notification_digest_secs: job_notification_digest_secs,
+                    bulk_operation_batch_size: job_bulk_operation_batch_size,
+                    site_import_batch_size: job_site_import_batch_size,
+                    backlink_rewrite_batch_size: job_backlink_rewrite_batch_size,
+                    tag_rename_batch_size: job_tag_rename_batch_size,
+                    score_snapshot_batch_size: job_score_snapshot_batch_size,
+                    purge_deleted_sites_secs: job_purge_deleted_sites_secs,
+                    detect_vote_fraud_secs: job_detect_vote_fraud_secs,
+                    score_snapshot_secs: job_score_snapshot_secs,
                 },
             locale: Locale {
                 path: localization_path,
@@ -316,10 +403,15 @@ impl ConfigFile {
                 },
             file:
                 FileSection {
+                    storage: blob_storage,
+                    local_storage_path,
+                    local_storage_url,
                     presigned_path_length,
                     presigned_expiration_minutes,
                     maximum_blob_size_kb,
                     maximum_avatar_size_kb,
+                    thumbnail_sizes,
+                    maximum_thumbnail_source_kb,
                 },
             message:
                 Message {
@@ -327,6 +419,36 @@ impl ConfigFile {
                     maximum_body_bytes: maximum_message_body_bytes,
                     maximum_recipients: maximum_message_recipients,
                 },
+            site:
+                SiteSection {
+                    deletion_grace_period_days: site_deletion_grace_period_days,
+                    maximum_archive_size_mb: site_maximum_archive_size_mb,
+                    maximum_archive_entries: site_maximum_archive_entries,
+                    import_path: site_import_path,
+                },
+            page:
+                Page {
+                    edit_session_secs,
+                    maximum_hierarchy_depth,
+                    maximum_recent_changes,
+                },
+            vote:
+                Vote {
+                    fraud_account_age_days: vote_fraud_account_age_days,
+                    fraud_lookback_days: vote_fraud_lookback_days,
+                    fraud_creation_window_mins: vote_fraud_creation_window_mins,
+                    fraud_minimum_cluster_size: vote_fraud_minimum_cluster_size,
+                    fraud_minimum_shared_pages: vote_fraud_minimum_shared_pages,
+                },
+            rate_limit:
+                RateLimit {
+                    enable: rate_limit_enable,
+                    methods: rate_limit_methods,
+                },
+            cache:
+                Cache {
+                    page_view_secs: page_view_cache_secs,
+                },
         } = self;
 
         // Assertions for bad values
@@ -348,6 +470,33 @@ impl ConfigFile {
             job_lift_expired_punishments_secs < RSMQ_DELAY_LIMIT,
             "Expired punishment cleanup job period time too long",
         );
+        assert!(
+            job_notification_digest_secs < RSMQ_DELAY_LIMIT,
+            "Notification digest job period time too long",
+        );
+        assert!(
+            job_purge_deleted_sites_secs < RSMQ_DELAY_LIMIT,
+            "Purge deleted sites job period time too long",
+        );
+        assert!(
+            job_detect_vote_fraud_secs < RSMQ_DELAY_LIMIT,
+            "Vote fraud detection job period time too long",
+        );
+        assert!(
+            job_score_snapshot_secs < RSMQ_DELAY_LIMIT,
+            "Score snapshot job period time too long",
+        );
+        assert!(
+            vote_fraud_minimum_cluster_size >= 2,
+            "Vote fraud clusters must have at least two accounts",
+        );
+
+        for (method, limit) in &rate_limit_methods {
+            assert!(
+                limit.requests > 0 && limit.period_secs > 0,
+                "Rate limit for '{method}' must allow requests",
+            );
+        }
 
         // Prefix domains with '.' so we can do easy subdomain checks
         // and concatenations.
@@ -368,6 +517,7 @@ impl ConfigFile {
             logger,
             logger_level,
             address,
+            metrics_address,
             pid_file,
             main_domain,
             main_domain_no_dot,
@@ -394,6 +544,10 @@ impl ConfigFile {
             recovery_code_length,
             totp_time_step: time_step,
             totp_time_skew: time_skew,
+            webauthn_relying_party_name,
+            webauthn_challenge_lifetime: StdDuration::from_secs(
+                webauthn_challenge_lifetime_secs,
+            ),
             job_workers,
             job_max_attempts,
             job_work_delay: StdDuration::from_millis(job_work_delay_ms),
@@ -405,6 +559,15 @@ impl ConfigFile {
             job_lift_expired_punishments: StdDuration::from_secs(
                 job_lift_expired_punishments_secs,
             ),
+            job_notification_digest: StdDuration::from_secs(job_notification_digest_secs),
+            job_bulk_operation_batch_size,
+            job_site_import_batch_size,
+            job_backlink_rewrite_batch_size,
+            job_tag_rename_batch_size,
+            job_score_snapshot_batch_size,
+            job_purge_deleted_sites: StdDuration::from_secs(job_purge_deleted_sites_secs),
+            job_detect_vote_fraud: StdDuration::from_secs(job_detect_vote_fraud_secs),
+            job_score_snapshot: StdDuration::from_secs(job_score_snapshot_secs),
             render_timeout: StdDuration::from_millis(render_timeout_ms),
             rerender_skip: rerender_skip
                 .iter()
@@ -442,13 +605,65 @@ impl ConfigFile {
                 ))
             },
             minimum_name_bytes,
+            blob_storage,
+            local_storage_path,
+            local_storage_url,
             presigned_path_length,
             presigned_expiry_secs: presigned_expiration_minutes * 60,
             maximum_blob_size: maximum_blob_size_kb * 1024,
             maximum_avatar_size: maximum_avatar_size_kb * 1024,
+            thumbnail_sizes,
+            maximum_thumbnail_source_size: maximum_thumbnail_source_kb * 1024,
             maximum_message_subject_bytes,
             maximum_message_body_bytes,
             maximum_message_recipients,
+            site_deletion_grace_period: TimeDuration::days(i64::from(
+                site_deletion_grace_period_days,
+            )),
+            site_maximum_archive_size: site_maximum_archive_size_mb * 1024 * 1024,
+            site_maximum_archive_entries,
+            site_import_path,
+            edit_session_ttl: StdDuration::from_secs(edit_session_secs),
+            maximum_hierarchy_depth,
+            maximum_recent_changes,
+            vote_fraud_account_age: TimeDuration::days(i64::from(
+                vote_fraud_account_age_days,
+            )),
+            vote_fraud_lookback: TimeDuration::days(i64::from(vote_fraud_lookback_days)),
+            vote_fraud_creation_window: TimeDuration::minutes(i64::from(
+                vote_fraud_creation_window_mins,
+            )),
+            vote_fraud_minimum_cluster_size,
+            vote_fraud_minimum_shared_pages,
+            page_view_cache_ttl: match page_view_cache_secs {
+                0 => None,
+                secs => Some(StdDuration::from_secs(secs)),
+            },
+            rate_limits: if rate_limit_enable {
+                rate_limit_methods
+                    .into_iter()
+                    .map(
+                        |(
+                            method,
+                            RateLimitMethod {
+                                requests,
+                                period_secs,
+                                bypass_bots,
+                            },
+                        )| {
+                            let rule = RateLimitRule {
+                                requests,
+                                period: StdDuration::from_secs(period_secs),
+                                bypass_bots,
+                            };
+
+                            (method, rule)
+                        },
+                    )
+                    .collect()
+            } else {
+                HashMap::new()
+            },
         }
     }
 }
@@ -29,6 +29,7 @@ pub use self::secrets::Secrets;
 
 use self::args::parse_args;
 use self::special_action::run_special_action;
+use crate::services::blob::BlobStorageType;
 
 #[derive(Debug, Clone)]
 pub struct SetupConfig {
@@ -39,8 +40,8 @@ pub struct SetupConfig {
 impl SetupConfig {
     pub fn load() -> Self {
         run_special_action();
-        let secrets = Secrets::load();
         let config = parse_args();
+        let secrets = Secrets::load(config.blob_storage == BlobStorageType::S3);
 
         SetupConfig { secrets, config }
     }
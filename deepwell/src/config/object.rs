@@ -19,9 +19,12 @@
  */
 
 use super::file::ConfigFile;
+use crate::services::blob::BlobStorageType;
+use crate::services::rate_limit::RateLimitRule;
 use anyhow::Result;
 use femme::LevelFilter;
 use ftml::layout::Layout;
+use std::collections::HashMap;
 use std::env;
 use std::net::SocketAddr;
 use std::num::NonZeroU16;
@@ -51,6 +54,12 @@ pub struct Config {
     /// The address the server will be hosted on.
     pub address: SocketAddr,
 
+    /// The address Prometheus metrics will be served on, if any.
+    ///
+    /// This is a separate listener from the API, so that metrics
+    /// are not exposed wherever the API is reachable.
+    pub metrics_address: Option<SocketAddr>,
+
     /// The PID file (if any) to write to on boot.
     pub pid_file: Option<PathBuf>,
 
@@ -116,6 +125,12 @@ pub struct Config {
     /// How much leniency should be allowed for TOTP.
     pub totp_time_skew: i64,
 
+    /// The relying party name shown to users during WebAuthn prompts.
+    pub webauthn_relying_party_name: String,
+
+    /// How long WebAuthn challenges are valid for.
+    pub webauthn_challenge_lifetime: StdDuration,
+
     /// The number of job workers to run in this process.
     pub job_workers: NonZeroU16,
 
@@ -145,6 +160,33 @@ pub struct Config {
     /// How often to run the "lift expired punishments" recurring job.
     pub job_lift_expired_punishments: StdDuration,
 
+    /// How often to run the "send notification digests" recurring job.
+    pub job_notification_digest: StdDuration,
+
+    /// How many pages to process in each run of a bulk page operation job.
+    pub job_bulk_operation_batch_size: NonZeroU16,
+
+    /// How many archive entries to store in each run of a site import job.
+    pub job_site_import_batch_size: NonZeroU16,
+
+    /// How many linking pages to edit in each run of a backlink rewrite job.
+    pub job_backlink_rewrite_batch_size: NonZeroU16,
+
+    /// How many pages to retag in each run of a tag rename job.
+    pub job_tag_rename_batch_size: NonZeroU16,
+
+    /// How many pages to check in each run of the "score snapshot" recurring job.
+    pub job_score_snapshot_batch_size: NonZeroU16,
+
+    /// How often to run the "purge deleted sites" recurring job.
+    pub job_purge_deleted_sites: StdDuration,
+
+    /// How often to run the "detect vote fraud" recurring job.
+    pub job_detect_vote_fraud: StdDuration,
+
+    /// How often to run the "score snapshot" recurring job.
+    pub job_score_snapshot: StdDuration,
+
     /// Maximum run time for a render request.
     pub render_timeout: StdDuration,
 
@@ -200,6 +242,17 @@ pub struct Config {
     /// Minimum length of bytes in a username.
     pub minimum_name_bytes: usize,
 
+    /// Which backend to store blobs in.
+    pub blob_storage: BlobStorageType,
+
+    /// The directory to store blobs in, when using local storage.
+    pub local_storage_path: PathBuf,
+
+    /// The base URL presigned URLs use, when using local storage.
+    ///
+    /// This should point to the address DEEPWELL is hosted on.
+    pub local_storage_url: String,
+
     /// Length of randomly-generated portion of S3 presigned URLs.
     pub presigned_path_length: usize,
 
@@ -212,6 +265,12 @@ pub struct Config {
     /// Maximum size of a user's avatar image.
     pub maximum_avatar_size: i64,
 
+    /// Dimensions (in pixels, along the longest side) of image thumbnails to generate.
+    pub thumbnail_sizes: Vec<u32>,
+
+    /// Maximum size of an image to generate thumbnails for.
+    pub maximum_thumbnail_source_size: i64,
+
     /// Maximum size of the subject line allowed in a direct message.
     pub maximum_message_subject_bytes: usize,
 
@@ -220,6 +279,57 @@ pub struct Config {
 
     /// Maximum number of total recipients allowed in a direct message.
     pub maximum_message_recipients: usize,
+
+    /// How long a deleted site can be restored before it is purged.
+    pub site_deletion_grace_period: TimeDuration,
+
+    /// Maximum total size of the contents of a site archive, when uncompressed.
+    pub site_maximum_archive_size: u64,
+
+    /// Maximum number of entries in a site archive.
+    pub site_maximum_archive_entries: u32,
+
+    /// The directory wikicomma backups are imported from.
+    ///
+    /// Import requests name a backup directory relative to this one.
+    pub site_import_path: PathBuf,
+
+    /// How long a page edit session lasts without a heartbeat.
+    pub edit_session_ttl: StdDuration,
+
+    /// Maximum number of levels to follow when traversing page parents.
+    pub maximum_hierarchy_depth: u32,
+
+    /// Maximum number of recent changes returned in one request.
+    pub maximum_recent_changes: u64,
+
+    /// How recently an account must have been created to be
+    /// considered by vote fraud detection.
+    pub vote_fraud_account_age: TimeDuration,
+
+    /// How far back to look for votes during vote fraud detection.
+    pub vote_fraud_lookback: TimeDuration,
+
+    /// How close together accounts sharing a user agent must have been
+    /// created to be linked during vote fraud detection.
+    pub vote_fraud_creation_window: TimeDuration,
+
+    /// Minimum number of linked accounts needed to flag a voting cluster.
+    pub vote_fraud_minimum_cluster_size: usize,
+
+    /// Minimum number of pages a cluster must have voted on together to be flagged.
+    pub vote_fraud_minimum_shared_pages: usize,
+
+    /// How long page view data is cached for.
+    ///
+    /// If `None`, then page views are not cached.
+    pub page_view_cache_ttl: Option<StdDuration>,
+
+    /// Rate limits for RPC methods, by method name.
+    ///
+    /// Methods not listed here are not rate limited.
+    /// This is empty if rate limiting is disabled.
+    pub rate_limits: HashMap<String, RateLimitRule>,
 }
 
 impl Config {
@@ -242,6 +352,10 @@ impl Config {
 
         info!("Configuration details:");
         info!("Serving on {}", self.address);
+        match self.metrics_address {
+            Some(address) => info!("Serving metrics on {address}"),
+            None => info!("Metrics: disabled"),
+        }
         info!(
             "Auto-restart on config change: {}",
             bool_str(self.watch_files),
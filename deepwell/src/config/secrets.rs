@@ -35,22 +35,31 @@ pub struct Secrets {
     /// Set using environment variable `REDIS_URL`.
     pub redis_url: String,
 
+    /// The S3 configuration for blob storage.
+    ///
+    /// Only loaded if S3 is the configured blob storage backend,
+    /// see `BlobStorageType`.
+    pub s3: Option<S3Secrets>,
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Secrets {
     /// The name of the S3 bucket that file blobs are kept in.
     /// The bucket must already exist prior to program invocation.
     ///
     /// Set using environment variable `S3_BUCKET`.
-    pub s3_bucket: String,
+    pub bucket: String,
 
     /// The region to use for S3.
     ///
     /// Set using environment variable `S3_AWS_REGION` if standard,
     /// or `S3_REGION_NAME` and `S3_CUSTOM_ENDPOINT` if custom.
-    pub s3_region: Region,
+    pub region: Region,
 
     /// Whether to use path style for S3.
     ///
     /// Set using environment variable `S3_PATH_STYLE`.
-    pub s3_path_style: bool,
+    pub path_style: bool,
 
     /// The credentials to use for S3.
     ///
@@ -58,11 +67,11 @@ pub struct Secrets {
     ///
     /// Alternatively you can have it read from the AWS credentials file.
     /// The profile to read from can be set in the `AWS_PROFILE_NAME` environment variable.
-    pub s3_credentials: Credentials,
+    pub credentials: Credentials,
 }
 
 impl Secrets {
-    pub fn load() -> Self {
+    pub fn load(load_s3: bool) -> Self {
         dotenv().ok();
 
         // Essentially .expect(), but allows inserting the environment variable name.
@@ -84,72 +93,78 @@ impl Secrets {
         let database_url = get_env!("DATABASE_URL");
         let redis_url = get_env!("REDIS_URL");
 
-        let s3_bucket = get_env!("S3_BUCKET");
-        let s3_region = match env::var("S3_AWS_REGION") {
-            // Standard AWS S3 region, parse out into enum.
-            Ok(value) => {
-                match value.parse() {
+        let s3 = if load_s3 {
+            let bucket = get_env!("S3_BUCKET");
+            let region = match env::var("S3_AWS_REGION") {
+                // Standard AWS S3 region, parse out into enum.
+                Ok(value) => match value.parse() {
                     Ok(region) => region,
                     Err(error) => {
                         eprintln!("S3_AWS_REGION variable is not a valid AWS region ID: {error}");
                         process::exit(1);
                     }
-                }
-            }
-
-            // Custom region, with a specific S3 endpoint.
-            Err(_) => {
-                let region = get_env!("S3_REGION_NAME");
-                let endpoint = get_env!("S3_CUSTOM_ENDPOINT");
+                },
 
-                Region::Custom { region, endpoint }
-            }
-        };
+                // Custom region, with a specific S3 endpoint.
+                Err(_) => {
+                    let region = get_env!("S3_REGION_NAME");
+                    let endpoint = get_env!("S3_CUSTOM_ENDPOINT");
 
-        let s3_path_style = match get_env!("S3_PATH_STYLE").parse() {
-            Ok(path_style) => path_style,
-            Err(_) => {
-                eprintln!("S3_PATH_STYLE variable is not a valid boolean");
-                process::exit(1);
-            }
-        };
+                    Region::Custom { region, endpoint }
+                }
+            };
 
-        let s3_credentials = {
-            // Try to read from environment
-            // Reads from S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY
-            let env_creds = Credentials::from_env_specific(
-                Some("S3_ACCESS_KEY_ID"),
-                Some("S3_SECRET_ACCESS_KEY"),
-                None,
-                None,
-            );
-
-            match env_creds {
-                Ok(credentials) => credentials,
+            let path_style = match get_env!("S3_PATH_STYLE").parse() {
+                Ok(path_style) => path_style,
                 Err(_) => {
-                    // Try to read from profile
-                    let profile_name = env::var("AWS_PROFILE_NAME").ok();
-                    let profile_name = profile_name.ref_map(|s| s.as_str());
-
-                    match Credentials::from_profile(profile_name) {
-                        Ok(credentials) => credentials,
-                        Err(error) => {
-                            eprintln!("Unable to read AWS credentials file: {error}");
-                            process::exit(1);
+                    eprintln!("S3_PATH_STYLE variable is not a valid boolean");
+                    process::exit(1);
+                }
+            };
+
+            let credentials = {
+                // Try to read from environment
+                // Reads from S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY
+                let env_creds = Credentials::from_env_specific(
+                    Some("S3_ACCESS_KEY_ID"),
+                    Some("S3_SECRET_ACCESS_KEY"),
+                    None,
+                    None,
+                );
+
+                match env_creds {
+                    Ok(credentials) => credentials,
+                    Err(_) => {
+                        // Try to read from profile
+                        let profile_name = env::var("AWS_PROFILE_NAME").ok();
+                        let profile_name = profile_name.ref_map(|s| s.as_str());
+
+                        match Credentials::from_profile(profile_name) {
+                            Ok(credentials) => credentials,
+                            Err(error) => {
+                                eprintln!("Unable to read AWS credentials file: {error}");
+                                process::exit(1);
+                            }
                         }
                     }
                 }
-            }
+            };
+
+            Some(S3Secrets {
+                bucket,
+                region,
+                path_style,
+                credentials,
+            })
+        } else {
+            None
         };
 
         // Build and return
         Secrets {
             database_url,
             redis_url,
-            s3_bucket,
-            s3_region,
-            s3_path_style,
-            s3_credentials,
+            s3,
         }
     }
 }
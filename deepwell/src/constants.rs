@@ -25,3 +25,4 @@ pub const ADMIN_USER_ID: i64 = 1;
 pub const SYSTEM_USER_ID: i64 = 2;
 pub const ANONYMOUS_USER_ID: i64 = 2;
 pub const SAMPLE_USER_ID: i64 = 2;
+pub const MAINTENANCE_BOT_USER_ID: i64 = 6;
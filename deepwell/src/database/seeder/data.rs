@@ -19,6 +19,7 @@
  */
 
 use crate::models::sea_orm_active_enums::UserType;
+use crate::services::file::License;
 use anyhow::Result;
 use ftml::layout::Layout;
 use serde::Deserialize;
@@ -143,6 +144,9 @@ pub struct Site {
     pub default_page: Option<String>,
     pub layout: Option<Layout>,
     pub locale: String,
+
+    #[serde(default)]
+    pub default_license: Option<License>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -39,7 +39,6 @@ use anyhow::Result;
 use sea_orm::{
     ConnectionTrait, DatabaseBackend, DatabaseTransaction, Statement, TransactionTrait,
 };
-use serde_json::Value as JsonValue;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
@@ -92,6 +91,7 @@ pub async fn seed(state: &ServerState) -> Result<()> {
                 name: user.name,
                 email: user.email,
                 password: user.password.unwrap_or_default(),
+                ip_address: None,
                 locales: user.locales,
                 bypass_filter: true,
                 bypass_email_verification: true,
@@ -162,6 +162,7 @@ pub async fn seed(state: &ServerState) -> Result<()> {
                 default_page: site.default_page,
                 layout: site.layout,
                 locale: site.locale,
+                default_license: site.default_license,
             },
         )
         .await?;
@@ -293,7 +294,7 @@ pub async fn seed(state: &ServerState) -> Result<()> {
                             direct_upload: Some(data),
                             revision_comments: str!(),
                             user_id: SYSTEM_USER_ID,
-                            licensing: JsonValue::Null,
+                            licensing: None,
                             bypass_filter: true,
                         },
                     )
@@ -421,7 +422,9 @@ pub async fn seed(state: &ServerState) -> Result<()> {
      * restart_sequence_with(&txn, < forum post seq >, 7000000).await?;
      */
 
+    let invalidations = ctx.invalidations();
     txn.commit().await?;
+    invalidations.run(state).await;
     info!("Finished running seeder.");
     Ok(())
 }
@@ -20,36 +20,39 @@
 
 use super::prelude::*;
 use crate::models::session::Model as SessionModel;
+use crate::models::user::Model as UserModel;
 use crate::services::authentication::{
     AuthenticateUserOutput, AuthenticationService, LoginUser, LoginUserMfa,
-    LoginUserOutput, MultiFactorAuthenticateUser,
+    LoginUserMfaWebauthn, LoginUserOutput, LoginUserWebauthn,
+    MultiFactorAuthenticateUser, MultiFactorWebauthnAuthenticateUser,
 };
+use crate::services::login_attempt::{GetLoginAttempts, LoginAttemptOutput};
 use crate::services::mfa::{
     MultiFactorConfigure, MultiFactorResetOutput, MultiFactorSetupOutput,
 };
 use crate::services::session::{
     CreateSession, GetOtherSessions, GetOtherSessionsOutput, InvalidateOtherSessions,
-    RenewSession,
+    InvalidateSession, RenewSession, SessionInfo,
 };
 use crate::services::user::GetUser;
-use crate::services::Error;
+use crate::services::webauthn::{
+    WebauthnAuthStartOutput, WebauthnCredentialOutput, WebauthnRegister,
+    WebauthnRegisterStartOutput, WebauthnRemove,
+};
+use crate::services::{Error, WebauthnService};
 
 pub async fn auth_login(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
 ) -> Result<LoginUserOutput> {
-    let LoginUser {
-        authenticate,
-        ip_address,
-        user_agent,
-    } = params.parse()?;
+    let input: LoginUser = params.parse()?;
 
     // Don't allow empty passwords.
     //
     // They are never valid, and are potentially indicative of the user
     // entering the password in the name field instead, which we do
     // *not* want to be logging.
-    if authenticate.password.is_empty() {
+    if input.authenticate.password.is_empty() {
         error!("User submitted empty password in auth request");
         return Err(Error::EmptyPassword);
     }
@@ -64,7 +67,7 @@ pub async fn auth_login(
     // * success
     // * invalid authentication
     // * server error
-    let result = AuthenticationService::auth_password(ctx, authenticate).await;
+    let result = AuthenticationService::auth_password(ctx, &input).await;
     let AuthenticateUserOutput { needs_mfa, user_id } = match result {
         Ok(output) => output,
         Err(mut error) => {
@@ -82,6 +85,17 @@ pub async fn auth_login(
         "Password authentication for user ID {user_id} succeeded (login complete: {login_complete})",
     );
 
+    let LoginUser {
+        ip_address,
+        user_agent,
+        ..
+    } = input;
+
+    if login_complete {
+        AuthenticationService::record_success(ctx, user_id, ip_address, &user_agent)
+            .await?;
+    }
+
     let session_token = SessionService::create(
         ctx,
         CreateSession {
@@ -143,7 +157,7 @@ pub async fn auth_session_get_others(
         .iter()
         .position(|session| session.session_token == session_token)
     {
-        Some(index) => sessions.remove(index),
+        Some(index) => SessionInfo::from(sessions.remove(index)),
         None => {
             error!(
                 "Cannot find own session token in list of all sessions, must be invalid",
@@ -154,10 +168,19 @@ pub async fn auth_session_get_others(
 
     Ok(GetOtherSessionsOutput {
         current,
-        others: sessions,
+        others: sessions.into_iter().map(SessionInfo::from).collect(),
     })
 }
 
+/// Invalidates one of the user's other sessions, such as one on a lost device.
+pub async fn auth_session_invalidate(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: InvalidateSession = params.parse()?;
+    SessionService::invalidate_by_id(ctx, input).await
+}
+
 pub async fn auth_session_invalidate_others(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -188,10 +211,15 @@ pub async fn auth_mfa_verify(
         MultiFactorAuthenticateUser {
             session_token: &session_token,
             totp_or_code: &totp_or_code,
+            ip_address,
+            user_agent: &user_agent,
         },
     )
     .await?;
 
+    AuthenticationService::record_success(ctx, user.user_id, ip_address, &user_agent)
+        .await?;
+
     SessionService::renew(
         ctx,
         RenewSession {
@@ -222,19 +250,7 @@ pub async fn auth_mfa_disable(
         session_token,
     } = params.parse()?;
 
-    let user = SessionService::get_user(ctx, &session_token, false).await?;
-    if user.user_id != user_id {
-        error!(
-            "Passed user ID ({}) does not match session token ({})",
-            user_id, user.user_id,
-        );
-
-        return Err(Error::SessionUserId {
-            active_user_id: user_id,
-            session_user_id: user.user_id,
-        });
-    }
-
+    let user = get_session_user(ctx, &session_token, user_id).await?;
     MfaService::disable(ctx, user.user_id).await
 }
 
@@ -247,7 +263,189 @@ pub async fn auth_mfa_reset_recovery(
         session_token,
     } = params.parse()?;
 
-    let user = SessionService::get_user(ctx, &session_token, false).await?;
+    let user = get_session_user(ctx, &session_token, user_id).await?;
+    MfaService::reset_recovery_codes(ctx, &user).await
+}
+
+pub async fn auth_mfa_webauthn_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<WebauthnAuthStartOutput> {
+    let session_token: String = params.one()?;
+    let user = SessionService::get_user(ctx, &session_token, true).await?;
+    WebauthnService::auth_start(ctx, Some(&user)).await
+}
+
+pub async fn auth_mfa_webauthn_verify(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<String> {
+    let LoginUserMfaWebauthn {
+        session_token,
+        ip_address,
+        user_agent,
+        assertion,
+    } = params.parse()?;
+
+    info!(
+        "Verifying user's WebAuthn credential for login (temporary session token {session_token})",
+    );
+
+    let user = AuthenticationService::auth_mfa_webauthn(
+        ctx,
+        MultiFactorWebauthnAuthenticateUser {
+            session_token: &session_token,
+            assertion,
+            ip_address,
+            user_agent: &user_agent,
+        },
+    )
+    .await?;
+
+    AuthenticationService::record_success(ctx, user.user_id, ip_address, &user_agent)
+        .await?;
+
+    SessionService::renew(
+        ctx,
+        RenewSession {
+            old_session_token: session_token,
+            user_id: user.user_id,
+            ip_address,
+            user_agent,
+        },
+    )
+    .await
+}
+
+pub async fn auth_webauthn_login_start(
+    ctx: &ServiceContext<'_>,
+    _params: Params<'static>,
+) -> Result<WebauthnAuthStartOutput> {
+    WebauthnService::auth_start(ctx, None).await
+}
+
+pub async fn auth_webauthn_login(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<LoginUserOutput> {
+    let LoginUserWebauthn {
+        ip_address,
+        user_agent,
+        assertion,
+    } = params.parse()?;
+
+    // Same as auth_login(), only authentication failures are permitted to surface
+    let user_id = match AuthenticationService::auth_webauthn(ctx, assertion).await {
+        Ok(user_id) => user_id,
+        Err(mut error) => {
+            if !matches!(error, Error::InvalidAuthentication) {
+                error!("Unexpected error during WebAuthn authentication: {error}");
+                error = Error::AuthenticationBackend(Box::new(error));
+            }
+
+            return Err(error);
+        }
+    };
+
+    info!("WebAuthn authentication for user ID {user_id} succeeded");
+    AuthenticationService::record_success(ctx, user_id, ip_address, &user_agent).await?;
+
+    let session_token = SessionService::create(
+        ctx,
+        CreateSession {
+            user_id,
+            ip_address,
+            user_agent,
+            restricted: false,
+        },
+    )
+    .await?;
+
+    Ok(LoginUserOutput {
+        session_token,
+        needs_mfa: false,
+    })
+}
+
+pub async fn auth_webauthn_register_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<WebauthnRegisterStartOutput> {
+    let MultiFactorConfigure {
+        user_id,
+        session_token,
+    } = params.parse()?;
+
+    let user = get_session_user(ctx, &session_token, user_id).await?;
+    WebauthnService::register_start(ctx, &user).await
+}
+
+pub async fn auth_webauthn_register_finish(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<WebauthnCredentialOutput> {
+    let WebauthnRegister {
+        user_id,
+        session_token,
+        input,
+    } = params.parse()?;
+
+    let user = get_session_user(ctx, &session_token, user_id).await?;
+    WebauthnService::register_finish(ctx, &user, input).await
+}
+
+pub async fn auth_webauthn_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<WebauthnCredentialOutput>> {
+    let MultiFactorConfigure {
+        user_id,
+        session_token,
+    } = params.parse()?;
+
+    let user = get_session_user(ctx, &session_token, user_id).await?;
+    let credentials = WebauthnService::get_all(ctx, user.user_id).await?;
+    Ok(credentials.into_iter().map(Into::into).collect())
+}
+
+pub async fn auth_webauthn_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let WebauthnRemove {
+        user_id,
+        session_token,
+        credential_id,
+    } = params.parse()?;
+
+    let user = get_session_user(ctx, &session_token, user_id).await?;
+    WebauthnService::remove(ctx, user.user_id, credential_id).await
+}
+
+pub async fn auth_login_history(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<LoginAttemptOutput>> {
+    let GetLoginAttempts {
+        user_id,
+        session_token,
+        start_id,
+        limit,
+    } = params.parse()?;
+
+    let user = get_session_user(ctx, &session_token, user_id).await?;
+    LoginAttemptService::get_all(ctx, user.user_id, start_id, limit).await
+}
+
+// Helper functions
+
+/// Gets the user for a full (non-restricted) session, ensuring it matches the passed user ID.
+async fn get_session_user(
+    ctx: &ServiceContext<'_>,
+    session_token: &str,
+    user_id: i64,
+) -> Result<UserModel> {
+    let user = SessionService::get_user(ctx, session_token, false).await?;
     if user.user_id != user_id {
         error!(
             "Passed user ID ({}) does not match session token ({})",
@@ -260,5 +458,5 @@ pub async fn auth_mfa_reset_recovery(
         });
     }
 
-    MfaService::reset_recovery_codes(ctx, &user).await
+    Ok(user)
 }
@@ -0,0 +1,67 @@
+/*
+ * endpoints/bulk_operation.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_bulk_operation::Model as PageBulkOperationModel;
+use crate::services::bulk_operation::{CreateBulkOperation, GetBulkOperation};
+use crate::services::site::GetSite;
+
+pub async fn bulk_operation_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageBulkOperationModel> {
+    let input: CreateBulkOperation = params.parse()?;
+    BulkOperationService::create(ctx, input).await
+}
+
+pub async fn bulk_operation_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageBulkOperationModel> {
+    let GetBulkOperation {
+        site_id,
+        bulk_operation_id,
+    } = params.parse()?;
+
+    BulkOperationService::get(ctx, site_id, bulk_operation_id).await
+}
+
+pub async fn bulk_operation_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageBulkOperationModel>> {
+    let GetSite { site } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Getting all bulk operations in site ID {site_id}");
+    BulkOperationService::get_all(ctx, site_id).await
+}
+
+pub async fn bulk_operation_cancel(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageBulkOperationModel> {
+    let GetBulkOperation {
+        site_id,
+        bulk_operation_id,
+    } = params.parse()?;
+
+    info!("Canceling bulk operation ID {bulk_operation_id} in site ID {site_id}");
+    BulkOperationService::cancel(ctx, site_id, bulk_operation_id).await
+}
@@ -20,7 +20,10 @@
 
 use super::prelude::*;
 use crate::models::page_category::Model as PageCategoryModel;
-use crate::services::category::GetCategory;
+use crate::services::category::{
+    CreateCategory, GetCategory, MoveCategoryPages, MoveCategoryPagesOutput,
+    UpdateCategory, UpdateCategoryOutput,
+};
 use crate::services::site::GetSite;
 
 pub async fn category_get(
@@ -42,3 +45,62 @@ pub async fn category_get_all(
     info!("Getting all page categories in site ID {site_id}");
     CategoryService::get_all(ctx, site_id).await
 }
+
+pub async fn category_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageCategoryModel> {
+    let CreateCategory {
+        site,
+        slug,
+        settings,
+    } = params.parse()?;
+
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Creating page category '{slug}' in site ID {site_id}");
+    CategoryService::create(ctx, site_id, slug, settings).await
+}
+
+pub async fn category_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<UpdateCategoryOutput> {
+    let UpdateCategory {
+        site,
+        category,
+        user_id,
+        leave_redirects,
+        body,
+    } = params.parse()?;
+
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Updating page category {category:?} in site ID {site_id}");
+    CategoryService::update(ctx, site_id, category, body, user_id, leave_redirects).await
+}
+
+pub async fn category_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageCategoryModel> {
+    let GetCategory { site, category } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Deleting page category {category:?} in site ID {site_id}");
+    CategoryService::delete(ctx, site_id, category).await
+}
+
+pub async fn category_move_pages(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<MoveCategoryPagesOutput> {
+    let MoveCategoryPages {
+        site,
+        from,
+        to,
+        user_id,
+        leave_redirects,
+    } = params.parse()?;
+
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Moving all pages from category {from:?} to '{to}' in site ID {site_id}");
+    CategoryService::move_pages(ctx, site_id, from, to, user_id, leave_redirects).await
+}
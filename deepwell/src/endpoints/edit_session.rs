@@ -0,0 +1,90 @@
+/*
+ * endpoints/edit_session.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_draft::Model as PageDraftModel;
+use crate::services::edit_session::{
+    EditSession, EditSessionKey, ForceReleaseEditSession, GetEditSession, PageDraft,
+    SavePageDraft, StartEditSessionOutput,
+};
+
+pub async fn edit_session_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<StartEditSessionOutput> {
+    let input: EditSessionKey = params.parse()?;
+    EditSessionService::start(ctx, input).await
+}
+
+pub async fn edit_session_heartbeat(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<EditSession> {
+    let input: EditSessionKey = params.parse()?;
+    EditSessionService::heartbeat(ctx, input).await
+}
+
+pub async fn edit_session_release(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: EditSessionKey = params.parse()?;
+    EditSessionService::release(ctx, input).await
+}
+
+pub async fn edit_session_force_release(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<EditSession> {
+    let input: ForceReleaseEditSession = params.parse()?;
+    EditSessionService::force_release(ctx, input).await
+}
+
+pub async fn edit_session_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<EditSession>> {
+    let input: GetEditSession = params.parse()?;
+    EditSessionService::get(ctx, input).await
+}
+
+pub async fn page_draft_save(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageDraftModel> {
+    let input: SavePageDraft = params.parse()?;
+    EditSessionService::save_draft(ctx, input).await
+}
+
+pub async fn page_draft_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<PageDraft>> {
+    let input: EditSessionKey = params.parse()?;
+    EditSessionService::get_draft(ctx, input).await
+}
+
+pub async fn page_draft_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: EditSessionKey = params.parse()?;
+    EditSessionService::delete_draft(ctx, input).await
+}
@@ -23,8 +23,8 @@ use crate::models::file::Model as FileModel;
 use crate::models::file_revision::Model as FileRevisionModel;
 use crate::services::file::{
     CreateFile, CreateFileOutput, DeleteFile, DeleteFileOutput, EditFile, EditFileOutput,
-    GetFileDetails, GetFileOutput, MoveFile, MoveFileOutput, RestoreFile,
-    RestoreFileOutput, RollbackFile,
+    GetFileAttribution, GetFileAttributionOutput, GetFileDetails, GetFileOutput,
+    MoveFile, MoveFileOutput, RestoreFile, RestoreFileOutput, RollbackFile,
 };
 use crate::services::Result;
 use crate::services::{BlobService, FileRevisionService};
@@ -143,13 +143,25 @@ pub async fn file_rollback(
     FileService::rollback(ctx, input).await
 }
 
+pub async fn file_attribution(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetFileAttributionOutput> {
+    let GetFileAttribution { site_id, page_id } = params.parse()?;
+    FileService::get_file_attribution(ctx, site_id, page_id).await
+}
+
 async fn build_file_response(
     ctx: &ServiceContext<'_>,
     file: FileModel,
     revision: FileRevisionModel,
     details: FileDetails,
 ) -> Result<GetFileOutput> {
-    let data = BlobService::get_maybe(ctx, details.data, &revision.s3_hash).await?;
+    let (data, derivatives) = try_join!(
+        BlobService::get_maybe(ctx, details.data, &revision.s3_hash),
+        BlobService::get_derivatives(ctx, &revision.s3_hash),
+    )?;
+
     Ok(GetFileOutput {
         file_id: file.file_id,
         file_created_at: file.created_at,
@@ -168,5 +180,6 @@ async fn build_file_response(
         licensing: revision.licensing,
         revision_comments: revision.comments,
         hidden_fields: revision.hidden,
+        derivatives,
     })
 }
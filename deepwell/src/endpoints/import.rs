@@ -0,0 +1,47 @@
+/*
+ * endpoints/import.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::import_progress::Model as ImportProgressModel;
+use crate::services::import::{GetImportProgress, StartWikicommaImport};
+
+pub async fn import_wikicomma_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ImportProgressModel> {
+    let input: StartWikicommaImport = params.parse()?;
+    ImportService::start_wikicomma(ctx, input).await
+}
+
+pub async fn import_wikicomma_resume(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ImportProgressModel> {
+    let GetImportProgress { import_id } = params.parse()?;
+    ImportService::resume_wikicomma(ctx, import_id).await
+}
+
+pub async fn import_progress_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ImportProgressModel>> {
+    let GetImportProgress { import_id } = params.parse()?;
+    ImportService::get_progress_optional(ctx, import_id).await
+}
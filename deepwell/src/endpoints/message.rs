@@ -59,7 +59,10 @@ pub async fn message_draft_send(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
 ) -> Result<MessageRecordModel> {
-    let SendMessageDraft { message_draft_id } = params.parse()?;
-    info!("Sending message draft with ID {message_draft_id}");
+    let SendMessageDraft {
+        message_draft_id,
+        ip_address,
+    } = params.parse()?;
+    info!("Sending message draft with ID {message_draft_id} ({ip_address})");
     MessageService::send(ctx, &message_draft_id).await
 }
@@ -31,12 +31,15 @@
 mod prelude {
     pub use crate::api::ServerState;
     pub use crate::services::{
-        AliasService, BlobService, CategoryService, DomainService, Error as ServiceError,
-        FileRevisionService, FileService, LinkService, MessageReportService,
-        MessageService, MfaService, PageRevisionService, PageService, ParentService,
-        RelationService, RenderService, Result, ScoreService, ServiceContext,
-        SessionService, SettingsService, SiteService, StdResult, TextService,
-        UserService, ViewService, VoteService,
+        AliasService, AuditService, BlobService, BulkOperationService, CategoryService,
+        DomainService, EditSessionService, Error as ServiceError, FileRevisionService,
+        FileService, ImportService, LinkService, LoginAttemptService,
+        MessageReportService, MessageService, MfaService, NotificationService,
+        PageAttributionService, PageRevisionService, PageService, ParentService,
+        RecentChangesService, RedirectService, RelationService, RenderService, Result,
+        ScoreService, ServiceContext, SessionService, SettingsService,
+        SiteArchiveService, SiteService, StdResult, TagService, TextService, UserService,
+        ViewService, VoteFraudService, VoteService,
     };
     pub use jsonrpsee::types::params::Params;
     pub use std::convert::TryFrom;
@@ -44,23 +47,34 @@ mod prelude {
 
 pub mod auth;
 pub mod blob;
+pub mod bulk_operation;
 pub mod category;
 pub mod domain;
+pub mod edit_session;
 pub mod email;
 pub mod file;
 pub mod file_revision;
+pub mod import;
 pub mod info;
 pub mod link;
 pub mod locale;
 pub mod message;
 pub mod misc;
+pub mod notification;
 pub mod page;
+pub mod page_attribution;
 pub mod page_revision;
+pub mod page_star;
+pub mod page_watch;
 pub mod parent;
+pub mod recent_changes;
+pub mod redirect;
 pub mod site;
 pub mod site_member;
+pub mod tag;
 pub mod text;
 pub mod user;
 pub mod user_bot;
+pub mod user_follow;
 pub mod view;
 pub mod vote;
@@ -0,0 +1,90 @@
+/*
+ * endpoints/notification.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::notification::Model as NotificationModel;
+use crate::services::notification::{
+    GetNotifications, GetNotificationsOutput, NotificationPreferences, ReadNotifications,
+    SetNotificationPreferences, UpdateNotification,
+};
+
+pub async fn notification_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetNotificationsOutput> {
+    let input: GetNotifications = params.parse()?;
+    NotificationService::get_all(ctx, input).await
+}
+
+pub async fn notification_count_unread(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<u64> {
+    let user_id: i64 = params.one()?;
+    NotificationService::count_unread(ctx, user_id).await
+}
+
+pub async fn notification_read(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<NotificationModel> {
+    let input: UpdateNotification = params.parse()?;
+    NotificationService::set_read(ctx, input, true).await
+}
+
+pub async fn notification_unread(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<NotificationModel> {
+    let input: UpdateNotification = params.parse()?;
+    NotificationService::set_read(ctx, input, false).await
+}
+
+pub async fn notification_read_batch(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<u64> {
+    let input: ReadNotifications = params.parse()?;
+    NotificationService::read_batch(ctx, input).await
+}
+
+pub async fn notification_dismiss(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<NotificationModel> {
+    let input: UpdateNotification = params.parse()?;
+    NotificationService::dismiss(ctx, input).await
+}
+
+pub async fn notification_preferences_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<NotificationPreferences> {
+    let user_id: i64 = params.one()?;
+    NotificationService::get_preferences(ctx, user_id).await
+}
+
+pub async fn notification_preferences_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: SetNotificationPreferences = params.parse()?;
+    NotificationService::set_preferences(ctx, input).await
+}
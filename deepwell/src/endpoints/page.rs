@@ -329,6 +329,8 @@ async fn build_page_file_output(
         FileRevisionService::get_latest(ctx, file.site_id, file.page_id, file.file_id)
             .await?;
 
+    let derivatives = BlobService::get_derivatives(ctx, &revision.s3_hash).await?;
+
     // Build result struct
     Ok(Some(GetFileOutput {
         file_id: file.file_id,
@@ -348,5 +350,6 @@ async fn build_page_file_output(
         licensing: revision.licensing,
         revision_comments: revision.comments,
         hidden_fields: revision.hidden,
+        derivatives,
     }))
 }
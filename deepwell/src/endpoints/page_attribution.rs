@@ -0,0 +1,57 @@
+/*
+ * endpoints/page_attribution.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_attribution::Model as PageAttributionModel;
+use crate::services::page_attribution::{
+    GetPageAttributions, RemovePageAttribution, SetPageAttribution,
+};
+
+pub async fn page_attribution_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageAttributionModel>> {
+    let input: GetPageAttributions = params.parse()?;
+    PageAttributionService::get_all(ctx, input).await
+}
+
+pub async fn page_attribution_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageAttributionModel> {
+    let input: SetPageAttribution = params.parse()?;
+    PageAttributionService::set(ctx, input).await
+}
+
+pub async fn page_attribution_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: RemovePageAttribution = params.parse()?;
+    PageAttributionService::remove(ctx, input).await
+}
+
+pub async fn page_attribution_import(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<usize> {
+    let site_id: i64 = params.one()?;
+    PageAttributionService::import_wikidot_metadata(ctx, site_id).await
+}
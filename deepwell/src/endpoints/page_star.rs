@@ -0,0 +1,85 @@
+/*
+ * endpoints/page_star.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::relation::Model as RelationModel;
+use crate::services::relation::{
+    CreatePageStar, GetPageStar, GetStarredPages, RemovePageStar,
+};
+
+pub async fn page_star_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<RelationModel>> {
+    let input: GetPageStar = params.parse()?;
+    RelationService::get_optional_page_star(ctx, input).await
+}
+
+pub async fn page_star_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: CreatePageStar = params.parse()?;
+
+    info!(
+        "Adding star by user ID {} on page ID {}",
+        input.user_id, input.page_id,
+    );
+
+    RelationService::create_page_star(ctx, input).await
+}
+
+pub async fn page_star_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: RemovePageStar = params.parse()?;
+
+    info!(
+        "Removing star by user ID {} on page ID {}",
+        input.user_id, input.page_id,
+    );
+
+    RelationService::remove_page_star(ctx, input).await
+}
+
+pub async fn page_star_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RelationModel>> {
+    let input: GetStarredPages = params.parse()?;
+    RelationService::get_starred_pages(ctx, input).await
+}
+
+pub async fn page_star_list_count(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<u64> {
+    let user_id: i64 = params.one()?;
+    RelationService::count_starred_pages(ctx, user_id).await
+}
+
+pub async fn page_star_count(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<u64> {
+    let page_id: i64 = params.one()?;
+    RelationService::count_page_stars(ctx, page_id).await
+}
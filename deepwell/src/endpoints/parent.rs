@@ -22,7 +22,8 @@ use super::prelude::*;
 use crate::models::page_parent::Model as PageParentModel;
 use crate::services::page::GetPageReference;
 use crate::services::parent::{
-    GetParentRelationships, ParentDescription, RemoveParentOutput, UpdateParents,
+    GetPageHierarchy, GetParentRelationships, MoveSubtree, MoveSubtreeOutput,
+    PageAncestor, PageDescendant, ParentDescription, RemoveParentOutput, UpdateParents,
     UpdateParentsOutput,
 };
 use crate::types::Reference;
@@ -179,3 +180,33 @@ pub async fn parent_update(
         removed: removal,
     })
 }
+
+pub async fn parent_ancestors_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageAncestor>> {
+    let input: GetPageHierarchy = params.parse()?;
+    ParentService::get_ancestors(ctx, input).await
+}
+
+pub async fn parent_descendants_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageDescendant>> {
+    let input: GetPageHierarchy = params.parse()?;
+    ParentService::get_descendants(ctx, input).await
+}
+
+pub async fn parent_move_subtree(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<MoveSubtreeOutput> {
+    let input: MoveSubtree = params.parse()?;
+
+    info!(
+        "Moving subtree of page {:?} to parent {:?} in site ID {}",
+        input.page, input.new_parent, input.site_id,
+    );
+
+    ParentService::move_subtree(ctx, input).await
+}
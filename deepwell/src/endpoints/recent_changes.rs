@@ -0,0 +1,40 @@
+/*
+ * endpoints/recent_changes.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::recent_changes::{
+    GetRecentChanges, GetRecentChangesFeed, GetRecentChangesOutput,
+};
+
+pub async fn recent_changes(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetRecentChangesOutput> {
+    let input: GetRecentChanges = params.parse()?;
+    RecentChangesService::get(ctx, input).await
+}
+
+pub async fn recent_changes_feed(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<String> {
+    let input: GetRecentChangesFeed = params.parse()?;
+    RecentChangesService::get_feed(ctx, input).await
+}
@@ -0,0 +1,65 @@
+/*
+ * endpoints/redirect.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_redirect::Model as PageRedirectModel;
+use crate::services::redirect::{
+    BacklinkRewrite, GetPageRedirect, GetPageRedirects, RewriteBacklinks,
+};
+
+pub async fn redirect_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<PageRedirectModel>> {
+    let GetPageRedirect { site_id, slug } = params.parse()?;
+    RedirectService::get_optional(ctx, site_id, &slug).await
+}
+
+pub async fn redirect_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageRedirectModel>> {
+    let input: GetPageRedirects = params.parse()?;
+    RedirectService::get_all(ctx, input).await
+}
+
+pub async fn redirect_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: GetPageRedirect = params.parse()?;
+    RedirectService::delete(ctx, input).await
+}
+
+pub async fn backlink_rewrite_preview(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<BacklinkRewrite>> {
+    let input: RewriteBacklinks = params.parse()?;
+    RedirectService::preview_backlinks(ctx, input).await
+}
+
+pub async fn backlink_rewrite(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: RewriteBacklinks = params.parse()?;
+    RedirectService::queue_rewrite_backlinks(ctx, input).await
+}
@@ -21,8 +21,14 @@
 use super::prelude::*;
 use crate::models::sea_orm_active_enums::AliasType;
 use crate::models::site::Model as SiteModel;
+use crate::models::site_export::Model as SiteExportModel;
+use crate::models::site_import::Model as SiteImportModel;
 use crate::services::site::{
-    CreateSite, CreateSiteOutput, GetSite, GetSiteOutput, UpdateSite,
+    CloneSite, CloneSiteOutput, CreateSite, CreateSiteOutput, DeleteSite, GetSite,
+    GetSiteOutput, RestoreSite, UpdateSite,
+};
+use crate::services::site_archive::{
+    GetSiteExport, GetSiteImport, SiteExport, SiteExportOutput, SiteImport,
 };
 
 pub async fn site_create(
@@ -69,3 +75,60 @@ pub async fn site_update(
     info!("Updating site {:?}", site);
     SiteService::update(ctx, site, body, user_id).await
 }
+
+pub async fn site_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteModel> {
+    let DeleteSite { site, user_id } = params.parse()?;
+    info!("Deleting site {:?}", site);
+    SiteService::delete(ctx, site, user_id).await
+}
+
+pub async fn site_restore(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteModel> {
+    let input: RestoreSite = params.parse()?;
+    SiteService::restore(ctx, input).await
+}
+
+pub async fn site_clone(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<CloneSiteOutput> {
+    let input: CloneSite = params.parse()?;
+    SiteService::clone_from_template(ctx, input).await
+}
+
+pub async fn site_export(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteExportModel> {
+    let input: SiteExport = params.parse()?;
+    SiteArchiveService::export(ctx, input).await
+}
+
+pub async fn site_export_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<SiteExportOutput>> {
+    let GetSiteExport { export_id } = params.parse()?;
+    SiteArchiveService::get_export_optional(ctx, export_id).await
+}
+
+pub async fn site_import(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteImportModel> {
+    let input: SiteImport = params.parse()?;
+    SiteArchiveService::import(ctx, input).await
+}
+
+pub async fn site_import_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<SiteImportModel>> {
+    let GetSiteImport { import_id } = params.parse()?;
+    SiteArchiveService::get_import_optional(ctx, import_id).await
+}
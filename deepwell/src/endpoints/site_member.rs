@@ -19,8 +19,15 @@
  */
 
 use super::prelude::*;
+use crate::models::audit_log::Model as AuditLogModel;
 use crate::models::relation::Model as RelationModel;
-use crate::services::relation::{CreateSiteMember, GetSiteMember, RemoveSiteMember};
+use crate::services::audit::GetAuditLog;
+use crate::services::relation::{
+    CreateSiteApplication, CreateSiteMember, GetSiteApplication, GetSiteApplications,
+    GetSiteInvitation, GetSiteInvitations, GetSiteMember, GetUserSiteInvitations,
+    JoinSite, RemoveSiteInvitation, RemoveSiteMember, RespondSiteApplication,
+    SendSiteInvitation,
+};
 
 pub async fn membership_get(
     ctx: &ServiceContext<'_>,
@@ -45,3 +52,123 @@ pub async fn membership_delete(
     let input: RemoveSiteMember = params.parse()?;
     RelationService::remove_site_member(ctx, input).await
 }
+
+pub async fn membership_join(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: JoinSite = params.parse()?;
+    RelationService::join_site(ctx, input).await
+}
+
+pub async fn application_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<RelationModel>> {
+    let input: GetSiteApplication = params.parse()?;
+    RelationService::get_optional_site_application(ctx, input).await
+}
+
+pub async fn application_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RelationModel>> {
+    let input: GetSiteApplications = params.parse()?;
+    RelationService::get_site_applications(ctx, input).await
+}
+
+pub async fn application_submit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: CreateSiteApplication = params.parse()?;
+    RelationService::create_site_application(ctx, input).await
+}
+
+pub async fn application_withdraw(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: GetSiteApplication = params.parse()?;
+    RelationService::withdraw_site_application(ctx, input).await
+}
+
+pub async fn application_accept(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: RespondSiteApplication = params.parse()?;
+    RelationService::accept_site_application(ctx, input).await
+}
+
+pub async fn application_decline(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: RespondSiteApplication = params.parse()?;
+    RelationService::decline_site_application(ctx, input).await
+}
+
+pub async fn invitation_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<RelationModel>> {
+    let input: GetSiteInvitation = params.parse()?;
+    RelationService::get_optional_site_invitation(ctx, input).await
+}
+
+pub async fn invitation_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RelationModel>> {
+    let input: GetSiteInvitations = params.parse()?;
+    RelationService::get_site_invitations(ctx, input).await
+}
+
+pub async fn invitation_list_user(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RelationModel>> {
+    let input: GetUserSiteInvitations = params.parse()?;
+    RelationService::get_user_site_invitations(ctx, input).await
+}
+
+pub async fn invitation_send(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<i64>> {
+    let input: SendSiteInvitation = params.parse()?;
+    RelationService::send_site_invitation(ctx, input).await
+}
+
+pub async fn invitation_accept(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: GetSiteInvitation = params.parse()?;
+    RelationService::accept_site_invitation(ctx, input).await
+}
+
+pub async fn invitation_decline(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: GetSiteInvitation = params.parse()?;
+    RelationService::decline_site_invitation(ctx, input).await
+}
+
+pub async fn invitation_revoke(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: RemoveSiteInvitation = params.parse()?;
+    RelationService::revoke_site_invitation(ctx, input).await
+}
+
+pub async fn audit_log_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<AuditLogModel>> {
+    let input: GetAuditLog = params.parse()?;
+    AuditService::get_site_log(ctx, input).await
+}
@@ -0,0 +1,156 @@
+/*
+ * endpoints/tag.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::site_tag::Model as SiteTagModel;
+use crate::models::site_tag_group::Model as SiteTagGroupModel;
+use crate::services::site::GetSite;
+use crate::services::tag::{
+    CreateSiteTag, CreateSiteTagGroup, GetSiteTag, GetSiteTagGroup, RenameSiteTag,
+    RenameSiteTagOutput, TagUsage, UpdateSiteTag, UpdateSiteTagGroup,
+};
+
+// Tags
+
+pub async fn tag_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagModel> {
+    let input: CreateSiteTag = params.parse()?;
+    TagService::create(ctx, input).await
+}
+
+pub async fn tag_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagModel> {
+    let GetSiteTag { site_id, tag_id } = params.parse()?;
+    TagService::get(ctx, site_id, tag_id).await
+}
+
+pub async fn tag_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<SiteTagModel>> {
+    let GetSite { site } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Getting all tags in site ID {site_id}");
+    TagService::get_all(ctx, site_id).await
+}
+
+pub async fn tag_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagModel> {
+    let UpdateSiteTag {
+        site_id,
+        tag_id,
+        body,
+    } = params.parse()?;
+
+    info!("Updating tag ID {tag_id} in site ID {site_id}");
+    TagService::update(ctx, site_id, tag_id, body).await
+}
+
+pub async fn tag_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagModel> {
+    let GetSiteTag { site_id, tag_id } = params.parse()?;
+    info!("Deleting tag ID {tag_id} in site ID {site_id}");
+    TagService::delete(ctx, site_id, tag_id).await
+}
+
+pub async fn tag_rename(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RenameSiteTagOutput> {
+    let input: RenameSiteTag = params.parse()?;
+    TagService::rename(ctx, input).await
+}
+
+pub async fn tag_usage(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<TagUsage>> {
+    let GetSite { site } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Getting tag usage counts in site ID {site_id}");
+    TagService::get_usage(ctx, site_id).await
+}
+
+// Tag groups
+
+pub async fn tag_group_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagGroupModel> {
+    let input: CreateSiteTagGroup = params.parse()?;
+    TagService::create_group(ctx, input).await
+}
+
+pub async fn tag_group_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagGroupModel> {
+    let GetSiteTagGroup {
+        site_id,
+        tag_group_id,
+    } = params.parse()?;
+
+    TagService::get_group(ctx, site_id, tag_group_id).await
+}
+
+pub async fn tag_group_get_all(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<SiteTagGroupModel>> {
+    let GetSite { site } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Getting all tag groups in site ID {site_id}");
+    TagService::get_all_groups(ctx, site_id).await
+}
+
+pub async fn tag_group_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagGroupModel> {
+    let UpdateSiteTagGroup {
+        site_id,
+        tag_group_id,
+        body,
+    } = params.parse()?;
+
+    info!("Updating tag group ID {tag_group_id} in site ID {site_id}");
+    TagService::update_group(ctx, site_id, tag_group_id, body).await
+}
+
+pub async fn tag_group_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagGroupModel> {
+    let GetSiteTagGroup {
+        site_id,
+        tag_group_id,
+    } = params.parse()?;
+
+    info!("Deleting tag group ID {tag_group_id} in site ID {site_id}");
+    TagService::delete_group(ctx, site_id, tag_group_id).await
+}
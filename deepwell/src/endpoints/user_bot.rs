@@ -57,6 +57,7 @@ pub async fn bot_user_create(
             email,
             locales,
             password: String::new(), // TODO configure user-bot password
+            ip_address: None,
             bypass_filter,
             bypass_email_verification,
         },
@@ -0,0 +1,77 @@
+/*
+ * endpoints/user_follow.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::relation::Model as RelationModel;
+use crate::services::relation::{
+    CreateUserFollow, GetUserFollow, GetUserFollows, RemoveUserFollow,
+};
+
+pub async fn user_follow_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<RelationModel>> {
+    let input: GetUserFollow = params.parse()?;
+    RelationService::get_optional_user_follow(ctx, input).await
+}
+
+pub async fn user_follow_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: CreateUserFollow = params.parse()?;
+
+    info!(
+        "Adding follow by user ID {} of user ID {}",
+        input.following_user, input.followed_user,
+    );
+
+    RelationService::create_user_follow(ctx, input).await
+}
+
+pub async fn user_follow_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: RemoveUserFollow = params.parse()?;
+
+    info!(
+        "Removing follow by user ID {} of user ID {}",
+        input.following_user, input.followed_user,
+    );
+
+    RelationService::remove_user_follow(ctx, input).await
+}
+
+pub async fn user_followers_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RelationModel>> {
+    let input: GetUserFollows = params.parse()?;
+    RelationService::get_user_followers(ctx, input).await
+}
+
+pub async fn user_following_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RelationModel>> {
+    let input: GetUserFollows = params.parse()?;
+    RelationService::get_user_following(ctx, input).await
+}
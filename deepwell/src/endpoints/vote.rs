@@ -19,10 +19,18 @@
  */
 
 use super::prelude::*;
+use crate::models::page_score_snapshot::Model as PageScoreSnapshotModel;
 use crate::models::page_vote::Model as PageVoteModel;
+use crate::models::vote_fraud_report::Model as VoteFraudReportModel;
+use crate::services::score::{
+    DailyVoteCount, GetDailyVotes, GetScoreDrops, GetScoreTimeline, ScoreDrop,
+};
 use crate::services::vote::{
     CountVoteHistory, CreateVote, GetVote, GetVoteHistory, VoteAction,
 };
+use crate::services::vote_fraud::{
+    GetVoteFraudReport, GetVoteFraudReports, ReviewVoteFraudReport,
+};
 
 pub async fn vote_get(
     ctx: &ServiceContext<'_>,
@@ -96,3 +104,51 @@ pub async fn vote_list_count(
     let input: CountVoteHistory = params.parse()?;
     VoteService::count_history(ctx, input).await
 }
+
+pub async fn vote_fraud_report_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<VoteFraudReportModel>> {
+    let GetVoteFraudReport { site_id, report_id } = params.parse()?;
+    VoteFraudService::get_optional(ctx, site_id, report_id).await
+}
+
+pub async fn vote_fraud_report_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<VoteFraudReportModel>> {
+    let input: GetVoteFraudReports = params.parse()?;
+    VoteFraudService::get_all(ctx, input).await
+}
+
+pub async fn vote_fraud_report_review(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<VoteFraudReportModel> {
+    let input: ReviewVoteFraudReport = params.parse()?;
+    VoteFraudService::review(ctx, input).await
+}
+
+pub async fn score_timeline(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageScoreSnapshotModel>> {
+    let input: GetScoreTimeline = params.parse()?;
+    ScoreService::get_timeline(ctx, input).await
+}
+
+pub async fn score_daily_votes(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<DailyVoteCount>> {
+    let input: GetDailyVotes = params.parse()?;
+    ScoreService::get_daily_votes(ctx, input).await
+}
+
+pub async fn score_drops(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ScoreDrop>> {
+    let input: GetScoreDrops = params.parse()?;
+    ScoreService::get_drops(ctx, input).await
+}
@@ -50,6 +50,7 @@ mod endpoints;
 mod hash;
 mod info;
 mod locales;
+mod metrics;
 mod redis;
 mod services;
 mod types;
@@ -0,0 +1,115 @@
+/*
+ * metrics/handler.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! HTTP handler serving `/metrics`, on the metrics listener.
+
+use super::Metrics;
+use crate::api::ServerState;
+use http::header::CONTENT_TYPE;
+use http::{Method, Response, StatusCode};
+use jsonrpsee::core::BoxError;
+use jsonrpsee::server::{HttpBody, HttpRequest, HttpResponse};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// The route metrics are served under.
+pub const METRICS_ROUTE: &str = "/metrics";
+
+/// The content type of the Prometheus text exposition format.
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Layer which serves metrics to `GET /metrics`.
+///
+/// Any other request is answered with 404, since this is only
+/// used on the metrics listener and not the JSON-RPC server.
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    state: ServerState,
+}
+
+impl MetricsLayer {
+    #[inline]
+    pub fn new(state: &ServerState) -> Self {
+        MetricsLayer {
+            state: Arc::clone(state),
+        }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    state: ServerState,
+}
+
+impl<S> Service<HttpRequest> for MetricsService<S>
+where
+    S: Service<HttpRequest, Response = HttpResponse, Error = BoxError>,
+    S::Future: Send + 'static,
+{
+    type Response = HttpResponse;
+    type Error = BoxError;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: HttpRequest) -> Self::Future {
+        if request.uri().path() != METRICS_ROUTE || request.method() != Method::GET {
+            return Box::pin(async move {
+                let response = Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(HttpBody::empty())
+                    .expect("Unable to build HTTP response");
+
+                Ok(response)
+            });
+        }
+
+        let state = Arc::clone(&self.state);
+        Box::pin(async move {
+            let body = Metrics::render(&state).await;
+            let response = Response::builder()
+                .status(StatusCode::OK)
+                .header(CONTENT_TYPE, METRICS_CONTENT_TYPE)
+                .body(HttpBody::from(body))
+                .expect("Unable to build HTTP response");
+
+            Ok(response)
+        })
+    }
+}
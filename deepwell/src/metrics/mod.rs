@@ -0,0 +1,278 @@
+/*
+ * metrics/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Collection of runtime metrics, exposed in the Prometheus text format.
+//!
+//! Metrics are recorded in-process as requests and jobs are handled, and
+//! rendered when `/metrics` is scraped. Some values, such as the job queue
+//! depth, are instead read from their source at scrape time.
+//!
+//! Metrics are only served on the separate `server.metrics-address` listener,
+//! if one is configured, and never on the public API address.
+
+mod handler;
+
+pub use self::handler::MetricsLayer;
+
+use crate::api::ServerStateInner;
+use crate::services::job::JOB_QUEUE_NAME;
+use crate::services::Error;
+use prometheus::{
+    Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use rsmq_async::PooledRsmq;
+use rsmq_async::RsmqConnection;
+use std::time::Duration;
+
+/// Upper bounds for latency histograms, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Upper bounds for blob size histograms, in bytes.
+const SIZE_BUCKETS: &[f64] = &[
+    1024.0,       // 1 KiB
+    16384.0,      // 16 KiB
+    262144.0,     // 256 KiB
+    1048576.0,    // 1 MiB
+    4194304.0,    // 4 MiB
+    16777216.0,   // 16 MiB
+    67108864.0,   // 64 MiB
+    268435456.0,  // 256 MiB
+    1073741824.0, // 1 GiB
+];
+
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Registry,
+    rpc_requests: IntCounterVec,
+    rpc_errors: IntCounterVec,
+    rpc_duration: HistogramVec,
+    jobs: IntCounterVec,
+    render_duration: Histogram,
+    render_timeouts: IntCounter,
+    blob_upload_size: Histogram,
+    page_view_cache: IntCounterVec,
+    job_queue_messages: IntGaugeVec,
+    database_connections: IntGaugeVec,
+    database_connections_max: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        macro_rules! register {
+            ($metric:expr $(,)?) => {{
+                let metric = $metric.expect("Invalid metric definition");
+                registry
+                    .register(Box::new(metric.clone()))
+                    .expect("Unable to register metric");
+                metric
+            }};
+        }
+
+        Metrics {
+            rpc_requests: register!(IntCounterVec::new(
+                Opts::new(
+                    "deepwell_rpc_requests_total",
+                    "Number of JSON-RPC requests handled, by method.",
+                ),
+                &["method"],
+            )),
+            rpc_errors: register!(IntCounterVec::new(
+                Opts::new(
+                    "deepwell_rpc_errors_total",
+                    "Number of JSON-RPC requests which failed, by method and error.",
+                ),
+                &["method", "error"],
+            )),
+            rpc_duration: register!(HistogramVec::new(
+                HistogramOpts::new(
+                    "deepwell_rpc_duration_seconds",
+                    "Time taken to handle JSON-RPC requests, by method.",
+                )
+                .buckets(LATENCY_BUCKETS.to_vec()),
+                &["method"],
+            )),
+            jobs: register!(IntCounterVec::new(
+                Opts::new(
+                    "deepwell_jobs_total",
+                    "Number of jobs processed by workers, by job and outcome.",
+                ),
+                &["job", "outcome"],
+            )),
+            render_duration: register!(Histogram::with_opts(
+                HistogramOpts::new(
+                    "deepwell_render_duration_seconds",
+                    "Time taken to render wikitext.",
+                )
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            )),
+            render_timeouts: register!(IntCounter::new(
+                "deepwell_render_timeouts_total",
+                "Number of wikitext renders which timed out.",
+            )),
+            blob_upload_size: register!(Histogram::with_opts(
+                HistogramOpts::new(
+                    "deepwell_blob_upload_bytes",
+                    "Size of blobs uploaded by users.",
+                )
+                .buckets(SIZE_BUCKETS.to_vec()),
+            )),
+            page_view_cache: register!(IntCounterVec::new(
+                Opts::new(
+                    "deepwell_page_view_cache_total",
+                    "Number of page view cache lookups, by whether they hit or missed.",
+                ),
+                &["result"],
+            )),
+            job_queue_messages: register!(IntGaugeVec::new(
+                Opts::new(
+                    "deepwell_job_queue_messages",
+                    "Number of jobs in the queue, by whether they are currently hidden.",
+                ),
+                &["hidden"],
+            )),
+            database_connections: register!(IntGaugeVec::new(
+                Opts::new(
+                    "deepwell_database_connections",
+                    "Number of open database connections, by whether they are in use.",
+                ),
+                &["state"],
+            )),
+            database_connections_max: register!(IntGauge::new(
+                "deepwell_database_connections_max",
+                "Maximum number of database connections in the pool.",
+            )),
+            registry,
+        }
+    }
+
+    /// Records the handling of a JSON-RPC method call.
+    pub fn record_rpc(
+        &self,
+        method: &'static str,
+        elapsed: Duration,
+        error: Option<&Error>,
+    ) {
+        self.rpc_requests.with_label_values(&[method]).inc();
+        self.rpc_duration
+            .with_label_values(&[method])
+            .observe(elapsed.as_secs_f64());
+
+        if let Some(error) = error {
+            let error: &'static str = error.into();
+            self.rpc_errors.with_label_values(&[method, error]).inc();
+        }
+    }
+
+    /// Records the result of a job run by a `JobWorker`.
+    pub fn record_job(&self, job: &'static str, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.jobs.with_label_values(&[job, outcome]).inc();
+    }
+
+    pub fn record_render(&self, elapsed: Duration) {
+        self.render_duration.observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_render_timeout(&self) {
+        self.render_timeouts.inc();
+    }
+
+    pub fn record_blob_upload(&self, size: i64) {
+        self.blob_upload_size.observe(size as f64);
+    }
+
+    pub fn record_page_view_cache(&self, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.page_view_cache.with_label_values(&[result]).inc();
+    }
+
+    /// Renders all metrics in the Prometheus text format.
+    pub async fn render(state: &ServerStateInner) -> String {
+        let metrics = &state.metrics;
+
+        // Job queue
+        let mut rsmq = PooledRsmq::clone(&state.rsmq);
+        match rsmq.get_queue_attributes(JOB_QUEUE_NAME).await {
+            Ok(attributes) => {
+                let hidden = i64::try_from(attributes.hiddenmsgs).unwrap_or(i64::MAX);
+                let total = i64::try_from(attributes.msgs).unwrap_or(i64::MAX);
+
+                metrics
+                    .job_queue_messages
+                    .with_label_values(&["false"])
+                    .set(total.saturating_sub(hidden));
+
+                metrics
+                    .job_queue_messages
+                    .with_label_values(&["true"])
+                    .set(hidden);
+            }
+            Err(error) => {
+                warn!("Unable to get job queue attributes for metrics: {error}")
+            }
+        }
+
+        // Database connection pool
+        let pool = state.database.get_postgres_connection_pool();
+        let size = i64::from(pool.size());
+        let idle = i64::try_from(pool.num_idle()).unwrap_or(i64::MAX);
+
+        metrics
+            .database_connections
+            .with_label_values(&["active"])
+            .set(size.saturating_sub(idle));
+
+        metrics
+            .database_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+
+        metrics
+            .database_connections_max
+            .set(i64::from(pool.options().get_max_connections()));
+
+        let families = metrics.registry.gather();
+        TextEncoder::new()
+            .encode_to_string(&families)
+            .unwrap_or_else(|error| {
+                error!("Unable to encode metrics: {error}");
+                String::new()
+            })
+    }
+}
+
+#[test]
+fn metrics_registered() {
+    let metrics = Metrics::new();
+    metrics.record_rpc("ping", Duration::from_millis(5), None);
+    metrics.record_render_timeout();
+
+    let output = TextEncoder::new()
+        .encode_to_string(&metrics.registry.gather())
+        .expect("Unable to encode metrics");
+
+    assert!(output.contains(r#"deepwell_rpc_requests_total{method="ping"} 1"#));
+    assert!(output.contains("deepwell_render_timeouts_total 1"));
+}
@@ -3,31 +3,47 @@
 pub mod prelude;
 
 pub mod alias;
+pub mod audit_log;
 pub mod blob_blacklist;
+pub mod blob_derivative;
 pub mod blob_pending;
 pub mod file;
 pub mod file_revision;
 pub mod filter;
+pub mod import_progress;
+pub mod login_attempt;
 pub mod message;
 pub mod message_draft;
 pub mod message_recipient;
 pub mod message_record;
 pub mod message_report;
+pub mod notification;
+pub mod notification_preference;
 pub mod page;
 pub mod page_attribution;
+pub mod page_bulk_operation;
 pub mod page_category;
 pub mod page_connection;
 pub mod page_connection_missing;
+pub mod page_draft;
 pub mod page_link;
 pub mod page_lock;
 pub mod page_parent;
+pub mod page_redirect;
 pub mod page_revision;
+pub mod page_score_snapshot;
 pub mod page_vote;
 pub mod relation;
 pub mod sea_orm_active_enums;
 pub mod session;
 pub mod site;
 pub mod site_domain;
+pub mod site_export;
+pub mod site_import;
+pub mod site_tag;
+pub mod site_tag_group;
 pub mod text;
 pub mod user;
 pub mod user_bot_owner;
+pub mod user_webauthn_credential;
+pub mod vote_fraud_report;
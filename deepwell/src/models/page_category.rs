@@ -12,11 +12,20 @@ pub struct Model {
     pub created_at: TimeDateTimeWithTimeZone,
     #[serde(with = "time::serde::rfc3339::option")]
     pub updated_at: Option<TimeDateTimeWithTimeZone>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<TimeDateTimeWithTimeZone>,
     pub site_id: i64,
     #[sea_orm(column_type = "Text")]
     pub slug: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub layout: Option<String>,
+    pub default_license: Option<Json>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub scorer: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub template_slug: Option<String>,
+    pub use_templates: bool,
+    pub permissions: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,29 +1,45 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
 pub use super::alias::Entity as Alias;
+pub use super::audit_log::Entity as AuditLog;
+pub use super::blob_derivative::Entity as BlobDerivative;
 pub use super::blob_pending::Entity as BlobPending;
 pub use super::file::Entity as File;
 pub use super::file_revision::Entity as FileRevision;
 pub use super::filter::Entity as Filter;
+pub use super::import_progress::Entity as ImportProgress;
+pub use super::login_attempt::Entity as LoginAttempt;
 pub use super::message::Entity as Message;
 pub use super::message_draft::Entity as MessageDraft;
 pub use super::message_recipient::Entity as MessageRecipient;
 pub use super::message_record::Entity as MessageRecord;
 pub use super::message_report::Entity as MessageReport;
+pub use super::notification::Entity as Notification;
+pub use super::notification_preference::Entity as NotificationPreference;
 pub use super::page::Entity as Page;
 pub use super::page_attribution::Entity as PageAttribution;
+pub use super::page_bulk_operation::Entity as PageBulkOperation;
 pub use super::page_category::Entity as PageCategory;
 pub use super::page_connection::Entity as PageConnection;
 pub use super::page_connection_missing::Entity as PageConnectionMissing;
+pub use super::page_draft::Entity as PageDraft;
 pub use super::page_link::Entity as PageLink;
 pub use super::page_lock::Entity as PageLock;
 pub use super::page_parent::Entity as PageParent;
+pub use super::page_redirect::Entity as PageRedirect;
 pub use super::page_revision::Entity as PageRevision;
+pub use super::page_score_snapshot::Entity as PageScoreSnapshot;
 pub use super::page_vote::Entity as PageVote;
 pub use super::relation::Entity as Relation;
 pub use super::session::Entity as Session;
 pub use super::site::Entity as Site;
 pub use super::site_domain::Entity as SiteDomain;
+pub use super::site_export::Entity as SiteExport;
+pub use super::site_import::Entity as SiteImport;
+pub use super::site_tag::Entity as SiteTag;
+pub use super::site_tag_group::Entity as SiteTagGroup;
 pub use super::text::Entity as Text;
 pub use super::user::Entity as User;
 pub use super::user_bot_owner::Entity as UserBotOwner;
+pub use super::user_webauthn_credential::Entity as UserWebauthnCredential;
+pub use super::vote_fraud_report::Entity as VoteFraudReport;
@@ -36,6 +36,40 @@ pub enum FileRevisionType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "import_stage")]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportStage {
+    #[sea_orm(string_value = "done")]
+    Done,
+    #[sea_orm(string_value = "files")]
+    Files,
+    #[sea_orm(string_value = "pages")]
+    Pages,
+    #[sea_orm(string_value = "site")]
+    Site,
+    #[sea_orm(string_value = "users")]
+    Users,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "login_attempt_result"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum LoginAttemptResult {
+    #[sea_orm(string_value = "bad_mfa")]
+    BadMfa,
+    #[sea_orm(string_value = "bad_password")]
+    BadPassword,
+    #[sea_orm(string_value = "success")]
+    Success,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(
     rs_type = "String",
     db_type = "Enum",
@@ -53,6 +87,29 @@ pub enum MessageRecipientType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "notification_type")]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationType {
+    #[sea_orm(string_value = "message")]
+    Message,
+    #[sea_orm(string_value = "page_create")]
+    PageCreate,
+    #[sea_orm(string_value = "page_delete")]
+    PageDelete,
+    #[sea_orm(string_value = "page_edit")]
+    PageEdit,
+    #[sea_orm(string_value = "page_move")]
+    PageMove,
+    #[sea_orm(string_value = "site_application_accepted")]
+    SiteApplicationAccepted,
+    #[sea_orm(string_value = "site_application_declined")]
+    SiteApplicationDeclined,
+    #[sea_orm(string_value = "site_invitation")]
+    SiteInvitation,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "page_revision_type")]
 #[serde(rename_all = "kebab-case")]
 pub enum PageRevisionType {
@@ -93,6 +150,21 @@ pub enum RelationObjectType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "site_join_policy")]
+#[serde(rename_all = "kebab-case")]
+pub enum SiteJoinPolicy {
+    #[sea_orm(string_value = "application")]
+    Application,
+    #[sea_orm(string_value = "invite")]
+    Invite,
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "password")]
+    Password,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_type")]
 #[serde(rename_all = "kebab-case")]
 pub enum UserType {
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
+use super::sea_orm_active_enums::SiteJoinPolicy;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -31,6 +32,10 @@ pub struct Model {
     pub custom_domain: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub layout: Option<String>,
+    pub default_license: Option<Json>,
+    pub join_policy: SiteJoinPolicy,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub join_password: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,92 @@
+/*
+ * services/audit/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::audit_log::{self, Entity as AuditLog, Model as AuditLogModel};
+use serde::Serialize;
+
+#[derive(Debug)]
+pub struct AuditService;
+
+impl AuditService {
+    /// Records an action in the audit log.
+    ///
+    /// The `user_id` is the user affected by the action, if any,
+    /// which may or may not be the same as the actor.
+    pub async fn record<D: Serialize>(
+        ctx: &ServiceContext<'_>,
+        site_id: Option<i64>,
+        actor_id: i64,
+        user_id: Option<i64>,
+        event: AuditEvent,
+        data: &D,
+    ) -> Result<()> {
+        info!(
+            "Recording audit event {event:?} by user ID {actor_id} (site ID {site_id:?}, user ID {user_id:?})",
+        );
+
+        let txn = ctx.transaction();
+        let model = audit_log::ActiveModel {
+            site_id: Set(site_id),
+            actor_id: Set(actor_id),
+            user_id: Set(user_id),
+            event: Set(str!(event.value())),
+            data: Set(serde_json::to_value(data)?),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
+        Ok(())
+    }
+
+    /// Gets the audit log for a site, newest first.
+    ///
+    /// The `start_id` argument gives the audit ID to search from, exclusive.
+    /// If `0`, then means "everything".
+    pub async fn get_site_log(
+        ctx: &ServiceContext<'_>,
+        GetAuditLog {
+            site_id,
+            start_id,
+            limit,
+        }: GetAuditLog,
+    ) -> Result<Vec<AuditLogModel>> {
+        info!("Getting audit log for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let start_condition = if start_id > 0 {
+            Some(audit_log::Column::AuditId.lt(start_id))
+        } else {
+            None
+        };
+
+        let entries = AuditLog::find()
+            .filter(
+                Condition::all()
+                    .add(audit_log::Column::SiteId.eq(site_id))
+                    .add_option(start_condition),
+            )
+            .order_by_desc(audit_log::Column::AuditId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * services/audit/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// The kind of action recorded in an audit log entry.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditEvent {
+    MemberJoin,
+    MemberRemove,
+    ApplicationSubmit,
+    ApplicationWithdraw,
+    ApplicationAccept,
+    ApplicationDecline,
+    InvitationSend,
+    InvitationAccept,
+    InvitationDecline,
+    InvitationRevoke,
+    EditSessionRelease,
+    SiteDelete,
+    SiteRestore,
+}
+
+impl AuditEvent {
+    /// Get the constant string value used to represent this event in the database.
+    pub fn value(self) -> &'static str {
+        match self {
+            AuditEvent::MemberJoin => "member-join",
+            AuditEvent::MemberRemove => "member-remove",
+            AuditEvent::ApplicationSubmit => "application-submit",
+            AuditEvent::ApplicationWithdraw => "application-withdraw",
+            AuditEvent::ApplicationAccept => "application-accept",
+            AuditEvent::ApplicationDecline => "application-decline",
+            AuditEvent::InvitationSend => "invitation-send",
+            AuditEvent::InvitationAccept => "invitation-accept",
+            AuditEvent::InvitationDecline => "invitation-decline",
+            AuditEvent::InvitationRevoke => "invitation-revoke",
+            AuditEvent::EditSessionRelease => "edit-session-release",
+            AuditEvent::SiteDelete => "site-delete",
+            AuditEvent::SiteRestore => "site-restore",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetAuditLog {
+    pub site_id: i64,
+    pub start_id: i64,
+    pub limit: u64,
+}
+
+#[test]
+fn audit_event_values() {
+    macro_rules! check {
+        ($event:expr) => {{
+            let value = serde_json::to_value($event).expect("Unable to serialize event");
+            assert_eq!(value, $event.value(), "Event value mismatch");
+        }};
+    }
+
+    check!(AuditEvent::MemberJoin);
+    check!(AuditEvent::MemberRemove);
+    check!(AuditEvent::ApplicationSubmit);
+    check!(AuditEvent::ApplicationWithdraw);
+    check!(AuditEvent::ApplicationAccept);
+    check!(AuditEvent::ApplicationDecline);
+    check!(AuditEvent::InvitationSend);
+    check!(AuditEvent::InvitationAccept);
+    check!(AuditEvent::InvitationDecline);
+    check!(AuditEvent::InvitationRevoke);
+    check!(AuditEvent::EditSessionRelease);
+    check!(AuditEvent::SiteDelete);
+    check!(AuditEvent::SiteRestore);
+}
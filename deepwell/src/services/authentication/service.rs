@@ -19,8 +19,15 @@
  */
 
 use super::prelude::*;
+use crate::models::sea_orm_active_enums::LoginAttemptResult;
 use crate::models::user::{self, Entity as User, Model as UserModel};
-use crate::services::{MfaService, PasswordService, SessionService};
+use crate::services::login_attempt::CreateLoginAttempt;
+use crate::services::webauthn::WebauthnAssertion;
+use crate::services::{
+    LoginAttemptService, MfaService, PasswordService, SessionService, UserService,
+    WebauthnService,
+};
+use std::net::IpAddr;
 
 #[derive(Debug)]
 pub struct AuthenticationService;
@@ -28,15 +35,42 @@ pub struct AuthenticationService;
 impl AuthenticationService {
     /// Verifies the passed credentials for a user.
     /// If so, they are cleared to log in (or perform some other sensitive action).
+    ///
+    /// Failed attempts for users which exist are recorded in their login history.
     pub async fn auth_password(
         ctx: &ServiceContext<'_>,
-        AuthenticateUser {
-            name_or_email,
-            password,
-        }: AuthenticateUser,
+        LoginUser {
+            authenticate:
+                AuthenticateUser {
+                    name_or_email,
+                    password,
+                },
+            ip_address,
+            user_agent,
+        }: &LoginUser,
     ) -> Result<AuthenticateUserOutput> {
-        let auth = Self::get_user_auth(ctx, &name_or_email).await?;
-        PasswordService::verify(ctx, &password, &auth.password_hash).await?;
+        let auth = Self::get_user_auth(ctx, name_or_email).await?;
+        if let Err(error) =
+            PasswordService::verify(ctx, password, &auth.password_hash).await
+        {
+            if auth.valid {
+                Self::record_failure(
+                    ctx,
+                    auth.user_id,
+                    LoginAttemptResult::BadPassword,
+                    *ip_address,
+                    user_agent,
+                )
+                .await?;
+            }
+
+            return Err(error);
+        }
+
+        // Either a TOTP or a WebAuthn credential can serve as the second factor.
+        //
+        // This is checked even for the dummy user to keep timing consistent.
+        let webauthn_count = WebauthnService::count(ctx, auth.user_id).await?;
 
         // User not found, return authentication failure
         if !auth.valid {
@@ -44,7 +78,7 @@ impl AuthenticationService {
         }
 
         Ok(AuthenticateUserOutput {
-            needs_mfa: auth.multi_factor_secret.is_some(),
+            needs_mfa: auth.multi_factor_secret.is_some() || webauthn_count > 0,
             user_id: auth.user_id,
         })
     }
@@ -58,6 +92,8 @@ impl AuthenticationService {
         MultiFactorAuthenticateUser {
             session_token,
             totp_or_code,
+            ip_address,
+            user_agent,
         }: MultiFactorAuthenticateUser<'_>,
     ) -> Result<UserModel> {
         // Get associated user model from the session
@@ -67,20 +103,137 @@ impl AuthenticationService {
         let user = SessionService::get_user(ctx, session_token, true).await?;
 
         // Process input, verifying depending on type
-        match totp_or_code.parse() {
+        let result = match totp_or_code.parse() {
             // If the value is a positive integer, treat it as a TOTP
-            Ok(totp) => MfaService::verify(ctx, &user, totp).await?,
+            Ok(totp) => MfaService::verify(ctx, &user, totp).await,
 
             // Otherwise treat it as a recovery code string
             //
             // We don't need to validate it for length because
             // we want consistent time checks on recovery codes anyways.
-            Err(_) => MfaService::verify_recovery(ctx, &user, totp_or_code).await?,
+            Err(_) => MfaService::verify_recovery(ctx, &user, totp_or_code).await,
+        };
+
+        if let Err(error) = result {
+            Self::record_failure(
+                ctx,
+                user.user_id,
+                LoginAttemptResult::BadMfa,
+                ip_address,
+                user_agent,
+            )
+            .await?;
+
+            return Err(error);
         }
 
         Ok(user)
     }
 
+    /// Verifies a WebAuthn assertion as the second factor, after they have logged in.
+    ///
+    /// # Returns
+    /// The user model for the authenticated session.
+    pub async fn auth_mfa_webauthn(
+        ctx: &ServiceContext<'_>,
+        MultiFactorWebauthnAuthenticateUser {
+            session_token,
+            assertion,
+            ip_address,
+            user_agent,
+        }: MultiFactorWebauthnAuthenticateUser<'_>,
+    ) -> Result<UserModel> {
+        // Like auth_mfa(), requires the session is restricted
+        let user = SessionService::get_user(ctx, session_token, true).await?;
+        let result =
+            WebauthnService::auth_finish(ctx, Some(user.user_id), assertion).await;
+
+        if let Err(error) = result {
+            Self::record_failure(
+                ctx,
+                user.user_id,
+                LoginAttemptResult::BadMfa,
+                ip_address,
+                user_agent,
+            )
+            .await?;
+
+            return Err(error);
+        }
+
+        Ok(user)
+    }
+
+    /// Verifies a WebAuthn assertion on its own, for passwordless login.
+    ///
+    /// User verification is required by the authenticator, so this
+    /// does not need a second factor. The owner of the credential
+    /// must still be able to log in, same as with `auth_password()`.
+    ///
+    /// # Returns
+    /// The ID of the user who owns the credential.
+    pub async fn auth_webauthn(
+        ctx: &ServiceContext<'_>,
+        assertion: WebauthnAssertion,
+    ) -> Result<i64> {
+        let user_id = WebauthnService::auth_finish(ctx, None, assertion).await?;
+        let user = UserService::get_optional(ctx, Reference::Id(user_id)).await?;
+        match user {
+            Some(user) if Self::can_log_in(&user) => Ok(user_id),
+            _ => {
+                error!("User ID {user_id} owning WebAuthn credential cannot log in");
+                Err(Error::InvalidAuthentication)
+            }
+        }
+    }
+
+    /// Records a successful login in the user's login history.
+    ///
+    /// This should only be called once the login is complete,
+    /// that is, when a non-restricted session is created.
+    pub async fn record_success(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        ip_address: IpAddr,
+        user_agent: &str,
+    ) -> Result<()> {
+        LoginAttemptService::record(
+            ctx,
+            CreateLoginAttempt {
+                user_id,
+                result: LoginAttemptResult::Success,
+                ip_address,
+                user_agent: user_agent.to_owned(),
+                created_at: now(),
+            },
+        )
+        .await
+    }
+
+    /// Records a failed login in the user's login history.
+    ///
+    /// Because the transaction is rolled back for failed logins, this goes
+    /// through the job queue. See `LoginAttemptService::queue()`.
+    async fn record_failure(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        result: LoginAttemptResult,
+        ip_address: IpAddr,
+        user_agent: &str,
+    ) -> Result<()> {
+        LoginAttemptService::queue(
+            ctx,
+            CreateLoginAttempt {
+                user_id,
+                result,
+                ip_address,
+                user_agent: user_agent.to_owned(),
+                created_at: now(),
+            },
+        )
+        .await
+    }
+
     /// Gets user information from the database, or return a dummy.
     ///
     /// To avoid timing attacks, all aspects of authentication (finding the user,
@@ -111,11 +264,19 @@ impl AuthenticationService {
 
         match result {
             // Found user, return real auth information
-            Some(user) => Ok(UserAuthInfo::valid(user)),
+            Some(user) if Self::can_log_in(&user) => Ok(UserAuthInfo::valid(user)),
 
-            // Didn't find user, return fake auth information
+            // Didn't find user, or they cannot log in, return fake auth information
             // Checking should proceed as normal to avoid timing attacks
-            None => Ok(UserAuthInfo::invalid()),
+            _ => Ok(UserAuthInfo::invalid()),
         }
     }
+
+    /// Determines whether this user is in a state where they may log in.
+    ///
+    /// This applies to every login method, so that none of them
+    /// can be used to get into an account the others would reject.
+    fn can_log_in(user: &UserModel) -> bool {
+        user.deleted_at.is_none()
+    }
 }
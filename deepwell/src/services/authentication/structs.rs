@@ -19,6 +19,7 @@
  */
 
 use crate::models::user::Model as UserModel;
+use crate::services::webauthn::WebauthnAssertion;
 use std::net::IpAddr;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -52,6 +53,16 @@ pub struct LoginUserOutput {
 pub struct MultiFactorAuthenticateUser<'a> {
     pub session_token: &'a str,
     pub totp_or_code: &'a str,
+    pub ip_address: IpAddr,
+    pub user_agent: &'a str,
+}
+
+#[derive(Debug, Clone)]
+pub struct MultiFactorWebauthnAuthenticateUser<'a> {
+    pub session_token: &'a str,
+    pub assertion: WebauthnAssertion,
+    pub ip_address: IpAddr,
+    pub user_agent: &'a str,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -62,6 +73,25 @@ pub struct LoginUserMfa {
     pub user_agent: String,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoginUserMfaWebauthn {
+    pub session_token: String,
+    pub ip_address: IpAddr,
+    pub user_agent: String,
+
+    #[serde(flatten)]
+    pub assertion: WebauthnAssertion,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoginUserWebauthn {
+    pub ip_address: IpAddr,
+    pub user_agent: String,
+
+    #[serde(flatten)]
+    pub assertion: WebauthnAssertion,
+}
+
 /// Password hash to compute against when a user does not exist.
 ///
 /// It has similar settings to other passwords on Wikijump, but
@@ -0,0 +1,480 @@
+/*
+ * services/blob/derivative.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Generation of derived images, such as thumbnails, from uploaded blobs.
+//!
+//! All of this is synchronous and CPU-bound, so callers should run it
+//! on a blocking thread rather than directly in async code.
+//!
+//! Derived images are produced by decoding and re-encoding the original,
+//! so none of its metadata is carried over. This includes EXIF location
+//! data, which cameras and phones frequently embed in photos.
+//!
+//! Originals have their location data removed when they are uploaded,
+//! see `strip_gps()`. This is done in place, without re-encoding.
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, ImageResult, Limits};
+use std::io::Cursor;
+use std::ops::Range;
+
+/// The largest image dimension, in pixels, which we will attempt to decode.
+const MAXIMUM_DIMENSION: u32 = 16384;
+
+/// The quality (out of 100) to use when encoding JPEG derivatives.
+const JPEG_QUALITY: u8 = 85;
+
+/// The size of the full-size derivative, which only has its metadata removed.
+pub const FULL_SIZE: u32 = 0;
+
+/// The EXIF tag which points to GPS information.
+const EXIF_GPS_TAG: u16 = 0x8825;
+
+#[derive(Debug)]
+pub struct Derivative {
+    /// The length of the longest side this derivative was generated for.
+    /// Or `FULL_SIZE` if it is not resized.
+    pub size: u32,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Determines if this MIME type is an image we can generate derivatives for.
+pub fn is_supported_image(mime: &str) -> bool {
+    // libmagic output includes parameters, e.g. "image/png; charset=binary"
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+
+    matches!(
+        essence,
+        "image/gif" | "image/jpeg" | "image/png" | "image/webp",
+    )
+}
+
+/// Decodes the given image and produces derivatives for it.
+///
+/// A thumbnail is generated for each size which is smaller than the original.
+/// If the original still has EXIF data with GPS information, for instance
+/// if it was uploaded before stripping was added, then a full-size copy
+/// is also generated, see `FULL_SIZE`.
+pub fn generate(data: &[u8], sizes: &[u32]) -> ImageResult<Vec<Derivative>> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAXIMUM_DIMENSION);
+    limits.max_image_height = Some(MAXIMUM_DIMENSION);
+
+    let mut reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder()?;
+    let has_gps = decoder
+        .exif_metadata()?
+        .as_deref()
+        .is_some_and(exif_has_gps);
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+
+    // Since the metadata is being discarded, apply any rotation now
+    image.apply_orientation(orientation);
+
+    let mut derivatives = Vec::new();
+    if has_gps {
+        derivatives.push(encode(&image, FULL_SIZE)?);
+    }
+
+    let longest_side = image.width().max(image.height());
+    for &size in sizes {
+        if size == FULL_SIZE || size >= longest_side {
+            continue;
+        }
+
+        let thumbnail = image.thumbnail(size, size);
+        derivatives.push(encode(&thumbnail, size)?);
+    }
+
+    Ok(derivatives)
+}
+
+/// Encodes the image, as a PNG if it has transparency and a JPEG otherwise.
+fn encode(image: &DynamicImage, size: u32) -> ImageResult<Derivative> {
+    let mut data = Vec::new();
+
+    if image.color().has_alpha() {
+        image.write_with_encoder(PngEncoder::new(&mut data))?;
+    } else {
+        let encoder = JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY);
+        DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)?;
+    }
+
+    Ok(Derivative {
+        size,
+        width: image.width(),
+        height: image.height(),
+        data,
+    })
+}
+
+/// Removes GPS information from an image's EXIF data, in place.
+///
+/// Only the location entries are blanked out, so the length of the image
+/// and all its other metadata (such as orientation) are preserved.
+/// Returns `true` if the image had GPS information which was removed.
+pub fn strip_gps(data: &mut [u8]) -> bool {
+    let Ok(format) = image::guess_format(data) else {
+        return false;
+    };
+
+    let Some(range) = find_exif(data, format) else {
+        return false;
+    };
+
+    if !exif_strip_gps(&mut data[range.clone()]) {
+        return false;
+    }
+
+    // PNG chunks have a checksum, which is over the chunk type and data
+    if format == ImageFormat::Png {
+        let chunk = range.start - 4..range.end;
+        let crc = crc32fast::hash(&data[chunk]).to_be_bytes();
+        data[range.end..range.end + 4].copy_from_slice(&crc);
+    }
+
+    true
+}
+
+/// Locates the EXIF data within an image file, returning its byte range.
+fn find_exif(data: &[u8], format: ImageFormat) -> Option<Range<usize>> {
+    let get_u16_be = |offset: usize| -> Option<usize> {
+        let bytes = data.get(offset..offset + 2)?;
+        Some(usize::from(u16::from_be_bytes(bytes.try_into().ok()?)))
+    };
+    let get_u32 = |offset: usize, from_bytes: fn([u8; 4]) -> u32| -> Option<usize> {
+        let bytes = data.get(offset..offset + 4)?;
+        usize::try_from(from_bytes(bytes.try_into().ok()?)).ok()
+    };
+
+    match format {
+        // Segments of: 0xFF, marker, length (including itself), data
+        ImageFormat::Jpeg => {
+            let mut offset = 2;
+            while data.get(offset) == Some(&0xff) {
+                let marker = *data.get(offset + 1)?;
+                if marker == 0xda {
+                    // Start of scan, no more metadata
+                    break;
+                }
+
+                let length = get_u16_be(offset + 2)?;
+                let start = offset + 4;
+                let end = offset + 2 + length;
+                if marker == 0xe1 && data.get(start..start + 6) == Some(&b"Exif\0\0"[..])
+                {
+                    return Some(start..end.min(data.len()));
+                }
+
+                offset = end;
+            }
+
+            None
+        }
+
+        // Chunks of: length, type, data, checksum
+        ImageFormat::Png => {
+            let mut offset = 8;
+            while let Some(length) = get_u32(offset, u32::from_be_bytes) {
+                let start = offset + 8;
+                let end = start.checked_add(length)?;
+                match data.get(offset + 4..offset + 8)? {
+                    b"eXIf" if end + 4 <= data.len() => return Some(start..end),
+                    b"IEND" => break,
+                    _ => offset = end + 4,
+                }
+            }
+
+            None
+        }
+
+        // RIFF chunks of: type, length, data (padded to an even length)
+        ImageFormat::WebP => {
+            let mut offset = 12;
+            while let Some(length) = get_u32(offset + 4, u32::from_le_bytes) {
+                let start = offset + 8;
+                let end = start.checked_add(length)?;
+                if data.get(offset..offset + 4)? == b"EXIF" {
+                    return Some(start..end.min(data.len()));
+                }
+
+                offset = end + (length % 2);
+            }
+
+            None
+        }
+
+        _ => None,
+    }
+}
+
+/// Reads integers from EXIF (TIFF-structured) data in the appropriate byte order.
+struct ExifReader {
+    big_endian: bool,
+}
+
+impl ExifReader {
+    /// Removes the APP1 identifier if present, and determines the byte order.
+    fn new(exif: &[u8]) -> Option<(Self, usize)> {
+        // Some decoders include the APP1 identifier, others don't
+        let prefix = if exif.starts_with(b"Exif\0\0") { 6 } else { 0 };
+        let big_endian = match exif.get(prefix..prefix + 2)? {
+            b"II" => false,
+            b"MM" => true,
+            _ => return None,
+        };
+
+        Some((ExifReader { big_endian }, prefix))
+    }
+
+    fn u16(&self, tiff: &[u8], offset: usize) -> Option<u16> {
+        let bytes = tiff.get(offset..offset.checked_add(2)?)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, tiff: &[u8], offset: usize) -> Option<usize> {
+        let bytes = tiff.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+        let value = if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+
+        usize::try_from(value).ok()
+    }
+
+    /// Finds the GPS pointer entry in the first IFD, returning the GPS IFD offset.
+    ///
+    /// A GPS IFD which has no entries, such as one which has been stripped, is ignored.
+    fn gps_ifd(&self, tiff: &[u8]) -> Option<usize> {
+        let ifd_offset = self.u32(tiff, 4)?;
+        let entries = usize::from(self.u16(tiff, ifd_offset)?);
+
+        (0..entries)
+            .map(|index| ifd_offset + 2 + index * 12)
+            .find(|&entry_offset| self.u16(tiff, entry_offset) == Some(EXIF_GPS_TAG))
+            .and_then(|entry_offset| self.u32(tiff, entry_offset + 8))
+            .filter(|&gps_offset| self.u16(tiff, gps_offset) != Some(0))
+    }
+}
+
+/// Checks whether the first IFD of this EXIF (TIFF-structured) data has GPS information.
+fn exif_has_gps(exif: &[u8]) -> bool {
+    let Some((reader, prefix)) = ExifReader::new(exif) else {
+        return false;
+    };
+
+    reader.gps_ifd(&exif[prefix..]).is_some()
+}
+
+/// Blanks out the GPS IFD of this EXIF data, leaving it with no entries.
+///
+/// Values which are stored outside of their entry are zeroed too.
+fn exif_strip_gps(exif: &mut [u8]) -> bool {
+    let Some((reader, prefix)) = ExifReader::new(exif) else {
+        return false;
+    };
+
+    let tiff = &mut exif[prefix..];
+    let Some(gps_offset) = reader.gps_ifd(tiff) else {
+        return false;
+    };
+
+    let Some(entries) = reader.u16(tiff, gps_offset) else {
+        return false;
+    };
+
+    for index in 0..usize::from(entries) {
+        let entry_offset = gps_offset + 2 + index * 12;
+        let value_size = match reader.u16(tiff, entry_offset + 2) {
+            Some(1 | 2 | 6 | 7) => 1, // BYTE, ASCII, SBYTE, UNDEFINED
+            Some(3 | 8) => 2,         // SHORT, SSHORT
+            Some(4 | 9 | 11) => 4,    // LONG, SLONG, FLOAT
+            Some(5 | 10 | 12) => 8,   // RATIONAL, SRATIONAL, DOUBLE
+            _ => continue,
+        };
+
+        let Some(count) = reader.u32(tiff, entry_offset + 4) else {
+            continue;
+        };
+
+        let length = count.saturating_mul(value_size);
+        if length > 4 {
+            if let Some(value_offset) = reader.u32(tiff, entry_offset + 8) {
+                let end = value_offset.saturating_add(length).min(tiff.len());
+                if let Some(value) = tiff.get_mut(value_offset..end) {
+                    value.fill(0);
+                }
+            }
+        }
+    }
+
+    // Zero the entry count and all the entries themselves
+    let end = (gps_offset + 2 + usize::from(entries) * 12).min(tiff.len());
+    match tiff.get_mut(gps_offset..end) {
+        Some(ifd) => {
+            ifd.fill(0);
+            true
+        }
+        None => false,
+    }
+}
+
+#[test]
+fn supported_images() {
+    assert!(is_supported_image("image/png"));
+    assert!(is_supported_image("image/jpeg; charset=binary"));
+    assert!(is_supported_image("image/webp; charset=binary"));
+    assert!(!is_supported_image("image/svg+xml; charset=us-ascii"));
+    assert!(!is_supported_image("application/pdf; charset=binary"));
+    assert!(!is_supported_image("inode/x-empty; charset=binary"));
+}
+
+#[test]
+fn exif_gps() {
+    // Little-endian, one IFD entry (GPS pointer)
+    let with_gps = [
+        b'I', b'I', 0x2a, 0x00, 0x08, 0x00, 0x00, 0x00, // header
+        0x01, 0x00, // entry count
+        0x25, 0x88, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00,
+    ];
+
+    // Big-endian, one IFD entry (orientation)
+    let without_gps = [
+        b'M', b'M', 0x00, 0x2a, 0x00, 0x00, 0x00, 0x08, // header
+        0x00, 0x01, // entry count
+        0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00,
+    ];
+
+    let mut prefixed = b"Exif\0\0".to_vec();
+    prefixed.extend_from_slice(&with_gps);
+
+    assert!(exif_has_gps(&with_gps));
+    assert!(exif_has_gps(&prefixed));
+    assert!(!exif_has_gps(&without_gps));
+    assert!(!exif_has_gps(b""));
+    assert!(!exif_has_gps(b"II*\0\xff\xff\xff\xff"));
+}
+
+#[test]
+fn thumbnails() {
+    use image::{ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn check(image: DynamicImage, expected: &[(u32, u32, u32, ImageFormat)]) {
+        let mut data = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .expect("Unable to encode test image");
+
+        let derivatives = generate(&data, &[0, 50, 100, 800]).expect("Generation failed");
+        let actual: Vec<_> = derivatives
+            .iter()
+            .map(|derivative| {
+                let format = image::guess_format(&derivative.data)
+                    .expect("Unable to determine derivative format");
+
+                (derivative.size, derivative.width, derivative.height, format)
+            })
+            .collect();
+
+        assert_eq!(actual, expected, "Generated derivatives don't match");
+    }
+
+    check(
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(400, 200, Rgb([255, 0, 0]))),
+        &[
+            (50, 50, 25, ImageFormat::Jpeg),
+            (100, 100, 50, ImageFormat::Jpeg),
+        ],
+    );
+    check(
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(60, 90, Rgba([0, 0, 255, 128]))),
+        &[(50, 33, 50, ImageFormat::Png)],
+    );
+}
+
+#[test]
+fn strip_gps_exif() {
+    use image::{Rgb, RgbImage};
+
+    // Little-endian, IFD0 with orientation and GPS pointer,
+    // then a GPS IFD with an out-of-line latitude (3 RATIONALs).
+    #[rustfmt::skip]
+    let tiff = [
+        b'I', b'I', 0x2a, 0x00, 0x08, 0x00, 0x00, 0x00, // header
+        0x02, 0x00, // entry count
+        0x12, 0x01, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00,
+        0x25, 0x88, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, 0x26, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, // next IFD
+        0x01, 0x00, // GPS entry count
+        0x02, 0x00, 0x05, 0x00, 0x03, 0x00, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, // next IFD
+        0x33, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, // latitude
+        0x1e, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x0f, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    ];
+
+    let mut exif = b"Exif\0\0".to_vec();
+    exif.extend_from_slice(&tiff);
+
+    // Build a JPEG with this EXIF as an APP1 segment after SOI
+    let mut jpeg = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([0, 255, 0])))
+        .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+        .expect("Unable to encode test image");
+
+    let length = u16::try_from(exif.len() + 2).unwrap().to_be_bytes();
+    let segment = [&[0xff, 0xe1], &length[..], &exif].concat();
+    jpeg.splice(2..2, segment);
+
+    let original_length = jpeg.len();
+    assert!(strip_gps(&mut jpeg), "GPS data was not stripped");
+    assert_eq!(jpeg.len(), original_length, "Image length changed");
+    assert!(!strip_gps(&mut jpeg), "GPS data stripped twice");
+
+    let stripped = &jpeg[6..6 + exif.len()];
+    assert!(!exif_has_gps(stripped), "GPS data still present");
+    assert_eq!(stripped[..0x2c], exif[..0x2c], "Other metadata was changed");
+    assert!(
+        stripped[0x2c..].iter().all(|&byte| byte == 0),
+        "GPS data not zeroed"
+    );
+
+    // The image must still decode, with its orientation intact
+    let mut decoder = ImageReader::new(Cursor::new(&jpeg))
+        .with_guessed_format()
+        .unwrap()
+        .into_decoder()
+        .expect("Unable to decode stripped image");
+    assert_eq!(
+        decoder.orientation().unwrap(),
+        image::metadata::Orientation::Rotate90,
+    );
+}
@@ -20,7 +20,8 @@
 
 //! The blob service, for interfacing with content-addressable S3 objects.
 //!
-//! This is essentially just a wrapper for how DEEPWELL interacts with S3.
+//! This is essentially just a wrapper for how DEEPWELL interacts with S3,
+//! or whichever other storage backend is configured (see `storage`).
 //! Method implementations should instead work with the relevant concept
 //! service instead, for instance the `FileService`.
 
@@ -34,8 +35,10 @@ mod prelude {
     pub use crate::hash::{blob_hash_to_hex, sha512_hash, BlobHash};
 }
 
+mod derivative;
 mod mime;
 mod service;
+mod storage;
 mod structs;
 
 pub use self::mime::MimeAnalyzer;
@@ -43,4 +46,7 @@ pub use self::service::{
     BlobService, EMPTY_BLOB_HASH, EMPTY_BLOB_MIME, EMPTY_BLOB_TIMESTAMP,
     PRESIGN_DIRECTORY,
 };
+pub use self::storage::{
+    BlobHead, BlobStorage, BlobStorageType, LocalStorage, LocalStorageLayer, S3Storage,
+};
 pub use self::structs::*;
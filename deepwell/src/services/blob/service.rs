@@ -18,12 +18,17 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::derivative::{self, Derivative};
 use super::prelude::*;
+use super::storage::BlobHead;
 use crate::constants::SYSTEM_USER_ID;
 use crate::hash::slice_to_blob_hash;
 use crate::models::blob_blacklist::{
     self, Entity as BlobBlacklist, Model as BlobBlacklistModel,
 };
+use crate::models::blob_derivative::{
+    self, Entity as BlobDerivative, Model as BlobDerivativeModel,
+};
 use crate::models::blob_pending::{
     self, Entity as BlobPending, Model as BlobPendingModel,
 };
@@ -35,14 +40,12 @@ use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::models::site::{self, Entity as Site, Model as SiteModel};
 use crate::models::user::{self, Entity as User, Model as UserModel};
 use crate::services::file::{DeleteFile, FileService};
+use crate::services::job::{Job, JobService};
 use crate::utils::assert_is_csprng;
-use bytes::Bytes;
 use cuid2::cuid;
 use futures::TryStreamExt;
 use rand::distributions::{Alphanumeric, DistString};
 use rand::thread_rng;
-use s3::request::request_trait::ResponseData;
-use s3::serde_types::HeadObjectResult;
 use sea_orm::{
     prelude::*, DatabaseBackend, FromQueryResult, Statement, StreamTrait,
     TransactionTrait, UpdateResult,
@@ -50,10 +53,11 @@ use sea_orm::{
 use sea_query::value::ArrayType;
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
+use std::path::Path;
 use std::str;
 use std::sync::Arc;
-use time::format_description::well_known::Rfc2822;
 use time::{Duration, OffsetDateTime};
+use tokio::task;
 
 /// How many samples to provide when providing hard deletion stats.
 const SAMPLE_COUNT: u16 = 10;
@@ -94,9 +98,15 @@ impl BlobService {
     /// The generated presign URL, which can be uploaded to.
     pub async fn start_upload(
         ctx: &ServiceContext<'_>,
-        StartBlobUpload { user_id, blob_size }: StartBlobUpload,
+        StartBlobUpload {
+            user_id,
+            blob_size,
+            ip_address,
+        }: StartBlobUpload,
     ) -> Result<StartBlobUploadOutput> {
-        info!("Creating upload by {user_id} with promised length {blob_size}");
+        info!(
+            "Creating upload by {user_id} ({ip_address}) with promised length {blob_size}",
+        );
         let config = ctx.config();
         let txn = ctx.transaction();
 
@@ -132,9 +142,9 @@ impl BlobService {
         info!("Creating presign upload URL for blob at path {s3_path} with primary key {pending_blob_id}");
 
         // Create presign URL
-        let bucket = ctx.s3_bucket();
-        let presign_url = bucket
-            .presign_put(&s3_path, config.presigned_expiry_secs, None, None)
+        let presign_url = ctx
+            .blob_storage()
+            .presign_upload(&s3_path, config.presigned_expiry_secs)
             .await?;
 
         // Get timestamps
@@ -213,8 +223,7 @@ impl BlobService {
         BlobPending::delete_by_id(pending_blob_id).exec(txn).await?;
 
         if Self::head(ctx, &s3_path).await?.is_some() {
-            let bucket = ctx.s3_bucket();
-            bucket.delete_object(&s3_path).await?;
+            ctx.blob_storage().delete(&s3_path).await?;
         }
 
         Ok(())
@@ -261,22 +270,16 @@ impl BlobService {
         s3_path: &str,
         expected_length: usize,
     ) -> Result<FinalizeBlobUploadOutput> {
-        let bucket = ctx.s3_bucket();
+        let storage = ctx.blob_storage();
         let txn = ctx.transaction();
 
         debug!("Download uploaded blob from S3 uploads to get metadata");
-        let response = bucket.get_object(s3_path).await?;
-        let data: Vec<u8> = match response.status_code() {
-            200 => response.into(),
-            404 => {
+        let mut data = match storage.get(s3_path).await? {
+            Some(data) => data,
+            None => {
                 error!("No blob uploaded at presign path {s3_path}");
                 return Err(Error::BlobNotUploaded);
             }
-            _ => {
-                error!("Unable to retrieve uploaded blob at {s3_path} from S3");
-                let error = s3_error(&response, "finalizing uploaded blob")?;
-                return Err(error);
-            }
         };
 
         if expected_length != data.len() {
@@ -285,7 +288,7 @@ impl BlobService {
                 expected_length,
                 data.len(),
             );
-            bucket.delete_object(&s3_path).await?;
+            storage.delete(s3_path).await?;
             return Err(Error::BlobSizeMismatch {
                 expected: expected_length,
                 actual: data.len(),
@@ -312,8 +315,16 @@ impl BlobService {
         //
         // In either case, we delete the blob at the temporary upload location.
 
+        // Remove location data from photos before they are stored
+        //
+        // This isn't done in direct_upload(), since imported blobs
+        // must keep the hash they are referred to by.
+        if derivative::strip_gps(&mut data) {
+            debug!("Removed GPS metadata from uploaded image");
+        }
+
         let result = Self::direct_upload(ctx, data).await?;
-        bucket.delete_object(&s3_path).await?;
+        storage.delete(s3_path).await?;
 
         // Check that new blob is not blacklisted
         if Self::on_blacklist(ctx, result.s3_hash).await? {
@@ -351,8 +362,6 @@ impl BlobService {
         ctx: &ServiceContext<'_>,
         data: Vec<u8>,
     ) -> Result<FinalizeBlobUploadOutput> {
-        let bucket = ctx.s3_bucket();
-
         // Get hash for blob
         let s3_hash = sha512_hash(&data);
         let hex_hash = blob_hash_to_hex(&s3_hash);
@@ -368,7 +377,7 @@ impl BlobService {
                 //       In case of changing file formats, etc.
 
                 // Content-Type header should be returned
-                let mime = result.content_type.ok_or(Error::S3Response)?;
+                let mime = result.mime.ok_or(Error::S3Response)?;
 
                 Ok(FinalizeBlobUploadOutput {
                     s3_hash,
@@ -383,25 +392,51 @@ impl BlobService {
                 // Determine MIME type for the new blob
                 let mime = ctx.mime().get_mime_type(data.clone()).await?;
 
-                // Upload S3 object
-                let response = bucket
-                    .put_object_with_content_type(&hex_hash, &data, &mime)
-                    .await?;
+                // Upload to storage
+                ctx.blob_storage().put(&hex_hash, &data, &mime).await?;
 
-                // We assume all unexpected statuses are errors, even if 1XX or 2XX
-                match response.status_code() {
-                    200 => Ok(FinalizeBlobUploadOutput {
-                        s3_hash,
-                        mime,
-                        size,
-                        created: true,
-                    }),
-                    _ => s3_error(&response, "creating final S3 blob")?,
-                }
+                Ok(FinalizeBlobUploadOutput {
+                    s3_hash,
+                    mime,
+                    size,
+                    created: true,
+                })
             }
         }
     }
 
+    /// Uploads a blob from a local file, whose hash and size are already known.
+    ///
+    /// Like `direct_upload()`, but the file is streamed to storage rather than
+    /// held in memory, for generated data which may be very large.
+    pub(crate) async fn upload_file(
+        ctx: &ServiceContext<'_>,
+        path: &Path,
+        s3_hash: BlobHash,
+        size: i64,
+        mime: &str,
+    ) -> Result<FinalizeBlobUploadOutput> {
+        let hex_hash = blob_hash_to_hex(&s3_hash);
+        let created = match Self::head(ctx, &hex_hash).await? {
+            Some(_) => {
+                debug!("Blob with hash {hex_hash} already exists");
+                false
+            }
+            None => {
+                debug!("Blob with hash {hex_hash} to be created from file");
+                ctx.blob_storage().put_file(&hex_hash, path, mime).await?;
+                true
+            }
+        };
+
+        Ok(FinalizeBlobUploadOutput {
+            s3_hash,
+            mime: str!(mime),
+            size,
+            created,
+        })
+    }
+
     pub async fn finish_upload(
         ctx: &ServiceContext<'_>,
         user_id: i64,
@@ -421,17 +456,22 @@ impl BlobService {
                 let expected_length =
                     expected_length.try_into().map_err(|_| Error::BlobTooBig)?;
 
-                Self::move_uploaded(
+                let output = Self::move_uploaded(
                     ctx,
                     pending_blob_id,
                     user_id,
                     &s3_path,
                     expected_length,
                 )
-                .await?
+                .await?;
+
+                ctx.metrics().record_blob_upload(output.size);
+                output
             }
 
             // Already moved
+            //
+            // Derivatives were queued the first time around, if needed.
             Some(hash_vec) => {
                 let BlobMetadata { mime, size, .. } =
                     Self::get_metadata(ctx, &hash_vec).await?;
@@ -447,10 +487,216 @@ impl BlobService {
             }
         };
 
+        // Generate derived images in the background, if this is new
+        if output.created && derivative::is_supported_image(&output.mime) {
+            let job = Job::GenerateBlobDerivatives {
+                s3_hash: output.s3_hash.into(),
+            };
+
+            JobService::queue_job(ctx, &job, None).await?;
+        }
+
         // Return result based on blob status
         Ok(output)
     }
 
+    // Derivative operations
+
+    /// Generates derived images, such as thumbnails, for an image blob.
+    ///
+    /// This is run as a job after an upload finishes, see `Job::GenerateBlobDerivatives`.
+    /// Blobs which are not images, or which are too large, are skipped.
+    ///
+    /// Image decoding failures are logged rather than returned, since
+    /// retrying the job would not produce any different result.
+    pub async fn generate_derivatives(
+        ctx: &ServiceContext<'_>,
+        source_hash: &[u8],
+    ) -> Result<()> {
+        let config = ctx.config();
+        let txn = ctx.transaction();
+        let hex_hash = blob_hash_to_hex(source_hash);
+        info!("Generating derivatives for blob {hex_hash}");
+
+        // If this is a retry, don't redo work
+        let existing = BlobDerivative::find()
+            .filter(blob_derivative::Column::SourceHash.eq(source_hash))
+            .count(txn)
+            .await?;
+
+        if existing > 0 {
+            debug!("Blob already has {existing} derivatives, skipping");
+            return Ok(());
+        }
+
+        let BlobMetadata { mime, size, .. } =
+            Self::get_metadata(ctx, source_hash).await?;
+        if !derivative::is_supported_image(&mime) {
+            debug!("Blob has MIME type {mime}, not an image we can process");
+            return Ok(());
+        }
+
+        if size > config.maximum_thumbnail_source_size {
+            debug!(
+                "Blob is too large to generate derivatives for ({size} > {})",
+                config.maximum_thumbnail_source_size,
+            );
+            return Ok(());
+        }
+
+        let data = Self::get(ctx, source_hash).await?;
+        let sizes = config.thumbnail_sizes.clone();
+        let result =
+            task::spawn_blocking(move || derivative::generate(&data, &sizes)).await;
+
+        let derivatives = match result {
+            Ok(Ok(derivatives)) => derivatives,
+            Ok(Err(error)) => {
+                warn!("Unable to generate derivatives for blob {hex_hash}: {error}");
+                return Ok(());
+            }
+            Err(error) => {
+                error!(
+                    "Derivative generation for blob {hex_hash} did not finish: {error}"
+                );
+                return Ok(());
+            }
+        };
+
+        debug!("Uploading {} derivatives", derivatives.len());
+        for Derivative {
+            size,
+            width,
+            height,
+            data,
+        } in derivatives
+        {
+            let FinalizeBlobUploadOutput { s3_hash, mime, .. } =
+                Self::direct_upload(ctx, data).await?;
+
+            let model = blob_derivative::ActiveModel {
+                source_hash: Set(source_hash.to_vec()),
+                size: Set(to_i32(size)),
+                s3_hash: Set(s3_hash.to_vec()),
+                mime: Set(mime),
+                width: Set(to_i32(width)),
+                height: Set(to_i32(height)),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets all the derivatives of a blob, with URLs to retrieve them.
+    pub async fn get_derivatives(
+        ctx: &ServiceContext<'_>,
+        source_hash: &[u8],
+    ) -> Result<Vec<BlobDerivativeOutput>> {
+        let config = ctx.config();
+        let txn = ctx.transaction();
+        let storage = ctx.blob_storage();
+
+        let models = BlobDerivative::find()
+            .filter(blob_derivative::Column::SourceHash.eq(source_hash))
+            .order_by_asc(blob_derivative::Column::Size)
+            .all(txn)
+            .await?;
+
+        let mut derivatives = Vec::with_capacity(models.len());
+        for BlobDerivativeModel {
+            size,
+            s3_hash,
+            mime,
+            width,
+            height,
+            ..
+        } in models
+        {
+            let hex_hash = blob_hash_to_hex(&s3_hash);
+            let url = storage
+                .presign_download(&hex_hash, config.presigned_expiry_secs)
+                .await?;
+
+            derivatives.push(BlobDerivativeOutput {
+                size,
+                s3_hash: s3_hash.into(),
+                mime,
+                width,
+                height,
+                url,
+            });
+        }
+
+        Ok(derivatives)
+    }
+
+    /// Removes all the derivatives of a blob, both the records and the S3 objects.
+    ///
+    /// Since derivatives are content-addressed, the same blob may also be
+    /// the derivative of a different source, or uploaded directly as a file
+    /// or avatar. Such blobs only have their derivative record removed.
+    async fn hard_delete_derivatives(
+        ctx: &ServiceContext<'_>,
+        source_hash: &[u8],
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let models = BlobDerivative::find()
+            .filter(blob_derivative::Column::SourceHash.eq(source_hash))
+            .all(txn)
+            .await?;
+
+        for model in models {
+            let s3_hash = model.s3_hash.clone();
+            model.delete(txn).await?;
+
+            if Self::blob_in_use(ctx, &s3_hash).await? {
+                debug!(
+                    "Derivative blob {} is still in use, not deleting",
+                    blob_hash_to_hex(&s3_hash),
+                );
+                continue;
+            }
+
+            Self::hard_delete(ctx, &s3_hash).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Determines if anything still references this blob.
+    ///
+    /// This checks file revisions, user avatars, and derivatives of other blobs.
+    async fn blob_in_use(ctx: &ServiceContext<'_>, s3_hash: &[u8]) -> Result<bool> {
+        let txn = ctx.transaction();
+
+        let revisions = FileRevision::find()
+            .filter(file_revision::Column::S3Hash.eq(s3_hash))
+            .count(txn)
+            .await?;
+
+        if revisions > 0 {
+            return Ok(true);
+        }
+
+        let users = User::find()
+            .filter(user::Column::AvatarS3Hash.eq(s3_hash))
+            .count(txn)
+            .await?;
+
+        if users > 0 {
+            return Ok(true);
+        }
+
+        let derivatives = BlobDerivative::find()
+            .filter(blob_derivative::Column::S3Hash.eq(s3_hash))
+            .count(txn)
+            .await?;
+
+        Ok(derivatives > 0)
+    }
+
     // Hard-deletion operations
 
     /// Does a dry run on a blob hard deletion, showing what would have been changed.
@@ -677,6 +923,7 @@ impl BlobService {
             try_join!(
                 BlobService::add_blacklist(ctx, s3_hash, user_id),
                 BlobService::hard_delete(ctx, &s3_hash),
+                BlobService::hard_delete_derivatives(ctx, &s3_hash),
             )?;
         }
 
@@ -810,15 +1057,9 @@ impl BlobService {
             return Ok(Some(Vec::new()));
         }
 
-        // Retrieve blob from S3
-        let bucket = ctx.s3_bucket();
+        // Retrieve blob from storage
         let hex_hash = blob_hash_to_hex(hash);
-        let response = bucket.get_object(&hex_hash).await?;
-        match response.status_code() {
-            200 => Ok(Some(response.into())),
-            404 => Ok(None),
-            _ => s3_error(&response, "fetching S3 blob"),
-        }
+        ctx.blob_storage().get(&hex_hash).await
     }
 
     #[inline]
@@ -840,27 +1081,20 @@ impl BlobService {
             }));
         }
 
-        // Retrieve metadata from S3
+        // Retrieve metadata from storage
         let hex_hash = blob_hash_to_hex(hash);
         match Self::head(ctx, &hex_hash).await? {
             None => Ok(None),
-            Some(result) => {
-                // Headers should be passed in
-                let size = result.content_length.ok_or(Error::S3Response)?;
-                let mime = result.content_type.ok_or(Error::S3Response)?;
-                let created_at = {
-                    let timestamp = result.last_modified.ok_or(Error::S3Response)?;
-
-                    OffsetDateTime::parse(&timestamp, &Rfc2822)
-                        .map_err(|_| Error::S3Response)?
-                };
-
-                Ok(Some(BlobMetadata {
-                    mime,
-                    size,
-                    created_at,
-                }))
-            }
+            Some(BlobHead {
+                mime,
+                size,
+                last_modified,
+            }) => Ok(Some(BlobMetadata {
+                // Content-Type should be stored for all final blobs
+                mime: mime.ok_or(Error::S3Response)?,
+                size,
+                created_at: last_modified,
+            })),
         }
     }
 
@@ -880,7 +1114,7 @@ impl BlobService {
             return Ok(true);
         }
 
-        // Fetch existence from S3
+        // Fetch existence from storage
         let hex_hash = blob_hash_to_hex(hash);
         let result = Self::head(ctx, &hex_hash).await?;
         Ok(result.is_some())
@@ -905,21 +1139,8 @@ impl BlobService {
         }
     }
 
-    async fn head(
-        ctx: &ServiceContext<'_>,
-        path: &str,
-    ) -> Result<Option<HeadObjectResult>> {
-        let bucket = ctx.s3_bucket();
-        let (result, status) = bucket.head_object(path).await?;
-
-        match status {
-            200 | 204 => Ok(Some(result)),
-            404 => Ok(None),
-            _ => {
-                let response = ResponseData::new(Bytes::new(), status, HashMap::new());
-                s3_error(&response, "heading S3 blob")
-            }
-        }
+    async fn head(ctx: &ServiceContext<'_>, path: &str) -> Result<Option<BlobHead>> {
+        ctx.blob_storage().head(path).await
     }
 
     pub async fn hard_delete(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<()> {
@@ -932,35 +1153,17 @@ impl BlobService {
             return Ok(());
         }
 
-        // Delete from S3
-        let bucket = ctx.s3_bucket();
+        // Delete from storage
         let hex_hash = blob_hash_to_hex(hash);
-
-        let response = bucket.delete_object(&hex_hash).await?;
-        match response.status_code() {
-            204 => Ok(()),
-            _ => s3_error(&response, "hard-deleting S3 blob"),
-        }
+        ctx.blob_storage().delete(&hex_hash).await
     }
 }
 
-/// Helper method to parse out an S3 error response and print the message (if any).
-fn s3_error<T>(response: &ResponseData, action: &str) -> Result<T> {
-    let error_message = match str::from_utf8(response.bytes()) {
-        Ok("") => "(no content)",
-        Ok(m) => m,
-        Err(_) => "(invalid UTF-8)",
-    };
-
-    error!(
-        "Error while {} (HTTP {}): {}",
-        action,
-        response.status_code(),
-        error_message,
-    );
-
-    // TODO replace with S3 backend-specific error
-    Err(Error::S3Response)
+/// Converts an image dimension for storage.
+///
+/// These are limited to well below `i32::MAX` when decoding.
+fn to_i32(value: u32) -> i32 {
+    i32::try_from(value).expect("Image dimension exceeds i32")
 }
 
 #[derive(Debug)]
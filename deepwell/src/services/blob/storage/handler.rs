@@ -0,0 +1,246 @@
+/*
+ * services/blob/storage/handler.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! HTTP handler for presigned URLs to local blob storage.
+//!
+//! When using S3, end users upload to and download from the bucket directly.
+//! For local storage, DEEPWELL serves these URLs itself, by intercepting
+//! requests under `LOCAL_STORAGE_ROUTE` before they reach the JSON-RPC server.
+
+use super::local::LOCAL_STORAGE_ROUTE;
+use super::{BlobStorage, LocalStorage};
+use http::header::CONTENT_TYPE;
+use http::{Method, Response, StatusCode};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use jsonrpsee::core::BoxError;
+use jsonrpsee::server::{HttpBody, HttpRequest, HttpResponse};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Layer which serves presigned URLs for local blob storage.
+///
+/// If no local storage is configured, all requests are passed through.
+#[derive(Debug, Clone)]
+pub struct LocalStorageLayer {
+    storage: Option<LocalStorage>,
+}
+
+impl LocalStorageLayer {
+    #[inline]
+    pub fn new(storage: Option<LocalStorage>) -> Self {
+        LocalStorageLayer { storage }
+    }
+}
+
+impl<S> Layer<S> for LocalStorageLayer {
+    type Service = LocalStorageService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LocalStorageService {
+            inner,
+            storage: self.storage.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalStorageService<S> {
+    inner: S,
+    storage: Option<LocalStorage>,
+}
+
+impl<S> Service<HttpRequest> for LocalStorageService<S>
+where
+    S: Service<HttpRequest, Response = HttpResponse, Error = BoxError>,
+    S::Future: Send + 'static,
+{
+    type Response = HttpResponse;
+    type Error = BoxError;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: HttpRequest) -> Self::Future {
+        match self.storage {
+            Some(ref storage)
+                if request.uri().path().starts_with(LOCAL_STORAGE_ROUTE) =>
+            {
+                let storage = storage.clone();
+                Box::pin(async move { Ok(handle(&storage, request).await) })
+            }
+            _ => Box::pin(self.inner.call(request)),
+        }
+    }
+}
+
+async fn handle(storage: &LocalStorage, request: HttpRequest) -> HttpResponse {
+    let uri = request.uri();
+    let path = &uri.path()[LOCAL_STORAGE_ROUTE.len()..];
+
+    // Get signature parameters from the query string
+    let mut expires = None;
+    let mut signature = None;
+    for pair in uri.query().unwrap_or("").split('&') {
+        match pair.split_once('=') {
+            Some(("expires", value)) => expires = value.parse::<i64>().ok(),
+            Some(("signature", value)) => signature = Some(value),
+            _ => (),
+        }
+    }
+
+    let (expires, signature) = match (expires, signature) {
+        (Some(expires), Some(signature)) => (expires, signature),
+        _ => return response(StatusCode::BAD_REQUEST),
+    };
+
+    let method = request.method().clone();
+    if !storage.verify(method.as_str(), path, expires, signature) {
+        warn!("Rejecting local blob request for {path} with invalid signature");
+        return response(StatusCode::FORBIDDEN);
+    }
+
+    let path = str!(path);
+    match method {
+        Method::GET => match storage.get(&path).await {
+            Ok(Some(data)) => {
+                let mime = match storage.head(&path).await {
+                    Ok(Some(head)) => head.mime,
+                    _ => None,
+                };
+
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .header(
+                        CONTENT_TYPE,
+                        mime.as_deref().unwrap_or("application/octet-stream"),
+                    )
+                    .body(HttpBody::from(data))
+                    .expect("Unable to build HTTP response")
+            }
+            Ok(None) => response(StatusCode::NOT_FOUND),
+            Err(error) => {
+                error!("Unable to read local blob {path}: {error}");
+                response(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        },
+        Method::PUT => {
+            let mime = request
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("application/octet-stream")
+                .to_owned();
+
+            let body = Limited::new(request.into_body(), storage.maximum_upload_size());
+            let data = match body.collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(error) if error.is::<LengthLimitError>() => {
+                    warn!("Local blob upload for {path} exceeds maximum size");
+                    return response(StatusCode::PAYLOAD_TOO_LARGE);
+                }
+                Err(error) => {
+                    warn!("Unable to receive local blob upload for {path}: {error}");
+                    return response(StatusCode::BAD_REQUEST);
+                }
+            };
+
+            match storage.put(&path, &data, &mime).await {
+                Ok(()) => response(StatusCode::OK),
+                Err(error) => {
+                    error!("Unable to write local blob {path}: {error}");
+                    response(StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            }
+        }
+        _ => response(StatusCode::METHOD_NOT_ALLOWED),
+    }
+}
+
+fn response(status: StatusCode) -> HttpResponse {
+    Response::builder()
+        .status(status)
+        .body(HttpBody::empty())
+        .expect("Unable to build HTTP response")
+}
+
+#[tokio::test]
+async fn local_storage_requests() {
+    use http::Request;
+    use tower::{service_fn, ServiceExt};
+
+    let root = std::env::temp_dir().join(format!("deepwell-blobs-{}", cuid2::cuid()));
+    let storage = LocalStorage::new(root.clone(), "http://localhost", 16);
+    let layer = LocalStorageLayer::new(Some(storage.clone()));
+
+    macro_rules! request {
+        ($method:expr, $url:expr, $body:expr $(,)?) => {{
+            let path_and_query = $url.trim_start_matches("http://localhost");
+            let request = Request::builder()
+                .method($method)
+                .uri(path_and_query)
+                .body(HttpBody::from($body))
+                .unwrap();
+
+            let inner = service_fn(|_: HttpRequest| async {
+                Ok::<_, BoxError>(response(StatusCode::IM_A_TEAPOT))
+            });
+
+            layer.layer(inner).oneshot(request).await.unwrap()
+        }};
+    }
+
+    // Upload
+    let upload_url = storage.presign_upload("uploads/abc", 60).await.unwrap();
+    let resp = request!(Method::PUT, upload_url.as_str(), b"apple".to_vec());
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(storage.get("uploads/abc").await.unwrap().unwrap(), b"apple");
+
+    // Too large
+    let resp = request!(Method::PUT, upload_url.as_str(), vec![0; 32]);
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Cannot download with an upload URL
+    let resp = request!(Method::GET, upload_url.as_str(), Vec::new());
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Tampered path
+    let tampered = upload_url.replace("uploads/abc", "uploads/abd");
+    let resp = request!(Method::PUT, tampered.as_str(), b"banana".to_vec());
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Download
+    let download_url = storage.presign_download("uploads/abc", 60).await.unwrap();
+    let resp = request!(Method::GET, download_url.as_str(), Vec::new());
+    assert_eq!(resp.status(), StatusCode::OK);
+    let data = resp.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(&data[..], b"apple");
+
+    // Other routes are passed through
+    let resp = request!(Method::POST, "/", Vec::new());
+    assert_eq!(resp.status(), StatusCode::IM_A_TEAPOT);
+
+    tokio::fs::remove_dir_all(&root).await.unwrap();
+}
@@ -0,0 +1,326 @@
+/*
+ * services/blob/storage/local.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::{BlobHead, BlobStorage};
+use crate::services::{Error, Result};
+use crate::utils::assert_is_csprng;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, RngCore};
+use sha2::Sha256;
+use std::fmt::{self, Debug};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+use tokio::fs;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The URL path prefix under which presigned URLs for local storage are served.
+pub const LOCAL_STORAGE_ROUTE: &str = "/local-blob/";
+
+/// Suffix of the file next to each blob which contains its MIME type.
+const MIME_SUFFIX: &str = ".mime";
+
+/// Blob storage backed by a directory on the local filesystem.
+///
+/// Each blob is stored as a file at its path relative to the root directory,
+/// with its MIME type (if known) in an adjacent file.
+///
+/// Presigned URLs are signed with HMAC-SHA256 using a key generated when the
+/// server starts, and are served by `LocalStorageLayer`. This means any
+/// outstanding presigned URLs stop working when the server restarts.
+#[derive(Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+    maximum_upload_size: usize,
+    key: [u8; 32],
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf, base_url: &str, maximum_upload_size: usize) -> Self {
+        let mut key = [0; 32];
+        let mut rng = thread_rng();
+        assert_is_csprng(&rng);
+        rng.fill_bytes(&mut key);
+
+        LocalStorage {
+            root,
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            maximum_upload_size,
+            key,
+        }
+    }
+
+    #[inline]
+    pub fn maximum_upload_size(&self) -> usize {
+        self.maximum_upload_size
+    }
+
+    /// Gets the filesystem path for a blob path.
+    ///
+    /// Blob paths are only ever hex hashes or generated upload paths,
+    /// so anything other than alphanumeric path segments is rejected.
+    fn resolve(&self, path: &str) -> Result<PathBuf> {
+        if !is_valid_path(path) {
+            error!("Invalid local blob storage path: {path}");
+            return Err(Error::BadRequest);
+        }
+
+        Ok(self.root.join(path))
+    }
+
+    fn sign(&self, method: &str, path: &str, expires: i64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+
+        mac.update(method.as_bytes());
+        mac.update(b"\n");
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+
+    fn presign(&self, method: &str, path: &str, expiry_secs: u32) -> Result<String> {
+        // Ensure the path is valid before handing out a URL for it
+        self.resolve(path)?;
+
+        let expires = OffsetDateTime::now_utc().unix_timestamp() + i64::from(expiry_secs);
+        let signature =
+            hex::encode(self.sign(method, path, expires).finalize().into_bytes());
+
+        Ok(format!(
+            "{}{}{}?expires={}&signature={}",
+            self.base_url, LOCAL_STORAGE_ROUTE, path, expires, signature,
+        ))
+    }
+
+    /// Checks that a presigned URL's signature is valid and has not expired.
+    pub fn verify(
+        &self,
+        method: &str,
+        path: &str,
+        expires: i64,
+        signature: &str,
+    ) -> bool {
+        if expires < OffsetDateTime::now_utc().unix_timestamp() {
+            debug!("Presigned URL for {path} has expired");
+            return false;
+        }
+
+        let signature = match hex::decode(signature) {
+            Ok(bytes) => bytes,
+            Err(_) => return false,
+        };
+
+        self.sign(method, path, expires)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+}
+
+impl Debug for LocalStorage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalStorage")
+            .field("root", &self.root)
+            .field("base_url", &self.base_url)
+            .field("maximum_upload_size", &self.maximum_upload_size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl BlobStorage for LocalStorage {
+    async fn put(&self, path: &str, data: &[u8], mime: &str) -> Result<()> {
+        let file_path = self.resolve(path)?;
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Write to a temporary file first, so partial blobs are never visible
+        let temp_path = with_suffix(&file_path, ".part");
+        fs::write(&temp_path, data).await?;
+        fs::write(with_suffix(&file_path, MIME_SUFFIX), mime).await?;
+        fs::rename(&temp_path, &file_path).await?;
+        Ok(())
+    }
+
+    async fn put_file(&self, path: &str, file_path: &Path, mime: &str) -> Result<()> {
+        let blob_path = self.resolve(path)?;
+        if let Some(parent) = blob_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Same as put(), but copying from the file
+        let temp_path = with_suffix(&blob_path, ".part");
+        fs::copy(file_path, &temp_path).await?;
+        fs::write(with_suffix(&blob_path, MIME_SUFFIX), mime).await?;
+        fs::rename(&temp_path, &blob_path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        let file_path = self.resolve(path)?;
+        match fs::read(&file_path).await {
+            Ok(data) => Ok(Some(data)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn head(&self, path: &str) -> Result<Option<BlobHead>> {
+        let file_path = self.resolve(path)?;
+        let metadata = match fs::metadata(&file_path).await {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mime = match fs::read_to_string(with_suffix(&file_path, MIME_SUFFIX)).await {
+            Ok(mime) => Some(mime),
+            Err(error) if error.kind() == ErrorKind::NotFound => None,
+            Err(error) => return Err(error.into()),
+        };
+
+        Ok(Some(BlobHead {
+            mime,
+            size: i64::try_from(metadata.len()).expect("File size exceeds i64"),
+            last_modified: OffsetDateTime::from(metadata.modified()?),
+        }))
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        let file_path = self.resolve(path)?;
+        for path in [with_suffix(&file_path, MIME_SUFFIX), file_path] {
+            match fs::remove_file(&path).await {
+                Err(error) if error.kind() != ErrorKind::NotFound => {
+                    return Err(error.into());
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn presign_upload(&self, path: &str, expiry_secs: u32) -> Result<String> {
+        self.presign("PUT", path, expiry_secs)
+    }
+
+    async fn presign_download(&self, path: &str, expiry_secs: u32) -> Result<String> {
+        self.presign("GET", path, expiry_secs)
+    }
+
+    #[inline]
+    fn as_local(&self) -> Option<&LocalStorage> {
+        Some(self)
+    }
+}
+
+fn is_valid_path(path: &str) -> bool {
+    !path.is_empty()
+        && path.split('/').all(|segment| {
+            !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_alphanumeric())
+        })
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[test]
+fn valid_paths() {
+    assert!(is_valid_path("abcdef0123456789"));
+    assert!(is_valid_path("uploads/Xy7q2Lm"));
+
+    assert!(!is_valid_path(""));
+    assert!(!is_valid_path("/abc"));
+    assert!(!is_valid_path("abc/"));
+    assert!(!is_valid_path("uploads//abc"));
+    assert!(!is_valid_path("../etc/passwd"));
+    assert!(!is_valid_path("uploads/abc.mime"));
+}
+
+#[test]
+fn signatures() {
+    let storage = LocalStorage::new(PathBuf::from("blobs"), "http://localhost/", 1024);
+    let url = storage.presign("PUT", "uploads/abc", 60).unwrap();
+    let (base, query) = url.split_once('?').unwrap();
+    assert_eq!(base, "http://localhost/local-blob/uploads/abc");
+
+    let (expires, signature) = query
+        .strip_prefix("expires=")
+        .and_then(|query| query.split_once("&signature="))
+        .unwrap();
+    let expires: i64 = expires.parse().unwrap();
+
+    assert!(storage.verify("PUT", "uploads/abc", expires, signature));
+    assert!(!storage.verify("GET", "uploads/abc", expires, signature));
+    assert!(!storage.verify("PUT", "uploads/abd", expires, signature));
+    assert!(!storage.verify("PUT", "uploads/abc", expires + 1, signature));
+    assert!(!storage.verify("PUT", "uploads/abc", expires, "00"));
+    assert!(!storage.verify("PUT", "uploads/abc", expires, "not hex"));
+
+    // Same URL, but signed by a different server instance
+    let other = LocalStorage::new(PathBuf::from("blobs"), "http://localhost/", 1024);
+    assert!(!other.verify("PUT", "uploads/abc", expires, signature));
+
+    // Expired URL
+    let expired = OffsetDateTime::now_utc().unix_timestamp() - 10;
+    let signature =
+        hex::encode(storage.sign("GET", "abc", expired).finalize().into_bytes());
+    assert!(!storage.verify("GET", "abc", expired, &signature));
+}
+
+#[tokio::test]
+async fn local_storage() {
+    let root = std::env::temp_dir().join(format!("deepwell-blobs-{}", cuid2::cuid()));
+    let storage = LocalStorage::new(root.clone(), "http://localhost", 1024);
+
+    assert!(storage.get("abc123").await.unwrap().is_none());
+    assert!(storage.head("abc123").await.unwrap().is_none());
+
+    storage.put("abc123", b"apple", "text/plain").await.unwrap();
+    assert_eq!(storage.get("abc123").await.unwrap().unwrap(), b"apple");
+
+    let head = storage.head("abc123").await.unwrap().unwrap();
+    assert_eq!(head.mime.as_deref(), Some("text/plain"));
+    assert_eq!(head.size, 5);
+
+    storage
+        .put("uploads/xyz", b"banana", "application/octet-stream")
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.get("uploads/xyz").await.unwrap().unwrap(),
+        b"banana"
+    );
+
+    storage.delete("abc123").await.unwrap();
+    storage.delete("abc123").await.unwrap();
+    assert!(storage.get("abc123").await.unwrap().is_none());
+    assert!(storage.get("../abc123").await.is_err());
+
+    fs::remove_dir_all(&root).await.unwrap();
+}
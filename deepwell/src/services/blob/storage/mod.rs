@@ -0,0 +1,101 @@
+/*
+ * services/blob/storage/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Backends for storing blob data.
+//!
+//! Blobs are kept in a flat namespace of paths, either their hex hash once
+//! finalized, or a random path under `PRESIGN_DIRECTORY` while pending upload.
+//! The `BlobService` only interacts with blobs through the `BlobStorage` trait,
+//! so the backend in use can be chosen in the configuration.
+
+mod handler;
+mod local;
+mod s3;
+
+pub use self::handler::LocalStorageLayer;
+pub use self::local::LocalStorage;
+pub use self::s3::S3Storage;
+
+use crate::services::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::path::Path;
+use time::OffsetDateTime;
+
+/// Which blob storage backend to use.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlobStorageType {
+    /// Store blobs in an S3 bucket, as specified in the secrets.
+    S3,
+
+    /// Store blobs in a directory on the local filesystem.
+    ///
+    /// Intended for development and testing, not production use.
+    Local,
+}
+
+/// Metadata about a stored blob, as returned by `BlobStorage::head()`.
+#[derive(Debug, Clone)]
+pub struct BlobHead {
+    /// The MIME type the blob was stored with.
+    ///
+    /// This is not available for blobs uploaded through a presigned URL.
+    pub mime: Option<String>,
+    pub size: i64,
+    pub last_modified: OffsetDateTime,
+}
+
+#[async_trait]
+pub trait BlobStorage: Debug + Send + Sync {
+    /// Stores the given data at this path, replacing anything already present.
+    async fn put(&self, path: &str, data: &[u8], mime: &str) -> Result<()>;
+
+    /// Stores the contents of a local file at this path, replacing anything already present.
+    ///
+    /// The file is streamed rather than read into memory, for data which may be very large.
+    async fn put_file(&self, path: &str, file_path: &Path, mime: &str) -> Result<()>;
+
+    /// Retrieves the data at this path, if it exists.
+    async fn get(&self, path: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Retrieves metadata for the data at this path, if it exists.
+    async fn head(&self, path: &str) -> Result<Option<BlobHead>>;
+
+    /// Deletes the data at this path.
+    ///
+    /// Deleting a path which does not exist is not an error.
+    async fn delete(&self, path: &str) -> Result<()>;
+
+    /// Creates a URL which an end user can upload data to at this path.
+    async fn presign_upload(&self, path: &str, expiry_secs: u32) -> Result<String>;
+
+    /// Creates a URL which an end user can download the data at this path from.
+    async fn presign_download(&self, path: &str, expiry_secs: u32) -> Result<String>;
+
+    /// Returns this backend if it is a `LocalStorage` instance.
+    ///
+    /// Local storage needs its presigned URLs to be served by DEEPWELL itself,
+    /// see `LocalStorageLayer`.
+    fn as_local(&self) -> Option<&LocalStorage> {
+        None
+    }
+}
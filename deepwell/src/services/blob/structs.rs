@@ -21,12 +21,14 @@
 use super::prelude::*;
 use crate::hash::BlobHash;
 use crate::types::Bytes;
+use std::net::IpAddr;
 use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct StartBlobUpload {
     pub user_id: i64,
     pub blob_size: u64,
+    pub ip_address: IpAddr,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -89,3 +91,17 @@ pub struct GetBlobOutput {
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
 }
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BlobDerivativeOutput {
+    /// The length of the longest side this was resized to,
+    /// or zero for a full-size copy without metadata.
+    pub size: i32,
+    pub s3_hash: Bytes<'static>,
+    pub mime: String,
+    pub width: i32,
+    pub height: i32,
+
+    /// A presigned URL to download this derivative from.
+    pub url: String,
+}
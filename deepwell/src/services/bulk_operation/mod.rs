@@ -0,0 +1,41 @@
+/*
+ * services/bulk_operation/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The bulk operation service, for applying one change to many pages.
+//!
+//! A bulk operation takes a selection of pages, either explicit page IDs or
+//! a simple query, and an operation such as retagging, moving, or deleting.
+//! The selection is resolved into a list of page IDs up front, which a job
+//! then works through in batches, creating one revision per page.
+//!
+//! Failures for individual pages are recorded rather than stopping the
+//! whole operation, and the operation can be canceled between batches.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::BulkOperationService;
+pub use self::structs::*;
@@ -0,0 +1,504 @@
+/*
+ * services/bulk_operation/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_bulk_operation::{
+    self, Entity as PageBulkOperation, Model as PageBulkOperationModel,
+};
+use crate::services::job::{Job, JobService};
+use crate::services::page::{DeletePage, EditPage, EditPageBody, MovePage};
+use crate::services::{PageRevisionService, PageService};
+use crate::utils::{join_category, split_category_name};
+use sea_orm::{DatabaseBackend, FromQueryResult, Statement, TransactionTrait, Value};
+use std::collections::BTreeSet;
+use std::ops::Range;
+use wikidot_normalize::normalize;
+
+#[derive(Debug)]
+pub struct BulkOperationService;
+
+impl BulkOperationService {
+    /// Starts a new bulk operation, queueing a job to process it.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateBulkOperation {
+            site_id,
+            user_id,
+            selection,
+            operation,
+            revision_comments,
+        }: CreateBulkOperation,
+    ) -> Result<PageBulkOperationModel> {
+        let txn = ctx.transaction();
+        let page_ids = Self::select_pages(ctx, site_id, selection).await?;
+        info!(
+            "Creating bulk operation on {} pages in site ID {site_id}: {operation:?}",
+            page_ids.len(),
+        );
+
+        let model = page_bulk_operation::ActiveModel {
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            operation: Set(serde_json::to_value(&operation)?),
+            revision_comments: Set(revision_comments),
+            page_ids: Set(page_ids),
+            ..Default::default()
+        };
+
+        let bulk_operation = model.insert(txn).await?;
+        let bulk_operation_id = bulk_operation.bulk_operation_id;
+        JobService::queue_job(ctx, &Job::BulkPageOperation { bulk_operation_id }, None)
+            .await?;
+
+        Ok(bulk_operation)
+    }
+
+    /// Resolves a page selection into a list of page IDs.
+    async fn select_pages(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        selection: BulkPageSelection,
+    ) -> Result<Vec<i64>> {
+        #[derive(FromQueryResult, Debug)]
+        struct PageIdRow {
+            page_id: i64,
+        }
+
+        match selection {
+            BulkPageSelection::Pages { page_ids } => {
+                // Remove duplicates, but otherwise keep the order given.
+                // Pages not in this site are reported as failures when processed.
+                let mut seen = BTreeSet::new();
+                Ok(page_ids.into_iter().filter(|id| seen.insert(*id)).collect())
+            }
+            BulkPageSelection::Query {
+                mut categories,
+                tags,
+            } => {
+                categories.iter_mut().for_each(normalize);
+
+                let txn = ctx.transaction();
+                let query = Statement::from_sql_and_values(
+                    DatabaseBackend::Postgres,
+                    str!(
+                        "
+                        SELECT page.page_id
+                        FROM page
+                        JOIN page_revision
+                            ON page.latest_revision_id = page_revision.revision_id
+                        JOIN page_category
+                            ON page.page_category_id = page_category.category_id
+                        WHERE page.site_id = $1
+                            AND page.deleted_at IS NULL
+                            AND (
+                                cardinality($2::text[]) = 0
+                                OR page_category.slug = ANY($2::text[])
+                            )
+                            AND page_revision.tags @> $3::text[]
+                        ORDER BY page.page_id
+                        ",
+                    ),
+                    [
+                        Value::from(site_id),
+                        Value::from(categories),
+                        Value::from(tags),
+                    ],
+                );
+
+                let rows = PageIdRow::find_by_statement(query).all(txn).await?;
+                Ok(rows.into_iter().map(|row| row.page_id).collect())
+            }
+        }
+    }
+
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        bulk_operation_id: i64,
+    ) -> Result<PageBulkOperationModel> {
+        let txn = ctx.transaction();
+        let bulk_operation = PageBulkOperation::find()
+            .filter(
+                Condition::all()
+                    .add(page_bulk_operation::Column::SiteId.eq(site_id))
+                    .add(
+                        page_bulk_operation::Column::BulkOperationId
+                            .eq(bulk_operation_id),
+                    ),
+            )
+            .one(txn)
+            .await?;
+
+        bulk_operation.ok_or(Error::BulkOperationNotFound)
+    }
+
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<PageBulkOperationModel>> {
+        let txn = ctx.transaction();
+        let bulk_operations = PageBulkOperation::find()
+            .filter(page_bulk_operation::Column::SiteId.eq(site_id))
+            .order_by_desc(page_bulk_operation::Column::BulkOperationId)
+            .all(txn)
+            .await?;
+
+        Ok(bulk_operations)
+    }
+
+    /// Cancels a bulk operation.
+    ///
+    /// Any batch which is currently running will finish,
+    /// but no further pages will be processed.
+    pub async fn cancel(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        bulk_operation_id: i64,
+    ) -> Result<PageBulkOperationModel> {
+        let txn = ctx.transaction();
+
+        // Lock the row, so this waits for any running batch
+        // and the next one sees the cancellation
+        let bulk_operation = PageBulkOperation::find()
+            .filter(
+                Condition::all()
+                    .add(page_bulk_operation::Column::SiteId.eq(site_id))
+                    .add(
+                        page_bulk_operation::Column::BulkOperationId
+                            .eq(bulk_operation_id),
+                    ),
+            )
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::BulkOperationNotFound)?;
+
+        if is_finished(&bulk_operation) {
+            error!("Bulk operation ID {bulk_operation_id} has already finished");
+            return Err(Error::BulkOperationFinished);
+        }
+
+        let model = page_bulk_operation::ActiveModel {
+            bulk_operation_id: Set(bulk_operation_id),
+            updated_at: Set(Some(now())),
+            canceled_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        let bulk_operation = model.update(txn).await?;
+        Ok(bulk_operation)
+    }
+
+    /// Processes the next batch of pages in a bulk operation.
+    ///
+    /// Returns `true` if there are more pages remaining.
+    pub async fn run_step(
+        ctx: &ServiceContext<'_>,
+        bulk_operation_id: i64,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+
+        // Lock the row, so that cancellation waits for the current batch
+        let bulk_operation = PageBulkOperation::find_by_id(bulk_operation_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::BulkOperationNotFound)?;
+
+        let batch_size = usize::from(ctx.config().job_bulk_operation_batch_size.get());
+        let Some(batch) = next_batch(&bulk_operation, batch_size) else {
+            info!("Bulk operation ID {bulk_operation_id} is finished, stopping");
+            return Ok(false);
+        };
+
+        let operation: BulkPageOperation =
+            serde_json::from_value(bulk_operation.operation.clone())?;
+        info!(
+            "Processing pages {}-{} of {} for bulk operation ID {bulk_operation_id}",
+            batch.start + 1,
+            batch.end,
+            bulk_operation.page_ids.len(),
+        );
+
+        let mut results = Vec::with_capacity(batch.len());
+        for &page_id in &bulk_operation.page_ids[batch.clone()] {
+            // Each page is done in its own savepoint, so that a
+            // failure partway through does not leave partial changes.
+            let savepoint = txn.begin().await?;
+            let page_ctx = ctx.savepoint(&savepoint);
+            let result =
+                Self::apply(&page_ctx, &bulk_operation, page_id, &operation).await;
+
+            match result {
+                Ok(()) => savepoint.commit().await?,
+                Err(ref error) => {
+                    warn!(
+                        "Bulk operation ID {bulk_operation_id} failed on page ID {page_id}: {error}",
+                    );
+                    savepoint.rollback().await?;
+                }
+            }
+
+            results.push((page_id, result));
+        }
+
+        let model = record_batch(&bulk_operation, batch.end, results)?;
+        let done = model.completed_at.as_ref().is_some();
+        model.update(txn).await?;
+        Ok(!done)
+    }
+
+    /// Applies the operation to a single page.
+    async fn apply(
+        ctx: &ServiceContext<'_>,
+        bulk_operation: &PageBulkOperationModel,
+        page_id: i64,
+        operation: &BulkPageOperation,
+    ) -> Result<()> {
+        let site_id = bulk_operation.site_id;
+        let user_id = bulk_operation.user_id;
+        let revision_comments = bulk_operation.revision_comments.clone();
+        let PageModel {
+            slug,
+            latest_revision_id,
+            ..
+        } = PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+        let last_revision_id =
+            latest_revision_id.expect("Page row has NULL latest_revision_id");
+
+        match operation {
+            BulkPageOperation::Retag { add, remove } => {
+                let revision =
+                    PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+
+                let tags = retag(revision.tags, add, remove);
+
+                // If the tags are unchanged, no revision is created
+                PageService::edit(
+                    ctx,
+                    EditPage {
+                        site_id,
+                        page: Reference::Id(page_id),
+                        last_revision_id,
+                        revision_comments,
+                        user_id,
+                        body: EditPageBody {
+                            tags: Maybe::Set(tags),
+                            ..Default::default()
+                        },
+                        bypass_filter: false,
+                        bypass_tag_check: false,
+                    },
+                )
+                .await?;
+            }
+            BulkPageOperation::Move {
+                category,
+                leave_redirects,
+            } => {
+                let (current_category, page_slug) = split_category_name(&slug);
+                if current_category == category {
+                    debug!("Page ID {page_id} is already in category '{category}'");
+                    return Ok(());
+                }
+
+                PageService::r#move(
+                    ctx,
+                    MovePage {
+                        site_id,
+                        page: Reference::Id(page_id),
+                        last_revision_id,
+                        new_slug: join_category(category, page_slug),
+                        revision_comments,
+                        user_id,
+                        leave_redirect: *leave_redirects,
+                        rewrite_backlinks: false,
+                    },
+                )
+                .await?;
+            }
+            BulkPageOperation::Delete => {
+                PageService::delete(
+                    ctx,
+                    DeletePage {
+                        site_id,
+                        page: Reference::Id(page_id),
+                        last_revision_id,
+                        revision_comments,
+                        user_id,
+                    },
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a bulk operation has completed or been canceled.
+fn is_finished(bulk_operation: &PageBulkOperationModel) -> bool {
+    bulk_operation.completed_at.is_some() || bulk_operation.canceled_at.is_some()
+}
+
+/// Gets the range of `page_ids` to process in the next batch.
+///
+/// Returns `None` if the bulk operation is finished.
+fn next_batch(
+    bulk_operation: &PageBulkOperationModel,
+    batch_size: usize,
+) -> Option<Range<usize>> {
+    if is_finished(bulk_operation) {
+        return None;
+    }
+
+    let start = bulk_operation.position as usize;
+    let end = (start + batch_size).min(bulk_operation.page_ids.len());
+    Some(start..end)
+}
+
+/// Builds the update to a bulk operation's progress after processing a batch.
+///
+/// The batch covered pages up to `end`, with `results` for each page in it.
+fn record_batch(
+    bulk_operation: &PageBulkOperationModel,
+    end: usize,
+    results: Vec<(i64, Result<()>)>,
+) -> Result<page_bulk_operation::ActiveModel> {
+    let mut failures: Vec<BulkPageFailure> =
+        serde_json::from_value(bulk_operation.failures.clone())?;
+    let mut succeeded = bulk_operation.succeeded;
+
+    for (page_id, result) in results {
+        match result {
+            Ok(()) => succeeded += 1,
+            Err(error) => failures.push(BulkPageFailure {
+                page_id,
+                code: error.code(),
+                message: error.to_string(),
+            }),
+        }
+    }
+
+    let done = end >= bulk_operation.page_ids.len();
+    Ok(page_bulk_operation::ActiveModel {
+        bulk_operation_id: Set(bulk_operation.bulk_operation_id),
+        updated_at: Set(Some(now())),
+        completed_at: Set(if done { Some(now()) } else { None }),
+        position: Set(end as i32),
+        succeeded: Set(succeeded),
+        failures: Set(serde_json::to_value(&failures)?),
+        ..Default::default()
+    })
+}
+
+/// Applies tag additions and removals, returning the sorted, deduplicated tags.
+fn retag(mut tags: Vec<String>, add: &[String], remove: &[String]) -> Vec<String> {
+    tags.retain(|tag| !remove.contains(tag));
+    tags.extend(add.iter().cloned());
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+#[test]
+fn bulk_operation_steps() {
+    let mut bulk_operation = PageBulkOperationModel {
+        bulk_operation_id: 1,
+        created_at: now(),
+        updated_at: None,
+        completed_at: None,
+        canceled_at: None,
+        site_id: 1,
+        user_id: 1,
+        operation: serde_json::to_value(BulkPageOperation::Delete).unwrap(),
+        revision_comments: String::new(),
+        page_ids: vec![10, 11, 12, 13, 14],
+        position: 0,
+        succeeded: 0,
+        failures: serde_json::json!([]),
+    };
+
+    // Runs a step where the given pages fail, updating the model like the database would
+    macro_rules! step {
+        ($failed:expr) => {{
+            let batch = next_batch(&bulk_operation, 2).expect("Operation finished early");
+            let results = bulk_operation.page_ids[batch.clone()]
+                .iter()
+                .map(|&page_id| {
+                    let result = if $failed.contains(&page_id) {
+                        Err(Error::PageNotFound)
+                    } else {
+                        Ok(())
+                    };
+
+                    (page_id, result)
+                })
+                .collect();
+
+            let model = record_batch(&bulk_operation, batch.end, results).unwrap();
+            bulk_operation.position = model.position.unwrap();
+            bulk_operation.succeeded = model.succeeded.unwrap();
+            bulk_operation.failures = model.failures.unwrap();
+            bulk_operation.completed_at = model.completed_at.unwrap();
+        }};
+    }
+
+    step!([11]);
+    assert_eq!(bulk_operation.position, 2);
+    assert_eq!(bulk_operation.succeeded, 1);
+    assert!(bulk_operation.completed_at.is_none());
+
+    step!([] as [i64; 0]);
+    assert_eq!(bulk_operation.position, 4);
+    assert_eq!(bulk_operation.succeeded, 3);
+
+    step!([14]);
+    assert_eq!(bulk_operation.position, 5);
+    assert_eq!(bulk_operation.succeeded, 3);
+    assert!(bulk_operation.completed_at.is_some());
+    assert!(next_batch(&bulk_operation, 2).is_none());
+
+    // Failures are kept across steps
+    let failures: Vec<BulkPageFailure> =
+        serde_json::from_value(bulk_operation.failures.clone()).unwrap();
+    let failed_pages = failures.iter().map(|f| f.page_id).collect::<Vec<_>>();
+    assert_eq!(failed_pages, [11, 14]);
+    assert!(failures
+        .iter()
+        .all(|f| f.code == Error::PageNotFound.code()));
+
+    // Canceled operations stop partway through
+    bulk_operation.position = 2;
+    bulk_operation.completed_at = None;
+    bulk_operation.canceled_at = Some(now());
+    assert!(is_finished(&bulk_operation));
+    assert!(next_batch(&bulk_operation, 2).is_none());
+}
+
+#[test]
+fn bulk_retag() {
+    let tags = vec![str!("scp"), str!("keter"), str!("euclid"), str!("scp")];
+    assert_eq!(
+        retag(tags, &[str!("alive"), str!("keter")], &[str!("euclid")]),
+        ["alive", "keter", "scp"],
+    );
+}
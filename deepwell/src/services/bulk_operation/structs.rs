@@ -0,0 +1,87 @@
+/*
+ * services/bulk_operation/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateBulkOperation {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub selection: BulkPageSelection,
+    pub operation: BulkPageOperation,
+    pub revision_comments: String,
+}
+
+/// Which pages a bulk operation applies to.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum BulkPageSelection {
+    /// An explicit list of pages.
+    Pages { page_ids: Vec<i64> },
+
+    /// All non-deleted pages matching these conditions.
+    ///
+    /// Pages must be in one of the listed categories (or any,
+    /// if none are listed), and have all of the listed tags.
+    Query {
+        #[serde(default)]
+        categories: Vec<String>,
+
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+/// The change to make to each selected page.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum BulkPageOperation {
+    /// Adds and removes tags from each page.
+    Retag {
+        #[serde(default)]
+        add: Vec<String>,
+
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+
+    /// Moves each page to another category, keeping its name.
+    Move {
+        category: String,
+
+        #[serde(default)]
+        leave_redirects: bool,
+    },
+
+    /// Deletes each page.
+    Delete,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetBulkOperation {
+    pub site_id: i64,
+    pub bulk_operation_id: i64,
+}
+
+/// A page which could not be changed by a bulk operation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BulkPageFailure {
+    pub page_id: i64,
+    pub code: i32,
+    pub message: String,
+}
@@ -19,20 +19,87 @@
  */
 
 use super::prelude::*;
+use crate::models::page::Model as PageModel;
 use crate::models::page_category::{
     self, Entity as PageCategory, Model as PageCategoryModel,
 };
+use crate::services::bulk_operation::{
+    BulkPageOperation, BulkPageSelection, CreateBulkOperation,
+};
+use crate::services::file::License;
+use crate::services::score::ScoreType;
+use crate::services::{BulkOperationService, PageService, ScoreService, SiteService};
+use crate::types::PageOrder;
+use serde_json::Value as JsonValue;
+use wikidot_normalize::normalize;
 
 #[derive(Debug)]
 pub struct CategoryService;
 
 impl CategoryService {
-    /// Internal method to create a category.
+    /// Creates a new category with the given settings.
+    ///
+    /// If a deleted category with this slug exists, it is restored instead.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        mut slug: String,
+        CategorySettings {
+            layout,
+            default_license,
+            scorer,
+            template_slug,
+            use_templates,
+            permissions,
+        }: CategorySettings,
+    ) -> Result<PageCategoryModel> {
+        let txn = ctx.transaction();
+        normalize_category(&mut slug)?;
+        let existing = Self::get_any(ctx, site_id, &slug).await?;
+        let mut model = match existing {
+            None => page_category::ActiveModel {
+                site_id: Set(site_id),
+                slug: Set(slug),
+                ..Default::default()
+            },
+            Some(ref category) if category.deleted_at.is_some() => {
+                info!(
+                    "Restoring deleted category ID {} for slug '{}'",
+                    category.category_id, category.slug,
+                );
+
+                page_category::ActiveModel {
+                    category_id: Set(category.category_id),
+                    updated_at: Set(Some(now())),
+                    deleted_at: Set(None),
+                    ..Default::default()
+                }
+            }
+            Some(_) => {
+                error!("Category '{slug}' already exists in site ID {site_id}");
+                return Err(Error::PageCategoryExists);
+            }
+        };
+
+        model.layout = Set(layout.map(|l| str!(l.value())));
+        model.default_license = Set(validate_license(default_license)?);
+        model.scorer = Set(validate_scorer(scorer)?);
+        model.template_slug = Set(normalize_template(template_slug));
+        model.use_templates = Set(use_templates);
+        model.permissions = Set(permissions.map(serde_json::to_value).transpose()?);
+
+        let category = match existing {
+            None => model.insert(txn).await?,
+            Some(_) => model.update(txn).await?,
+        };
+
+        Ok(category)
+    }
+
+    /// Internal method to create a category on-demand with default settings.
     ///
-    /// In addition to only returning the bare ID,
-    /// it also does not check for conflicts before
-    /// attempting to insert.
-    async fn create(
+    /// This does not check for conflicts before attempting to insert.
+    async fn create_implicit(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         slug: &str,
@@ -48,6 +115,248 @@ impl CategoryService {
         Ok(category)
     }
 
+    /// Updates the settings for a category.
+    ///
+    /// If the slug is changed, then a bulk operation is started to move
+    /// all the pages in the category to have the new category prefix.
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+        input: UpdateCategoryBody,
+        user_id: i64,
+        leave_redirects: bool,
+    ) -> Result<UpdateCategoryOutput> {
+        let txn = ctx.transaction();
+        let category = Self::get(ctx, site_id, reference).await?;
+        let mut model = page_category::ActiveModel {
+            category_id: Set(category.category_id),
+            ..Default::default()
+        };
+
+        let mut renamed = false;
+        if let Maybe::Set(mut new_slug) = input.slug {
+            normalize_category(&mut new_slug)?;
+
+            if new_slug != category.slug {
+                if category.slug == "_default" || new_slug == "_default" {
+                    error!("Cannot rename category '{}' to '{new_slug}'", category.slug);
+                    return Err(Error::PageCategoryDefault);
+                }
+
+                if Self::get_any(ctx, site_id, &new_slug).await?.is_some() {
+                    error!("Category '{new_slug}' already exists in site ID {site_id}");
+                    return Err(Error::PageCategoryExists);
+                }
+
+                model.slug = Set(new_slug);
+                renamed = true;
+            }
+        }
+
+        if let Maybe::Set(layout) = input.layout {
+            model.layout = Set(layout.map(|l| str!(l.value())));
+        }
+
+        if let Maybe::Set(default_license) = input.default_license {
+            model.default_license = Set(validate_license(default_license)?);
+        }
+
+        if let Maybe::Set(scorer) = input.scorer {
+            model.scorer = Set(validate_scorer(scorer)?);
+        }
+
+        if let Maybe::Set(template_slug) = input.template_slug {
+            model.template_slug = Set(normalize_template(template_slug));
+        }
+
+        if let Maybe::Set(use_templates) = input.use_templates {
+            model.use_templates = Set(use_templates);
+        }
+
+        if let Maybe::Set(permissions) = input.permissions {
+            model.permissions = Set(permissions.map(serde_json::to_value).transpose()?);
+        }
+
+        model.updated_at = Set(Some(now()));
+        let new_category = model.update(txn).await?;
+
+        // Now that the category has its new name,
+        // move all of its pages to match.
+        //
+        // Since the category row has already been renamed,
+        // each page move will find it rather than creating a new one.
+        let bulk_operation_id = if renamed {
+            let bulk_operation_id = Self::queue_move_pages(
+                ctx,
+                site_id,
+                &category,
+                &new_category.slug,
+                user_id,
+                leave_redirects,
+            )
+            .await?;
+
+            Some(bulk_operation_id)
+        } else {
+            None
+        };
+
+        Ok(UpdateCategoryOutput {
+            category: new_category,
+            bulk_operation_id,
+        })
+    }
+
+    /// Deletes a category.
+    ///
+    /// This is only permitted if the category has no pages in it.
+    /// Pages can be moved elsewhere first using `move_pages()`.
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+    ) -> Result<PageCategoryModel> {
+        let txn = ctx.transaction();
+        let category = Self::get(ctx, site_id, reference).await?;
+
+        if category.slug == "_default" {
+            error!("Cannot delete the _default category for site ID {site_id}");
+            return Err(Error::PageCategoryDefault);
+        }
+
+        let pages = Self::get_pages(ctx, site_id, category.category_id).await?;
+        if !pages.is_empty() {
+            error!(
+                "Cannot delete category ID {}, it still has {} pages",
+                category.category_id,
+                pages.len(),
+            );
+            return Err(Error::PageCategoryNotEmpty);
+        }
+
+        let model = page_category::ActiveModel {
+            category_id: Set(category.category_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        let category = model.update(txn).await?;
+        Ok(category)
+    }
+
+    /// Starts a bulk operation to move all the pages in one category to another.
+    ///
+    /// The destination category is created if it does not already exist.
+    pub async fn move_pages(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        from: Reference<'_>,
+        mut to: String,
+        user_id: i64,
+        leave_redirects: bool,
+    ) -> Result<MoveCategoryPagesOutput> {
+        let category = Self::get(ctx, site_id, from).await?;
+        normalize_category(&mut to)?;
+
+        if category.slug == to {
+            error!("Source and destination categories are the same: {to}");
+            return Err(Error::PageCategoryExists);
+        }
+
+        let PageCategoryModel { category_id, .. } =
+            Self::get_or_create(ctx, site_id, &to).await?;
+
+        let bulk_operation_id = Self::queue_move_pages(
+            ctx,
+            site_id,
+            &category,
+            &to,
+            user_id,
+            leave_redirects,
+        )
+        .await?;
+
+        Ok(MoveCategoryPagesOutput {
+            category_id,
+            bulk_operation_id,
+        })
+    }
+
+    /// Queues a bulk operation moving the pages currently in a category.
+    ///
+    /// Returns the ID of the bulk operation.
+    async fn queue_move_pages(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category: &PageCategoryModel,
+        new_category_slug: &str,
+        user_id: i64,
+        leave_redirects: bool,
+    ) -> Result<i64> {
+        let pages = Self::get_pages(ctx, site_id, category.category_id).await?;
+        info!(
+            "Moving {} pages from category '{}' to '{}' in site ID {}",
+            pages.len(),
+            category.slug,
+            new_category_slug,
+            site_id,
+        );
+
+        let bulk_operation = BulkOperationService::create(
+            ctx,
+            CreateBulkOperation {
+                site_id,
+                user_id,
+                selection: BulkPageSelection::Pages {
+                    page_ids: pages.iter().map(|page| page.page_id).collect(),
+                },
+                operation: BulkPageOperation::Move {
+                    category: str!(new_category_slug),
+                    leave_redirects,
+                },
+                revision_comments: format!(
+                    "Moving all pages from category '{}' to '{}'",
+                    category.slug, new_category_slug,
+                ),
+            },
+        )
+        .await?;
+
+        Ok(bulk_operation.bulk_operation_id)
+    }
+
+    /// Gets all non-deleted pages in this category.
+    async fn get_pages(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category_id: i64,
+    ) -> Result<Vec<PageModel>> {
+        PageService::get_all(
+            ctx,
+            site_id,
+            Some(Reference::Id(category_id)),
+            Some(false),
+            PageOrder::default(),
+        )
+        .await
+    }
+
+    /// Gets the default license for files uploaded to pages in this category.
+    ///
+    /// Falls back to the site's default license if the category has none.
+    pub async fn get_default_license(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category_id: i64,
+    ) -> Result<Option<License>> {
+        let category = Self::get_direct(ctx, category_id, true).await?;
+        match category.default_license {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => SiteService::get_default_license(ctx, site_id).await,
+        }
+    }
+
     pub async fn get_optional(
         ctx: &ServiceContext<'_>,
         site_id: i64,
@@ -63,6 +372,7 @@ impl CategoryService {
             .filter(
                 Condition::all()
                     .add(page_category::Column::SiteId.eq(site_id))
+                    .add(page_category::Column::DeletedAt.is_null())
                     .add(condition),
             )
             .one(txn)
@@ -80,16 +390,61 @@ impl CategoryService {
         find_or_error!(Self::get_optional(ctx, site_id, reference), PageCategory)
     }
 
+    /// Gets a category by ID, optionally including deleted categories.
+    ///
+    /// Pages which have been deleted may still reference a deleted category.
+    pub async fn get_direct(
+        ctx: &ServiceContext<'_>,
+        category_id: i64,
+        allow_deleted: bool,
+    ) -> Result<PageCategoryModel> {
+        let txn = ctx.transaction();
+        let category = PageCategory::find_by_id(category_id).one(txn).await?;
+        match category {
+            Some(category) if allow_deleted || category.deleted_at.is_none() => {
+                Ok(category)
+            }
+            _ => Err(Error::PageCategoryNotFound),
+        }
+    }
+
+    /// Gets a category by slug, including deleted categories.
+    async fn get_any(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<Option<PageCategoryModel>> {
+        let txn = ctx.transaction();
+        let category = PageCategory::find()
+            .filter(
+                Condition::all()
+                    .add(page_category::Column::SiteId.eq(site_id))
+                    .add(page_category::Column::Slug.eq(slug)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(category)
+    }
+
     pub async fn get_or_create(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         slug: &str,
     ) -> Result<PageCategoryModel> {
-        let category =
-            match Self::get_optional(ctx, site_id, Reference::from(slug)).await? {
-                Some(category) => category,
-                None => Self::create(ctx, site_id, slug).await?,
-            };
+        let category = match Self::get_any(ctx, site_id, slug).await? {
+            // Category exists, use it
+            Some(category) if category.deleted_at.is_none() => category,
+
+            // Category was deleted, restore it with default settings
+            Some(_) => {
+                Self::create(ctx, site_id, str!(slug), CategorySettings::default())
+                    .await?
+            }
+
+            // No category, make a new one
+            None => Self::create_implicit(ctx, site_id, slug).await?,
+        };
 
         Ok(category)
     }
@@ -101,7 +456,11 @@ impl CategoryService {
         let txn = ctx.transaction();
 
         let categories = PageCategory::find()
-            .filter(page_category::Column::SiteId.eq(site_id))
+            .filter(
+                Condition::all()
+                    .add(page_category::Column::SiteId.eq(site_id))
+                    .add(page_category::Column::DeletedAt.is_null()),
+            )
             .order_by_asc(page_category::Column::Slug)
             .all(txn)
             .await?;
@@ -109,3 +468,42 @@ impl CategoryService {
         Ok(categories)
     }
 }
+
+fn normalize_category(slug: &mut String) -> Result<()> {
+    normalize(slug);
+
+    if slug.is_empty() {
+        error!("Cannot use empty category slug");
+        return Err(Error::PageSlugEmpty);
+    }
+
+    Ok(())
+}
+
+fn normalize_template(template_slug: Option<String>) -> Option<String> {
+    template_slug.map(|mut slug| {
+        normalize(&mut slug);
+        slug
+    })
+}
+
+fn validate_license(license: Option<License>) -> Result<Option<JsonValue>> {
+    match license {
+        None => Ok(None),
+        Some(mut license) => {
+            license.validate()?;
+            Ok(Some(serde_json::to_value(license)?))
+        }
+    }
+}
+
+fn validate_scorer(scorer: Option<ScoreType>) -> Result<Option<String>> {
+    match scorer {
+        None => Ok(None),
+        Some(scorer) => {
+            // Ensure there is an implementation for this score type
+            ScoreService::get_scorer_for_type(scorer)?;
+            Ok(Some(str!(scorer.name())))
+        }
+    }
+}
@@ -18,10 +18,128 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::types::Reference;
+use crate::models::page_category::Model as PageCategoryModel;
+use crate::services::file::License;
+use crate::services::score::ScoreType;
+use crate::types::{Maybe, Reference};
+use ftml::layout::Layout;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetCategory<'a> {
     pub site: Reference<'a>,
     pub category: Reference<'a>,
 }
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateCategory<'a> {
+    pub site: Reference<'a>,
+    pub slug: String,
+
+    #[serde(flatten)]
+    pub settings: CategorySettings,
+}
+
+/// Per-category settings, which override the site-wide ones.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CategorySettings {
+    pub layout: Option<Layout>,
+    pub default_license: Option<License>,
+    pub scorer: Option<ScoreType>,
+    pub template_slug: Option<String>,
+    pub use_templates: bool,
+    pub permissions: Option<CategoryPermissions>,
+}
+
+impl Default for CategorySettings {
+    fn default() -> Self {
+        CategorySettings {
+            layout: None,
+            default_license: None,
+            scorer: None,
+            template_slug: None,
+            use_templates: true,
+            permissions: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateCategory<'a> {
+    pub site: Reference<'a>,
+    pub category: Reference<'a>,
+    pub user_id: i64,
+
+    /// If the category is renamed, whether to leave redirects
+    /// at the old slugs of the pages which were moved.
+    #[serde(default)]
+    pub leave_redirects: bool,
+
+    #[serde(flatten)]
+    pub body: UpdateCategoryBody,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct UpdateCategoryBody {
+    pub slug: Maybe<String>,
+    pub layout: Maybe<Option<Layout>>,
+    pub default_license: Maybe<Option<License>>,
+    pub scorer: Maybe<Option<ScoreType>>,
+    pub template_slug: Maybe<Option<String>>,
+    pub use_templates: Maybe<bool>,
+    pub permissions: Maybe<Option<CategoryPermissions>>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct UpdateCategoryOutput {
+    #[serde(flatten)]
+    pub category: PageCategoryModel,
+
+    /// The bulk operation moving the category's pages, if it was renamed.
+    pub bulk_operation_id: Option<i64>,
+}
+
+/// Which users may perform each action on pages in a category.
+///
+/// Actions which are not listed use the site's settings instead.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CategoryPermissions {
+    pub view: Option<PermissionLevel>,
+    pub create: Option<PermissionLevel>,
+    pub edit: Option<PermissionLevel>,
+    pub rename: Option<PermissionLevel>,
+    pub r#move: Option<PermissionLevel>,
+    pub delete: Option<PermissionLevel>,
+    pub files: Option<PermissionLevel>,
+    pub tags: Option<PermissionLevel>,
+}
+
+/// The least privileged group of users allowed to perform an action.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionLevel {
+    Anyone,
+    Registered,
+    Members,
+    Moderators,
+    Admins,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MoveCategoryPages<'a> {
+    pub site: Reference<'a>,
+    pub from: Reference<'a>,
+    pub to: String,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub leave_redirects: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MoveCategoryPagesOutput {
+    pub category_id: i64,
+    pub bulk_operation_id: i64,
+}
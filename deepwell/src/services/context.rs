@@ -21,18 +21,20 @@
 use crate::api::ServerState;
 use crate::config::Config;
 use crate::locales::Localizations;
-use crate::services::blob::MimeAnalyzer;
+use crate::metrics::Metrics;
+use crate::services::blob::{BlobStorage, MimeAnalyzer};
 use crate::services::error::Result;
 use redis::aio::MultiplexedConnection as RedisMultiplexedConnection;
+use redis::AsyncCommands;
 use rsmq_async::PooledRsmq;
-use s3::bucket::Bucket;
 use sea_orm::DatabaseTransaction;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone)]
 pub struct ServiceContext<'txn> {
     state: ServerState,
     transaction: &'txn DatabaseTransaction,
+    invalidations: PendingInvalidations,
 }
 
 impl<'txn> ServiceContext<'txn> {
@@ -41,12 +43,39 @@ impl<'txn> ServiceContext<'txn> {
     //
     //       For our endpoints, this is managed in the wrapper macro in api.rs
     pub fn new(state: &ServerState, transaction: &'txn DatabaseTransaction) -> Self {
+        Self::with_invalidations(state, transaction, PendingInvalidations::default())
+    }
+
+    /// Creates a context whose cache invalidations are collected in the given list.
+    ///
+    /// The caller must run them after committing, see `PendingInvalidations`.
+    pub fn with_invalidations(
+        state: &ServerState,
+        transaction: &'txn DatabaseTransaction,
+        invalidations: PendingInvalidations,
+    ) -> Self {
         ServiceContext {
             state: Arc::clone(state),
             transaction,
+            invalidations,
         }
     }
 
+    /// Creates a context for a savepoint within this context's transaction.
+    ///
+    /// Cache invalidations are shared with the parent, since they can only
+    /// run once the outer transaction commits.
+    pub fn savepoint<'sp>(
+        &self,
+        savepoint: &'sp DatabaseTransaction,
+    ) -> ServiceContext<'sp> {
+        ServiceContext::with_invalidations(
+            &self.state,
+            savepoint,
+            self.invalidations.clone(),
+        )
+    }
+
     // Getters
     #[inline]
     pub fn state(&self) -> ServerState {
@@ -88,12 +117,72 @@ impl<'txn> ServiceContext<'txn> {
     }
 
     #[inline]
-    pub fn s3_bucket(&self) -> &Bucket {
-        &self.state.s3_bucket
+    pub fn metrics(&self) -> &Metrics {
+        &self.state.metrics
+    }
+
+    #[inline]
+    pub fn blob_storage(&self) -> &dyn BlobStorage {
+        self.state.blob_storage.as_ref()
     }
 
     #[inline]
     pub fn transaction(&self) -> &'txn DatabaseTransaction {
         self.transaction
     }
+
+    #[inline]
+    pub fn invalidations(&self) -> PendingInvalidations {
+        self.invalidations.clone()
+    }
+
+    /// Removes this key from Redis, once the transaction has committed.
+    pub fn invalidate_after_commit(&self, key: String) {
+        self.invalidations.push(key);
+    }
+}
+
+/// Redis cache keys to remove once a transaction has committed.
+///
+/// Cached data must not be removed before the change it reflects is committed,
+/// or a concurrent request could still read the old rows and cache them again.
+#[derive(Debug, Clone, Default)]
+pub struct PendingInvalidations(Arc<Mutex<Vec<String>>>);
+
+impl PendingInvalidations {
+    fn push(&self, key: String) {
+        self.0
+            .lock()
+            .expect("Invalidation list lock poisoned")
+            .push(key);
+    }
+
+    /// Removes all the keys collected.
+    ///
+    /// This must only be called after the transaction has committed. Failures are
+    /// logged rather than returned, since the change itself has already been made.
+    pub async fn run(self, state: &ServerState) {
+        let mut keys = self
+            .0
+            .lock()
+            .map(|mut keys| std::mem::take(&mut *keys))
+            .expect("Invalidation list lock poisoned");
+
+        if keys.is_empty() {
+            return;
+        }
+
+        keys.sort();
+        keys.dedup();
+        debug!("Removing {} cache keys after commit", keys.len());
+
+        let result = match state.redis.get_multiplexed_tokio_connection().await {
+            Ok(mut redis) => redis.del::<_, ()>(keys).await,
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = result {
+            error!("Unable to remove cache keys after commit: {error}");
+        }
+    }
 }
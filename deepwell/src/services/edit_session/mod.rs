@@ -0,0 +1,42 @@
+/*
+ * services/edit_session/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The edit session service, for tracking who is currently editing a page.
+//!
+//! Like on Wikidot, only one user may edit a page at a time. A session is
+//! started when a user opens the editor, and kept alive by heartbeats until
+//! it is released or expires. Sessions are stored in Redis, as they are
+//! short-lived and do not need to survive a restart.
+//!
+//! Separately, the contents of the editor can be autosaved as a draft,
+//! which is kept in the database until the edit is submitted or discarded.
+//! This allows work to be restored if the editor is closed unexpectedly.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::EditSessionService;
+pub use self::structs::*;
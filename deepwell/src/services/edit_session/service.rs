@@ -0,0 +1,444 @@
+/*
+ * services/edit_session/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_draft::{
+    self, Entity as PageDraftEntity, Model as PageDraftModel,
+};
+use crate::services::audit::AuditEvent;
+use crate::services::{AuditService, PageRevisionService, PageService, TextService};
+use redis::{AsyncCommands, Script};
+use time::OffsetDateTime;
+
+/// Starts an edit session, unless another user already has one.
+///
+/// If the requesting user already has a session, it is extended instead,
+/// keeping its original start time. Returns the session for the page,
+/// which belongs to another user if the start failed.
+const START_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if current then
+    if string.match(current, '^(%d+):') == ARGV[1] then
+        redis.call('PEXPIRE', KEYS[1], ARGV[3])
+    end
+    return current
+end
+
+local value = ARGV[1] .. ':' .. ARGV[2]
+redis.call('SET', KEYS[1], value, 'PX', ARGV[3])
+return value
+"#;
+
+/// Extends an edit session, if it belongs to the given user.
+///
+/// Returns the session, or nil if the user does not have it.
+const HEARTBEAT_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if current and string.match(current, '^(%d+):') == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return current
+end
+
+return nil
+"#;
+
+/// Ends an edit session, if it belongs to the given user.
+const RELEASE_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if current and string.match(current, '^(%d+):') == ARGV[1] then
+    redis.call('DEL', KEYS[1])
+    return 1
+end
+
+return 0
+"#;
+
+#[derive(Debug)]
+pub struct EditSessionService;
+
+impl EditSessionService {
+    // Session methods
+
+    /// Starts editing a page.
+    ///
+    /// # Errors
+    /// Returns `Error::PageEditLocked` if another user is editing the page,
+    /// with the details of their session.
+    pub async fn start(
+        ctx: &ServiceContext<'_>,
+        EditSessionKey {
+            site_id,
+            page_id,
+            user_id,
+        }: EditSessionKey,
+    ) -> Result<StartEditSessionOutput> {
+        info!("Starting edit session for page ID {page_id} by user ID {user_id}");
+
+        // Ensure the page is in this site
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+
+        let ttl = ctx.config().edit_session_ttl;
+        let mut redis = ctx.redis_connect().await?;
+        let value: String = Script::new(START_SCRIPT)
+            .key(session_key(page_id))
+            .arg(user_id)
+            .arg(unix_millis(now()))
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut redis)
+            .await?;
+
+        let session = parse_session(page_id, &value, now() + ttl)
+            .ok_or(Error::EditSessionNotFound)?;
+
+        if session.user_id != user_id {
+            warn!(
+                "Page ID {page_id} is already being edited by user ID {}",
+                session.user_id,
+            );
+            return Err(Error::PageEditLocked(session));
+        }
+
+        let draft = Self::get_draft_direct(ctx, page_id, user_id).await?;
+        Ok(StartEditSessionOutput { session, draft })
+    }
+
+    /// Keeps a user's edit session from expiring.
+    ///
+    /// # Errors
+    /// Returns `Error::EditSessionNotFound` if the user does not have
+    /// a session, for instance if it expired or was force-released.
+    pub async fn heartbeat(
+        ctx: &ServiceContext<'_>,
+        EditSessionKey {
+            page_id, user_id, ..
+        }: EditSessionKey,
+    ) -> Result<EditSession> {
+        debug!("Heartbeat for edit session on page ID {page_id} by user ID {user_id}");
+
+        let ttl = ctx.config().edit_session_ttl;
+        let mut redis = ctx.redis_connect().await?;
+        let value: Option<String> = Script::new(HEARTBEAT_SCRIPT)
+            .key(session_key(page_id))
+            .arg(user_id)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut redis)
+            .await?;
+
+        match value {
+            Some(value) => parse_session(page_id, &value, now() + ttl)
+                .ok_or(Error::EditSessionNotFound),
+            None => Err(Error::EditSessionNotFound),
+        }
+    }
+
+    /// Ends a user's edit session.
+    pub async fn release(
+        ctx: &ServiceContext<'_>,
+        EditSessionKey {
+            page_id, user_id, ..
+        }: EditSessionKey,
+    ) -> Result<()> {
+        info!("Releasing edit session for page ID {page_id} by user ID {user_id}");
+
+        if Self::release_direct(ctx, page_id, user_id).await? {
+            Ok(())
+        } else {
+            Err(Error::EditSessionNotFound)
+        }
+    }
+
+    /// Ends whichever edit session is active on a page.
+    ///
+    /// This is for moderators to unlock a page which someone has left
+    /// open in their editor, so it is recorded in the audit log.
+    pub async fn force_release(
+        ctx: &ServiceContext<'_>,
+        ForceReleaseEditSession {
+            site_id,
+            page_id,
+            moderator_id,
+        }: ForceReleaseEditSession,
+    ) -> Result<EditSession> {
+        info!("Force-releasing edit session for page ID {page_id} by user ID {moderator_id}");
+
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+
+        let session = Self::get_direct(ctx, page_id)
+            .await?
+            .ok_or(Error::EditSessionNotFound)?;
+
+        if !Self::release_direct(ctx, page_id, session.user_id).await? {
+            // Released on its own in the meantime
+            return Err(Error::EditSessionNotFound);
+        }
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            moderator_id,
+            Some(session.user_id),
+            AuditEvent::EditSessionRelease,
+            &session,
+        )
+        .await?;
+
+        Ok(session)
+    }
+
+    /// Gets the active edit session for a page, if any.
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        GetEditSession { site_id, page_id }: GetEditSession,
+    ) -> Result<Option<EditSession>> {
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+        Self::get_direct(ctx, page_id).await
+    }
+
+    /// Gets the active edit session for a page, without checking which site it is in.
+    pub async fn get_direct(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Option<EditSession>> {
+        let key = session_key(page_id);
+        let mut redis = ctx.redis_connect().await?;
+        let (value, ttl_ms): (Option<String>, i64) = redis::pipe()
+            .get(&key)
+            .pttl(&key)
+            .query_async(&mut redis)
+            .await?;
+
+        let expires_at = now() + time::Duration::milliseconds(ttl_ms.max(0));
+        Ok(value.and_then(|value| parse_session(page_id, &value, expires_at)))
+    }
+
+    /// Called after a user has submitted their edit to a page.
+    ///
+    /// Ends their edit session and discards their draft, if they had them.
+    pub async fn finish(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<()> {
+        Self::release_direct(ctx, page_id, user_id).await?;
+        Self::delete_draft_direct(ctx, page_id, user_id).await?;
+        Ok(())
+    }
+
+    async fn release_direct(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<bool> {
+        let mut redis = ctx.redis_connect().await?;
+        let released: bool = Script::new(RELEASE_SCRIPT)
+            .key(session_key(page_id))
+            .arg(user_id)
+            .invoke_async(&mut redis)
+            .await?;
+
+        Ok(released)
+    }
+
+    // Draft methods
+
+    /// Autosaves the contents of a user's editor, replacing any previous draft.
+    pub async fn save_draft(
+        ctx: &ServiceContext<'_>,
+        SavePageDraft {
+            site_id,
+            page_id,
+            user_id,
+            revision_id,
+            title,
+            alt_title,
+            wikitext,
+        }: SavePageDraft,
+    ) -> Result<PageDraftModel> {
+        debug!("Saving draft for page ID {page_id} by user ID {user_id}");
+
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+
+        // Ensure the revision is of this page
+        let revision = PageRevisionService::get_direct(ctx, revision_id).await?;
+        if revision.page_id != page_id {
+            error!("Revision ID {revision_id} is not of page ID {page_id}");
+            return Err(Error::PageRevisionNotFound);
+        }
+
+        let txn = ctx.transaction();
+        let wikitext_hash = TextService::create(ctx, wikitext).await?;
+        let existing = PageDraftEntity::find_by_id((page_id, user_id))
+            .one(txn)
+            .await?;
+
+        let model = page_draft::ActiveModel {
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            revision_id: Set(revision_id),
+            title: Set(title),
+            alt_title: Set(alt_title),
+            wikitext_hash: Set(wikitext_hash.to_vec()),
+            ..Default::default()
+        };
+
+        let draft = match existing {
+            Some(_) => {
+                page_draft::ActiveModel {
+                    updated_at: Set(Some(now())),
+                    ..model
+                }
+                .update(txn)
+                .await?
+            }
+            None => model.insert(txn).await?,
+        };
+
+        Ok(draft)
+    }
+
+    /// Gets a user's autosaved draft for a page, if any.
+    pub async fn get_draft(
+        ctx: &ServiceContext<'_>,
+        EditSessionKey {
+            site_id,
+            page_id,
+            user_id,
+        }: EditSessionKey,
+    ) -> Result<Option<PageDraft>> {
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+        Self::get_draft_direct(ctx, page_id, user_id).await
+    }
+
+    async fn get_draft_direct(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<Option<PageDraft>> {
+        let txn = ctx.transaction();
+        let Some(draft) = PageDraftEntity::find_by_id((page_id, user_id))
+            .one(txn)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let wikitext = TextService::get(ctx, &draft.wikitext_hash).await?;
+        Ok(Some(PageDraft {
+            revision_id: draft.revision_id,
+            title: draft.title,
+            alt_title: draft.alt_title,
+            wikitext,
+            created_at: draft.created_at,
+            updated_at: draft.updated_at,
+        }))
+    }
+
+    /// Discards a user's autosaved draft for a page.
+    pub async fn delete_draft(
+        ctx: &ServiceContext<'_>,
+        EditSessionKey {
+            site_id,
+            page_id,
+            user_id,
+        }: EditSessionKey,
+    ) -> Result<()> {
+        info!("Deleting draft for page ID {page_id} by user ID {user_id}");
+
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+        Self::delete_draft_direct(ctx, page_id, user_id).await?;
+        Ok(())
+    }
+
+    async fn delete_draft_direct(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        PageDraftEntity::delete_by_id((page_id, user_id))
+            .exec(txn)
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn session_key(page_id: i64) -> String {
+    format!("page-edit:{page_id}")
+}
+
+fn unix_millis(timestamp: OffsetDateTime) -> i64 {
+    (timestamp.unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+/// Parses an edit session stored in Redis, as `{user_id}:{started_at}`.
+///
+/// The start time is in milliseconds since the Unix epoch.
+/// Invalid values are treated as if there were no session.
+fn parse_session(
+    page_id: i64,
+    value: &str,
+    expires_at: OffsetDateTime,
+) -> Option<EditSession> {
+    let parsed = value.split_once(':').and_then(|(user_id, started_at)| {
+        let user_id = user_id.parse::<i64>().ok()?;
+        let started_at = started_at.parse::<i64>().ok()?;
+        let started_at =
+            OffsetDateTime::from_unix_timestamp_nanos(i128::from(started_at) * 1_000_000)
+                .ok()?;
+
+        Some((user_id, started_at))
+    });
+
+    match parsed {
+        Some((user_id, started_at)) => Some(EditSession {
+            page_id,
+            user_id,
+            started_at,
+            expires_at,
+        }),
+        None => {
+            error!(
+                "Invalid edit session value in Redis for page ID {page_id}: {value:?}"
+            );
+            None
+        }
+    }
+}
+
+#[test]
+fn edit_session_values() {
+    let expires_at = OffsetDateTime::UNIX_EPOCH;
+    let started_at = now().replace_nanosecond(0).unwrap();
+    let value = format!("42:{}", unix_millis(started_at));
+
+    assert_eq!(session_key(100), "page-edit:100");
+    assert_eq!(
+        parse_session(100, &value, expires_at),
+        Some(EditSession {
+            page_id: 100,
+            user_id: 42,
+            started_at,
+            expires_at,
+        }),
+    );
+    assert_eq!(parse_session(100, "42", expires_at), None);
+    assert_eq!(parse_session(100, "x:100", expires_at), None);
+}
@@ -0,0 +1,92 @@
+/*
+ * services/edit_session/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use time::OffsetDateTime;
+
+/// A user's active edit session for a page.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EditSession {
+    pub page_id: i64,
+    pub user_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub started_at: OffsetDateTime,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct EditSessionKey {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetEditSession {
+    pub site_id: i64,
+    pub page_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StartEditSessionOutput {
+    pub session: EditSession,
+
+    /// The user's autosaved draft for this page, if any.
+    pub draft: Option<PageDraft>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct ForceReleaseEditSession {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub moderator_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SavePageDraft {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+
+    /// The revision the user is editing.
+    ///
+    /// This is passed as `last_revision_id` when submitting the draft,
+    /// so changes made since then are merged.
+    pub revision_id: i64,
+    pub title: String,
+    pub alt_title: Option<String>,
+    pub wikitext: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PageDraft {
+    pub revision_id: i64,
+    pub title: String,
+    pub alt_title: Option<String>,
+    pub wikitext: String,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub updated_at: Option<OffsetDateTime>,
+}
@@ -19,13 +19,19 @@
  */
 
 use crate::hash::{blob_hash_to_hex, BlobHash};
+use crate::services::edit_session::EditSession;
+use crate::services::page::MergeConflict;
+use crate::services::tag::TagViolation;
 use filemagic::FileMagicError;
 use jsonrpsee::types::error::ErrorObjectOwned;
 use reqwest::Error as ReqwestError;
 use s3::error::S3Error;
 use sea_orm::{error::DbErr, TransactionError};
+use std::io;
+use strum_macros::IntoStaticStr;
 use thiserror::Error as ThisError;
 use unic_langid::LanguageIdentifierError;
+use webauthn_rs::prelude::WebauthnError;
 
 pub use std::error::Error as StdError;
 
@@ -33,7 +39,7 @@ pub type StdResult<T, E> = std::result::Result<T, E>;
 pub type Result<T> = StdResult<T, Error>;
 
 /// Wrapper error for possible failure modes from service methods.
-#[derive(ThisError, Debug)]
+#[derive(ThisError, IntoStaticStr, Debug)]
 pub enum Error {
     // Error passed straight to ErrorObjectOwned without conversion
     #[error("{0}")]
@@ -72,6 +78,9 @@ pub enum Error {
     #[error("Magic library error: {0}")]
     Magic(#[from] FileMagicError),
 
+    #[error("WebAuthn error: {0}")]
+    Webauthn(#[from] WebauthnError),
+
     #[error("One-time password error: {0}")]
     Otp(#[from] rust_otp::Error),
 
@@ -84,6 +93,9 @@ pub enum Error {
     #[error("S3 service failed to respond properly")]
     S3Response,
 
+    #[error("Local blob storage error: {0}")]
+    LocalStorage(#[from] io::Error),
+
     #[error("Email verification error: {}", .0.as_ref().unwrap_or(&str!("<unspecified>")))]
     EmailVerification(Option<String>),
 
@@ -135,6 +147,45 @@ pub enum Error {
     #[error("Revision ID passed for this operation is not the latest")]
     NotLatestRevisionId,
 
+    #[error("Edit conflicts with changes made since the given revision")]
+    EditConflict(Vec<MergeConflict>),
+
+    #[error("Page is currently being edited by another user")]
+    PageEditLocked(EditSession),
+
+    #[error("Cannot add parent, as it would create a cycle in the page hierarchy")]
+    PageParentCycle,
+
+    #[error("Cannot delete a page category which still contains pages")]
+    PageCategoryNotEmpty,
+
+    #[error("Cannot rename or delete the default page category")]
+    PageCategoryDefault,
+
+    #[error("Page tags do not satisfy the site's tag rules")]
+    PageTagsInvalid(Vec<TagViolation>),
+
+    #[error("Tag group minimum and maximum are invalid")]
+    SiteTagGroupLimitsInvalid,
+
+    #[error("Bulk page operation has already finished or been canceled")]
+    BulkOperationFinished,
+
+    #[error("Cannot restore a non-deleted site")]
+    SiteNotDeleted,
+
+    #[error("Site was deleted too long ago to be restored")]
+    SiteRestoreExpired,
+
+    #[error("Vote fraud report has already been reviewed")]
+    VoteFraudReportReviewed,
+
+    #[error("Rate-limited requests must include the IP address or session token")]
+    RateLimitIdentityMissing,
+
+    #[error("Rate limit exceeded, retry after {retry_after} ms")]
+    RateLimitExceeded { retry_after: u64 },
+
     #[error("The regular expression found in the database is invalid")]
     FilterRegexInvalid(regex::Error),
 
@@ -156,6 +207,24 @@ pub enum Error {
     #[error("Cannot restore a non-deleted file")]
     FileNotDeleted,
 
+    #[error("File license identifier is not recognized")]
+    FileLicenseUnknown,
+
+    #[error("File licensing field is invalid: {0}")]
+    FileLicensingInvalid(&'static str),
+
+    #[error("No file license was given, and the site has no default license")]
+    FileLicenseMissing,
+
+    #[error("Site archive is invalid: {0}")]
+    SiteArchiveInvalid(&'static str),
+
+    #[error("Import backup is missing or invalid: {0}")]
+    ImportDumpInvalid(&'static str),
+
+    #[error("Site join policy requires a password, but none was set")]
+    SiteJoinPasswordMissing,
+
     #[error("Cannot restore a non-deleted page")]
     PageNotDeleted,
 
@@ -266,6 +335,45 @@ pub enum Error {
     #[error("Text item does not exist")]
     TextNotFound,
 
+    #[error("Notification does not exist")]
+    NotificationNotFound,
+
+    #[error("WebAuthn credential does not exist")]
+    WebauthnCredentialNotFound,
+
+    #[error("Session does not exist")]
+    SessionNotFound,
+
+    #[error("Import does not exist")]
+    ImportNotFound,
+
+    #[error("Page attribution does not exist")]
+    PageAttributionNotFound,
+
+    #[error("Page redirect does not exist")]
+    PageRedirectNotFound,
+
+    #[error("Edit session does not exist or has expired")]
+    EditSessionNotFound,
+
+    #[error("Site tag does not exist")]
+    SiteTagNotFound,
+
+    #[error("Site tag group does not exist")]
+    SiteTagGroupNotFound,
+
+    #[error("Bulk page operation does not exist")]
+    BulkOperationNotFound,
+
+    #[error("Vote fraud report does not exist")]
+    VoteFraudReportNotFound,
+
+    #[error("Site import does not exist")]
+    SiteImportNotFound,
+
+    #[error("Site export does not exist")]
+    SiteExportNotFound,
+
     #[error("Cannot perform, user already exists")]
     UserExists,
 
@@ -293,13 +401,37 @@ pub enum Error {
     #[error("Cannot perform, custom domain already exists")]
     CustomDomainExists,
 
+    #[error("Cannot perform, WebAuthn credential already exists")]
+    WebauthnCredentialExists,
+
+    #[error("Cannot perform, user is already a site member")]
+    SiteMemberExists,
+
+    #[error("Cannot perform, site application already exists")]
+    SiteApplicationExists,
+
+    #[error("Cannot perform, site invitation already exists")]
+    SiteInvitationExists,
+
+    #[error("Cannot perform, page category already exists")]
+    PageCategoryExists,
+
+    #[error("Cannot perform, site tag already exists")]
+    SiteTagExists,
+
+    #[error("Cannot perform, site tag group already exists")]
+    SiteTagGroupExists,
+
     #[error("Cannot perform this action because you are blocked by the user")]
     UserBlockedUser,
 
     #[error("Cannot perform this action because you are blocked by the site")]
     SiteBlockedUser,
 
-    #[error("The rate limit for an external API has been reached")]
+    #[error("The site's join policy does not allow this action")]
+    SiteJoinNotAllowed,
+
+    #[error("The rate limit has been reached, try again later")]
     RateLimited,
 }
 
@@ -342,6 +474,19 @@ impl Error {
             Error::MessageDraftNotFound => 2015,
             Error::BlobNotFound => 2016,
             Error::TextNotFound => 2017,
+            Error::NotificationNotFound => 2018,
+            Error::WebauthnCredentialNotFound => 2019,
+            Error::SessionNotFound => 2020,
+            Error::ImportNotFound => 2021,
+            Error::PageAttributionNotFound => 2022,
+            Error::PageRedirectNotFound => 2023,
+            Error::EditSessionNotFound => 2024,
+            Error::SiteTagNotFound => 2025,
+            Error::SiteTagGroupNotFound => 2026,
+            Error::BulkOperationNotFound => 2027,
+            Error::VoteFraudReportNotFound => 2028,
+            Error::SiteImportNotFound => 2029,
+            Error::SiteExportNotFound => 2030,
 
             // 2100 -- Existing data
             Error::UserExists => 2100,
@@ -353,6 +498,13 @@ impl Error {
             Error::FileExists => 2106,
             Error::FilterExists => 2107,
             Error::CustomDomainExists => 2108,
+            Error::WebauthnCredentialExists => 2109,
+            Error::SiteMemberExists => 2110,
+            Error::SiteApplicationExists => 2111,
+            Error::SiteInvitationExists => 2112,
+            Error::PageCategoryExists => 2113,
+            Error::SiteTagExists => 2114,
+            Error::SiteTagGroupExists => 2115,
 
             // 3000 - Server errors, unexpected
             Error::RateLimited => 3000,
@@ -373,6 +525,8 @@ impl Error {
             Error::Otp(_) => 3205,
             Error::Redis(_) => 3206,
             Error::Rsmq(_) => 3207,
+            Error::LocalStorage(_) => 3208,
+            Error::Webauthn(_) => 3209,
 
             // 4000 - Client, request errors
             //        BadRequest is pretty general, avoid it except for rare weird cases
@@ -407,6 +561,25 @@ impl Error {
             Error::BlobBlacklisted(_) => 4028,
             Error::BlobCannotBlacklistExisting => 4029,
             Error::NotLatestRevisionId => 4030,
+            Error::FileLicenseUnknown => 4031,
+            Error::FileLicensingInvalid(_) => 4032,
+            Error::FileLicenseMissing => 4033,
+            Error::SiteArchiveInvalid(_) => 4034,
+            Error::ImportDumpInvalid(_) => 4035,
+            Error::RateLimitExceeded { .. } => 4036,
+            Error::SiteJoinPasswordMissing => 4037,
+            Error::EditConflict(_) => 4038,
+            Error::PageEditLocked(_) => 4039,
+            Error::PageParentCycle => 4040,
+            Error::PageCategoryNotEmpty => 4041,
+            Error::PageCategoryDefault => 4042,
+            Error::PageTagsInvalid(_) => 4043,
+            Error::SiteTagGroupLimitsInvalid => 4044,
+            Error::BulkOperationFinished => 4045,
+            Error::SiteNotDeleted => 4046,
+            Error::SiteRestoreExpired => 4047,
+            Error::VoteFraudReportReviewed => 4048,
+            Error::RateLimitIdentityMissing => 4049,
 
             // 4100 -- Localization
             Error::LocaleInvalid(_) => 4100,
@@ -424,6 +597,7 @@ impl Error {
             // 4300 -- Relationship conflicts
             Error::SiteBlockedUser => 4300,
             Error::UserBlockedUser => 4301,
+            Error::SiteJoinNotAllowed => 4302,
 
             // 5000 - Authentication, permission, or role errors
             Error::InvalidAuthentication => 5000,
@@ -464,16 +638,27 @@ impl Error {
                 "length": length,
                 "maximum": maximum,
             }),
+            Error::RateLimitExceeded { retry_after } => json!({
+                "retry_after": retry_after,
+            }),
 
             // Emit as-is
             Error::EmailVerification(value) => json!(value),
+            Error::FileLicensingInvalid(field) => json!(field),
+            Error::SiteArchiveInvalid(part) => json!(part),
+            Error::ImportDumpInvalid(part) => json!(part),
+            Error::EditConflict(conflicts) => json!(conflicts),
+            Error::PageEditLocked(session) => json!(session),
+            Error::PageTagsInvalid(violations) => json!(violations),
 
             // Emit as a Debug string
             Error::Cryptography(value) => json!(format!("{value:?}")),
             Error::Database(value) => json!(format!("{value:?}")),
+            Error::LocalStorage(value) => json!(format!("{value:?}")),
             Error::LocaleInvalid(value) => json!(format!("{value:?}")),
             Error::Magic(value) => json!(format!("{value:?}")),
             Error::Otp(value) => json!(format!("{value:?}")),
+            Error::Webauthn(value) => json!(format!("{value:?}")),
             Error::Serde(value) => json!(format!("{value:?}")),
             Error::S3Service(value) => json!(format!("{value:?}")),
             Error::WebRequest(value) => json!(format!("{value:?}")),
@@ -0,0 +1,570 @@
+/*
+ * services/file/licensing.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Structured licensing information for files.
+//!
+//! Each file revision stores its licensing as JSON. This module defines the
+//! shape of that data and the checks it must pass before being stored, so
+//! that every file on a site can be properly attributed.
+
+use super::prelude::*;
+use crate::utils::escape_xml;
+use reqwest::Url;
+
+pub const MAXIMUM_LICENSE_NAME_LENGTH: usize = 128;
+pub const MAXIMUM_AUTHOR_LENGTH: usize = 256;
+pub const MAXIMUM_URL_LENGTH: usize = 2048;
+pub const MAXIMUM_NOTES_LENGTH: usize = 2000;
+
+/// A license with an SPDX identifier that we recognize.
+#[derive(Debug)]
+struct KnownLicense {
+    id: &'static str,
+    name: &'static str,
+    url: &'static str,
+    attribution: bool,
+}
+
+macro_rules! known {
+    ($id:expr, $name:expr, $url:expr, $attribution:expr $(,)?) => {
+        KnownLicense {
+            id: $id,
+            name: $name,
+            url: $url,
+            attribution: $attribution,
+        }
+    };
+}
+
+/// The SPDX licenses which may be used for files.
+///
+/// This is not the full SPDX list, most of which are software licenses
+/// that are not meaningful for wiki content. Anything else can be
+/// described using `License::Custom`.
+const KNOWN_LICENSES: &[KnownLicense] = &[
+    known!(
+        "CC0-1.0",
+        "CC0 1.0",
+        "https://creativecommons.org/publicdomain/zero/1.0/",
+        false,
+    ),
+    known!(
+        "CC-PDDC",
+        "Public Domain",
+        "https://creativecommons.org/licenses/publicdomain/",
+        false,
+    ),
+    known!(
+        "CC-BY-2.0",
+        "CC BY 2.0",
+        "https://creativecommons.org/licenses/by/2.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-2.5",
+        "CC BY 2.5",
+        "https://creativecommons.org/licenses/by/2.5/",
+        true,
+    ),
+    known!(
+        "CC-BY-3.0",
+        "CC BY 3.0",
+        "https://creativecommons.org/licenses/by/3.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-4.0",
+        "CC BY 4.0",
+        "https://creativecommons.org/licenses/by/4.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-SA-2.0",
+        "CC BY-SA 2.0",
+        "https://creativecommons.org/licenses/by-sa/2.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-SA-2.5",
+        "CC BY-SA 2.5",
+        "https://creativecommons.org/licenses/by-sa/2.5/",
+        true,
+    ),
+    known!(
+        "CC-BY-SA-3.0",
+        "CC BY-SA 3.0",
+        "https://creativecommons.org/licenses/by-sa/3.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-SA-4.0",
+        "CC BY-SA 4.0",
+        "https://creativecommons.org/licenses/by-sa/4.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-NC-3.0",
+        "CC BY-NC 3.0",
+        "https://creativecommons.org/licenses/by-nc/3.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-NC-4.0",
+        "CC BY-NC 4.0",
+        "https://creativecommons.org/licenses/by-nc/4.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-NC-SA-3.0",
+        "CC BY-NC-SA 3.0",
+        "https://creativecommons.org/licenses/by-nc-sa/3.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-NC-SA-4.0",
+        "CC BY-NC-SA 4.0",
+        "https://creativecommons.org/licenses/by-nc-sa/4.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-ND-4.0",
+        "CC BY-ND 4.0",
+        "https://creativecommons.org/licenses/by-nd/4.0/",
+        true,
+    ),
+    known!(
+        "CC-BY-NC-ND-4.0",
+        "CC BY-NC-ND 4.0",
+        "https://creativecommons.org/licenses/by-nc-nd/4.0/",
+        true,
+    ),
+    known!(
+        "GFDL-1.3-or-later",
+        "GNU Free Documentation License 1.3",
+        "https://www.gnu.org/licenses/fdl-1.3.html",
+        true,
+    ),
+    known!(
+        "OFL-1.1",
+        "SIL Open Font License 1.1",
+        "https://openfontlicense.org/",
+        true,
+    ),
+    known!(
+        "MIT",
+        "MIT License",
+        "https://opensource.org/license/mit",
+        true
+    ),
+    known!(
+        "Apache-2.0",
+        "Apache License 2.0",
+        "https://www.apache.org/licenses/LICENSE-2.0",
+        true,
+    ),
+    known!(
+        "Unlicense",
+        "The Unlicense",
+        "https://unlicense.org/",
+        false
+    ),
+];
+
+/// The license a work is made available under.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum License {
+    /// A license with an SPDX identifier, such as `CC-BY-SA-4.0`.
+    Spdx { id: String },
+
+    /// A license which is not in our list of SPDX identifiers.
+    Custom {
+        name: String,
+
+        #[serde(default)]
+        url: Option<String>,
+
+        #[serde(default)]
+        attribution_required: bool,
+    },
+}
+
+impl License {
+    fn known(&self) -> Option<&'static KnownLicense> {
+        match self {
+            License::Spdx { id } => KNOWN_LICENSES.iter().find(|known| known.id == id),
+            License::Custom { .. } => None,
+        }
+    }
+
+    /// The human-readable name of this license.
+    pub fn name(&self) -> &str {
+        match self {
+            License::Spdx { id } => self.known().map_or(id, |known| known.name),
+            License::Custom { name, .. } => name,
+        }
+    }
+
+    /// Where the text of this license can be read, if known.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            License::Spdx { .. } => self.known().map(|known| known.url),
+            License::Custom { url, .. } => url.as_deref(),
+        }
+    }
+
+    /// Whether works under this license must credit their author.
+    pub fn requires_attribution(&self) -> bool {
+        match self {
+            License::Spdx { .. } => self.known().is_none_or(|known| known.attribution),
+            License::Custom {
+                attribution_required,
+                ..
+            } => *attribution_required,
+        }
+    }
+
+    /// Validates the license, normalizing it in-place.
+    ///
+    /// SPDX identifiers are matched case-insensitively,
+    /// and replaced with their canonical form.
+    pub fn validate(&mut self) -> Result<()> {
+        match self {
+            License::Spdx { id } => {
+                let trimmed = id.trim();
+                match KNOWN_LICENSES
+                    .iter()
+                    .find(|known| known.id.eq_ignore_ascii_case(trimmed))
+                {
+                    Some(known) => {
+                        *id = str!(known.id);
+                        Ok(())
+                    }
+                    None => {
+                        error!("Unknown SPDX license identifier: {id}");
+                        Err(Error::FileLicenseUnknown)
+                    }
+                }
+            }
+            License::Custom { name, url, .. } => {
+                let trimmed = name.trim();
+                if trimmed.is_empty() || trimmed.len() > MAXIMUM_LICENSE_NAME_LENGTH {
+                    error!("Custom license name is empty or too long");
+                    return Err(Error::FileLicensingInvalid("license.name"));
+                }
+
+                *name = str!(trimmed);
+                validate_url(url, "license.url")
+            }
+        }
+    }
+}
+
+/// The licensing information attached to a file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileLicensing {
+    pub license: License,
+
+    /// Who created the work.
+    ///
+    /// If absent, the file is the uploader's own work.
+    #[serde(default)]
+    pub author: Option<String>,
+
+    /// Where the work was retrieved from, if it was not made by the uploader.
+    #[serde(default)]
+    pub source_url: Option<String>,
+
+    /// The work this file was adapted from, as a title or URL.
+    #[serde(default)]
+    pub derivative_of: Option<String>,
+
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl FileLicensing {
+    /// Licensing for a file which is the uploader's own work.
+    pub fn own_work(license: License) -> Self {
+        FileLicensing {
+            license,
+            author: None,
+            source_url: None,
+            derivative_of: None,
+            notes: None,
+        }
+    }
+
+    /// Validates the licensing information, normalizing it in-place.
+    ///
+    /// Blank optional fields are cleared, and the remainder are trimmed.
+    pub fn validate(&mut self) -> Result<()> {
+        self.license.validate()?;
+        normalize_field(&mut self.author, MAXIMUM_AUTHOR_LENGTH, "author")?;
+        validate_url(&mut self.source_url, "source_url")?;
+        normalize_field(&mut self.derivative_of, MAXIMUM_URL_LENGTH, "derivative_of")?;
+        normalize_field(&mut self.notes, MAXIMUM_NOTES_LENGTH, "notes")?;
+
+        // If the work came from elsewhere, we cannot credit the uploader for it.
+        if self.source_url.is_some()
+            && self.author.is_none()
+            && self.license.requires_attribution()
+        {
+            error!("File is from an external source, but its license requires an author");
+            return Err(Error::FileLicensingInvalid("author"));
+        }
+
+        Ok(())
+    }
+
+    /// Produces a plain-text attribution line for this file.
+    ///
+    /// The `uploader` is credited if no author is specified.
+    pub fn attribution_text(&self, file_name: &str, uploader: &str) -> String {
+        let author = self.author.as_deref().unwrap_or(uploader);
+        let mut output = format!("\"{file_name}\" by {author}");
+
+        if let Some(ref source_url) = self.source_url {
+            str_write!(output, ", from {source_url}");
+        }
+
+        if let Some(ref original) = self.derivative_of {
+            str_write!(output, ", adapted from {original}");
+        }
+
+        str_write!(output, ", licensed under {}", self.license.name());
+        if let Some(url) = self.license.url() {
+            str_write!(output, " ({url})");
+        }
+
+        output.push('.');
+        if let Some(ref notes) = self.notes {
+            str_write!(output, " {notes}");
+        }
+
+        output
+    }
+
+    /// Produces an HTML attribution line for this file.
+    ///
+    /// Same as `attribution_text()`, but with the source and license linked.
+    pub fn attribution_html(&self, file_name: &str, uploader: &str) -> String {
+        let author = self.author.as_deref().unwrap_or(uploader);
+        let mut output = match self.source_url {
+            Some(ref source_url) => format!(
+                "\"<a href=\"{}\">{}</a>\"",
+                escape_xml(source_url),
+                escape_xml(file_name),
+            ),
+            None => format!("\"{}\"", escape_xml(file_name)),
+        };
+
+        str_write!(output, " by {}", escape_xml(author));
+        if let Some(ref original) = self.derivative_of {
+            str_write!(output, ", adapted from {}", escape_xml(original));
+        }
+
+        let license_name = escape_xml(self.license.name());
+        match self.license.url() {
+            Some(url) => {
+                str_write!(
+                    output,
+                    ", licensed under <a href=\"{}\">{license_name}</a>",
+                    escape_xml(url),
+                );
+            }
+            None => {
+                str_write!(output, ", licensed under {license_name}");
+            }
+        }
+
+        output.push('.');
+        if let Some(ref notes) = self.notes {
+            str_write!(output, " {}", escape_xml(notes));
+        }
+
+        output
+    }
+}
+
+/// Trims an optional field, clearing it if blank, and checks its length.
+fn normalize_field(
+    field: &mut Option<String>,
+    maximum: usize,
+    name: &'static str,
+) -> Result<()> {
+    match field.as_deref().map(str::trim) {
+        None => Ok(()),
+        Some("") => {
+            *field = None;
+            Ok(())
+        }
+        Some(value) if value.len() > maximum => {
+            error!(
+                "Licensing field {name} is too long ({} > {maximum})",
+                value.len(),
+            );
+            Err(Error::FileLicensingInvalid(name))
+        }
+        Some(value) => {
+            let value = str!(value);
+            *field = Some(value);
+            Ok(())
+        }
+    }
+}
+
+/// Like `normalize_field()`, but also requires the value be a web URL.
+fn validate_url(field: &mut Option<String>, name: &'static str) -> Result<()> {
+    normalize_field(field, MAXIMUM_URL_LENGTH, name)?;
+
+    if let Some(ref value) = field {
+        let valid = match Url::parse(value) {
+            Ok(url) => matches!(url.scheme(), "http" | "https"),
+            Err(_) => false,
+        };
+
+        if !valid {
+            error!("Licensing field {name} is not a valid web URL: {value}");
+            return Err(Error::FileLicensingInvalid(name));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn validate_licensing() {
+    // Normalization
+    let mut licensing = FileLicensing {
+        license: License::Spdx {
+            id: str!("cc-by-sa-4.0"),
+        },
+        author: Some(str!("  Dr. Bright ")),
+        source_url: Some(str!("")),
+        derivative_of: None,
+        notes: Some(str!("   ")),
+    };
+    licensing
+        .validate()
+        .expect("Valid licensing failed validation");
+    assert_eq!(
+        licensing,
+        FileLicensing {
+            license: License::Spdx {
+                id: str!("CC-BY-SA-4.0"),
+            },
+            author: Some(str!("Dr. Bright")),
+            source_url: None,
+            derivative_of: None,
+            notes: None,
+        },
+    );
+
+    // Failures
+    macro_rules! check_err {
+        ($licensing:expr, $pattern:pat $(,)?) => {{
+            let mut licensing = $licensing;
+            let result = licensing.validate();
+            assert!(
+                matches!(result, Err($pattern)),
+                "Unexpected validation result: {result:?}",
+            );
+        }};
+    }
+
+    check_err!(
+        FileLicensing::own_work(License::Spdx {
+            id: str!("CC-BY-SA-9.0"),
+        }),
+        Error::FileLicenseUnknown,
+    );
+    check_err!(
+        FileLicensing::own_work(License::Custom {
+            name: str!(" "),
+            url: None,
+            attribution_required: false,
+        }),
+        Error::FileLicensingInvalid("license.name"),
+    );
+    check_err!(
+        FileLicensing {
+            source_url: Some(str!("https://example.com/image.png")),
+            ..FileLicensing::own_work(License::Spdx {
+                id: str!("CC-BY-4.0"),
+            })
+        },
+        Error::FileLicensingInvalid("author"),
+    );
+    check_err!(
+        FileLicensing {
+            author: Some(str!("Someone")),
+            source_url: Some(str!("javascript:alert(1)")),
+            ..FileLicensing::own_work(License::Spdx {
+                id: str!("CC0-1.0"),
+            })
+        },
+        Error::FileLicensingInvalid("source_url"),
+    );
+
+    // Public domain works from elsewhere don't need an author
+    let mut licensing = FileLicensing {
+        source_url: Some(str!("https://example.com/image.png")),
+        ..FileLicensing::own_work(License::Spdx {
+            id: str!("CC0-1.0"),
+        })
+    };
+    licensing
+        .validate()
+        .expect("Valid licensing failed validation");
+}
+
+#[test]
+fn attribution() {
+    let licensing = FileLicensing {
+        license: License::Spdx {
+            id: str!("CC-BY-SA-3.0"),
+        },
+        author: Some(str!("Dr. Clef")),
+        source_url: Some(str!("https://example.com/?a=1&b=2")),
+        derivative_of: Some(str!("SCP-173 original photo")),
+        notes: None,
+    };
+
+    assert_eq!(
+        licensing.attribution_text("scp-173.jpg", "uploader"),
+        "\"scp-173.jpg\" by Dr. Clef, from https://example.com/?a=1&b=2, adapted from SCP-173 original photo, licensed under CC BY-SA 3.0 (https://creativecommons.org/licenses/by-sa/3.0/).",
+    );
+    assert_eq!(
+        licensing.attribution_html("scp-173.jpg", "uploader"),
+        "\"<a href=\"https://example.com/?a=1&amp;b=2\">scp-173.jpg</a>\" by Dr. Clef, adapted from SCP-173 original photo, licensed under <a href=\"https://creativecommons.org/licenses/by-sa/3.0/\">CC BY-SA 3.0</a>.",
+    );
+
+    let licensing = FileLicensing::own_work(License::Custom {
+        name: str!("All rights reserved"),
+        url: None,
+        attribution_required: true,
+    });
+
+    assert_eq!(
+        licensing.attribution_text("logo.png", "site-admin"),
+        "\"logo.png\" by site-admin, licensed under All rights reserved.",
+    );
+}
@@ -18,6 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::licensing::{FileLicensing, License};
 use super::prelude::*;
 use crate::hash::slice_to_blob_hash;
 use crate::models::file::{self, Entity as File, Model as FileModel};
@@ -32,12 +33,16 @@ use crate::services::file_revision::{
     GetFileRevision,
 };
 use crate::services::filter::{FilterClass, FilterType};
-use crate::services::{BlobService, FileRevisionService, FilterService, PageService};
+use crate::services::{
+    BlobService, CategoryService, FileRevisionService, FilterService, PageService,
+    UserService,
+};
 use crate::types::FileOrder;
-use crate::utils::regex_replace_in_place;
+use crate::utils::{escape_xml, regex_replace_in_place};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use sea_orm::ActiveValue;
+use std::collections::hash_map::{Entry, HashMap};
 
 pub const MAXIMUM_FILE_NAME_LENGTH: usize = 256;
 
@@ -82,6 +87,18 @@ impl FileService {
             Self::run_filter(ctx, site_id, Some(&name)).await?;
         }
 
+        // Fall back to the category's (or site's) default license
+        let licensing = match licensing {
+            Some(licensing) => licensing,
+            None => match Self::get_default_license(ctx, site_id, page_id).await? {
+                Some(license) => FileLicensing::own_work(license),
+                None => {
+                    error!("No licensing for file, and page ID {page_id} has no default");
+                    return Err(Error::FileLicenseMissing);
+                }
+            },
+        };
+
         // Finish blob upload
         let FinalizeBlobUploadOutput {
             s3_hash,
@@ -534,6 +551,16 @@ impl FileService {
             ..
         } = target_revision;
 
+        // Revisions from before licensing was structured cannot be restored,
+        // so the current licensing is kept in that case.
+        let licensing = match serde_json::from_value(licensing) {
+            Ok(licensing) => Maybe::Set(licensing),
+            Err(error) => {
+                warn!("Target revision has unstructured licensing, not rolling back: {error}");
+                Maybe::Unset
+            }
+        };
+
         let mut new_name = ActiveValue::NotSet;
 
         // Check name change
@@ -568,7 +595,7 @@ impl FileService {
             body: CreateFileRevisionBody {
                 name: Maybe::Set(name),
                 blob: Maybe::Set(blob),
-                licensing: Maybe::Set(licensing),
+                licensing,
                 page_id: Maybe::Unset, // rollbacks should never move files
             },
         };
@@ -659,6 +686,84 @@ impl FileService {
         Ok(files)
     }
 
+    /// Produces attribution for all the files on a page.
+    ///
+    /// Files which do not name an author are credited to their uploader.
+    pub async fn get_file_attribution(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<GetFileAttributionOutput> {
+        info!("Getting file attribution for page ID {page_id} in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let files =
+            Self::get_all(ctx, site_id, page_id, Some(false), FileOrder::default())
+                .await?;
+
+        let mut attributions = Vec::with_capacity(files.len());
+        let mut html = str!("<ul class=\"wj-file-attribution\">\n");
+        let mut user_names = HashMap::new();
+
+        for file in files {
+            let first_revision = find_or_error!(
+                FileRevision::find()
+                    .filter(
+                        Condition::all()
+                            .add(file_revision::Column::FileId.eq(file.file_id))
+                            .add(file_revision::Column::RevisionNumber.eq(0)),
+                    )
+                    .one(txn),
+                FileRevision,
+            )?;
+            let last_revision =
+                FileRevisionService::get_latest(ctx, site_id, page_id, file.file_id)
+                    .await?;
+
+            let uploader_id = first_revision.user_id;
+            if let Entry::Vacant(entry) = user_names.entry(uploader_id) {
+                let user = UserService::get(ctx, Reference::Id(uploader_id)).await?;
+                entry.insert(user.name);
+            }
+            let uploader = &user_names[&uploader_id];
+
+            let licensing: Option<FileLicensing> =
+                serde_json::from_value(last_revision.licensing).ok();
+
+            let (author, text, line) = match licensing {
+                Some(ref licensing) => (
+                    licensing.author.as_ref().unwrap_or(uploader).clone(),
+                    licensing.attribution_text(&file.name, uploader),
+                    licensing.attribution_html(&file.name, uploader),
+                ),
+                None => (
+                    uploader.clone(),
+                    format!("\"{}\" by {uploader}, license unknown.", file.name),
+                    format!(
+                        "\"{}\" by {}, license unknown.",
+                        escape_xml(&file.name),
+                        escape_xml(uploader),
+                    ),
+                ),
+            };
+
+            str_writeln!(&mut html, "  <li>{line}</li>");
+            attributions.push(FileAttribution {
+                file_id: file.file_id,
+                name: file.name,
+                author,
+                licensing,
+                text,
+            });
+        }
+
+        html.push_str("</ul>\n");
+        Ok(GetFileAttributionOutput {
+            files: attributions,
+            html,
+        })
+    }
+
     /// Gets the file ID from a reference, looking up if necessary.
     ///
     /// Convenience method since this is much more common than the optional
@@ -724,6 +829,19 @@ impl FileService {
         find_or_error!(Self::get_direct_optional(ctx, file_id, allow_deleted), File)
     }
 
+    /// Gets the default license for files uploaded to this page.
+    ///
+    /// This uses the license set on the page's category,
+    /// falling back to the site's default license.
+    async fn get_default_license(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<Option<License>> {
+        let page = PageService::get_direct(ctx, page_id, false).await?;
+        CategoryService::get_default_license(ctx, site_id, page.page_category_id).await
+    }
+
     /// Checks to see if a file already exists at the name specified.
     ///
     /// If so, this method fails with `Error::FileExists`. Otherwise it returns nothing.
@@ -18,7 +18,9 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::licensing::FileLicensing;
 use crate::models::sea_orm_active_enums::FileRevisionType;
+use crate::services::blob::BlobDerivativeOutput;
 use crate::services::file_revision::{
     CreateFileRevisionOutput, CreateFirstFileRevisionOutput,
 };
@@ -34,7 +36,13 @@ pub struct CreateFile {
     pub uploaded_blob_id: String,
     pub revision_comments: String,
     pub user_id: i64,
-    pub licensing: JsonValue, // TODO
+
+    /// The licensing for this file.
+    ///
+    /// If absent, the file is taken to be the uploader's own work,
+    /// under the site's default license.
+    #[serde(default)]
+    pub licensing: Option<FileLicensing>,
 
     #[serde(default)]
     pub bypass_filter: bool,
@@ -92,6 +100,7 @@ pub struct GetFileOutput {
     pub licensing: JsonValue,
     pub revision_comments: String,
     pub hidden_fields: Vec<String>,
+    pub derivatives: Vec<BlobDerivativeOutput>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -101,6 +110,31 @@ pub struct GetPageFiles {
     pub deleted: Maybe<bool>,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetFileAttribution {
+    pub site_id: i64,
+    pub page_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileAttribution {
+    pub file_id: i64,
+    pub name: String,
+
+    /// The credited author, the uploader if the licensing doesn't name one.
+    pub author: String,
+
+    /// The file's licensing, `None` if it predates structured licensing.
+    pub licensing: Option<FileLicensing>,
+    pub text: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetFileAttributionOutput {
+    pub files: Vec<FileAttribution>,
+    pub html: String,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct EditFile {
     pub site_id: i64,
@@ -121,7 +155,7 @@ pub struct EditFile {
 #[serde(default)]
 pub struct EditFileBody {
     pub name: Maybe<String>,
-    pub licensing: Maybe<serde_json::Value>,
+    pub licensing: Maybe<FileLicensing>,
     pub uploaded_blob_id: Maybe<String>,
 
     /// Allows internal users to upload directly.
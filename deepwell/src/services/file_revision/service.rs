@@ -127,7 +127,10 @@ impl FileRevisionService {
             }
         }
 
-        if let Maybe::Set(new_licensing) = body.licensing {
+        if let Maybe::Set(mut new_licensing) = body.licensing {
+            new_licensing.validate()?;
+
+            let new_licensing = serde_json::to_value(new_licensing)?;
             if licensing != new_licensing {
                 changes.push(str!("licensing"));
                 licensing = new_licensing;
@@ -142,15 +145,14 @@ impl FileRevisionService {
         }
 
         // Validate inputs
-        // (Note that filename checks are done in FileService)
+        // (Note that filename checks are done in FileService,
+        //  and licensing is checked above as it is applied)
 
         if mime.is_empty() {
             error!("MIME type is empty");
             return Err(Error::FileMimeEmpty);
         }
 
-        // TODO validate licensing field
-
         // Run outdater
         let page_slug = Self::get_page_slug(ctx, site_id, page_id).await?;
         OutdateService::process_page_edit(ctx, site_id, page_id, &page_slug, 0).await?;
@@ -197,12 +199,16 @@ impl FileRevisionService {
             size,
             mime,
             blob_created,
-            licensing,
+            mut licensing,
             revision_comments,
         }: CreateFirstFileRevision,
     ) -> Result<CreateFirstFileRevisionOutput> {
         let txn = ctx.transaction();
 
+        // Validate inputs
+        licensing.validate()?;
+        let licensing = serde_json::to_value(licensing)?;
+
         // Run outdater
         let page_slug = Self::get_page_slug(ctx, site_id, page_id).await?;
         OutdateService::process_page_displace(ctx, site_id, page_id, &page_slug, 0)
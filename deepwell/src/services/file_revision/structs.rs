@@ -20,6 +20,7 @@
 
 use super::prelude::*;
 use crate::models::sea_orm_active_enums::FileRevisionType;
+use crate::services::file::FileLicensing;
 use crate::services::page_revision::PageRevisionCountOutput;
 use crate::types::FetchDirection;
 
@@ -39,7 +40,7 @@ pub struct CreateFileRevisionBody {
     pub page_id: Maybe<i64>, // for changing the page this file is on
     pub name: Maybe<String>,
     pub blob: Maybe<FileBlob>,
-    pub licensing: Maybe<serde_json::Value>,
+    pub licensing: Maybe<FileLicensing>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -70,7 +71,7 @@ pub struct CreateFirstFileRevision {
     pub size: i64,
     pub mime: String,
     pub blob_created: bool,
-    pub licensing: serde_json::Value,
+    pub licensing: FileLicensing,
     pub revision_comments: String,
 }
 
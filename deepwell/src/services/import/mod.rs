@@ -26,6 +26,7 @@ mod prelude {
 
 mod service;
 mod structs;
+mod wikicomma;
 
 pub use self::service::ImportService;
 pub use self::structs::*;
@@ -26,17 +26,57 @@
 //!
 //! It is for limited use during initial setup only.
 
-// TODO implement and use this service
-#![allow(dead_code)]
-
 use super::prelude::*;
+use super::wikicomma::{check_relative_path, WikicommaDump, WikicommaFileEntry};
+use crate::constants::ANONYMOUS_USER_ID;
+use crate::models::file::{self, Entity as File};
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::import_progress::{
+    self, Entity as ImportProgress, Model as ImportProgressModel,
+};
 use crate::models::page::{self, Entity as Page};
-use crate::models::page_category::Model as PageCategoryModel;
-use crate::models::sea_orm_active_enums::UserType;
+use crate::models::page_attribution::{self, Entity as PageAttribution};
+use crate::models::page_category::{
+    self, Entity as PageCategory, Model as PageCategoryModel,
+};
+use crate::models::page_parent::{self, Entity as PageParent};
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::page_vote::{self, Entity as PageVote};
+use crate::models::sea_orm_active_enums::{
+    FileRevisionType, ImportStage, PageRevisionType, UserType,
+};
 use crate::models::site::{self, Entity as Site};
 use crate::models::user::{self, Entity as User};
-use crate::services::{BlobService, CategoryService};
-use crate::utils::get_category_name;
+use crate::services::blob::FinalizeBlobUploadOutput;
+use crate::services::file::FileLicensing;
+use crate::services::job::{Job, JobService};
+use crate::services::{
+    BlobService, CategoryService, PageAttributionService, SiteService, TextService,
+};
+use crate::utils::{get_category_name, validate_locale};
+use percent_encoding::percent_decode_str;
+use sea_orm::NotSet;
+use std::collections::HashSet;
+
+/// How many files to import in each step of a wikicomma import.
+const FILE_BATCH_SIZE: usize = 100;
+
+/// How many new users to import in each step of a wikicomma import.
+///
+/// This bounds users rather than user blocks, since each may have an avatar.
+const USER_BATCH_SIZE: usize = 100;
+
+/// How many page revisions to import in each step of a wikicomma import.
+const REVISION_BATCH_SIZE: usize = 100;
+
+/// The `compiled_generator` of imported revisions, which are not rendered.
+///
+/// Only the latest revision of each page is rendered, by a `RerenderPage` job
+/// queued once all of its revisions are in.
+const NOT_COMPILED_GENERATOR: &str = "not compiled (wikicomma import)";
+
+/// How many user IDs to look up at once, to stay under the bind parameter limit.
+const USER_CHUNK_SIZE: usize = 10_000;
 
 #[derive(Debug)]
 pub struct ImportService;
@@ -68,13 +108,8 @@ impl ImportService {
         let avatar_s3_hash = match avatar {
             None => None,
             Some(bytes) => {
-                // FIXME import - uploading avatars
-                /*
-                let output = BlobService::create(ctx, &bytes).await?;
-                Some(output.hash.to_vec())
-                */
-                let _ = bytes;
-                todo!()
+                let output = BlobService::direct_upload(ctx, bytes).await?;
+                Some(output.s3_hash.to_vec())
             }
         };
 
@@ -102,16 +137,27 @@ impl ImportService {
         Ok(())
     }
 
+    /// Inserts a site, along with its site user.
+    ///
+    /// # Returns
+    /// The ID of the imported site.
     pub async fn add_site(
         ctx: &ServiceContext<'_>,
         ImportSite {
             site_id,
             created_at,
+            updated_at,
+            from_wikidot,
             name,
             slug,
+            tagline,
+            description,
+            default_page,
+            layout,
             locale,
+            default_license,
         }: ImportSite,
-    ) -> Result<()> {
+    ) -> Result<i64> {
         info!(
             "Importing site (name '{}', slug '{}', locale '{}')",
             name, slug, locale,
@@ -119,30 +165,85 @@ impl ImportService {
 
         let txn = ctx.transaction();
         let site = site::ActiveModel {
-            site_id: Set(site_id),
+            site_id: site_id.map(Set).unwrap_or(NotSet),
             created_at: Set(created_at),
-            from_wikidot: Set(true),
+            updated_at: Set(updated_at),
+            from_wikidot: Set(from_wikidot),
             name: Set(name),
+            slug: Set(slug.clone()),
+            tagline: Set(tagline),
+            description: Set(description.clone()),
+            default_page: default_page.map(Set).unwrap_or(NotSet),
+            layout: Set(layout),
+            locale: Set(locale.clone()),
+            default_license: Set(default_license),
+            ..Default::default()
+        };
+
+        let site_id = Site::insert(site).exec(txn).await?.last_insert_id;
+        SiteService::create_site_user(ctx, site_id, &slug, locale, description).await?;
+        Ok(site_id)
+    }
+
+    pub async fn add_category(
+        ctx: &ServiceContext<'_>,
+        ImportCategory {
+            site_id,
+            created_at,
+            deleted_at,
+            slug,
+            layout,
+            default_license,
+            scorer,
+            template_slug,
+            use_templates,
+            permissions,
+        }: ImportCategory,
+    ) -> Result<i64> {
+        info!("Importing category '{}' in site ID {}", slug, site_id);
+
+        let txn = ctx.transaction();
+        let category = page_category::ActiveModel {
+            site_id: Set(site_id),
+            created_at: Set(created_at),
+            deleted_at: Set(deleted_at),
             slug: Set(slug),
-            locale: Set(locale),
+            layout: Set(layout),
+            default_license: Set(default_license),
+            scorer: Set(scorer),
+            template_slug: Set(template_slug),
+            use_templates: Set(use_templates),
+            permissions: Set(permissions),
             ..Default::default()
         };
 
-        Site::insert(site).exec(txn).await?;
-        Ok(())
+        let category_id = PageCategory::insert(category)
+            .exec(txn)
+            .await?
+            .last_insert_id;
+
+        Ok(category_id)
     }
 
+    /// Inserts a page, creating its category if it does not already exist.
+    ///
+    /// # Returns
+    /// The ID of the imported page.
     pub async fn add_page(
         ctx: &ServiceContext<'_>,
         ImportPage {
             page_id,
             site_id,
             created_at,
+            updated_at,
+            deleted_at,
+            from_wikidot,
             slug,
+            layout,
             locked,
             discussion_thread_id,
         }: ImportPage,
-    ) -> Result<()> {
+    ) -> Result<i64> {
         info!("Creating page '{}' in site ID {}", slug, site_id);
 
         let txn = ctx.transaction();
@@ -154,13 +255,16 @@ impl ImportService {
 
         // Insert page row into table
         let page = page::ActiveModel {
-            page_id: Set(page_id),
+            page_id: page_id.map(Set).unwrap_or(NotSet),
             site_id: Set(site_id),
             created_at: Set(created_at),
-            from_wikidot: Set(true),
+            updated_at: Set(updated_at),
+            deleted_at: Set(deleted_at),
+            from_wikidot: Set(from_wikidot),
             slug: Set(slug),
             page_category_id: Set(category_id),
             discussion_thread_id: Set(discussion_thread_id),
+            layout: Set(layout),
             ..Default::default()
         };
 
@@ -169,13 +273,1059 @@ impl ImportService {
             // TODO
         }
 
-        Page::insert(page).exec(txn).await?;
+        let page_id = Page::insert(page).exec(txn).await?.last_insert_id;
+        Ok(page_id)
+    }
+
+    /// Inserts a page revision, and makes it the latest revision of its page.
+    ///
+    /// This means revisions for a page must be imported in order.
+    ///
+    /// # Returns
+    /// The ID of the imported revision.
+    pub async fn add_page_revision(
+        ctx: &ServiceContext<'_>,
+        ImportPageRevision {
+            page_id,
+            site_id,
+            user_id,
+            created_at,
+            updated_at,
+            from_wikidot,
+            revision_type,
+            revision_number,
+            changes,
+            wikitext_hash,
+            compiled_hash,
+            compiled_at,
+            compiled_generator,
+            comments,
+            hidden,
+            title,
+            alt_title,
+            slug,
+            tags,
+        }: ImportPageRevision,
+    ) -> Result<i64> {
+        debug!(
+            "Importing revision {} for page ID {} in site ID {}",
+            revision_number, page_id, site_id,
+        );
+
+        let txn = ctx.transaction();
+        let revision = page_revision::ActiveModel {
+            revision_type: Set(revision_type),
+            created_at: Set(created_at),
+            updated_at: Set(updated_at),
+            revision_number: Set(revision_number),
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            from_wikidot: Set(from_wikidot),
+            changes: Set(changes),
+            wikitext_hash: Set(wikitext_hash.to_vec()),
+            compiled_hash: Set(compiled_hash.to_vec()),
+            compiled_at: Set(compiled_at),
+            compiled_generator: Set(compiled_generator),
+            comments: Set(comments),
+            hidden: Set(hidden),
+            title: Set(title),
+            alt_title: Set(alt_title),
+            slug: Set(slug),
+            tags: Set(tags),
+            ..Default::default()
+        };
+
+        let revision_id = PageRevision::insert(revision)
+            .exec(txn)
+            .await?
+            .last_insert_id;
+
+        let page = page::ActiveModel {
+            page_id: Set(page_id),
+            latest_revision_id: Set(Some(revision_id)),
+            ..Default::default()
+        };
+        page.update(txn).await?;
+
+        Ok(revision_id)
+    }
+
+    pub async fn add_page_vote(
+        ctx: &ServiceContext<'_>,
+        ImportPageVote {
+            page_id,
+            user_id,
+            created_at,
+            deleted_at,
+            disabled_at,
+            disabled_by,
+            from_wikidot,
+            value,
+        }: ImportPageVote,
+    ) -> Result<()> {
+        debug!(
+            "Importing vote by user ID {} on page ID {}",
+            user_id, page_id
+        );
+
+        let txn = ctx.transaction();
+        let vote = page_vote::ActiveModel {
+            created_at: Set(created_at),
+            deleted_at: Set(deleted_at),
+            disabled_at: Set(disabled_at),
+            disabled_by: Set(disabled_by),
+            from_wikidot: Set(from_wikidot),
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            value: Set(value),
+            ..Default::default()
+        };
+
+        PageVote::insert(vote).exec(txn).await?;
+        Ok(())
+    }
+
+    pub async fn add_page_attribution(
+        ctx: &ServiceContext<'_>,
+        ImportPageAttribution {
+            page_id,
+            user_id,
+            attribution_type,
+            attribution_date,
+            created_at,
+        }: ImportPageAttribution,
+    ) -> Result<()> {
+        debug!(
+            "Importing {} attribution for user ID {} on page ID {}",
+            attribution_type, user_id, page_id,
+        );
+
+        let txn = ctx.transaction();
+        let attribution = page_attribution::ActiveModel {
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            attribution_type: Set(attribution_type),
+            attribution_date: Set(attribution_date),
+            created_at: Set(created_at),
+        };
+
+        PageAttribution::insert(attribution).exec(txn).await?;
+        Ok(())
+    }
+
+    pub async fn add_page_parent(
+        ctx: &ServiceContext<'_>,
+        ImportPageParent {
+            parent_page_id,
+            child_page_id,
+            created_at,
+        }: ImportPageParent,
+    ) -> Result<()> {
+        debug!(
+            "Importing parent relationship from page ID {} to page ID {}",
+            parent_page_id, child_page_id,
+        );
+
+        let txn = ctx.transaction();
+        let parent = page_parent::ActiveModel {
+            parent_page_id: Set(parent_page_id),
+            child_page_id: Set(child_page_id),
+            created_at: Set(created_at),
+        };
+
+        PageParent::insert(parent).exec(txn).await?;
+        Ok(())
+    }
+
+    /// Inserts a file. Its contents are added separately as file revisions.
+    ///
+    /// # Returns
+    /// The ID of the imported file.
+    pub async fn add_file(
+        ctx: &ServiceContext<'_>,
+        ImportFile {
+            file_id,
+            page_id,
+            site_id,
+            created_at,
+            updated_at,
+            deleted_at,
+            from_wikidot,
+            name,
+        }: ImportFile,
+    ) -> Result<i64> {
+        info!("Importing file '{}' on page ID {}", name, page_id);
+
+        let txn = ctx.transaction();
+        let file = file::ActiveModel {
+            file_id: file_id.map(Set).unwrap_or(NotSet),
+            created_at: Set(created_at),
+            updated_at: Set(updated_at),
+            deleted_at: Set(deleted_at),
+            from_wikidot: Set(from_wikidot),
+            name: Set(name),
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+        };
+
+        let file_id = File::insert(file).exec(txn).await?.last_insert_id;
+        Ok(file_id)
+    }
+
+    pub async fn add_file_revision(
+        ctx: &ServiceContext<'_>,
+        ImportFileRevision {
+            file_id,
+            page_id,
+            site_id,
+            user_id,
+            created_at,
+            revision_type,
+            revision_number,
+            name,
+            s3_hash,
+            mime,
+            size,
+            licensing,
+            changes,
+            comments,
+            hidden,
+        }: ImportFileRevision,
+    ) -> Result<i64> {
+        debug!(
+            "Importing revision {} for file ID {}",
+            revision_number, file_id,
+        );
+
+        let txn = ctx.transaction();
+        let revision = file_revision::ActiveModel {
+            revision_type: Set(revision_type),
+            created_at: Set(created_at),
+            revision_number: Set(revision_number),
+            file_id: Set(file_id),
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            name: Set(name),
+            s3_hash: Set(s3_hash.to_vec()),
+            mime: Set(mime),
+            size: Set(size),
+            licensing: Set(licensing),
+            changes: Set(changes),
+            comments: Set(comments),
+            hidden: Set(hidden),
+            ..Default::default()
+        };
+
+        let revision_id = FileRevision::insert(revision)
+            .exec(txn)
+            .await?
+            .last_insert_id;
+
+        Ok(revision_id)
+    }
+
+    // Wikicomma imports
+
+    /// Begins importing a wikicomma backup of Wikidot sites.
+    ///
+    /// The import runs in the background as a series of jobs, each of which
+    /// imports a small part of the backup. Users, sites, pages, and files keep
+    /// their Wikidot IDs, so anything already imported is skipped. This means
+    /// an import can be run again over the same data to fill in anything missing.
+    pub async fn start_wikicomma(
+        ctx: &ServiceContext<'_>,
+        StartWikicommaImport {
+            directory,
+            locale,
+            mut sites,
+        }: StartWikicommaImport,
+    ) -> Result<ImportProgressModel> {
+        info!("Starting import of wikicomma backup at {directory}");
+
+        let txn = ctx.transaction();
+        validate_locale(&locale)?;
+        for site in &mut sites {
+            check_relative_path(&site.directory)?;
+            validate_locale(&site.locale)?;
+            site.license.validate()?;
+        }
+
+        // Check that the backup is readable before starting
+        WikicommaDump::new(&ctx.config().site_import_path, &directory)?
+            .user_blocks()
+            .await?;
+
+        let model = import_progress::ActiveModel {
+            directory: Set(directory),
+            locale: Set(locale),
+            sites: Set(serde_json::to_value(sites)?),
+            ..Default::default()
+        };
+        let progress = model.insert(txn).await?;
+
+        let job = Job::ImportWikicomma {
+            import_id: progress.import_id,
+            step: progress.step,
+        };
+        JobService::queue_job(ctx, &job, None).await?;
+        Ok(progress)
+    }
+
+    /// Restarts an import which stopped because a step failed too many times.
+    ///
+    /// If the import is still running this is harmless, since only the
+    /// job for the current step of an import will run it.
+    pub async fn resume_wikicomma(
+        ctx: &ServiceContext<'_>,
+        import_id: i64,
+    ) -> Result<ImportProgressModel> {
+        let progress = Self::get_progress(ctx, import_id).await?;
+        if progress.stage != ImportStage::Done {
+            info!("Resuming import ID {import_id}");
+            let job = Job::ImportWikicomma {
+                import_id,
+                step: progress.step,
+            };
+            JobService::queue_job(ctx, &job, None).await?;
+        }
+
+        Ok(progress)
+    }
+
+    pub async fn get_progress_optional(
+        ctx: &ServiceContext<'_>,
+        import_id: i64,
+    ) -> Result<Option<ImportProgressModel>> {
+        let txn = ctx.transaction();
+        let progress = ImportProgress::find_by_id(import_id).one(txn).await?;
+        Ok(progress)
+    }
+
+    #[inline]
+    pub async fn get_progress(
+        ctx: &ServiceContext<'_>,
+        import_id: i64,
+    ) -> Result<ImportProgressModel> {
+        find_or_error!(Self::get_progress_optional(ctx, import_id), Import)
+    }
+
+    /// Performs the next step of a wikicomma import.
+    ///
+    /// Users are imported first, then each site in turn, with its pages
+    /// (including revisions and votes) followed by its files.
+    ///
+    /// Each step is numbered, and the job for it carries that number. If a job
+    /// is delivered more than once, or an import is resumed while it is still
+    /// running, only the job for the current step continues the import.
+    ///
+    /// # Returns
+    /// Whether there are more steps to perform.
+    pub async fn run_wikicomma_step(
+        ctx: &ServiceContext<'_>,
+        import_id: i64,
+        step: i32,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+
+        // Lock the row, so that steps of the same import run one at a time
+        let progress = ImportProgress::find_by_id(import_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::ImportNotFound)?;
+
+        if progress.step != step {
+            warn!(
+                "Import ID {import_id} is at step {}, not {step}, stopping duplicate job",
+                progress.step,
+            );
+            return Ok(false);
+        }
+
+        let sites: Vec<WikicommaSite> = serde_json::from_value(progress.sites)?;
+        let site = sites.get(progress.site_index as usize);
+        let dump =
+            WikicommaDump::new(&ctx.config().site_import_path, &progress.directory)?;
+        let position = progress.position as usize;
+
+        let mut model = import_progress::ActiveModel {
+            import_id: Set(import_id),
+            updated_at: Set(Some(now())),
+            step: Set(step + 1),
+            ..Default::default()
+        };
+
+        match progress.stage {
+            ImportStage::Users => {
+                let blocks = dump.user_blocks().await?;
+                match blocks.get(position) {
+                    Some(block) => {
+                        info!(
+                            "Importing users from '{block}' ({}/{}, import ID {import_id})",
+                            position + 1,
+                            blocks.len(),
+                        );
+
+                        let (count, done) = Self::import_wikicomma_users(
+                            ctx,
+                            &dump,
+                            block,
+                            &progress.locale,
+                        )
+                        .await?;
+
+                        // Large blocks take more than one step
+                        model.users_imported = Set(progress.users_imported + count);
+                        if done {
+                            model.position = Set(progress.position + 1);
+                        }
+                        model.total = Set(Some(blocks.len() as i32));
+                    }
+                    None => {
+                        model.stage = Set(ImportStage::Site);
+                        model.position = Set(0);
+                        model.total = Set(None);
+                    }
+                }
+            }
+            ImportStage::Site => match site {
+                Some(site) => {
+                    Self::import_wikicomma_site(ctx, site).await?;
+                    let pages = dump.pages(&site.directory).await?;
+                    model.stage = Set(ImportStage::Pages);
+                    model.position = Set(0);
+                    model.total = Set(Some(pages.len() as i32));
+                }
+                None => {
+                    info!("Finished importing wikicomma backup (import ID {import_id})");
+                    model.stage = Set(ImportStage::Done);
+                    model.completed_at = Set(Some(now()));
+                    model.total = Set(None);
+                }
+            },
+            ImportStage::Pages => {
+                let site = site.ok_or(Error::ImportDumpInvalid("sites"))?;
+                let pages = dump.pages(&site.directory).await?;
+                match pages.get(position) {
+                    Some(page) => {
+                        info!(
+                            "Importing page '{page}' in site '{}' ({}/{}, import ID {import_id})",
+                            site.slug,
+                            position + 1,
+                            pages.len(),
+                        );
+
+                        let (revisions, votes, done) =
+                            Self::import_wikicomma_page(ctx, &dump, site, page).await?;
+
+                        // Pages with long histories take more than one step
+                        model.revisions_imported =
+                            Set(progress.revisions_imported + revisions);
+                        model.votes_imported = Set(progress.votes_imported + votes);
+                        if done {
+                            model.pages_imported = Set(progress.pages_imported + 1);
+                            model.position = Set(progress.position + 1);
+                        }
+                        model.total = Set(Some(pages.len() as i32));
+                    }
+                    None => {
+                        let files = dump.read_file_map(&site.directory).await?;
+                        model.stage = Set(ImportStage::Files);
+                        model.position = Set(0);
+                        model.total = Set(Some(files.len() as i32));
+                    }
+                }
+            }
+            ImportStage::Files => {
+                let site = site.ok_or(Error::ImportDumpInvalid("sites"))?;
+                let files = dump.read_file_map(&site.directory).await?;
+                if position < files.len() {
+                    let end = files.len().min(position + FILE_BATCH_SIZE);
+                    info!(
+                        "Importing files in site '{}' ({}-{}/{}, import ID {import_id})",
+                        site.slug,
+                        position + 1,
+                        end,
+                        files.len(),
+                    );
+
+                    let count = Self::import_wikicomma_files(
+                        ctx,
+                        &dump,
+                        site,
+                        &files[position..end],
+                    )
+                    .await?;
+
+                    model.files_imported = Set(progress.files_imported + count);
+                    model.position = Set(end as i32);
+                    model.total = Set(Some(files.len() as i32));
+                } else {
+                    // Now that all pages are in, credit their authors
+                    PageAttributionService::import_wikidot_metadata(ctx, site.site_id)
+                        .await?;
+
+                    model.stage = Set(ImportStage::Site);
+                    model.site_index = Set(progress.site_index + 1);
+                    model.position = Set(0);
+                    model.total = Set(None);
+                }
+            }
+            ImportStage::Done => {
+                debug!("Import ID {import_id} is already complete");
+                return Ok(false);
+            }
+        }
+
+        let progress = model.update(txn).await?;
+        Ok(progress.stage != ImportStage::Done)
+    }
+
+    /// Imports up to `USER_BATCH_SIZE` users from one block.
+    ///
+    /// # Returns
+    /// The number of users which were added, and whether the block is finished.
+    async fn import_wikicomma_users(
+        ctx: &ServiceContext<'_>,
+        dump: &WikicommaDump,
+        block: &str,
+        locale: &str,
+    ) -> Result<(i32, bool)> {
+        let txn = ctx.transaction();
+        let mut count = 0;
+
+        for user in dump.read_user_block(block).await? {
+            if User::find_by_id(user.user_id).one(txn).await?.is_some() {
+                debug!("User ID {} was already imported", user.user_id);
+                continue;
+            }
+
+            // The rest are left for the next step, which skips those added here
+            if count as usize >= USER_BATCH_SIZE {
+                return Ok((count, false));
+            }
+
+            let conflict = User::find()
+                .filter(
+                    Condition::all()
+                        .add(
+                            Condition::any()
+                                .add(user::Column::Slug.eq(&user.username))
+                                .add(user::Column::Name.eq(&user.full_name)),
+                        )
+                        .add(user::Column::DeletedAt.is_null()),
+                )
+                .one(txn)
+                .await?;
+
+            if conflict.is_some() {
+                warn!(
+                    "User '{}' conflicts with an existing user, skipping Wikidot user ID {}",
+                    user.username, user.user_id,
+                );
+                continue;
+            }
+
+            let avatar = dump.read_avatar(user.user_id).await?;
+            Self::add_user(
+                ctx,
+                ImportUser {
+                    user_id: user.user_id,
+                    created_at: user.created_at()?,
+                    birthday: user.birthday()?,
+                    name: user.full_name,
+                    slug: user.username,
+                    email: String::new(),
+                    locale: str!(locale),
+                    avatar,
+                    real_name: profile_field(user.real_name, 300),
+                    gender: profile_field(user.gender, 100),
+                    location: profile_field(user.location, 100),
+                    biography: None,
+                    user_page: profile_field(user.website, usize::MAX),
+                },
+            )
+            .await?;
+
+            count += 1;
+        }
+
+        Ok((count, true))
+    }
+
+    async fn import_wikicomma_site(
+        ctx: &ServiceContext<'_>,
+        site: &WikicommaSite,
+    ) -> Result<()> {
+        if SiteService::exists(ctx, Reference::Id(site.site_id)).await? {
+            debug!("Site ID {} was already imported", site.site_id);
+            return Ok(());
+        }
+
+        Self::add_site(
+            ctx,
+            ImportSite {
+                site_id: Some(site.site_id),
+                created_at: now(),
+                updated_at: None,
+                from_wikidot: true,
+                name: site.name.clone(),
+                slug: site.slug.clone(),
+                tagline: String::new(),
+                description: String::new(),
+                default_page: None,
+                layout: None,
+                locale: site.locale.clone(),
+                default_license: Some(serde_json::to_value(&site.license)?),
+            },
+        )
+        .await?;
+
         Ok(())
     }
 
-    // TODO page_revision
-    // TODO page_vote
+    /// Imports a page, with up to `REVISION_BATCH_SIZE` of its revisions.
+    ///
+    /// Once all of its revisions are in, its votes are imported
+    /// and a job is queued to render the latest revision.
+    ///
+    /// # Returns
+    /// The number of revisions and votes which were added, and whether the page is finished.
+    async fn import_wikicomma_page(
+        ctx: &ServiceContext<'_>,
+        dump: &WikicommaDump,
+        site: &WikicommaSite,
+        name: &str,
+    ) -> Result<(i32, i32, bool)> {
+        let txn = ctx.transaction();
+        let site_id = site.site_id;
+        let metadata = dump.read_page(&site.directory, name).await?;
+        let page_id = metadata.page_id;
+        let updated_at = metadata.updated_at()?;
+
+        // Create page, if it has not already been imported.
+        //
+        // A page which was moved while the backup was being made can appear
+        // more than once, in which case the most recent copy is used.
+        match Page::find_by_id(page_id).one(txn).await? {
+            None => {
+                let created_at =
+                    match metadata.revisions.iter().min_by_key(|r| r.revision) {
+                        Some(revision) => revision.created_at()?,
+                        None => updated_at,
+                    };
+
+                Self::add_page(
+                    ctx,
+                    ImportPage {
+                        page_id: Some(page_id),
+                        site_id,
+                        created_at,
+                        updated_at: Some(updated_at),
+                        deleted_at: None,
+                        from_wikidot: true,
+                        slug: metadata.name.clone(),
+                        layout: None,
+                        locked: metadata.is_locked,
+                        discussion_thread_id: None,
+                    },
+                )
+                .await?;
+            }
+            Some(page)
+                if page.slug != metadata.name
+                    && page.updated_at.is_some_and(|time| time < updated_at) =>
+            {
+                warn!(
+                    "Page ID {page_id} appears more than once, moving from '{}' to newer slug '{}'",
+                    page.slug, metadata.name,
+                );
+
+                let category = CategoryService::get_or_create(
+                    ctx,
+                    site_id,
+                    get_category_name(&metadata.name),
+                )
+                .await?;
+
+                let model = page::ActiveModel {
+                    page_id: Set(page_id),
+                    updated_at: Set(Some(updated_at)),
+                    slug: Set(metadata.name.clone()),
+                    page_category_id: Set(category.category_id),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+            }
+            Some(_) => debug!("Page ID {page_id} was already imported"),
+        }
+
+        // Add revisions which have not already been imported
+        let existing: HashSet<i32> = PageRevision::find()
+            .select_only()
+            .column(page_revision::Column::RevisionNumber)
+            .filter(page_revision::Column::PageId.eq(page_id))
+            .into_tuple()
+            .all(txn)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut revisions = metadata
+            .revisions
+            .iter()
+            .filter(|revision| !existing.contains(&revision.revision))
+            .collect::<Vec<_>>();
+
+        revisions.sort_by_key(|revision| revision.revision);
+
+        let added_revisions = !revisions.is_empty();
+        let done = revisions.len() <= REVISION_BATCH_SIZE;
+        revisions.truncate(REVISION_BATCH_SIZE);
+
+        let mut revision_count = 0;
+        if !revisions.is_empty() {
+            let mut wikitexts = dump.read_page_wikitext(&site.directory, name).await?;
+            let authors =
+                Self::existing_users(ctx, revisions.iter().filter_map(|r| r.author))
+                    .await?;
+            let compiled_hash = TextService::create(ctx, String::new()).await?;
+
+            for revision in revisions {
+                let Some(wikitext) = wikitexts.remove(&revision.revision) else {
+                    warn!(
+                        "No wikitext for revision {} of page ID {page_id}, skipping",
+                        revision.revision,
+                    );
+                    continue;
+                };
+
+                let created_at = revision.created_at()?;
+                let wikitext_hash = TextService::create(ctx, wikitext).await?;
+                let (revision_type, changes) = revision_changes(&revision.flags);
+                let user_id = revision
+                    .author
+                    .filter(|user_id| authors.contains(user_id))
+                    .unwrap_or(ANONYMOUS_USER_ID);
+
+                Self::add_page_revision(
+                    ctx,
+                    ImportPageRevision {
+                        page_id,
+                        site_id,
+                        user_id,
+                        created_at,
+                        updated_at: None,
+                        from_wikidot: true,
+                        revision_type,
+                        revision_number: revision.revision,
+                        changes,
+                        wikitext_hash,
+                        compiled_hash,
+                        compiled_at: created_at,
+                        compiled_generator: str!(NOT_COMPILED_GENERATOR),
+                        comments: revision.commentary.clone(),
+                        hidden: vec![],
+                        title: metadata.title.clone(),
+                        alt_title: None,
+                        slug: metadata.name.clone(),
+                        tags: metadata.tags.clone(),
+                    },
+                )
+                .await?;
+
+                revision_count += 1;
+            }
+        }
+
+        if !done {
+            return Ok((revision_count, 0, false));
+        }
+
+        // Fill in the compiled text, links, and other derived data for the latest revision
+        //
+        // The final batch always has revisions if any were missing, but it's
+        // possible none of them had wikitext, leaving nothing to render.
+        if added_revisions && (revision_count > 0 || !existing.is_empty()) {
+            let job = Job::RerenderPage {
+                site_id,
+                page_id,
+                depth: 0,
+            };
+            JobService::queue_job(ctx, &job, None).await?;
+        }
+
+        // Add votes which have not already been imported.
+        //
+        // Wikidot does not record when votes were cast.
+        let voted: HashSet<i64> = PageVote::find()
+            .select_only()
+            .column(page_vote::Column::UserId)
+            .filter(
+                Condition::all()
+                    .add(page_vote::Column::PageId.eq(page_id))
+                    .add(page_vote::Column::DeletedAt.is_null()),
+            )
+            .into_tuple()
+            .all(txn)
+            .await?
+            .into_iter()
+            .collect();
+
+        let voters = Self::existing_users(
+            ctx,
+            metadata.votings.iter().map(|&(user_id, _)| user_id),
+        )
+        .await?;
+
+        let mut vote_count = 0;
+        for &(user_id, upvote) in &metadata.votings {
+            if voted.contains(&user_id) || !voters.contains(&user_id) {
+                continue;
+            }
+
+            Self::add_page_vote(
+                ctx,
+                ImportPageVote {
+                    page_id,
+                    user_id,
+                    created_at: updated_at,
+                    deleted_at: None,
+                    disabled_at: None,
+                    disabled_by: None,
+                    from_wikidot: true,
+                    value: if upvote { 1 } else { -1 },
+                },
+            )
+            .await?;
+
+            vote_count += 1;
+        }
+
+        Ok((revision_count, vote_count, true))
+    }
+
+    /// Imports a batch of files, returning how many were added.
+    ///
+    /// Wikidot backups do not record who uploaded a file or when,
+    /// so they are attributed to the anonymous user.
+    async fn import_wikicomma_files(
+        ctx: &ServiceContext<'_>,
+        dump: &WikicommaDump,
+        site: &WikicommaSite,
+        files: &[(i64, WikicommaFileEntry)],
+    ) -> Result<i32> {
+        let txn = ctx.transaction();
+        let site_id = site.site_id;
+        let maximum_size = ctx.config().maximum_blob_size;
+        let licensing =
+            serde_json::to_value(FileLicensing::own_work(site.license.clone()))?;
+        let mut count = 0;
+
+        for (wikidot_file_id, WikicommaFileEntry { path }) in files {
+            let wikidot_file_id = *wikidot_file_id;
+            if File::find_by_id(wikidot_file_id).one(txn).await?.is_some() {
+                debug!("File ID {wikidot_file_id} was already imported");
+                continue;
+            }
+
+            let Some((page_slug_encoded, name)) = path.rsplit_once('/') else {
+                warn!("Invalid path for file ID {wikidot_file_id}: {path}");
+                continue;
+            };
+
+            let page_slug = percent_decode_str(page_slug_encoded).decode_utf8_lossy();
+            let page = Page::find()
+                .filter(
+                    Condition::all()
+                        .add(page::Column::SiteId.eq(site_id))
+                        .add(page::Column::Slug.eq(page_slug.as_ref()))
+                        .add(page::Column::DeletedAt.is_null()),
+                )
+                .one(txn)
+                .await?;
+
+            let Some(page) = page else {
+                warn!("No page '{page_slug}' for file ID {wikidot_file_id}, skipping");
+                continue;
+            };
+
+            let name_taken = File::find()
+                .filter(
+                    Condition::all()
+                        .add(file::Column::PageId.eq(page.page_id))
+                        .add(file::Column::Name.eq(name))
+                        .add(file::Column::DeletedAt.is_null()),
+                )
+                .one(txn)
+                .await?
+                .is_some();
+
+            if name_taken {
+                warn!("File '{name}' already exists on page '{page_slug}', skipping");
+                continue;
+            }
+
+            let data = dump
+                .read_file(&site.directory, page_slug_encoded, wikidot_file_id)
+                .await?;
+
+            let Some(data) = data else {
+                warn!("File ID {wikidot_file_id} was not downloaded, skipping");
+                continue;
+            };
+
+            if i64::try_from(data.len()).map_or(true, |size| size > maximum_size) {
+                warn!("File ID {wikidot_file_id} is too large, skipping");
+                continue;
+            }
+
+            let FinalizeBlobUploadOutput {
+                s3_hash,
+                mime,
+                size,
+                ..
+            } = BlobService::direct_upload(ctx, data).await?;
+
+            let created_at = now();
+            let file_id = Self::add_file(
+                ctx,
+                ImportFile {
+                    file_id: Some(wikidot_file_id),
+                    page_id: page.page_id,
+                    site_id,
+                    created_at,
+                    updated_at: None,
+                    deleted_at: None,
+                    from_wikidot: true,
+                    name: str!(name),
+                },
+            )
+            .await?;
+
+            Self::add_file_revision(
+                ctx,
+                ImportFileRevision {
+                    file_id,
+                    page_id: page.page_id,
+                    site_id,
+                    user_id: ANONYMOUS_USER_ID,
+                    created_at,
+                    revision_type: FileRevisionType::Create,
+                    revision_number: 0,
+                    name: str!(name),
+                    s3_hash,
+                    mime,
+                    size,
+                    licensing: licensing.clone(),
+                    changes: vec![],
+                    comments: String::new(),
+                    hidden: vec![],
+                },
+            )
+            .await?;
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Returns which of the given user IDs exist.
+    async fn existing_users<I>(
+        ctx: &ServiceContext<'_>,
+        user_ids: I,
+    ) -> Result<HashSet<i64>>
+    where
+        I: IntoIterator<Item = i64>,
+    {
+        let txn = ctx.transaction();
+        let user_ids = user_ids
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let mut existing = HashSet::new();
+        for chunk in user_ids.chunks(USER_CHUNK_SIZE) {
+            let found: Vec<i64> = User::find()
+                .select_only()
+                .column(user::Column::UserId)
+                .filter(user::Column::UserId.is_in(chunk.iter().copied()))
+                .into_tuple()
+                .all(txn)
+                .await?;
+
+            existing.extend(found);
+        }
+
+        Ok(existing)
+    }
 
-    // TODO file
     // TODO forum
 }
+
+/// Converts Wikidot's revision flags into a revision type and list of changes.
+fn revision_changes(flags: &str) -> (PageRevisionType, Vec<String>) {
+    if flags.contains('N') {
+        let changes = vec![str!("wikitext"), str!("title"), str!("slug"), str!("tags")];
+
+        return (PageRevisionType::Create, changes);
+    }
+
+    let mut changes = Vec::new();
+    for (flag, change) in [
+        ('S', "wikitext"),
+        ('T', "title"),
+        ('R', "slug"),
+        ('A', "tags"),
+    ] {
+        if flags.contains(flag) {
+            changes.push(str!(change));
+        }
+    }
+
+    let revision_type = if changes == ["slug"] {
+        PageRevisionType::Move
+    } else {
+        PageRevisionType::Regular
+    };
+
+    (revision_type, changes)
+}
+
+/// Cleans up an optional profile field, which must be non-empty and shorter than a limit.
+fn profile_field(value: Option<String>, maximum: usize) -> Option<String> {
+    value
+        .map(|value| value.trim().to_owned())
+        .filter(|value| !value.is_empty() && value.chars().count() < maximum)
+}
+
+#[test]
+fn wikidot_revision_flags() {
+    assert_eq!(
+        revision_changes("N"),
+        (
+            PageRevisionType::Create,
+            vec![str!("wikitext"), str!("title"), str!("slug"), str!("tags"),],
+        ),
+    );
+    assert_eq!(
+        revision_changes("S"),
+        (PageRevisionType::Regular, vec![str!("wikitext")]),
+    );
+    assert_eq!(
+        revision_changes("ST"),
+        (
+            PageRevisionType::Regular,
+            vec![str!("wikitext"), str!("title")]
+        ),
+    );
+    assert_eq!(
+        revision_changes("R"),
+        (PageRevisionType::Move, vec![str!("slug")])
+    );
+    assert_eq!(revision_changes("F"), (PageRevisionType::Regular, vec![]));
+}
@@ -18,6 +18,10 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::hash::{BlobHash, TextHash};
+use crate::models::sea_orm_active_enums::{FileRevisionType, PageRevisionType};
+use crate::services::file::License;
+use serde_json::Value as JsonValue;
 use time::{Date, OffsetDateTime};
 
 #[derive(Deserialize, Debug)]
@@ -39,25 +43,204 @@ pub struct ImportUser {
     pub user_page: Option<String>,
 }
 
+/// A site to import.
+///
+/// If `site_id` is `None`, then a new ID is assigned.
 #[derive(Deserialize, Debug)]
 pub struct ImportSite {
-    pub site_id: i64,
+    pub site_id: Option<i64>,
 
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub updated_at: Option<OffsetDateTime>,
+    pub from_wikidot: bool,
     pub name: String,
     pub slug: String,
+
+    #[serde(default)]
+    pub tagline: String,
+
+    #[serde(default)]
+    pub description: String,
+    pub default_page: Option<String>,
+    pub layout: Option<String>,
     pub locale: String,
+
+    #[serde(default)]
+    pub default_license: Option<JsonValue>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportCategory {
+    pub site_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<OffsetDateTime>,
+    pub slug: String,
+    pub layout: Option<String>,
+
+    #[serde(default)]
+    pub default_license: Option<JsonValue>,
+
+    #[serde(default)]
+    pub scorer: Option<String>,
+
+    #[serde(default)]
+    pub template_slug: Option<String>,
+
+    pub use_templates: bool,
+
+    #[serde(default)]
+    pub permissions: Option<JsonValue>,
 }
 
+/// A page to import.
+///
+/// If `page_id` is `None`, then a new ID is assigned.
 #[derive(Deserialize, Debug)]
 pub struct ImportPage {
-    pub page_id: i64,
+    pub page_id: Option<i64>,
     pub site_id: i64,
 
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub updated_at: Option<OffsetDateTime>,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<OffsetDateTime>,
+    pub from_wikidot: bool,
     pub slug: String,
+    pub layout: Option<String>,
     pub locked: bool,
     pub discussion_thread_id: Option<i64>,
 }
+
+/// A page revision to import.
+///
+/// The wikitext and compiled HTML must already be stored using `TextService`.
+#[derive(Debug)]
+pub struct ImportPageRevision {
+    pub page_id: i64,
+    pub site_id: i64,
+    pub user_id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+    pub from_wikidot: bool,
+    pub revision_type: PageRevisionType,
+    pub revision_number: i32,
+    pub changes: Vec<String>,
+    pub wikitext_hash: TextHash,
+    pub compiled_hash: TextHash,
+    pub compiled_at: OffsetDateTime,
+    pub compiled_generator: String,
+    pub comments: String,
+    pub hidden: Vec<String>,
+    pub title: String,
+    pub alt_title: Option<String>,
+    pub slug: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ImportPageVote {
+    pub page_id: i64,
+    pub user_id: i64,
+    pub created_at: OffsetDateTime,
+    pub deleted_at: Option<OffsetDateTime>,
+    pub disabled_at: Option<OffsetDateTime>,
+    pub disabled_by: Option<i64>,
+    pub from_wikidot: bool,
+    pub value: i16,
+}
+
+#[derive(Debug)]
+pub struct ImportPageAttribution {
+    pub page_id: i64,
+    pub user_id: i64,
+    pub attribution_type: String,
+    pub attribution_date: Date,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug)]
+pub struct ImportPageParent {
+    pub parent_page_id: i64,
+    pub child_page_id: i64,
+    pub created_at: OffsetDateTime,
+}
+
+/// A file to import.
+///
+/// If `file_id` is `None`, then a new ID is assigned.
+#[derive(Debug)]
+pub struct ImportFile {
+    pub file_id: Option<i64>,
+    pub page_id: i64,
+    pub site_id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+    pub deleted_at: Option<OffsetDateTime>,
+    pub from_wikidot: bool,
+    pub name: String,
+}
+
+/// A file revision to import.
+///
+/// The blob must already be uploaded using `BlobService`.
+#[derive(Debug)]
+pub struct ImportFileRevision {
+    pub file_id: i64,
+    pub page_id: i64,
+    pub site_id: i64,
+    pub user_id: i64,
+    pub created_at: OffsetDateTime,
+    pub revision_type: FileRevisionType,
+    pub revision_number: i32,
+    pub name: String,
+    pub s3_hash: BlobHash,
+    pub mime: String,
+    pub size: i64,
+    pub licensing: JsonValue,
+    pub changes: Vec<String>,
+    pub comments: String,
+    pub hidden: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StartWikicommaImport {
+    /// The path to the backup directory, relative to the configured import path.
+    pub directory: String,
+
+    /// The locale to give imported users.
+    pub locale: String,
+    pub sites: Vec<WikicommaSite>,
+}
+
+/// A site to import from a wikicomma backup.
+///
+/// The site ID is Wikidot's, which is kept so imports can be resumed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WikicommaSite {
+    /// The name of the site in the wikicomma configuration.
+    /// This is the name of its directory in the backup.
+    pub directory: String,
+    pub site_id: i64,
+    pub slug: String,
+    pub name: String,
+    pub locale: String,
+
+    /// The license of the site's content, which is also used for its files.
+    pub license: License,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetImportProgress {
+    pub import_id: i64,
+}
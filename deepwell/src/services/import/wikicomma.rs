@@ -0,0 +1,390 @@
+/*
+ * services/import/wikicomma.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Reading Wikidot backups in the format produced by wikicomma.
+//!
+//! The layout of a backup directory is:
+//! * `_users/*.json` &mdash; Blocks of user information, keyed by user ID.
+//! * `_users/avatars/<user-id>.png` &mdash; User avatars, if they were downloaded.
+//! * `<site>/meta/pages/<page>.json` &mdash; Page metadata, revision history, and votes.
+//! * `<site>/pages/<page>.7z` &mdash; Wikitext for each revision, as `<revision-number>.txt`.
+//! * `<site>/meta/file_map.json` &mdash; The page slug and filename for each file ID.
+//! * `<site>/files/<page-slug>/<file-id>` &mdash; The contents of each file.
+//!
+//! Here `<site>` is the name wikicomma was configured with, which is not
+//! necessarily the site's slug. Similarly, `<page>` is a name derived from
+//! the page slug, but the actual slug is found in the page metadata.
+//!
+//! Backups are only read from under the configured import path. Directories
+//! given in requests must be relative to it, see `check_relative_path()`.
+
+use super::prelude::*;
+use serde::de::DeserializeOwned;
+use sevenz_rust::{Password, SevenZReader};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use time::{Date, OffsetDateTime};
+use tokio::{fs, task};
+
+const USER_DIRECTORY: &str = "_users";
+const AVATAR_DIRECTORY: &str = "avatars";
+const PENDING_USERS_FILENAME: &str = "pending.json";
+
+#[derive(Deserialize, Debug)]
+pub struct WikicommaUser {
+    pub user_id: i64,
+    pub username: String,
+    pub full_name: String,
+
+    /// UNIX timestamp, in seconds.
+    pub wikidot_user_since: i64,
+    pub real_name: Option<String>,
+    pub gender: Option<String>,
+
+    /// UNIX timestamp, in milliseconds.
+    pub birthday: Option<i64>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+}
+
+impl WikicommaUser {
+    pub fn created_at(&self) -> Result<OffsetDateTime> {
+        timestamp(self.wikidot_user_since)
+    }
+
+    pub fn birthday(&self) -> Result<Option<Date>> {
+        match self.birthday {
+            Some(millis) => Ok(Some(timestamp(millis / 1000)?.date())),
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WikicommaPage {
+    pub page_id: i64,
+
+    /// The page's slug.
+    pub name: String,
+
+    #[serde(default)]
+    pub title: String,
+
+    #[serde(default)]
+    pub is_locked: bool,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// UNIX timestamp, in milliseconds.
+    pub sitemap_update: i64,
+
+    #[serde(default)]
+    pub revisions: Vec<WikicommaRevision>,
+
+    /// Pairs of user ID and whether the vote was an upvote.
+    #[serde(default)]
+    pub votings: Vec<(i64, bool)>,
+}
+
+impl WikicommaPage {
+    pub fn updated_at(&self) -> Result<OffsetDateTime> {
+        timestamp(self.sitemap_update / 1000)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WikicommaRevision {
+    pub revision: i32,
+    pub author: Option<i64>,
+
+    /// UNIX timestamp, in seconds.
+    pub stamp: i64,
+
+    /// Wikidot's single-letter codes for what the revision changed.
+    #[serde(default)]
+    pub flags: String,
+
+    #[serde(default)]
+    pub commentary: String,
+}
+
+impl WikicommaRevision {
+    pub fn created_at(&self) -> Result<OffsetDateTime> {
+        timestamp(self.stamp)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WikicommaFileEntry {
+    /// The page slug (percent-encoded) and filename, separated by a slash.
+    pub path: String,
+}
+
+/// Access to a wikicomma backup directory.
+#[derive(Debug)]
+pub struct WikicommaDump {
+    directory: PathBuf,
+}
+
+impl WikicommaDump {
+    /// Opens the backup at the given directory, relative to the import path.
+    pub fn new(import_path: &Path, directory: &str) -> Result<Self> {
+        check_relative_path(directory)?;
+        Ok(WikicommaDump {
+            directory: import_path.join(directory),
+        })
+    }
+
+    /// Lists the files containing blocks of users, in a stable order.
+    pub async fn user_blocks(&self) -> Result<Vec<String>> {
+        let directory = self.directory.join(USER_DIRECTORY);
+        let mut names = list_files(&directory, ".json").await?;
+        names.retain(|name| name != PENDING_USERS_FILENAME);
+        Ok(names)
+    }
+
+    pub async fn read_user_block(&self, name: &str) -> Result<Vec<WikicommaUser>> {
+        let path = self.directory.join(USER_DIRECTORY).join(name);
+        let block: BTreeMap<String, WikicommaUser> = read_json(&path, "users").await?;
+        Ok(block.into_values().collect())
+    }
+
+    pub async fn read_avatar(&self, user_id: i64) -> Result<Option<Vec<u8>>> {
+        let path = self
+            .directory
+            .join(USER_DIRECTORY)
+            .join(AVATAR_DIRECTORY)
+            .join(format!("{user_id}.png"));
+
+        read_optional(&path).await
+    }
+
+    /// Lists the names of all pages in a site, in a stable order.
+    pub async fn pages(&self, site: &str) -> Result<Vec<String>> {
+        let directory = self.directory.join(site).join("meta").join("pages");
+        let names = list_files(&directory, ".json").await?;
+        let pages = names
+            .into_iter()
+            .map(|mut name| {
+                name.truncate(name.len() - ".json".len());
+                name
+            })
+            .collect();
+
+        Ok(pages)
+    }
+
+    pub async fn read_page(&self, site: &str, page: &str) -> Result<WikicommaPage> {
+        let path = self
+            .directory
+            .join(site)
+            .join("meta")
+            .join("pages")
+            .join(format!("{page}.json"));
+
+        read_json(&path, "page").await
+    }
+
+    /// Reads the wikitext of every revision of a page, keyed by revision number.
+    ///
+    /// If the page has no wikitext archive, then this is empty.
+    pub async fn read_page_wikitext(
+        &self,
+        site: &str,
+        page: &str,
+    ) -> Result<HashMap<i32, String>> {
+        let path = self
+            .directory
+            .join(site)
+            .join("pages")
+            .join(format!("{page}.7z"));
+
+        if !fs::try_exists(&path).await? {
+            return Ok(HashMap::new());
+        }
+
+        let result = task::spawn_blocking(move || read_7z_texts(&path))
+            .await
+            .expect("Wikitext extraction task panicked");
+
+        result.map_err(|error| {
+            error!("Unable to read wikitext archive: {error}");
+            Error::ImportDumpInvalid("wikitext")
+        })
+    }
+
+    /// Reads the files in a site, sorted by file ID.
+    ///
+    /// If the site has no file map, then this is empty.
+    pub async fn read_file_map(
+        &self,
+        site: &str,
+    ) -> Result<Vec<(i64, WikicommaFileEntry)>> {
+        let path = self.directory.join(site).join("meta").join("file_map.json");
+        if !fs::try_exists(&path).await? {
+            return Ok(Vec::new());
+        }
+
+        let map: BTreeMap<String, WikicommaFileEntry> =
+            read_json(&path, "file_map").await?;
+
+        let mut files = map
+            .into_iter()
+            .map(|(file_id, entry)| match file_id.parse() {
+                Ok(file_id) => Ok((file_id, entry)),
+                Err(_) => Err(Error::ImportDumpInvalid("file_map")),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        files.sort_by_key(|(file_id, _)| *file_id);
+        Ok(files)
+    }
+
+    pub async fn read_file(
+        &self,
+        site: &str,
+        page_slug: &str,
+        file_id: i64,
+    ) -> Result<Option<Vec<u8>>> {
+        // The page slug comes from the file map, not the filesystem
+        check_relative_path(page_slug)?;
+
+        let path = self
+            .directory
+            .join(site)
+            .join("files")
+            .join(page_slug)
+            .join(file_id.to_string());
+
+        read_optional(&path).await
+    }
+}
+
+/// Checks that a path from a request or backup stays within the directory it is joined to.
+///
+/// It must be relative, and may not contain any `..` or other special components.
+pub fn check_relative_path(path: &str) -> Result<()> {
+    let path = Path::new(path);
+    let valid = path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+
+    if !valid {
+        error!(
+            "Import path {} is not a plain relative path",
+            path.display()
+        );
+        return Err(Error::ImportDumpInvalid("directory"));
+    }
+
+    Ok(())
+}
+
+/// Lists the names of files in a directory with the given extension, sorted.
+async fn list_files(directory: &Path, extension: &str) -> Result<Vec<String>> {
+    let mut entries = fs::read_dir(directory).await.map_err(|error| {
+        error!("Unable to read directory {}: {error}", directory.display());
+        Error::ImportDumpInvalid("directory")
+    })?;
+
+    let mut names = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if let Ok(name) = entry.file_name().into_string() {
+            if name.ends_with(extension) && entry.file_type().await?.is_file() {
+                names.push(name);
+            }
+        }
+    }
+
+    names.sort();
+    Ok(names)
+}
+
+async fn read_json<T>(path: &Path, part: &'static str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let data = fs::read(path).await.map_err(|error| {
+        error!("Unable to read {}: {error}", path.display());
+        Error::ImportDumpInvalid(part)
+    })?;
+
+    serde_json::from_slice(&data).map_err(|error| {
+        error!("Unable to parse {}: {error}", path.display());
+        Error::ImportDumpInvalid(part)
+    })
+}
+
+async fn read_optional(path: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(path).await {
+        Ok(data) => Ok(Some(data)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn read_7z_texts(path: &Path) -> StdResult<HashMap<i32, String>, sevenz_rust::Error> {
+    let mut texts = HashMap::new();
+    let mut archive = SevenZReader::open(path, Password::empty())?;
+    archive.for_each_entries(|entry, reader| {
+        let revision = entry
+            .name()
+            .strip_suffix(".txt")
+            .and_then(|number| number.parse().ok());
+
+        match revision {
+            Some(revision) => {
+                let mut contents = String::new();
+                reader.read_to_string(&mut contents)?;
+                texts.insert(revision, contents);
+            }
+            None => {
+                warn!(
+                    "Ignoring unexpected entry in wikitext archive: {}",
+                    entry.name()
+                );
+                io::copy(reader, &mut io::sink())?;
+            }
+        }
+
+        Ok(true)
+    })?;
+
+    Ok(texts)
+}
+
+fn timestamp(seconds: i64) -> Result<OffsetDateTime> {
+    OffsetDateTime::from_unix_timestamp(seconds)
+        .map_err(|_| Error::ImportDumpInvalid("timestamp"))
+}
+
+#[test]
+fn relative_paths() {
+    for path in ["backup", "backups/2024", "scp-wiki"] {
+        assert!(check_relative_path(path).is_ok(), "Rejected {path}");
+    }
+
+    for path in ["", "/", "/etc", ".", "..", "../backup", "backups/../../etc"] {
+        assert!(check_relative_path(path).is_err(), "Accepted {path}");
+    }
+}
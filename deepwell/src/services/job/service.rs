@@ -41,8 +41,8 @@ pub const JOB_QUEUE_DELAY: Option<Duration> = None;
 
 /// The maximum size, in bytes, that a job payload is allowed to be
 ///
-/// Presently, our jobs are mostly unit types, and the biggest variant
-/// is composed of three integers, so this is more than large enough.
+/// Presently, our jobs are mostly unit types, and the biggest variants
+/// are composed of a few integers, so this is more than large enough.
 /// If larger jobs become a thing in the future, this may need to be updated.
 ///
 /// (But as a general code principle there shouldn't be huge jobs, they should
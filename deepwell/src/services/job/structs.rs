@@ -18,8 +18,16 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::services::login_attempt::CreateLoginAttempt;
+use crate::services::notification::NotificationEvent;
+use crate::services::redirect::RewriteBacklinks;
+use crate::services::tag::RenameSiteTag;
+use crate::types::Bytes;
+use strum_macros::IntoStaticStr;
+
+#[derive(Serialize, Deserialize, IntoStaticStr, Debug, Clone)]
 #[serde(rename_all = "snake_case", tag = "job", content = "data")]
+#[strum(serialize_all = "snake_case")]
 pub enum Job {
     RerenderPage {
         site_id: i64,
@@ -28,7 +36,39 @@ pub enum Job {
     },
     PruneSessions,
     PruneText,
+    PurgeDeletedSites,
+    DetectVoteFraud,
+    ScoreSnapshot {
+        after_page_id: i64,
+    },
     // TODO add job for pruning incomplete uploads (pending_blob table and corresponding columns for foreign keys)
     NameChangeRefill,
     LiftExpiredPunishments,
+    FanOutNotification(NotificationEvent),
+    NotificationDigest,
+    RecordLoginAttempt(CreateLoginAttempt),
+    GenerateBlobDerivatives {
+        s3_hash: Bytes<'static>,
+    },
+    ImportWikicomma {
+        import_id: i64,
+        step: i32,
+    },
+    RewriteBacklinks {
+        input: RewriteBacklinks,
+        after_page_id: i64,
+    },
+    RenameTag {
+        input: RenameSiteTag,
+        after_page_id: i64,
+    },
+    BulkPageOperation {
+        bulk_operation_id: i64,
+    },
+    ImportSiteArchive {
+        import_id: i64,
+    },
+    ExportSiteArchive {
+        export_id: i64,
+    },
 }
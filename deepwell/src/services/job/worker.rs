@@ -22,7 +22,12 @@
 
 use super::prelude::*;
 use crate::api::ServerState;
-use crate::services::{PageRevisionService, SessionService, TextService, UserService};
+use crate::services::{
+    BlobService, BulkOperationService, ImportService, LoginAttemptService,
+    NotificationService, PageRevisionService, RedirectService, ScoreService,
+    SessionService, SiteArchiveService, SiteService, TagService, TextService,
+    UserService, VoteFraudService,
+};
 use crate::utils::debug_pointer;
 use rsmq_async::{PooledRsmq, RsmqConnection, RsmqMessage};
 use sea_orm::TransactionTrait;
@@ -160,7 +165,7 @@ impl JobWorker {
         debug!("* Previously received: {}", data.rc);
         debug!("* Created:             {}", data.sent);
         debug!("* Received:            {}", data.fr);
-        let job = serde_json::from_slice(&data.message)?;
+        let job: Job = serde_json::from_slice(&data.message)?;
 
         let no_more_retries = data.rc >= u64::from(self.state.config.job_max_attempts);
         if no_more_retries {
@@ -174,6 +179,43 @@ impl JobWorker {
         let ctx = &ServiceContext::new(&self.state, &txn);
 
         trace!("Beginning job processing");
+        let job_name: &'static str = (&job).into();
+        let result = self.run_job(ctx, job).await;
+        self.state.metrics.record_job(job_name, result.is_ok());
+        let next = result?;
+
+        // Don't delete more than once
+        //
+        // NOTE: We're only at this point if the job succeeded.
+        if !no_more_retries {
+            trace!("Job execution finished, deleting message");
+            self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
+        }
+
+        // Add follow-up job to queue, if required.
+        match next {
+            NextJob::Done => debug!("Job execution finished, no follow-up job to add"),
+            NextJob::Next { job, delay } => {
+                debug!("Job execution finished, follow-up job has been produced");
+                trace!("* Job:   {job:?}");
+                trace!("* Delay: {delay:?}");
+
+                JobService::queue_job(ctx, &job, delay).await?;
+            }
+        }
+
+        trace!("Committing transaction, returning success");
+        let invalidations = ctx.invalidations();
+        txn.commit().await?;
+        invalidations.run(&self.state).await;
+        Ok(JobProcessStatus::ReceivedJob)
+    }
+
+    /// Performs the task for the given job.
+    ///
+    /// # Returns
+    /// The follow-up job to queue, if any.
+    async fn run_job(&self, ctx: &ServiceContext<'_>, job: Job) -> Result<NextJob> {
         let next = match job {
             Job::RerenderPage {
                 site_id,
@@ -202,6 +244,48 @@ impl JobWorker {
                     delay: Some(self.state.config.job_prune_text),
                 }
             }
+            Job::PurgeDeletedSites => {
+                debug!("Purging the next site past its deletion grace period");
+
+                // Continue right away while there are more sites to purge,
+                // otherwise wait until the next scheduled run.
+                let delay = if SiteService::purge_deleted(ctx).await? {
+                    None
+                } else {
+                    Some(self.state.config.job_purge_deleted_sites)
+                };
+
+                NextJob::Next {
+                    job: Job::PurgeDeletedSites,
+                    delay,
+                }
+            }
+            Job::DetectVoteFraud => {
+                debug!("Looking for suspected sockpuppet voting");
+                VoteFraudService::detect(ctx).await?;
+                NextJob::Next {
+                    job: Job::DetectVoteFraud,
+                    delay: Some(self.state.config.job_detect_vote_fraud),
+                }
+            }
+            Job::ScoreSnapshot { after_page_id } => {
+                debug!(
+                    "Recording scores for pages without a recent snapshot after page ID {after_page_id}",
+                );
+
+                // Continue right away with the next batch while there are more pages,
+                // otherwise start over from the beginning at the next scheduled run.
+                match ScoreService::snapshot_stale(ctx, after_page_id).await? {
+                    Some(after_page_id) => NextJob::Next {
+                        job: Job::ScoreSnapshot { after_page_id },
+                        delay: None,
+                    },
+                    None => NextJob::Next {
+                        job: Job::ScoreSnapshot { after_page_id: 0 },
+                        delay: Some(self.state.config.job_score_snapshot),
+                    },
+                }
+            }
             Job::NameChangeRefill => {
                 debug!("Checking users for those who can get a name change token refill");
                 UserService::refresh_name_change_tokens(ctx).await?;
@@ -224,31 +308,121 @@ impl JobWorker {
                     delay: Some(self.state.config.job_lift_expired_punishments),
                 }
             }
-        };
-
-        // Don't delete more than once
-        //
-        // NOTE: We're only at this point if the job succeeded.
-        if !no_more_retries {
-            trace!("Job execution finished, deleting message");
-            self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
-        }
+            Job::FanOutNotification(event) => {
+                debug!("Creating notifications for event");
+                NotificationService::fan_out(ctx, event).await?;
+                NextJob::Done
+            }
+            Job::NotificationDigest => {
+                debug!("Collecting notification digests for users");
+                NotificationService::process_digests(ctx).await?;
+                NextJob::Next {
+                    job: Job::NotificationDigest,
+                    delay: Some(self.state.config.job_notification_digest),
+                }
+            }
+            Job::RecordLoginAttempt(input) => {
+                debug!("Recording login attempt");
+                LoginAttemptService::record(ctx, input).await?;
+                NextJob::Done
+            }
+            Job::GenerateBlobDerivatives { s3_hash } => {
+                debug!("Generating derived images for blob");
+                BlobService::generate_derivatives(ctx, s3_hash.as_ref()).await?;
+                NextJob::Done
+            }
+            Job::ImportWikicomma { import_id, step } => {
+                debug!("Running step {step} of import ID {import_id}");
+                if ImportService::run_wikicomma_step(ctx, import_id, step).await? {
+                    NextJob::Next {
+                        job: Job::ImportWikicomma {
+                            import_id,
+                            step: step + 1,
+                        },
+                        delay: None,
+                    }
+                } else {
+                    NextJob::Done
+                }
+            }
+            Job::RewriteBacklinks {
+                input,
+                after_page_id,
+            } => {
+                debug!(
+                    "Rewriting backlinks to moved page ID {} after page ID {after_page_id}",
+                    input.page_id,
+                );
 
-        // Add follow-up job to queue, if required.
-        match next {
-            NextJob::Done => debug!("Job execution finished, no follow-up job to add"),
-            NextJob::Next { job, delay } => {
-                debug!("Job execution finished, follow-up job has been produced");
-                trace!("* Job:   {job:?}");
-                trace!("* Delay: {delay:?}");
+                match RedirectService::rewrite_backlinks(
+                    ctx,
+                    input.clone(),
+                    after_page_id,
+                )
+                .await?
+                {
+                    Some(after_page_id) => NextJob::Next {
+                        job: Job::RewriteBacklinks {
+                            input,
+                            after_page_id,
+                        },
+                        delay: None,
+                    },
+                    None => NextJob::Done,
+                }
+            }
+            Job::RenameTag {
+                input,
+                after_page_id,
+            } => {
+                debug!(
+                    "Renaming tag '{}' in site ID {} after page ID {after_page_id}",
+                    input.from, input.site_id,
+                );
 
-                JobService::queue_job(ctx, &job, delay).await?;
+                match TagService::rename_in_pages(ctx, input.clone(), after_page_id)
+                    .await?
+                {
+                    Some(after_page_id) => NextJob::Next {
+                        job: Job::RenameTag {
+                            input,
+                            after_page_id,
+                        },
+                        delay: None,
+                    },
+                    None => NextJob::Done,
+                }
             }
-        }
+            Job::BulkPageOperation { bulk_operation_id } => {
+                debug!("Running next batch of bulk operation ID {bulk_operation_id}");
+                if BulkOperationService::run_step(ctx, bulk_operation_id).await? {
+                    NextJob::Next {
+                        job: Job::BulkPageOperation { bulk_operation_id },
+                        delay: None,
+                    }
+                } else {
+                    NextJob::Done
+                }
+            }
+            Job::ImportSiteArchive { import_id } => {
+                debug!("Running next step of site import ID {import_id}");
+                if SiteArchiveService::run_import_step(ctx, import_id).await? {
+                    NextJob::Next {
+                        job: Job::ImportSiteArchive { import_id },
+                        delay: None,
+                    }
+                } else {
+                    NextJob::Done
+                }
+            }
+            Job::ExportSiteArchive { export_id } => {
+                debug!("Running site export ID {export_id}");
+                SiteArchiveService::run_export(ctx, export_id).await?;
+                NextJob::Done
+            }
+        };
 
-        trace!("Committing transaction, returning success");
-        txn.commit().await?;
-        Ok(JobProcessStatus::ReceivedJob)
+        Ok(next)
     }
 }
 
@@ -23,7 +23,7 @@ use crate::models::page;
 use crate::models::page_connection::{self, Entity as PageConnection};
 use crate::models::page_connection_missing::{self, Entity as PageConnectionMissing};
 use crate::models::page_link::{self, Entity as PageLink, Model as PageLinkModel};
-use crate::services::{PageService, SiteService};
+use crate::services::{PageService, RedirectService, SiteService};
 use crate::types::ConnectionType;
 use ftml::data::{Backlinks, PageRef};
 use sea_orm::NotSet;
@@ -499,6 +499,20 @@ async fn count_connections(
             *entry += 1;
         }
         None => {
+            // Links to a redirected slug count towards the page it points to
+            if connection_type == ConnectionType::Link {
+                if let Some(to_page) =
+                    RedirectService::get_target(ctx, to_site_id, page_slug).await?
+                {
+                    let entry = connections
+                        .entry((to_page.page_id, ConnectionType::Redirect))
+                        .or_insert(0);
+
+                    *entry += 1;
+                    return Ok(());
+                }
+            }
+
             let entry = connections_missing
                 .entry((to_site_id, str!(page_slug), connection_type))
                 .or_insert(0);
@@ -0,0 +1,158 @@
+/*
+ * services/login_attempt/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::login_attempt::{self, Entity as LoginAttempt};
+use crate::models::sea_orm_active_enums::LoginAttemptResult;
+use crate::services::job::{Job, JobService};
+use sea_orm::prelude::IpNetwork;
+
+/// The maximum length, in bytes, of user agents stored from failed attempts.
+///
+/// Failed attempts go through the job queue, which has a limited payload size.
+/// Real user agents are well under this length.
+const MAXIMUM_QUEUED_USER_AGENT_LENGTH: usize = 512;
+
+#[derive(Debug)]
+pub struct LoginAttemptService;
+
+impl LoginAttemptService {
+    /// Records a login attempt via the job queue.
+    ///
+    /// This is used for failed attempts, which cannot be recorded inline
+    /// because the login's transaction is rolled back.
+    pub async fn queue(
+        ctx: &ServiceContext<'_>,
+        mut input: CreateLoginAttempt,
+    ) -> Result<()> {
+        debug!(
+            "Queuing login attempt for user ID {} ({:?})",
+            input.user_id, input.result,
+        );
+
+        if input.user_agent.len() > MAXIMUM_QUEUED_USER_AGENT_LENGTH {
+            let mut index = MAXIMUM_QUEUED_USER_AGENT_LENGTH;
+            while !input.user_agent.is_char_boundary(index) {
+                index -= 1;
+            }
+
+            input.user_agent.truncate(index);
+        }
+
+        JobService::queue_job(ctx, &Job::RecordLoginAttempt(input), None).await
+    }
+
+    /// Records a login attempt.
+    ///
+    /// For successful logins, this also determines if the login came from a new
+    /// device, that is, a user agent which has not successfully logged in before.
+    /// A user's very first login is not considered to be from a new device.
+    pub async fn record(
+        ctx: &ServiceContext<'_>,
+        CreateLoginAttempt {
+            user_id,
+            result,
+            ip_address,
+            user_agent,
+            created_at,
+        }: CreateLoginAttempt,
+    ) -> Result<()> {
+        info!("Recording login attempt for user ID {user_id} ({result:?})");
+
+        let txn = ctx.transaction();
+        let new_device = match result {
+            LoginAttemptResult::Success => {
+                Self::is_new_device(ctx, user_id, &user_agent).await?
+            }
+            _ => false,
+        };
+
+        let model = login_attempt::ActiveModel {
+            created_at: Set(created_at),
+            user_id: Set(user_id),
+            result: Set(result),
+            ip_address: Set(IpNetwork::from(ip_address)),
+            user_agent: Set(user_agent),
+            new_device: Set(new_device),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
+        Ok(())
+    }
+
+    /// Gets a user's login history, newest first.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        start_id: i64,
+        limit: u64,
+    ) -> Result<Vec<LoginAttemptOutput>> {
+        info!("Getting login attempts for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let start_condition = if start_id > 0 {
+            Some(login_attempt::Column::LoginAttemptId.lt(start_id))
+        } else {
+            None
+        };
+
+        let attempts = LoginAttempt::find()
+            .filter(
+                Condition::all()
+                    .add(login_attempt::Column::UserId.eq(user_id))
+                    .add_option(start_condition),
+            )
+            .order_by_desc(login_attempt::Column::LoginAttemptId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        Ok(attempts.into_iter().map(LoginAttemptOutput::from).collect())
+    }
+
+    async fn is_new_device(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        user_agent: &str,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+        let success_condition = Condition::all()
+            .add(login_attempt::Column::UserId.eq(user_id))
+            .add(login_attempt::Column::Result.eq(LoginAttemptResult::Success));
+
+        let total = LoginAttempt::find()
+            .filter(success_condition.clone())
+            .count(txn)
+            .await?;
+
+        if total == 0 {
+            return Ok(false);
+        }
+
+        let matching = LoginAttempt::find()
+            .filter(
+                success_condition.add(login_attempt::Column::UserAgent.eq(user_agent)),
+            )
+            .count(txn)
+            .await?;
+
+        Ok(matching == 0)
+    }
+}
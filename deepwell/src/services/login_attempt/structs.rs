@@ -0,0 +1,75 @@
+/*
+ * services/login_attempt/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::login_attempt::Model as LoginAttemptModel;
+use crate::models::sea_orm_active_enums::LoginAttemptResult;
+use crate::services::session::DeviceSummary;
+use std::net::IpAddr;
+use time::OffsetDateTime;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateLoginAttempt {
+    pub user_id: i64,
+    pub result: LoginAttemptResult,
+    pub ip_address: IpAddr,
+    pub user_agent: String,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetLoginAttempts {
+    pub user_id: i64,
+    pub session_token: String,
+
+    #[serde(default)]
+    pub start_id: i64,
+    pub limit: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LoginAttemptOutput {
+    pub login_attempt_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+
+    pub result: LoginAttemptResult,
+    pub ip_address: IpAddr,
+    pub user_agent: String,
+    pub device: DeviceSummary,
+    pub new_device: bool,
+}
+
+impl From<LoginAttemptModel> for LoginAttemptOutput {
+    fn from(model: LoginAttemptModel) -> Self {
+        LoginAttemptOutput {
+            login_attempt_id: model.login_attempt_id,
+            created_at: model.created_at,
+            result: model.result,
+            device: DeviceSummary::parse(&model.user_agent),
+            ip_address: model.ip_address.ip(),
+            user_agent: model.user_agent,
+            new_device: model.new_device,
+        }
+    }
+}
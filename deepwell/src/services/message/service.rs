@@ -28,8 +28,9 @@ use crate::models::message_record::{
     self, Entity as MessageRecord, Model as MessageRecordModel,
 };
 use crate::models::sea_orm_active_enums::{MessageRecipientType, UserType};
+use crate::services::notification::NotificationEvent;
 use crate::services::render::{RenderOutput, RenderService};
-use crate::services::{RelationService, TextService, UserService};
+use crate::services::{NotificationService, RelationService, TextService, UserService};
 use crate::utils::validate_locale;
 use cuid2::cuid;
 use ftml::data::{PageInfo, ScoreValue};
@@ -398,6 +399,16 @@ impl MessageService {
         };
         model.insert(txn).await?;
 
+        // Notify recipients
+        NotificationService::queue(
+            ctx,
+            NotificationEvent::Message {
+                record_id: record_model.external_id.clone(),
+                user_id: sender_id,
+            },
+        )
+        .await?;
+
         Ok(record_model)
     }
 
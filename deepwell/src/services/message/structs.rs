@@ -18,6 +18,8 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::net::IpAddr;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateMessageDraft {
     pub user_id: i64,
@@ -45,9 +47,13 @@ pub struct UpdateMessageDraft {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SendMessageDraft {
     pub message_draft_id: String,
+    pub ip_address: IpAddr,
 }
 
-pub type DeleteMessageDraft = SendMessageDraft;
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteMessageDraft {
+    pub message_draft_id: String,
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DraftRecipients {
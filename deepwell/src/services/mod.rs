@@ -57,11 +57,13 @@ mod context;
 mod error;
 
 pub mod alias;
-// TODO create audit
+pub mod audit;
 pub mod authentication;
 pub mod blob;
+pub mod bulk_operation;
 pub mod category;
 pub mod domain;
+pub mod edit_session;
 pub mod email;
 pub mod file;
 pub mod file_revision;
@@ -69,60 +71,83 @@ pub mod filter;
 pub mod import;
 pub mod job;
 pub mod link;
+pub mod login_attempt;
 pub mod message;
 pub mod message_report;
 pub mod mfa;
+pub mod notification;
 pub mod outdate;
 pub mod page;
+pub mod page_attribution;
 pub mod page_query;
 pub mod page_revision;
 pub mod parent;
 pub mod password;
+pub mod rate_limit;
+pub mod recent_changes;
+pub mod redirect;
 pub mod relation;
 pub mod render;
 pub mod score;
 pub mod session;
 pub mod settings;
 pub mod site;
+pub mod site_archive;
 pub mod special_page;
+pub mod tag;
 pub mod text;
 pub mod user;
 pub mod user_bot_owner;
 pub mod view;
 pub mod vote;
+pub mod vote_fraud;
+pub mod webauthn;
 
 pub use self::alias::AliasService;
+pub use self::audit::AuditService;
 pub use self::authentication::AuthenticationService;
 pub use self::blob::BlobService;
+pub use self::bulk_operation::BulkOperationService;
 pub use self::category::CategoryService;
-pub use self::context::ServiceContext;
+pub use self::context::{PendingInvalidations, ServiceContext};
 pub use self::domain::DomainService;
+pub use self::edit_session::EditSessionService;
 pub use self::error::*;
 pub use self::file::FileService;
 pub use self::file_revision::FileRevisionService;
 pub use self::filter::FilterService;
+pub use self::import::ImportService;
 pub use self::job::JobService;
 pub use self::link::LinkService;
+pub use self::login_attempt::LoginAttemptService;
 pub use self::message::MessageService;
 pub use self::message_report::MessageReportService;
 pub use self::mfa::MfaService;
+pub use self::notification::NotificationService;
 pub use self::outdate::OutdateService;
 pub use self::page::PageService;
-// TODO convert page attribution to a type of relation
+pub use self::page_attribution::PageAttributionService;
 pub use self::page_query::PageQueryService;
 pub use self::page_revision::PageRevisionService;
 pub use self::parent::ParentService;
 pub use self::password::PasswordService;
+pub use self::rate_limit::RateLimitService;
+pub use self::recent_changes::RecentChangesService;
+pub use self::redirect::RedirectService;
 pub use self::relation::RelationService;
 pub use self::render::RenderService;
 pub use self::score::ScoreService;
 pub use self::session::SessionService;
 pub use self::settings::SettingsService;
 pub use self::site::SiteService;
+pub use self::site_archive::SiteArchiveService;
 pub use self::special_page::SpecialPageService;
+pub use self::tag::TagService;
 pub use self::text::TextService;
 pub use self::user::UserService;
 // TODO convert user-bot to a type of relation
 pub use self::user_bot_owner::UserBotOwnerService;
 pub use self::view::ViewService;
 pub use self::vote::VoteService;
+pub use self::vote_fraud::VoteFraudService;
+pub use self::webauthn::WebauthnService;
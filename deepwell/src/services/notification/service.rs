@@ -0,0 +1,497 @@
+/*
+ * services/notification/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::message::{self, Entity as Message};
+use crate::models::notification::{
+    self, Entity as Notification, Model as NotificationModel,
+};
+use crate::models::notification_preference::{
+    self, Entity as NotificationPreference, Model as NotificationPreferenceModel,
+};
+use crate::services::job::{Job, JobService};
+use crate::services::relation::{RelationDirection, RelationObject, RelationType};
+use crate::services::RelationService;
+
+/// How many relation rows to fetch at a time when fanning out notifications.
+const FAN_OUT_BATCH_SIZE: u64 = 100;
+
+#[derive(Debug)]
+pub struct NotificationService;
+
+impl NotificationService {
+    /// Queues a job to fan out the given event to all interested users.
+    pub async fn queue(ctx: &ServiceContext<'_>, event: NotificationEvent) -> Result<()> {
+        debug!("Queuing notification event {event:?}");
+        JobService::queue_job(ctx, &Job::FanOutNotification(event), None).await
+    }
+
+    /// Creates notifications for all users who are interested in this event.
+    ///
+    /// This is run by the job worker, see `NotificationService::queue()`.
+    ///
+    /// The user who caused the event is never notified about it, and users
+    /// who have disabled this kind of notification are skipped.
+    pub async fn fan_out(
+        ctx: &ServiceContext<'_>,
+        event: NotificationEvent,
+    ) -> Result<()> {
+        info!("Fanning out notification event {event:?}");
+
+        let notification_type = event.notification_type();
+        let actor_id = event.actor_id();
+        let (user_ids, site_id, page_id, revision_id, message_record_id) = match event {
+            NotificationEvent::PageEdit {
+                site_id,
+                page_id,
+                revision_id,
+                ..
+            }
+            | NotificationEvent::PageMove {
+                site_id,
+                page_id,
+                revision_id,
+                ..
+            }
+            | NotificationEvent::PageDelete {
+                site_id,
+                page_id,
+                revision_id,
+                ..
+            } => {
+                let user_ids = Self::get_relation_users(
+                    ctx,
+                    RelationType::PageWatch,
+                    RelationObject::Page(page_id),
+                    RelationDirection::Dest,
+                )
+                .await?;
+
+                (
+                    user_ids,
+                    Some(site_id),
+                    Some(page_id),
+                    Some(revision_id),
+                    None,
+                )
+            }
+            NotificationEvent::PageCreate {
+                site_id,
+                page_id,
+                revision_id,
+                user_id,
+            } => {
+                let user_ids = Self::get_relation_users(
+                    ctx,
+                    RelationType::UserFollow,
+                    RelationObject::User(user_id),
+                    RelationDirection::Dest,
+                )
+                .await?;
+
+                (
+                    user_ids,
+                    Some(site_id),
+                    Some(page_id),
+                    Some(revision_id),
+                    None,
+                )
+            }
+            NotificationEvent::Message { record_id, .. } => {
+                let txn = ctx.transaction();
+                let user_ids = Message::find()
+                    .filter(
+                        Condition::all()
+                            .add(message::Column::RecordId.eq(record_id.as_str()))
+                            .add(message::Column::FlagInbox.eq(true)),
+                    )
+                    .all(txn)
+                    .await?
+                    .into_iter()
+                    .map(|message| message.user_id)
+                    .collect();
+
+                (user_ids, None, None, None, Some(record_id))
+            }
+            NotificationEvent::SiteInvitation {
+                site_id,
+                invitee_id: target_id,
+                ..
+            }
+            | NotificationEvent::SiteApplicationResponse {
+                site_id,
+                applicant_id: target_id,
+                ..
+            } => (vec![target_id], Some(site_id), None, None, None),
+        };
+
+        let txn = ctx.transaction();
+        for user_id in user_ids {
+            if user_id == actor_id {
+                continue;
+            }
+
+            let preferences = Self::get_preferences(ctx, user_id).await?;
+            if !preferences.allows(notification_type) {
+                debug!(
+                    "User ID {user_id} has disabled {notification_type:?} notifications"
+                );
+                continue;
+            }
+
+            let model = notification::ActiveModel {
+                user_id: Set(user_id),
+                notification_type: Set(notification_type),
+                actor_id: Set(Some(actor_id)),
+                site_id: Set(site_id),
+                page_id: Set(page_id),
+                revision_id: Set(revision_id),
+                message_record_id: Set(message_record_id.clone()),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the user on the other side of every active relation for this object.
+    async fn get_relation_users(
+        ctx: &ServiceContext<'_>,
+        relation_type: RelationType,
+        object: RelationObject,
+        direction: RelationDirection,
+    ) -> Result<Vec<i64>> {
+        let mut user_ids = Vec::new();
+        let mut start_id = 0;
+
+        loop {
+            let relations = RelationService::get_entries(
+                ctx,
+                relation_type,
+                object,
+                direction,
+                start_id,
+                FAN_OUT_BATCH_SIZE,
+            )
+            .await?;
+
+            let last_id = match relations.last() {
+                Some(relation) => relation.relation_id,
+                None => break,
+            };
+
+            user_ids.extend(relations.into_iter().map(|relation| match direction {
+                RelationDirection::Dest => relation.from_id,
+                RelationDirection::From => relation.dest_id,
+            }));
+
+            start_id = last_id;
+        }
+
+        Ok(user_ids)
+    }
+
+    /// Gets notifications for a user, most recent first.
+    ///
+    /// The `start_id` argument gives the notification ID to search from, exclusive.
+    /// If `0`, then means "everything".
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetNotifications {
+            user_id,
+            start_id,
+            limit,
+            unread_only,
+            include_dismissed,
+        }: GetNotifications,
+    ) -> Result<GetNotificationsOutput> {
+        info!("Getting notifications for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let start_condition = if start_id > 0 {
+            Some(notification::Column::NotificationId.lt(start_id))
+        } else {
+            None
+        };
+
+        let unread_condition = if unread_only {
+            Some(notification::Column::ReadAt.is_null())
+        } else {
+            None
+        };
+
+        let dismissed_condition = if include_dismissed {
+            None
+        } else {
+            Some(notification::Column::DismissedAt.is_null())
+        };
+
+        let notifications = Notification::find()
+            .filter(
+                Condition::all()
+                    .add(notification::Column::UserId.eq(user_id))
+                    .add_option(start_condition)
+                    .add_option(unread_condition)
+                    .add_option(dismissed_condition),
+            )
+            .order_by_desc(notification::Column::NotificationId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        let unread_count = Self::count_unread(ctx, user_id).await?;
+        Ok(GetNotificationsOutput {
+            notifications,
+            unread_count,
+        })
+    }
+
+    pub async fn count_unread(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        let txn = ctx.transaction();
+        let count = Notification::find()
+            .filter(
+                Condition::all()
+                    .add(notification::Column::UserId.eq(user_id))
+                    .add(notification::Column::ReadAt.is_null())
+                    .add(notification::Column::DismissedAt.is_null()),
+            )
+            .count(txn)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Sets whether a notification is read or unread.
+    pub async fn set_read(
+        ctx: &ServiceContext<'_>,
+        UpdateNotification {
+            notification_id,
+            user_id,
+        }: UpdateNotification,
+        read: bool,
+    ) -> Result<NotificationModel> {
+        info!(
+            "Marking notification ID {notification_id} for user ID {user_id} as {}",
+            if read { "read" } else { "unread" },
+        );
+
+        let txn = ctx.transaction();
+        let notification = Self::get(ctx, notification_id, user_id).await?;
+        let mut model = notification.into_active_model();
+        model.read_at = Set(if read { Some(now()) } else { None });
+        let notification = model.update(txn).await?;
+        Ok(notification)
+    }
+
+    /// Marks many notifications as read at once.
+    ///
+    /// # Returns
+    /// The number of notifications which were newly marked as read.
+    pub async fn read_batch(
+        ctx: &ServiceContext<'_>,
+        ReadNotifications {
+            user_id,
+            notification_ids,
+        }: ReadNotifications,
+    ) -> Result<u64> {
+        info!("Marking notifications for user ID {user_id} as read");
+
+        let txn = ctx.transaction();
+        let ids_condition =
+            notification_ids.map(|ids| notification::Column::NotificationId.is_in(ids));
+
+        let result = Notification::update_many()
+            .col_expr(notification::Column::ReadAt, now().into())
+            .filter(
+                Condition::all()
+                    .add(notification::Column::UserId.eq(user_id))
+                    .add(notification::Column::ReadAt.is_null())
+                    .add_option(ids_condition),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Dismisses a notification, hiding it from the user's list.
+    pub async fn dismiss(
+        ctx: &ServiceContext<'_>,
+        UpdateNotification {
+            notification_id,
+            user_id,
+        }: UpdateNotification,
+    ) -> Result<NotificationModel> {
+        info!("Dismissing notification ID {notification_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let notification = Self::get(ctx, notification_id, user_id).await?;
+        let mut model = notification.into_active_model();
+        model.dismissed_at = Set(Some(now()));
+        let notification = model.update(txn).await?;
+        Ok(notification)
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        notification_id: i64,
+        user_id: i64,
+    ) -> Result<Option<NotificationModel>> {
+        let txn = ctx.transaction();
+        let notification = Notification::find()
+            .filter(
+                Condition::all()
+                    .add(notification::Column::NotificationId.eq(notification_id))
+                    .add(notification::Column::UserId.eq(user_id)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(notification)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        notification_id: i64,
+        user_id: i64,
+    ) -> Result<NotificationModel> {
+        find_or_error!(
+            Self::get_optional(ctx, notification_id, user_id),
+            Notification,
+        )
+    }
+
+    /// Gets the user's notification preferences, or the defaults if they have not set any.
+    pub async fn get_preferences(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<NotificationPreferences> {
+        let txn = ctx.transaction();
+        let preferences = NotificationPreference::find_by_id(user_id)
+            .one(txn)
+            .await?
+            .map(NotificationPreferences::from)
+            .unwrap_or_default();
+
+        Ok(preferences)
+    }
+
+    pub async fn set_preferences(
+        ctx: &ServiceContext<'_>,
+        SetNotificationPreferences {
+            user_id,
+            preferences:
+                NotificationPreferences {
+                    notify_page_edit,
+                    notify_page_move,
+                    notify_page_delete,
+                    notify_page_create,
+                    notify_message,
+                    notify_site_membership,
+                    email_digest,
+                },
+        }: SetNotificationPreferences,
+    ) -> Result<()> {
+        info!("Setting notification preferences for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let exists = NotificationPreference::find_by_id(user_id)
+            .one(txn)
+            .await?
+            .is_some();
+
+        let model = notification_preference::ActiveModel {
+            user_id: Set(user_id),
+            updated_at: Set(Some(now())),
+            notify_page_edit: Set(notify_page_edit),
+            notify_page_move: Set(notify_page_move),
+            notify_page_delete: Set(notify_page_delete),
+            notify_page_create: Set(notify_page_create),
+            notify_message: Set(notify_message),
+            notify_site_membership: Set(notify_site_membership),
+            email_digest: Set(email_digest),
+        };
+
+        if exists {
+            model.update(txn).await?;
+        } else {
+            model.insert(txn).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Collects pending notifications for all users who have email digests enabled.
+    ///
+    /// Each notification is only meant to be included in one digest, after which
+    /// it is marked with `emailed_at`. Notifications the user has already read or
+    /// dismissed are not included.
+    ///
+    /// Outgoing mail is not implemented yet, so nothing is sent and no
+    /// notifications are marked, leaving them for the first real digest.
+    pub async fn process_digests(ctx: &ServiceContext<'_>) -> Result<()> {
+        info!("Processing notification email digests");
+
+        let txn = ctx.transaction();
+        let users = NotificationPreference::find()
+            .filter(notification_preference::Column::EmailDigest.eq(true))
+            .all(txn)
+            .await?;
+
+        for NotificationPreferenceModel { user_id, .. } in users {
+            let pending = Notification::find()
+                .filter(
+                    Condition::all()
+                        .add(notification::Column::UserId.eq(user_id))
+                        .add(notification::Column::ReadAt.is_null())
+                        .add(notification::Column::DismissedAt.is_null())
+                        .add(notification::Column::EmailedAt.is_null()),
+                )
+                .count(txn)
+                .await?;
+
+            if pending == 0 {
+                continue;
+            }
+
+            // TODO send digest email once outgoing mail is implemented,
+            //      then set emailed_at on the notifications which were sent
+            debug!("Notification digest for user ID {user_id} has {pending} items");
+        }
+
+        Ok(())
+    }
+}
+
+impl From<NotificationPreferenceModel> for NotificationPreferences {
+    fn from(model: NotificationPreferenceModel) -> NotificationPreferences {
+        NotificationPreferences {
+            notify_page_edit: model.notify_page_edit,
+            notify_page_move: model.notify_page_move,
+            notify_page_delete: model.notify_page_delete,
+            notify_page_create: model.notify_page_create,
+            notify_message: model.notify_message,
+            notify_site_membership: model.notify_site_membership,
+            email_digest: model.email_digest,
+        }
+    }
+}
@@ -0,0 +1,193 @@
+/*
+ * services/notification/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::notification::Model as NotificationModel;
+use crate::models::sea_orm_active_enums::NotificationType;
+
+/// An event which may produce notifications for some set of users.
+///
+/// These are queued as jobs and fanned out by the worker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case", tag = "event")]
+pub enum NotificationEvent {
+    /// A watched page was edited.
+    PageEdit {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        user_id: i64,
+    },
+
+    /// A watched page was moved.
+    PageMove {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        user_id: i64,
+    },
+
+    /// A watched page was deleted.
+    PageDelete {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        user_id: i64,
+    },
+
+    /// A followed user created a page.
+    PageCreate {
+        site_id: i64,
+        page_id: i64,
+        revision_id: i64,
+        user_id: i64,
+    },
+
+    /// A direct message was sent.
+    Message { record_id: String, user_id: i64 },
+
+    /// A user was invited to join a site.
+    SiteInvitation {
+        site_id: i64,
+        invitee_id: i64,
+        user_id: i64,
+    },
+
+    /// A user's application to join a site was accepted or declined.
+    SiteApplicationResponse {
+        site_id: i64,
+        applicant_id: i64,
+        accepted: bool,
+        user_id: i64,
+    },
+}
+
+impl NotificationEvent {
+    pub fn notification_type(&self) -> NotificationType {
+        match self {
+            NotificationEvent::PageEdit { .. } => NotificationType::PageEdit,
+            NotificationEvent::PageMove { .. } => NotificationType::PageMove,
+            NotificationEvent::PageDelete { .. } => NotificationType::PageDelete,
+            NotificationEvent::PageCreate { .. } => NotificationType::PageCreate,
+            NotificationEvent::Message { .. } => NotificationType::Message,
+            NotificationEvent::SiteInvitation { .. } => NotificationType::SiteInvitation,
+            NotificationEvent::SiteApplicationResponse { accepted: true, .. } => {
+                NotificationType::SiteApplicationAccepted
+            }
+            NotificationEvent::SiteApplicationResponse {
+                accepted: false, ..
+            } => NotificationType::SiteApplicationDeclined,
+        }
+    }
+
+    /// The user who caused this event.
+    pub fn actor_id(&self) -> i64 {
+        match self {
+            NotificationEvent::PageEdit { user_id, .. }
+            | NotificationEvent::PageMove { user_id, .. }
+            | NotificationEvent::PageDelete { user_id, .. }
+            | NotificationEvent::PageCreate { user_id, .. }
+            | NotificationEvent::Message { user_id, .. }
+            | NotificationEvent::SiteInvitation { user_id, .. }
+            | NotificationEvent::SiteApplicationResponse { user_id, .. } => *user_id,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetNotifications {
+    pub user_id: i64,
+    pub start_id: i64,
+    pub limit: u64,
+
+    #[serde(default)]
+    pub unread_only: bool,
+
+    #[serde(default)]
+    pub include_dismissed: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetNotificationsOutput {
+    pub notifications: Vec<NotificationModel>,
+    pub unread_count: u64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct UpdateNotification {
+    pub notification_id: i64,
+    pub user_id: i64,
+}
+
+/// Marks a number of a user's notifications as read.
+///
+/// If `notification_ids` is `None`, then all of the user's notifications are marked.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReadNotifications {
+    pub user_id: i64,
+    pub notification_ids: Option<Vec<i64>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+pub struct NotificationPreferences {
+    pub notify_page_edit: bool,
+    pub notify_page_move: bool,
+    pub notify_page_delete: bool,
+    pub notify_page_create: bool,
+    pub notify_message: bool,
+    pub notify_site_membership: bool,
+    pub email_digest: bool,
+}
+
+impl NotificationPreferences {
+    pub fn allows(self, notification_type: NotificationType) -> bool {
+        match notification_type {
+            NotificationType::PageEdit => self.notify_page_edit,
+            NotificationType::PageMove => self.notify_page_move,
+            NotificationType::PageDelete => self.notify_page_delete,
+            NotificationType::PageCreate => self.notify_page_create,
+            NotificationType::Message => self.notify_message,
+            NotificationType::SiteInvitation
+            | NotificationType::SiteApplicationAccepted
+            | NotificationType::SiteApplicationDeclined => self.notify_site_membership,
+        }
+    }
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        NotificationPreferences {
+            notify_page_edit: true,
+            notify_page_move: true,
+            notify_page_delete: true,
+            notify_page_create: true,
+            notify_message: true,
+            notify_site_membership: true,
+            email_digest: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct SetNotificationPreferences {
+    pub user_id: i64,
+
+    #[serde(flatten)]
+    pub preferences: NotificationPreferences,
+}
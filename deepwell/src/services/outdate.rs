@@ -20,9 +20,12 @@
 
 use super::prelude::*;
 use crate::models::page::Model as PageModel;
-use crate::services::{JobService, LinkService, PageService};
+use crate::services::{
+    CategoryService, JobService, LinkService, PageService, ViewService,
+};
 use crate::types::{ConnectionType, PageOrder};
 use crate::utils::split_category_name;
+use std::borrow::Cow;
 
 #[derive(Debug)]
 pub struct OutdateService;
@@ -38,6 +41,7 @@ impl OutdateService {
         let (category_slug, page_slug) = split_category_name(slug);
 
         try_join!(
+            ViewService::invalidate_page(ctx, site_id, slug),
             OutdateService::outdate_outgoing_includes(ctx, page_id, depth),
             OutdateService::outdate_templates(
                 ctx,
@@ -103,7 +107,8 @@ impl OutdateService {
         page_id: i64,
         depth: u32,
     ) -> Result<()> {
-        const CONNECTION_TYPES: &[ConnectionType] = &[ConnectionType::Link];
+        const CONNECTION_TYPES: &[ConnectionType] =
+            &[ConnectionType::Link, ConnectionType::Redirect];
 
         for id in LinkService::get_to(ctx, page_id, Some(CONNECTION_TYPES))
             .await?
@@ -147,24 +152,41 @@ impl OutdateService {
         page_slug: &str,
         depth: u32,
     ) -> Result<()> {
+        let full_slug = match category_slug {
+            "_default" => Cow::Borrowed(page_slug),
+            _ => Cow::Owned(format!("{category_slug}:{page_slug}")),
+        };
+
         // If a template page has been updated,
-        // we need to recompile everything in that category.
-        if page_slug == "_template" {
-            let category_select = if category_slug == "_default" {
-                // If the category is _default, we need to recompile everything.
-                // All other categories may inherit from _default.
-                //
-                // Specifying "None" here means that we aren't filtering by category.
-                None
-            } else {
-                // Otherwise, filter by whatever category slug we have here.
-                Some(category_slug.into())
+        // we need to recompile everything in the categories using it.
+        //
+        // By default this is the _template page in the category itself,
+        // or in _default, since all other categories inherit from it.
+        // Categories can also override which page they use, or opt out entirely.
+        let template_slug = &ctx.config().special_page_template;
+        let categories = CategoryService::get_all(ctx, site_id).await?;
+
+        for category in categories {
+            if !category.use_templates {
+                continue;
+            }
+
+            let uses_template = match category.template_slug {
+                Some(ref slug) => slug == &full_slug,
+                None => {
+                    page_slug == template_slug
+                        && (category_slug == "_default" || category_slug == category.slug)
+                }
             };
 
+            if !uses_template {
+                continue;
+            }
+
             let pages = PageService::get_all(
                 ctx,
                 site_id,
-                category_select,
+                Some(Reference::Id(category.category_id)),
                 Some(false),
                 PageOrder::default(),
             )
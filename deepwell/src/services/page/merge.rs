@@ -0,0 +1,249 @@
+/*
+ * services/page/merge.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Line-based three-way merging of wikitext, for resolving concurrent edits.
+//!
+//! Both the latest revision and the submitted wikitext are compared against
+//! the revision the editor started from (the base). Regions which were only
+//! changed on one side take that side's version, and regions changed the
+//! same way on both sides are taken as-is. Regions changed differently on
+//! both sides are conflicts, which are returned instead of a merged result.
+
+use similar::{Algorithm, DiffOp};
+use std::time::{Duration as StdDuration, Instant};
+
+/// How long to spend finding the smallest diff between two versions.
+const DIFF_TIMEOUT: StdDuration = StdDuration::from_secs(1);
+
+/// A region of the wikitext which was changed differently by both edits.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// The line in the base wikitext where this conflict begins, starting at 1.
+    pub line: usize,
+    pub base: String,
+    pub latest: String,
+    pub submitted: String,
+}
+
+/// How one side of the merge changed the base text.
+///
+/// Index `i` refers to the position just before base line `i`,
+/// with the final index being the end of the text.
+#[derive(Debug)]
+struct LineChanges<'a> {
+    kept: Vec<bool>,
+    inserted: Vec<Vec<&'a str>>,
+}
+
+impl<'a> LineChanges<'a> {
+    fn new(base: &[&'a str], changed: &'a str) -> Self {
+        let changed = changed.lines().collect::<Vec<_>>();
+        let mut kept = vec![false; base.len()];
+        let mut inserted = vec![Vec::new(); base.len() + 1];
+
+        // Past the deadline, the diff is still valid but may not be minimal,
+        // which only means more of the text is treated as changed.
+        let deadline = Instant::now() + DIFF_TIMEOUT;
+        let ops = similar::capture_diff_slices_deadline(
+            Algorithm::Myers,
+            base,
+            &changed,
+            Some(deadline),
+        );
+
+        for op in ops {
+            match op {
+                DiffOp::Equal { old_index, len, .. } => {
+                    kept[old_index..old_index + len].fill(true);
+                }
+                DiffOp::Delete { .. } => (),
+                DiffOp::Insert {
+                    old_index,
+                    new_index,
+                    new_len,
+                } => {
+                    inserted[old_index].extend(&changed[new_index..new_index + new_len]);
+                }
+                DiffOp::Replace {
+                    old_index,
+                    old_len,
+                    new_index,
+                    new_len,
+                } => {
+                    inserted[old_index + old_len]
+                        .extend(&changed[new_index..new_index + new_len]);
+                }
+            }
+        }
+
+        LineChanges { kept, inserted }
+    }
+
+    /// Gets this side's version of base lines `start..end`.
+    ///
+    /// This includes anything inserted before the line at `end`.
+    fn region(&self, base: &[&'a str], start: usize, end: usize) -> Vec<&'a str> {
+        let mut lines = Vec::new();
+        for (offset, line) in base[start..end].iter().enumerate() {
+            lines.extend(&self.inserted[start + offset]);
+            if self.kept[start + offset] {
+                lines.push(*line);
+            }
+        }
+
+        lines.extend(&self.inserted[end]);
+        lines
+    }
+}
+
+/// Merges the changes made in `latest` and `submitted`, relative to `base`.
+///
+/// Returns the merged wikitext, or the list of conflicting regions.
+pub fn merge_wikitext(
+    base: &str,
+    latest: &str,
+    submitted: &str,
+) -> Result<String, Vec<MergeConflict>> {
+    let base_lines = base.lines().collect::<Vec<_>>();
+    let latest_changes = LineChanges::new(&base_lines, latest);
+    let submitted_changes = LineChanges::new(&base_lines, submitted);
+    let is_stable =
+        |index: usize| latest_changes.kept[index] && submitted_changes.kept[index];
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut index = 0;
+
+    loop {
+        // Find the extent of the changed region before the next unchanged line.
+        // This may be empty, or consist only of insertions.
+        let start = index;
+        while index < base_lines.len() && !is_stable(index) {
+            index += 1;
+        }
+
+        let base_region = &base_lines[start..index];
+        let latest_region = latest_changes.region(&base_lines, start, index);
+        let submitted_region = submitted_changes.region(&base_lines, start, index);
+
+        if latest_region == base_region || latest_region == submitted_region {
+            merged.extend(submitted_region);
+        } else if submitted_region == base_region {
+            merged.extend(latest_region);
+        } else {
+            conflicts.push(MergeConflict {
+                line: start + 1,
+                base: base_region.join("\n"),
+                latest: latest_region.join("\n"),
+                submitted: submitted_region.join("\n"),
+            });
+        }
+
+        if index == base_lines.len() {
+            break;
+        }
+
+        // Copy over the line neither side changed
+        merged.push(base_lines[index]);
+        index += 1;
+    }
+
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+
+    let mut wikitext = merged.join("\n");
+    if submitted.ends_with('\n') {
+        wikitext.push('\n');
+    }
+
+    Ok(wikitext)
+}
+
+#[test]
+fn merge() {
+    const BASE: &str = "= Title\n\nFirst section.\n\nSecond section.\n\nThird section.";
+
+    // Changes to different sections
+    assert_eq!(
+        merge_wikitext(
+            BASE,
+            "= Title\n\nFirst section, edited.\n\nSecond section.\n\nThird section.",
+            "= Title\n\nFirst section.\n\nSecond section.\n\nThird section, edited.",
+        ),
+        Ok(str!(
+            "= Title\n\nFirst section, edited.\n\nSecond section.\n\nThird section, edited."
+        )),
+    );
+
+    // Insertions at the start and end
+    assert_eq!(
+        merge_wikitext("a\nb", "z\na\nb", "a\nb\nc"),
+        Ok(str!("z\na\nb\nc")),
+    );
+
+    // Trailing newlines
+    assert_eq!(
+        merge_wikitext("a\nb\n", "a\nb\nc\n", "x\nb\n"),
+        Ok(str!("x\nb\nc\n")),
+    );
+
+    // Identical changes on both sides
+    assert_eq!(
+        merge_wikitext("a\nb\nc", "a\nx\nc", "a\nx\nc"),
+        Ok(str!("a\nx\nc"))
+    );
+
+    // Deletion on one side
+    assert_eq!(
+        merge_wikitext("a\nb\nc\nd", "a\nc\nd", "a\nb\nc\ne"),
+        Ok(str!("a\nc\ne"))
+    );
+
+    // Conflicting changes to the same line
+    assert_eq!(
+        merge_wikitext("a\nb\nc", "a\nx\nc", "a\ny\nc"),
+        Err(vec![MergeConflict {
+            line: 2,
+            base: str!("b"),
+            latest: str!("x"),
+            submitted: str!("y"),
+        }]),
+    );
+}
+
+#[test]
+fn merge_large() {
+    // Ensure large texts with scattered changes merge quickly
+    let base = (0..50_000).map(|n| format!("Line {n}")).collect::<Vec<_>>();
+    let mut latest = base.clone();
+    let mut submitted = base.clone();
+    latest[10] = str!("Latest edit");
+    submitted[40_000] = str!("Submitted edit");
+
+    let mut expected = base.clone();
+    expected[10] = str!("Latest edit");
+    expected[40_000] = str!("Submitted edit");
+
+    assert_eq!(
+        merge_wikitext(&base.join("\n"), &latest.join("\n"), &submitted.join("\n")),
+        Ok(expected.join("\n")),
+    );
+}
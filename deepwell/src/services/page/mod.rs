@@ -24,8 +24,10 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod merge;
 mod service;
 mod structs;
 
+pub use self::merge::{merge_wikitext, MergeConflict};
 pub use self::service::PageService;
 pub use self::structs::*;
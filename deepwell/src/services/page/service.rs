@@ -18,19 +18,24 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::merge::merge_wikitext;
 use super::prelude::*;
 use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::models::page_category::Model as PageCategoryModel;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::sea_orm_active_enums::PageRevisionType;
 use crate::services::filter::{FilterClass, FilterType};
+use crate::services::notification::NotificationEvent;
 use crate::services::page_revision::{
     CreateFirstPageRevision, CreateFirstPageRevisionOutput, CreatePageRevision,
     CreatePageRevisionBody, CreatePageRevisionOutput, CreateResurrectionPageRevision,
     CreateTombstonePageRevision,
 };
+use crate::services::redirect::RewriteBacklinks;
 use crate::services::{
-    CategoryService, FilterService, PageRevisionService, SiteService, TextService,
+    CategoryService, EditSessionService, FilterService, NotificationService,
+    PageRevisionService, RedirectService, SiteService, TagService, TextService,
+    ViewService,
 };
 use crate::types::PageOrder;
 use crate::utils::{get_category_name, trim_default};
@@ -61,6 +66,7 @@ impl PageService {
         // Ensure row consistency
         normalize(&mut slug);
         Self::check_conflicts(ctx, site_id, &slug, "create").await?;
+        RedirectService::remove_at(ctx, site_id, &slug).await?;
 
         // Perform filter validation
         if !bypass_filter {
@@ -114,6 +120,18 @@ impl PageService {
         let page = model.update(txn).await?;
         assert_latest_revision(&page);
 
+        // Notify followers of the page creator
+        NotificationService::queue(
+            ctx,
+            NotificationEvent::PageCreate {
+                site_id,
+                page_id,
+                revision_id,
+                user_id,
+            },
+        )
+        .await?;
+
         // Build and return
         Ok(CreatePageOutput {
             page_id,
@@ -138,6 +156,8 @@ impl PageService {
                     alt_title,
                     tags,
                 },
+            bypass_filter,
+            bypass_tag_check,
         }: EditPage<'_>,
     ) -> Result<Option<EditPageOutput>> {
         let txn = ctx.transaction();
@@ -147,25 +167,55 @@ impl PageService {
             ..
         } = Self::get(ctx, site_id, reference).await?;
 
-        // Perform filter validation
-        Self::run_filter(
-            ctx,
-            site_id,
-            wikitext.to_option(),
-            title.to_option(),
-            // Flatten what is essentially Option<Option<_>>
-            match alt_title {
-                Maybe::Set(Some(ref alt_title)) => Some(alt_title),
-                _ => None,
-            },
-        )
-        .await?;
+        // Perform filter and tag validation
+        if !bypass_filter {
+            Self::run_filter(
+                ctx,
+                site_id,
+                wikitext.to_option(),
+                title.to_option(),
+                // Flatten what is essentially Option<Option<_>>
+                match alt_title {
+                    Maybe::Set(Some(ref alt_title)) => Some(alt_title),
+                    _ => None,
+                },
+            )
+            .await?;
+        }
+
+        if !bypass_tag_check {
+            if let Maybe::Set(ref tags) = tags {
+                TagService::validate_page_tags(ctx, site_id, tags).await?;
+            }
+        }
 
         // Get and check latest revision
+        //
+        // If the edit was based on an older revision, then
+        // try to merge it with the changes made since then.
         let last_revision =
             PageRevisionService::get_latest(ctx, site_id, page_id).await?;
 
-        check_last_revision(Some(&last_revision), latest_revision_id, last_revision_id)?;
+        let wikitext = match check_last_revision(
+            Some(&last_revision),
+            latest_revision_id,
+            last_revision_id,
+        ) {
+            Ok(()) => wikitext,
+            Err(Error::NotLatestRevisionId) => {
+                Self::merge_concurrent_edit(
+                    ctx,
+                    &last_revision,
+                    last_revision_id,
+                    wikitext,
+                    &title,
+                    &alt_title,
+                    &tags,
+                )
+                .await?
+            }
+            Err(error) => return Err(error),
+        };
 
         // Create new revision
         //
@@ -212,6 +262,23 @@ impl PageService {
         let page = model.update(txn).await?;
         assert_latest_revision(&page);
 
+        // The user's edit is done, so they no longer need their session or draft
+        EditSessionService::finish(ctx, page_id, user_id).await?;
+
+        // Notify page watchers, if anything changed
+        if let Some(ref output) = revision_output {
+            NotificationService::queue(
+                ctx,
+                NotificationEvent::PageEdit {
+                    site_id,
+                    page_id,
+                    revision_id: output.revision_id,
+                    user_id,
+                },
+            )
+            .await?;
+        }
+
         // Build and return
         Ok(revision_output)
     }
@@ -226,6 +293,8 @@ impl PageService {
             last_revision_id,
             revision_comments: comments,
             user_id,
+            leave_redirect,
+            rewrite_backlinks,
         }: MovePage<'_>,
     ) -> Result<MovePageOutput> {
         let txn = ctx.transaction();
@@ -299,6 +368,26 @@ impl PageService {
         let page = model.update(txn).await?;
         assert_latest_revision(&page);
 
+        // Update redirects, now that the page is at its new slug
+        RedirectService::remove_at(ctx, site_id, &new_slug).await?;
+
+        if leave_redirect {
+            RedirectService::create(ctx, site_id, &old_slug, page_id, user_id).await?;
+        }
+
+        if rewrite_backlinks {
+            RedirectService::queue_rewrite_backlinks(
+                ctx,
+                RewriteBacklinks {
+                    site_id,
+                    page_id,
+                    old_slug: old_slug.clone(),
+                    user_id,
+                },
+            )
+            .await?;
+        }
+
         // Build and return
 
         match revision_output {
@@ -306,13 +395,26 @@ impl PageService {
                 revision_id,
                 revision_number,
                 parser_errors,
-            }) => Ok(MovePageOutput {
-                old_slug,
-                new_slug,
-                revision_id,
-                revision_number,
-                parser_errors,
-            }),
+            }) => {
+                NotificationService::queue(
+                    ctx,
+                    NotificationEvent::PageMove {
+                        site_id,
+                        page_id,
+                        revision_id,
+                        user_id,
+                    },
+                )
+                .await?;
+
+                Ok(MovePageOutput {
+                    old_slug,
+                    new_slug,
+                    revision_id,
+                    revision_number,
+                    parser_errors,
+                })
+            }
             None => {
                 error!("Page move did not create new revision");
                 Err(Error::BadRequest)
@@ -367,6 +469,18 @@ impl PageService {
         let page = model.update(txn).await?;
         assert_latest_revision(&page);
 
+        // Notify page watchers
+        NotificationService::queue(
+            ctx,
+            NotificationEvent::PageDelete {
+                site_id,
+                page_id,
+                revision_id: output.revision_id,
+                user_id,
+            },
+        )
+        .await?;
+
         Ok((output, page_id).into())
     }
 
@@ -401,6 +515,7 @@ impl PageService {
         }
 
         Self::check_conflicts(ctx, site_id, &slug, "restore").await?;
+        RedirectService::remove_at(ctx, site_id, &slug).await?;
 
         // Create category if not already present
         let category =
@@ -557,7 +672,8 @@ impl PageService {
             ..Default::default()
         };
 
-        model.update(txn).await?;
+        let page = model.update(txn).await?;
+        ViewService::invalidate_page(ctx, site_id, &page.slug).await?;
         Ok(())
     }
 
@@ -767,6 +883,91 @@ impl PageService {
         Ok(pages)
     }
 
+    /// Merges an edit based on an older revision with the latest one.
+    ///
+    /// Wikitext is merged line-by-line, failing with `Error::EditConflict`
+    /// if both revisions changed the same region. Other fields cannot be
+    /// merged, so if any were changed both since the base revision and in
+    /// this edit, the edit is rejected as before.
+    ///
+    /// Returns the wikitext to use for the new revision.
+    async fn merge_concurrent_edit(
+        ctx: &ServiceContext<'_>,
+        latest_revision: &PageRevisionModel,
+        base_revision_id: i64,
+        wikitext: Maybe<String>,
+        title: &Maybe<String>,
+        alt_title: &Maybe<Option<String>>,
+        tags: &Maybe<Vec<String>>,
+    ) -> Result<Maybe<String>> {
+        let base_revision = match PageRevisionService::get_direct_optional(
+            ctx,
+            base_revision_id,
+        )
+        .await?
+        {
+            Some(revision)
+                if revision.page_id == latest_revision.page_id
+                    && revision.revision_number < latest_revision.revision_number =>
+            {
+                revision
+            }
+            _ => {
+                error!(
+                    "Base revision ID {} is not an earlier revision of page ID {}",
+                    base_revision_id, latest_revision.page_id,
+                );
+                return Err(Error::NotLatestRevisionId);
+            }
+        };
+
+        info!(
+            "Merging edit based on revision ID {} with latest revision ID {}",
+            base_revision.revision_id, latest_revision.revision_id,
+        );
+
+        macro_rules! check_field {
+            ($field:ident $(,)?) => {
+                if let Maybe::Set(value) = $field {
+                    if base_revision.$field != latest_revision.$field
+                        && value != &latest_revision.$field
+                    {
+                        error!(
+                            "Field '{}' was changed since revision ID {}, cannot merge",
+                            stringify!($field),
+                            base_revision.revision_id,
+                        );
+                        return Err(Error::NotLatestRevisionId);
+                    }
+                }
+            };
+        }
+
+        check_field!(title);
+        check_field!(alt_title);
+        check_field!(tags);
+
+        let submitted = match wikitext {
+            Maybe::Set(wikitext) => wikitext,
+            Maybe::Unset => return Ok(Maybe::Unset),
+        };
+
+        let base = TextService::get(ctx, &base_revision.wikitext_hash).await?;
+        let latest = TextService::get(ctx, &latest_revision.wikitext_hash).await?;
+
+        match merge_wikitext(&base, &latest, &submitted) {
+            Ok(merged) => Ok(Maybe::Set(merged)),
+            Err(conflicts) => {
+                warn!(
+                    "Edit has {} conflict(s) with latest revision ID {}",
+                    conflicts.len(),
+                    latest_revision.revision_id,
+                );
+                Err(Error::EditConflict(conflicts))
+            }
+        }
+    }
+
     /// Checks to see if a page already exists at the slug specified.
     ///
     /// If so, this method fails with `Error::PageExists`. Otherwise it returns nothing.
@@ -168,6 +168,20 @@ pub struct EditPage<'a> {
 
     #[serde(flatten)]
     pub body: EditPageBody,
+
+    /// Skips the text filters, for internal edits such as jobs.
+    ///
+    /// Always `false` when called via API.
+    #[serde(skip)]
+    pub(crate) bypass_filter: bool,
+
+    /// Skips checking tags against the site's tag definitions.
+    ///
+    /// Only used when renaming tags, since the new tag is not
+    /// defined until every page has been updated.
+    /// Always `false` when called via API.
+    #[serde(skip)]
+    pub(crate) bypass_tag_check: bool,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -187,6 +201,14 @@ pub struct MovePage<'a> {
     pub new_slug: String,
     pub revision_comments: String,
     pub user_id: i64,
+
+    /// Whether to leave a redirect at the old slug pointing to this page.
+    #[serde(default)]
+    pub leave_redirect: bool,
+
+    /// Whether to queue a job to update links to the old slug.
+    #[serde(default)]
+    pub rewrite_backlinks: bool,
     // NOTE: slug field is a parameter, not in the body
 }
 
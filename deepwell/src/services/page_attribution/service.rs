@@ -0,0 +1,251 @@
+/*
+ * services/page_attribution/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_attribution::{
+    self, Entity as PageAttribution, Model as PageAttributionModel,
+};
+use crate::services::{PageRevisionService, PageService, TextService, UserService};
+use std::collections::{HashMap, HashSet};
+use wikidot_normalize::normalize;
+
+/// The slug of the page Wikidot sites use to record attributions.
+const WIKIDOT_METADATA_SLUG: &str = "attribution-metadata";
+
+#[derive(Debug)]
+pub struct PageAttributionService;
+
+impl PageAttributionService {
+    /// Credits a user for a page.
+    ///
+    /// A user has at most one attribution of each type per page,
+    /// so this replaces any existing one of the same type.
+    pub async fn set(
+        ctx: &ServiceContext<'_>,
+        SetPageAttribution {
+            site_id,
+            page_id,
+            user_id,
+            attribution_type,
+            attribution_date,
+        }: SetPageAttribution,
+    ) -> Result<PageAttributionModel> {
+        info!(
+            "Setting {} attribution for user ID {user_id} on page ID {page_id}",
+            attribution_type.value(),
+        );
+
+        // Ensure the page is in this site
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+
+        let txn = ctx.transaction();
+        Self::delete(ctx, page_id, user_id, attribution_type).await?;
+
+        let model = page_attribution::ActiveModel {
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            attribution_type: Set(str!(attribution_type.value())),
+            attribution_date: Set(attribution_date.unwrap_or_else(|| now().date())),
+            ..Default::default()
+        };
+
+        let attribution = model.insert(txn).await?;
+        Ok(attribution)
+    }
+
+    /// Removes a user's attribution of the given type from a page.
+    pub async fn remove(
+        ctx: &ServiceContext<'_>,
+        RemovePageAttribution {
+            site_id,
+            page_id,
+            user_id,
+            attribution_type,
+        }: RemovePageAttribution,
+    ) -> Result<()> {
+        info!(
+            "Removing {} attribution for user ID {user_id} on page ID {page_id}",
+            attribution_type.value(),
+        );
+
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+
+        if Self::delete(ctx, page_id, user_id, attribution_type).await? == 0 {
+            return Err(Error::PageAttributionNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Gets all attributions for a page, oldest first.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetPageAttributions { site_id, page_id }: GetPageAttributions,
+    ) -> Result<Vec<PageAttributionModel>> {
+        info!("Getting attributions for page ID {page_id} in site ID {site_id}");
+
+        PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+        Self::get_all_direct(ctx, page_id).await
+    }
+
+    /// Gets all attributions for a page, without checking which site it is in.
+    pub async fn get_all_direct(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Vec<PageAttributionModel>> {
+        let txn = ctx.transaction();
+        let attributions = PageAttribution::find()
+            .filter(page_attribution::Column::PageId.eq(page_id))
+            .order_by_asc(page_attribution::Column::AttributionDate)
+            .order_by_asc(page_attribution::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(attributions)
+    }
+
+    /// Imports attributions from a site's Wikidot `attribution-metadata` page.
+    ///
+    /// The metadata is taken as authoritative, so any page it lists has its
+    /// existing attributions replaced. Rows naming pages or users which do not
+    /// exist are skipped. Returns how many attributions were added.
+    pub async fn import_wikidot_metadata(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<usize> {
+        let txn = ctx.transaction();
+        let Some(metadata_page) = PageService::get_optional(
+            ctx,
+            site_id,
+            Reference::Slug(cow!(WIKIDOT_METADATA_SLUG)),
+        )
+        .await?
+        else {
+            debug!("No attribution metadata page in site ID {site_id}");
+            return Ok(0);
+        };
+
+        info!("Importing Wikidot attribution metadata for site ID {site_id}");
+
+        let revision =
+            PageRevisionService::get_latest(ctx, site_id, metadata_page.page_id).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+
+        let mut pages = HashMap::new();
+        let mut users = HashMap::new();
+        let mut seen = HashSet::new();
+        let mut count = 0;
+
+        for entry in parse_wikidot_metadata(&wikitext) {
+            // Look up page, clearing its attributions the first time it is seen
+            let page = match pages.get(entry.page_slug) {
+                Some(page) => *page,
+                None => {
+                    let mut slug = str!(entry.page_slug);
+                    normalize(&mut slug);
+
+                    let page = PageService::get_optional(
+                        ctx,
+                        site_id,
+                        Reference::Slug(cow!(slug)),
+                    )
+                    .await?
+                    .map(|page| (page.page_id, page.created_at.date()));
+
+                    if let Some((page_id, _)) = page {
+                        PageAttribution::delete_many()
+                            .filter(page_attribution::Column::PageId.eq(page_id))
+                            .exec(txn)
+                            .await?;
+                    }
+
+                    pages.insert(entry.page_slug, page);
+                    page
+                }
+            };
+
+            let user_id = match users.get(entry.username) {
+                Some(user_id) => *user_id,
+                None => {
+                    let mut slug = str!(entry.username);
+                    normalize(&mut slug);
+
+                    let user_id =
+                        UserService::get_optional(ctx, Reference::Slug(cow!(slug)))
+                            .await?
+                            .map(|user| user.user_id);
+
+                    users.insert(entry.username, user_id);
+                    user_id
+                }
+            };
+
+            let (Some((page_id, page_created)), Some(user_id)) = (page, user_id) else {
+                warn!(
+                    "Skipping attribution for '{}' on '{}', page or user not found",
+                    entry.username, entry.page_slug,
+                );
+                continue;
+            };
+
+            let attribution_date = entry.attribution_date.unwrap_or(page_created);
+            let key = (page_id, user_id, entry.attribution_type, attribution_date);
+            if !seen.insert(key) {
+                continue;
+            }
+
+            let model = page_attribution::ActiveModel {
+                page_id: Set(page_id),
+                user_id: Set(user_id),
+                attribution_type: Set(str!(entry.attribution_type.value())),
+                attribution_date: Set(attribution_date),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Deletes all of a user's attributions of a type on a page, returning how many.
+    async fn delete(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+        attribution_type: AttributionType,
+    ) -> Result<u64> {
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected, .. } = PageAttribution::delete_many()
+            .filter(
+                Condition::all()
+                    .add(page_attribution::Column::PageId.eq(page_id))
+                    .add(page_attribution::Column::UserId.eq(user_id))
+                    .add(
+                        page_attribution::Column::AttributionType
+                            .eq(attribution_type.value()),
+                    ),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(rows_affected)
+    }
+}
@@ -0,0 +1,202 @@
+/*
+ * services/page_attribution/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use time::{Date, Month};
+
+/// The kind of credit a user is given for a page.
+///
+/// These values are kept in sync with Crom.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum AttributionType {
+    Author,
+    Rewrite,
+    Translator,
+    Maintainer,
+}
+
+impl AttributionType {
+    /// Get the constant string value used to represent this attribution in the database.
+    pub fn value(self) -> &'static str {
+        match self {
+            AttributionType::Author => "author",
+            AttributionType::Rewrite => "rewrite",
+            AttributionType::Translator => "translator",
+            AttributionType::Maintainer => "maintainer",
+        }
+    }
+
+    /// Parses an attribution type as written in Wikidot attribution metadata.
+    pub fn parse_wikidot(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "author" | "co-author" | "coauthor" => Some(AttributionType::Author),
+            "rewrite" | "rewriter" => Some(AttributionType::Rewrite),
+            "translator" | "translation" => Some(AttributionType::Translator),
+            "maintainer" => Some(AttributionType::Maintainer),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct SetPageAttribution {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+    pub attribution_type: AttributionType,
+
+    /// When the user did this work. If absent, then today is used.
+    #[serde(default)]
+    pub attribution_date: Option<Date>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct RemovePageAttribution {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+    pub attribution_type: AttributionType,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetPageAttributions {
+    pub site_id: i64,
+    pub page_id: i64,
+}
+
+/// A single row of a Wikidot attribution metadata table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikidotAttribution<'a> {
+    pub page_slug: &'a str,
+    pub username: &'a str,
+    pub attribution_type: AttributionType,
+    pub attribution_date: Option<Date>,
+}
+
+/// Parses the table in a Wikidot `attribution-metadata` page.
+///
+/// Each row has the form `|| page || user || type || date ||`, where the date
+/// is either `YYYY-MM-DD` or `YYYY-MM`, and may be left out. Header rows and
+/// rows with unknown attribution types are skipped.
+pub fn parse_wikidot_metadata(wikitext: &str) -> Vec<WikidotAttribution<'_>> {
+    let mut attributions = Vec::new();
+
+    for line in wikitext.lines() {
+        let Some(row) = line.trim().strip_prefix("||") else {
+            continue;
+        };
+
+        // Header cells start with a tilde
+        if row.starts_with('~') {
+            continue;
+        }
+
+        let cells: Vec<&str> = row
+            .trim_end_matches("||")
+            .split("||")
+            .map(str::trim)
+            .collect();
+
+        let (page_slug, username, attribution_type, date) = match cells[..] {
+            [page, user, kind] => (page, user, kind, None),
+            [page, user, kind, date, ..] => (page, user, kind, Some(date)),
+            _ => continue,
+        };
+
+        if page_slug.is_empty() || username.is_empty() {
+            continue;
+        }
+
+        let Some(attribution_type) = AttributionType::parse_wikidot(attribution_type)
+        else {
+            warn!("Unknown attribution type '{attribution_type}' for page '{page_slug}'");
+            continue;
+        };
+
+        attributions.push(WikidotAttribution {
+            page_slug,
+            username,
+            attribution_type,
+            attribution_date: date.and_then(parse_wikidot_date),
+        });
+    }
+
+    attributions
+}
+
+fn parse_wikidot_date(value: &str) -> Option<Date> {
+    let mut parts = value.split('-').map(|part| part.trim().parse::<u16>().ok());
+    let year = parts.next()??;
+    let month = parts.next()??;
+    let day = parts.next().unwrap_or(Some(1))?;
+    let month = Month::try_from(u8::try_from(month).ok()?).ok()?;
+    Date::from_calendar_date(i32::from(year), month, u8::try_from(day).ok()?).ok()
+}
+
+#[test]
+fn wikidot_metadata() {
+    macro_rules! date {
+        ($year:expr, $month:ident, $day:expr $(,)?) => {
+            Some(Date::from_calendar_date($year, Month::$month, $day).unwrap())
+        };
+    }
+
+    let wikitext = "\
+[[table]]
+||~ Page ||~ User ||~ Type ||~ Date ||
+|| scp-173 || Moto42 || author || 2008-07-19 ||
+|| scp-173 || Someone Else || Co-Author || 2008-07 ||
+|| scp-999 || translator-person || translator ||
+|| scp-1000 || nobody || editor || 2012-01-01 ||
+|| broken ||
+not a row
+|| scp-002 || rewriter-person || rewrite || not a date ||
+";
+
+    assert_eq!(
+        parse_wikidot_metadata(wikitext),
+        vec![
+            WikidotAttribution {
+                page_slug: "scp-173",
+                username: "Moto42",
+                attribution_type: AttributionType::Author,
+                attribution_date: date!(2008, July, 19),
+            },
+            WikidotAttribution {
+                page_slug: "scp-173",
+                username: "Someone Else",
+                attribution_type: AttributionType::Author,
+                attribution_date: date!(2008, July, 1),
+            },
+            WikidotAttribution {
+                page_slug: "scp-999",
+                username: "translator-person",
+                attribution_type: AttributionType::Translator,
+                attribution_date: None,
+            },
+            WikidotAttribution {
+                page_slug: "scp-002",
+                username: "rewriter-person",
+                attribution_type: AttributionType::Rewrite,
+                attribution_date: None,
+            },
+        ],
+    );
+}
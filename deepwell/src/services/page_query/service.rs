@@ -22,13 +22,17 @@
 
 use super::prelude::*;
 use crate::models::page::{self, Entity as Page};
+use crate::models::page_attribution::{self, Entity as PageAttribution};
 use crate::models::page_category::{self, Entity as PageCategory};
 use crate::models::page_connection::{self, Entity as PageConnection};
 use crate::models::page_parent::{self, Entity as PageParent};
-use crate::models::{page_revision, text};
-use crate::services::{PageService, ParentService};
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::text;
+use crate::services::page_attribution::AttributionType;
+use crate::services::{PageService, ParentService, UserService};
 use sea_query::{Expr, Query};
 use std::convert::Infallible;
+use wikidot_normalize::normalize;
 
 #[derive(Debug)]
 pub struct PageQueryService;
@@ -286,6 +290,71 @@ impl PageQueryService {
             condition = condition.add(page::Column::Slug.eq(slug));
         }
 
+        // Author
+        //
+        // Selects pages created by any of the given users. Author attributions
+        // override whoever made the first revision, so the revision creator is
+        // only considered for pages which have no authors attributed.
+        if !author.is_empty() {
+            debug!("Filtering based on authors {author:?}");
+
+            let mut author_ids = Vec::new();
+            for name in author {
+                let mut slug = name.to_string();
+                normalize(&mut slug);
+
+                if let Some(user) =
+                    UserService::get_optional(ctx, Reference::Slug(cow!(slug))).await?
+                {
+                    author_ids.push(user.user_id);
+                }
+            }
+
+            let attributed_pages = || {
+                Query::select()
+                    .column(page_attribution::Column::PageId)
+                    .from(PageAttribution)
+                    .and_where(
+                        page_attribution::Column::AttributionType
+                            .eq(AttributionType::Author.value()),
+                    )
+                    .to_owned()
+            };
+
+            condition = condition.add(
+                Condition::any()
+                    .add(
+                        page::Column::PageId.in_subquery(
+                            attributed_pages()
+                                .and_where(
+                                    page_attribution::Column::UserId
+                                        .is_in(author_ids.iter().copied()),
+                                )
+                                .to_owned(),
+                        ),
+                    )
+                    .add(
+                        Condition::all()
+                            .add(page::Column::PageId.not_in_subquery(attributed_pages()))
+                            .add(
+                                page::Column::PageId.in_subquery(
+                                    Query::select()
+                                        .column(page_revision::Column::PageId)
+                                        .from(PageRevision)
+                                        .and_where(
+                                            page_revision::Column::RevisionNumber.eq(0),
+                                        )
+                                        .and_where(
+                                            page_revision::Column::UserId
+                                                .is_in(author_ids.iter().copied()),
+                                        )
+                                        .to_owned(),
+                                ),
+                            ),
+                    ),
+            );
+        }
+
         // Contains-link
         //
         // Selects pages that have an outgoing link (`from_page_id`)
@@ -390,12 +459,12 @@ impl PageQueryService {
                     query = query.order_by(expr, order);
                 }
                 OrderProperty::Score => {
-                    error!("Ordering by score, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by latest score snapshot");
+                    query = query.order_by(latest_snapshot_expr("score"), order);
                 }
                 OrderProperty::Votes => {
-                    error!("Ordering by vote count, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by latest vote count snapshot");
+                    query = query.order_by(latest_snapshot_expr("vote_count"), order);
                 }
                 OrderProperty::Revisions => {
                     error!("Ordering by revision count, not yet implemented");
@@ -441,3 +510,18 @@ impl PageQueryService {
         todo!()
     }
 }
+
+/// Builds an expression for a column of the page's most recent score snapshot.
+///
+/// Pages without any snapshots are treated as having a value of zero.
+fn latest_snapshot_expr(column: &str) -> sea_query::SimpleExpr {
+    Expr::cust(format!(
+        "COALESCE((
+            SELECT page_score_snapshot.{column}
+            FROM page_score_snapshot
+            WHERE page_score_snapshot.page_id = page.page_id
+            ORDER BY page_score_snapshot.snapshot_id DESC
+            LIMIT 1
+        ), 0)",
+    ))
+}
@@ -27,7 +27,7 @@ use crate::services::render::RenderOutput;
 use crate::services::score::ScoreValue;
 use crate::services::{
     LinkService, OutdateService, PageService, ParentService, RenderService, ScoreService,
-    SettingsService, SiteService, TextService,
+    SettingsService, SiteService, TextService, ViewService,
 };
 use crate::types::FetchDirection;
 use crate::utils::{split_category, split_category_name};
@@ -275,6 +275,7 @@ impl PageRevisionService {
                 // is true, otherwise does nothing.
 
                 try_join!(
+                    ViewService::invalidate_page(ctx, site_id, &slug),
                     conditional_future!(
                         tasks.rerender_incoming_links,
                         OutdateService::outdate_incoming_links(ctx, page_id, 0),
@@ -775,6 +776,11 @@ impl PageRevisionService {
         // TODO: record revision edit in audit log
         let _ = user_id;
 
+        // Only the latest revision is part of the page view
+        if revision_id == latest.revision_id {
+            ViewService::invalidate_page(ctx, site_id, &latest.slug).await?;
+        }
+
         // Update the revision
 
         let model = page_revision::ActiveModel {
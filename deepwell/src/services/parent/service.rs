@@ -23,6 +23,7 @@
 use super::prelude::*;
 use crate::models::page_parent::{self, Entity as PageParent, Model as PageParentModel};
 use crate::services::PageService;
+use sea_orm::{DatabaseBackend, FromQueryResult, Statement, Value};
 
 #[derive(Debug)]
 pub struct ParentService;
@@ -59,6 +60,16 @@ impl ParentService {
             return Err(Error::PageParentExists);
         }
 
+        // Check that the parent isn't already below the child
+        Self::lock_hierarchy(ctx, site_id).await?;
+        if Self::is_descendant(ctx, child_page.page_id, parent_page.page_id).await? {
+            error!(
+                "Cannot parent page ID {} to page ID {}, as it is one of its descendants",
+                child_page.page_id, parent_page.page_id,
+            );
+            return Err(Error::PageParentCycle);
+        }
+
         // Check if this relationship already exists
         let relationship =
             PageParent::find_by_id((parent_page.page_id, child_page.page_id))
@@ -191,6 +202,276 @@ impl ParentService {
             .await
     }
 
+    /// Gets all pages above the given one in the hierarchy, nearest first.
+    pub async fn get_ancestors(
+        ctx: &ServiceContext<'_>,
+        GetPageHierarchy {
+            site_id,
+            page: reference,
+            max_depth,
+        }: GetPageHierarchy<'_>,
+    ) -> Result<Vec<PageAncestor>> {
+        let txn = ctx.transaction();
+        let page_id = PageService::get_id(ctx, site_id, reference).await?;
+        let max_depth = depth_limit(ctx.config(), max_depth);
+        info!("Getting ancestors of page ID {page_id} (max depth {max_depth})");
+
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                WITH RECURSIVE ancestors (page_id, depth) AS (
+                    SELECT parent_page_id, 1
+                    FROM page_parent
+                    WHERE child_page_id = $1
+                    UNION
+                    SELECT pp.parent_page_id, a.depth + 1
+                    FROM page_parent AS pp
+                    JOIN ancestors AS a
+                        ON pp.child_page_id = a.page_id
+                    WHERE a.depth < $2
+                )
+                SELECT
+                    a.page_id AS page_id,
+                    p.slug AS slug,
+                    MIN(a.depth) AS depth
+                FROM ancestors AS a
+                JOIN page AS p
+                    ON p.page_id = a.page_id
+                WHERE p.deleted_at IS NULL
+                AND a.page_id <> $1
+                GROUP BY a.page_id, p.slug
+                ORDER BY depth, slug
+            "
+            ),
+            [Value::from(page_id), Value::from(max_depth)],
+        );
+
+        let ancestors = PageAncestor::find_by_statement(query).all(txn).await?;
+        Ok(ancestors)
+    }
+
+    /// Gets all pages below the given one in the hierarchy, nearest first.
+    pub async fn get_descendants(
+        ctx: &ServiceContext<'_>,
+        GetPageHierarchy {
+            site_id,
+            page: reference,
+            max_depth,
+        }: GetPageHierarchy<'_>,
+    ) -> Result<Vec<PageDescendant>> {
+        let txn = ctx.transaction();
+        let page_id = PageService::get_id(ctx, site_id, reference).await?;
+        let max_depth = depth_limit(ctx.config(), max_depth);
+        info!("Getting descendants of page ID {page_id} (max depth {max_depth})");
+
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                WITH RECURSIVE descendants (page_id, parent_page_id, depth) AS (
+                    SELECT child_page_id, parent_page_id, 1
+                    FROM page_parent
+                    WHERE parent_page_id = $1
+                    UNION
+                    SELECT pp.child_page_id, pp.parent_page_id, d.depth + 1
+                    FROM page_parent AS pp
+                    JOIN descendants AS d
+                        ON pp.parent_page_id = d.page_id
+                    WHERE d.depth < $2
+                )
+                SELECT
+                    d.page_id AS page_id,
+                    d.parent_page_id AS parent_page_id,
+                    p.slug AS slug,
+                    MIN(d.depth) AS depth
+                FROM descendants AS d
+                JOIN page AS p
+                    ON p.page_id = d.page_id
+                WHERE p.deleted_at IS NULL
+                AND d.page_id <> $1
+                GROUP BY d.page_id, d.parent_page_id, p.slug
+                ORDER BY depth, slug
+            "
+            ),
+            [Value::from(page_id), Value::from(max_depth)],
+        );
+
+        let descendants = PageDescendant::find_by_statement(query).all(txn).await?;
+        Ok(descendants)
+    }
+
+    /// Gets the breadcrumb trail for a page, starting from the topmost page.
+    ///
+    /// Where a page has several parents, the one it was given first is followed.
+    pub async fn get_breadcrumbs(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Vec<PageAncestor>> {
+        let txn = ctx.transaction();
+        let max_depth = depth_limit(ctx.config(), None);
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                WITH RECURSIVE breadcrumbs (page_id, depth) AS (
+                    SELECT $1::bigint, 0
+                    UNION ALL
+                    SELECT
+                        (
+                            SELECT pp.parent_page_id
+                            FROM page_parent AS pp
+                            WHERE pp.child_page_id = b.page_id
+                            ORDER BY pp.created_at, pp.parent_page_id
+                            LIMIT 1
+                        ),
+                        b.depth + 1
+                    FROM breadcrumbs AS b
+                    WHERE b.page_id IS NOT NULL
+                    AND b.depth < $2
+                )
+                SELECT
+                    b.page_id AS page_id,
+                    p.slug AS slug,
+                    b.depth AS depth
+                FROM breadcrumbs AS b
+                JOIN page AS p
+                    ON p.page_id = b.page_id
+                WHERE b.depth > 0
+                AND p.deleted_at IS NULL
+                ORDER BY b.depth DESC
+            "
+            ),
+            [Value::from(page_id), Value::from(max_depth)],
+        );
+
+        let breadcrumbs = PageAncestor::find_by_statement(query).all(txn).await?;
+        Ok(breadcrumbs)
+    }
+
+    /// Moves a page, along with everything below it, to a new place in the hierarchy.
+    ///
+    /// This replaces all of the page's parents with the new one.
+    /// Its descendants keep their relationships, and so move with it.
+    pub async fn move_subtree(
+        ctx: &ServiceContext<'_>,
+        MoveSubtree {
+            site_id,
+            page: reference,
+            new_parent,
+        }: MoveSubtree<'_>,
+    ) -> Result<MoveSubtreeOutput> {
+        let txn = ctx.transaction();
+        let page_id = PageService::get_id(ctx, site_id, reference).await?;
+        let new_parent_id = match new_parent {
+            Some(reference) => Some(PageService::get_id(ctx, site_id, reference).await?),
+            None => None,
+        };
+
+        info!("Moving subtree of page ID {page_id} to parent {new_parent_id:?}");
+
+        Self::lock_hierarchy(ctx, site_id).await?;
+        if let Some(parent_id) = new_parent_id {
+            if parent_id == page_id
+                || Self::is_descendant(ctx, page_id, parent_id).await?
+            {
+                error!(
+                    "Cannot move page ID {page_id} below page ID {parent_id}, which is in its subtree",
+                );
+                return Err(Error::PageParentCycle);
+            }
+        }
+
+        let removed_parent_ids = PageParent::find()
+            .filter(page_parent::Column::ChildPageId.eq(page_id))
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|model| model.parent_page_id)
+            .collect();
+
+        PageParent::delete_many()
+            .filter(page_parent::Column::ChildPageId.eq(page_id))
+            .exec(txn)
+            .await?;
+
+        if let Some(parent_id) = new_parent_id {
+            let model = page_parent::ActiveModel {
+                parent_page_id: Set(parent_id),
+                child_page_id: Set(page_id),
+                ..Default::default()
+            };
+
+            model.insert(txn).await?;
+        }
+
+        Ok(MoveSubtreeOutput {
+            removed_parent_ids,
+            new_parent_id,
+        })
+    }
+
+    /// Locks the page hierarchy of a site until the end of the transaction.
+    ///
+    /// Two concurrent changes could each pass the cycle check on their own,
+    /// but form a cycle together. So this must be held from before checking
+    /// with `is_descendant()` until the new relationship is committed.
+    async fn lock_hierarchy(ctx: &ServiceContext<'_>, site_id: i64) -> Result<()> {
+        debug!("Locking page hierarchy for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        txn.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "SELECT pg_advisory_xact_lock(hashtextextended('page_parent:' || $1, 0))",
+            [Value::from(site_id.to_string())],
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Checks whether `page_id` is anywhere below `ancestor_id` in the hierarchy.
+    async fn is_descendant(
+        ctx: &ServiceContext<'_>,
+        ancestor_id: i64,
+        page_id: i64,
+    ) -> Result<bool> {
+        #[derive(Debug, FromQueryResult)]
+        struct Row {
+            is_descendant: bool,
+        }
+
+        // No depth limit here, since a cycle past that would still be a cycle.
+        // UNION discards repeated rows, so this ends even if one already exists.
+        let txn = ctx.transaction();
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                WITH RECURSIVE descendants (page_id) AS (
+                    SELECT child_page_id
+                    FROM page_parent
+                    WHERE parent_page_id = $1
+                    UNION
+                    SELECT pp.child_page_id
+                    FROM page_parent AS pp
+                    JOIN descendants AS d
+                        ON pp.parent_page_id = d.page_id
+                )
+                SELECT EXISTS (
+                    SELECT 1
+                    FROM descendants
+                    WHERE page_id = $2
+                ) AS is_descendant
+            "
+            ),
+            [Value::from(ancestor_id), Value::from(page_id)],
+        );
+
+        let row = Row::find_by_statement(query).one(txn).await?;
+        Ok(row.is_some_and(|row| row.is_descendant))
+    }
+
     /// Removes all parent relationships involving this page.
     ///
     /// Whether this page is a parent or a child, this method
@@ -214,3 +495,10 @@ impl ParentService {
         Ok(rows_deleted)
     }
 }
+
+/// Gets how many levels of the hierarchy to traverse.
+fn depth_limit(config: &Config, requested: Option<u32>) -> i32 {
+    let maximum = config.maximum_hierarchy_depth;
+    let depth = requested.map_or(maximum, |depth| depth.min(maximum));
+    i32::try_from(depth).unwrap_or(i32::MAX)
+}
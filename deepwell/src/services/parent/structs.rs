@@ -20,6 +20,7 @@
 
 use crate::services::Error;
 use crate::types::Reference;
+use sea_orm::FromQueryResult;
 use std::str::FromStr;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -84,3 +85,49 @@ pub struct UpdateParentsOutput {
     pub added: Option<Vec<i64>>,
     pub removed: Option<Vec<bool>>,
 }
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageHierarchy<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    /// How many levels to traverse, limited by the configured maximum.
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+}
+
+/// A page above the given one in the hierarchy.
+///
+/// If there are multiple paths to the page, this has the shortest depth.
+#[derive(Serialize, FromQueryResult, Debug, Clone)]
+pub struct PageAncestor {
+    pub page_id: i64,
+    pub slug: String,
+    pub depth: i32,
+}
+
+/// A page below the given one in the hierarchy.
+///
+/// A page with multiple parents in the subtree is listed once per parent.
+#[derive(Serialize, FromQueryResult, Debug, Clone)]
+pub struct PageDescendant {
+    pub page_id: i64,
+    pub parent_page_id: i64,
+    pub slug: String,
+    pub depth: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MoveSubtree<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    /// The page's new parent, or `None` to make it a top-level page.
+    pub new_parent: Option<Reference<'a>>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MoveSubtreeOutput {
+    pub removed_parent_ids: Vec<i64>,
+    pub new_parent_id: Option<i64>,
+}
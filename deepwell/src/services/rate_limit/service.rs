@@ -0,0 +1,232 @@
+/*
+ * services/rate_limit/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::SessionService;
+use jsonrpsee::types::params::Params;
+use redis::Script;
+use sha2::{Digest, Sha256};
+
+/// Checks and takes from a set of token buckets atomically.
+///
+/// Each bucket is a hash with its current token count and when it was last updated.
+/// Either every bucket has a token and one is taken from each, or nothing is changed
+/// and the script returns how many milliseconds until all of them have one again.
+///
+/// Buckets which are full and unused expire on their own.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local interval = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+local levels = {}
+local wait = 0
+
+for i, key in ipairs(KEYS) do
+    local bucket = redis.call('HMGET', key, 'tokens', 'updated')
+    local tokens = tonumber(bucket[1]) or capacity
+    local updated = tonumber(bucket[2]) or now
+    tokens = math.min(capacity, tokens + math.max(0, now - updated) / interval)
+    levels[i] = tokens
+
+    if tokens < 1 then
+        wait = math.max(wait, math.ceil((1 - tokens) * interval))
+    end
+end
+
+if wait > 0 then
+    return wait
+end
+
+for i, key in ipairs(KEYS) do
+    redis.call('HSET', key, 'tokens', tostring(levels[i] - 1), 'updated', now)
+    redis.call('PEXPIRE', key, math.ceil(capacity * interval))
+end
+
+return 0
+"#;
+
+#[derive(Debug)]
+pub struct RateLimitService;
+
+impl RateLimitService {
+    /// Checks whether a request to the given method is within its rate limit.
+    ///
+    /// Methods without a configured limit are always allowed. Otherwise,
+    /// the identities are taken from the request's parameters, which must
+    /// include the caller's IP address or session token.
+    ///
+    /// Bot users are only exempt from the limits of methods which
+    /// list them in `bypass-bots`.
+    ///
+    /// # Errors
+    /// Returns `Error::RateLimitIdentityMissing` if the request has neither,
+    /// and `Error::RateLimitExceeded` if the request is denied.
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        method: &str,
+        params: &Params<'_>,
+    ) -> Result<()> {
+        let rule = match ctx.config().rate_limits.get(method) {
+            Some(rule) => rule,
+            None => return Ok(()),
+        };
+
+        // Methods with positional or no parameters have no identities to key on.
+        let key: RateLimitKey = params.parse().unwrap_or_default();
+        if key.ip_address.is_none() && key.session_token.is_none() {
+            error!(
+                "Request to rate-limited method '{method}' has no IP address or session"
+            );
+            return Err(Error::RateLimitIdentityMissing);
+        }
+
+        // Only trust the bot status of whoever the session belongs to,
+        // not any user ID the client happens to pass in.
+        if !rule.bypass_bots.is_empty() {
+            if let Some(ref session_token) = key.session_token {
+                match SessionService::get_user(ctx, session_token, false).await {
+                    Ok(user) if rule.bypasses(user.user_id, user.user_type) => {
+                        debug!(
+                            "Bot user ID {} bypasses rate limit for '{method}'",
+                            user.user_id,
+                        );
+                        return Ok(());
+                    }
+                    Ok(_) | Err(Error::UserNotFound) => (),
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+
+        let keys = bucket_keys(method, &key);
+        let mut redis = ctx.redis_connect().await?;
+        let wait_ms: u64 = Script::new(TOKEN_BUCKET_SCRIPT)
+            .key(&keys)
+            .arg(rule.requests)
+            .arg(rule.interval().as_secs_f64() * 1000.0)
+            .invoke_async(&mut redis)
+            .await?;
+
+        if wait_ms > 0 {
+            warn!("Rate limit exceeded for '{method}', retry in {wait_ms} ms");
+            return Err(Error::RateLimitExceeded {
+                retry_after: wait_ms,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets the Redis keys of the buckets which apply to this request.
+fn bucket_keys(method: &str, key: &RateLimitKey) -> Vec<String> {
+    let mut keys = Vec::new();
+
+    if let Some(ip_address) = key.ip_address {
+        keys.push(format!("rate-limit:{method}:ip:{ip_address}"));
+    }
+
+    if let Some(user_id) = key.user_id {
+        keys.push(format!("rate-limit:{method}:user:{user_id}"));
+    }
+
+    // Don't store session tokens or account names in Redis as-is
+    if let Some(ref session_token) = key.session_token {
+        let hash = Sha256::digest(session_token.as_bytes());
+        keys.push(format!("rate-limit:{method}:session:{}", hex::encode(hash)));
+    }
+
+    if let Some(ref name_or_email) = key.name_or_email {
+        let hash = Sha256::digest(name_or_email.trim().to_lowercase().as_bytes());
+        keys.push(format!("rate-limit:{method}:account:{}", hex::encode(hash)));
+    }
+
+    keys
+}
+
+#[test]
+fn rate_limit_keys() {
+    macro_rules! check {
+        ($key:expr, $expected:expr $(,)?) => {{
+            let keys = bucket_keys("login", &$key);
+            assert_eq!(keys, $expected, "Actual bucket keys don't match expected");
+        }};
+    }
+
+    check!(RateLimitKey::default(), [] as [&str; 0]);
+    check!(
+        RateLimitKey {
+            ip_address: Some("127.0.0.1".parse().unwrap()),
+            user_id: Some(5),
+            ..Default::default()
+        },
+        ["rate-limit:login:ip:127.0.0.1", "rate-limit:login:user:5"],
+    );
+
+    // The same account is limited however its name is typed
+    let keys = bucket_keys(
+        "login",
+        &RateLimitKey {
+            ip_address: Some("127.0.0.1".parse().unwrap()),
+            name_or_email: Some(str!("Alice@example.com ")),
+            ..Default::default()
+        },
+    );
+    let other_keys = bucket_keys(
+        "login",
+        &RateLimitKey {
+            ip_address: Some("10.0.0.1".parse().unwrap()),
+            name_or_email: Some(str!("alice@example.com")),
+            ..Default::default()
+        },
+    );
+    assert_eq!(keys.len(), 2);
+    assert!(keys[1].starts_with("rate-limit:login:account:"));
+    assert!(!keys[1].contains("alice"));
+    assert_eq!(keys[1], other_keys[1]);
+
+    let keys = bucket_keys(
+        "mfa_verify",
+        &RateLimitKey {
+            session_token: Some(str!("wj:secret")),
+            ..Default::default()
+        },
+    );
+    assert_eq!(keys.len(), 1);
+    assert!(keys[0].starts_with("rate-limit:mfa_verify:session:"));
+    assert!(!keys[0].contains("secret"));
+}
+
+#[test]
+fn rate_limit_bypass() {
+    use crate::models::sea_orm_active_enums::UserType;
+
+    let rule = RateLimitRule {
+        requests: 10,
+        period: std::time::Duration::from_secs(60),
+        bypass_bots: vec![5],
+    };
+
+    assert!(rule.bypasses(5, UserType::Bot));
+    assert!(!rule.bypasses(6, UserType::Bot));
+    assert!(!rule.bypasses(5, UserType::Regular));
+}
@@ -0,0 +1,71 @@
+/*
+ * services/rate_limit/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::UserType;
+use std::net::IpAddr;
+use std::time::Duration as StdDuration;
+
+/// The rate limit applied to a particular method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitRule {
+    /// How many requests can be made in a burst.
+    pub requests: u32,
+
+    /// How long it takes for a fully emptied bucket to refill.
+    pub period: StdDuration,
+
+    /// The IDs of bot users which are exempt from this limit.
+    pub bypass_bots: Vec<i64>,
+}
+
+impl RateLimitRule {
+    /// How long it takes to refill a single request.
+    #[inline]
+    pub fn interval(&self) -> StdDuration {
+        self.period / self.requests
+    }
+
+    /// Whether this user is a bot allowed to bypass this limit.
+    pub fn bypasses(&self, user_id: i64, user_type: UserType) -> bool {
+        user_type == UserType::Bot && self.bypass_bots.contains(&user_id)
+    }
+}
+
+/// The identities of the client making a request.
+///
+/// These are pulled from the request parameters, so any method can be
+/// rate limited on whichever of these fields it has. Every rate-limited
+/// request must have at least an IP address or session token.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct RateLimitKey {
+    #[serde(default)]
+    pub ip_address: Option<IpAddr>,
+
+    #[serde(default)]
+    pub user_id: Option<i64>,
+
+    #[serde(default)]
+    pub session_token: Option<String>,
+
+    /// The account being logged into, so that guessing one account's
+    /// password is limited no matter how many addresses it comes from.
+    #[serde(default)]
+    pub name_or_email: Option<String>,
+}
@@ -0,0 +1,146 @@
+/*
+ * services/recent_changes/feed.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Rendering of recent changes into syndication feed formats.
+//!
+//! The documents produced are small and regular, so we write the XML
+//! directly rather than pulling in a serialization library.
+
+use crate::utils::escape_xml as escape;
+use std::fmt::Write;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub title: String,
+    pub link: String,
+    pub updated: OffsetDateTime,
+    pub entries: Vec<FeedEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub author: String,
+    pub summary: String,
+    pub category: &'static str,
+    pub updated: OffsetDateTime,
+}
+
+fn rfc3339(timestamp: OffsetDateTime) -> String {
+    timestamp
+        .format(&Rfc3339)
+        .expect("Unable to format timestamp as RFC 3339")
+}
+
+fn rfc2822(timestamp: OffsetDateTime) -> String {
+    timestamp
+        .format(&Rfc2822)
+        .expect("Unable to format timestamp as RFC 2822")
+}
+
+// Writing to a String cannot fail, so the results of write!() are discarded below.
+
+pub fn render_atom(feed: &Feed) -> String {
+    let mut output = String::new();
+    output.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    output.push('\n');
+    output.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    output.push('\n');
+
+    let _ = writeln!(output, "  <id>{}</id>", escape(&feed.link));
+    let _ = writeln!(output, "  <title>{}</title>", escape(&feed.title));
+    let _ = writeln!(output, "  <link href=\"{}\"/>", escape(&feed.link));
+    let _ = writeln!(output, "  <updated>{}</updated>", rfc3339(feed.updated));
+
+    for entry in &feed.entries {
+        output.push_str("  <entry>\n");
+        let _ = writeln!(output, "    <id>{}</id>", escape(&entry.id));
+        let _ = writeln!(output, "    <title>{}</title>", escape(&entry.title));
+        let _ = writeln!(output, "    <link href=\"{}\"/>", escape(&entry.link));
+        let _ = writeln!(output, "    <updated>{}</updated>", rfc3339(entry.updated));
+        let _ = writeln!(
+            output,
+            "    <author><name>{}</name></author>",
+            escape(&entry.author),
+        );
+        let _ = writeln!(output, "    <category term=\"{}\"/>", entry.category);
+        let _ = writeln!(output, "    <summary>{}</summary>", escape(&entry.summary));
+        output.push_str("  </entry>\n");
+    }
+
+    output.push_str("</feed>\n");
+    output
+}
+
+pub fn render_rss(feed: &Feed) -> String {
+    let mut output = String::new();
+    output.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    output.push('\n');
+    output.push_str(r#"<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">"#);
+    output.push_str("\n  <channel>\n");
+
+    let _ = writeln!(output, "    <title>{}</title>", escape(&feed.title));
+    let _ = writeln!(output, "    <link>{}</link>", escape(&feed.link));
+    let _ = writeln!(
+        output,
+        "    <description>{}</description>",
+        escape(&feed.title),
+    );
+    let _ = writeln!(
+        output,
+        "    <lastBuildDate>{}</lastBuildDate>",
+        rfc2822(feed.updated),
+    );
+
+    for entry in &feed.entries {
+        output.push_str("    <item>\n");
+        let _ = writeln!(
+            output,
+            "      <guid isPermaLink=\"false\">{}</guid>",
+            escape(&entry.id),
+        );
+        let _ = writeln!(output, "      <title>{}</title>", escape(&entry.title));
+        let _ = writeln!(output, "      <link>{}</link>", escape(&entry.link));
+        let _ = writeln!(
+            output,
+            "      <pubDate>{}</pubDate>",
+            rfc2822(entry.updated)
+        );
+        let _ = writeln!(
+            output,
+            "      <dc:creator>{}</dc:creator>",
+            escape(&entry.author),
+        );
+        let _ = writeln!(output, "      <category>{}</category>", entry.category);
+        let _ = writeln!(
+            output,
+            "      <description>{}</description>",
+            escape(&entry.summary),
+        );
+        output.push_str("    </item>\n");
+    }
+
+    output.push_str("  </channel>\n</rss>\n");
+    output
+}
@@ -0,0 +1,38 @@
+/*
+ * services/recent_changes/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The recent changes service, which produces a site-wide feed of page and file activity.
+//!
+//! Entries are gathered from both `page_revision` and `file_revision`, merged by time,
+//! and have any hidden revision fields stripped before being returned. The feed can
+//! also be rendered as Atom or RSS for syndication.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod feed;
+mod service;
+mod structs;
+
+pub use self::service::RecentChangesService;
+pub use self::structs::*;
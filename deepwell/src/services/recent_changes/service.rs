@@ -0,0 +1,557 @@
+/*
+ * services/recent_changes/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::feed::{self, Feed, FeedEntry};
+use super::prelude::*;
+use crate::models::file_revision::{
+    self, Entity as FileRevision, Model as FileRevisionModel,
+};
+use crate::models::page;
+use crate::models::page::Model as PageModel;
+use crate::models::page_revision::{
+    self, Entity as PageRevision, Model as PageRevisionModel,
+};
+use crate::models::site::Model as SiteModel;
+use crate::services::{
+    CategoryService, DomainService, PageService, SiteService, UserService,
+};
+use std::cmp::Reverse;
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt::Write;
+use time::OffsetDateTime;
+
+#[derive(Debug)]
+pub struct RecentChangesService;
+
+impl RecentChangesService {
+    /// Gets recent changes to pages and files, newest first.
+    ///
+    /// Both revision tables are queried for up to `limit` rows, which are then
+    /// merged by creation time and truncated. To get the next batch, pass the
+    /// returned `next_cursor` value as `cursor`.
+    ///
+    /// The limit is capped at the configured maximum, see `maximum_recent_changes`.
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        GetRecentChanges {
+            site_id,
+            category,
+            change_types,
+            source,
+            user_id,
+            after,
+            before,
+            cursor,
+            limit,
+        }: GetRecentChanges<'_>,
+    ) -> Result<GetRecentChangesOutput> {
+        let limit = limit.min(ctx.config().maximum_recent_changes);
+        info!(
+            "Getting {limit} recent changes for site ID {site_id:?} (category {category:?}, types {change_types:?}, source {source:?}, user ID {user_id:?})",
+        );
+
+        let txn = ctx.transaction();
+
+        // Categories are per-site, so we can only filter on one if we know the site.
+        let category_id = match (site_id, category) {
+            (_, None) => None,
+            (Some(site_id), Some(reference)) => {
+                let category = CategoryService::get(ctx, site_id, reference).await?;
+                Some(category.category_id)
+            }
+            (None, Some(_)) => {
+                error!("Cannot filter recent changes by category without a site ID");
+                return Err(Error::BadRequest);
+            }
+        };
+
+        let mut changes = Vec::new();
+
+        // Page revisions
+        if source != Some(RecentChangeSource::File) {
+            let mut condition = Condition::all();
+
+            if let Some(site_id) = site_id {
+                condition = condition.add(page_revision::Column::SiteId.eq(site_id));
+            }
+
+            if let Some(user_id) = user_id {
+                condition = condition.add(page_revision::Column::UserId.eq(user_id));
+            }
+
+            if let Some(after) = after {
+                condition = condition.add(page_revision::Column::CreatedAt.gte(after));
+            }
+
+            if let Some(before) = before {
+                condition = condition.add(page_revision::Column::CreatedAt.lt(before));
+            }
+
+            if let Some(cursor) = cursor {
+                condition = condition.add(cursor_condition(
+                    cursor,
+                    RecentChangeSource::Page,
+                    page_revision::Column::CreatedAt,
+                    page_revision::Column::RevisionId,
+                ));
+            }
+
+            if !change_types.is_empty() {
+                let revision_types = change_types
+                    .iter()
+                    .flat_map(|change_type| change_type.page_revision_types())
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                condition = condition
+                    .add(page_revision::Column::RevisionType.is_in(revision_types));
+            }
+
+            let mut query = PageRevision::find();
+            if let Some(category_id) = category_id {
+                query = query
+                    .join(JoinType::Join, page_revision::Relation::Page.def())
+                    .filter(page::Column::PageCategoryId.eq(category_id));
+            }
+
+            let revisions = query
+                .filter(condition)
+                .order_by_desc(page_revision::Column::CreatedAt)
+                .order_by_desc(page_revision::Column::RevisionId)
+                .limit(limit)
+                .all(txn)
+                .await?;
+
+            changes.extend(revisions.into_iter().map(Self::from_page_revision));
+        }
+
+        // File revisions
+        if source != Some(RecentChangeSource::Page) {
+            let mut condition = Condition::all();
+
+            if let Some(site_id) = site_id {
+                condition = condition.add(file_revision::Column::SiteId.eq(site_id));
+            }
+
+            if let Some(user_id) = user_id {
+                condition = condition.add(file_revision::Column::UserId.eq(user_id));
+            }
+
+            if let Some(after) = after {
+                condition = condition.add(file_revision::Column::CreatedAt.gte(after));
+            }
+
+            if let Some(before) = before {
+                condition = condition.add(file_revision::Column::CreatedAt.lt(before));
+            }
+
+            if let Some(cursor) = cursor {
+                condition = condition.add(cursor_condition(
+                    cursor,
+                    RecentChangeSource::File,
+                    file_revision::Column::CreatedAt,
+                    file_revision::Column::RevisionId,
+                ));
+            }
+
+            if !change_types.is_empty() {
+                let revision_types = change_types
+                    .iter()
+                    .flat_map(|change_type| change_type.file_revision_types())
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                condition = condition
+                    .add(file_revision::Column::RevisionType.is_in(revision_types));
+            }
+
+            let mut query = FileRevision::find();
+            if let Some(category_id) = category_id {
+                query = query
+                    .join(JoinType::Join, file_revision::Relation::Page.def())
+                    .filter(page::Column::PageCategoryId.eq(category_id));
+            }
+
+            let revisions = query
+                .filter(condition)
+                .order_by_desc(file_revision::Column::CreatedAt)
+                .order_by_desc(file_revision::Column::RevisionId)
+                .limit(limit)
+                .all(txn)
+                .await?;
+
+            changes.extend(revisions.into_iter().map(Self::from_file_revision));
+        }
+
+        // Merge both sources, newest first, and only keep the requested number.
+        //
+        // If we have a full batch, then there may be more changes, so
+        // return a cursor to continue from.
+        changes.sort_by_key(|change| change_order(&cursor_for(change)));
+        changes.truncate(limit as usize);
+
+        let next_cursor = if changes.len() as u64 == limit {
+            changes.last().map(cursor_for)
+        } else {
+            None
+        };
+
+        Ok(GetRecentChangesOutput {
+            changes,
+            next_cursor,
+        })
+    }
+
+    /// Renders recent changes as a syndication feed, returning the XML document.
+    pub async fn get_feed(
+        ctx: &ServiceContext<'_>,
+        GetRecentChangesFeed { input, format }: GetRecentChangesFeed<'_>,
+    ) -> Result<String> {
+        info!("Rendering recent changes feed ({format:?})");
+
+        let config = ctx.config();
+        let site = match input.site_id {
+            Some(site_id) => Some(SiteService::get(ctx, Reference::Id(site_id)).await?),
+            None => None,
+        };
+
+        let GetRecentChangesOutput { changes, .. } = Self::get(ctx, input).await?;
+
+        let (title, link) = match site {
+            Some(ref site) => (
+                format!("{} - Recent changes", site.name),
+                format!("https://{}/", DomainService::domain_for_site(config, site)),
+            ),
+            None => (
+                format!("{} - Recent changes", config.main_domain_no_dot),
+                format!("https://{}/", config.main_domain_no_dot),
+            ),
+        };
+
+        // Cache lookups, since the same sites, pages, and users tend to
+        // appear many times in a feed.
+        let mut sites = HashMap::new();
+        let mut pages = HashMap::new();
+        let mut users = HashMap::new();
+
+        if let Some(site) = site {
+            sites.insert(site.site_id, site);
+        }
+
+        let mut entries = Vec::with_capacity(changes.len());
+        for change in changes {
+            if let Entry::Vacant(entry) = sites.entry(change.site_id) {
+                entry.insert(SiteService::get(ctx, Reference::Id(change.site_id)).await?);
+            }
+
+            if let Entry::Vacant(entry) = pages.entry(change.page_id) {
+                entry.insert(PageService::get_direct(ctx, change.page_id, true).await?);
+            }
+
+            if let Entry::Vacant(entry) = users.entry(change.user_id) {
+                let user = UserService::get(ctx, Reference::Id(change.user_id)).await?;
+                entry.insert(user.name);
+            }
+
+            let site = &sites[&change.site_id];
+            let page = &pages[&change.page_id];
+            let author = users[&change.user_id].clone();
+            entries.push(Self::feed_entry(config, site, page, author, change));
+        }
+
+        let feed = Feed {
+            title,
+            link,
+            updated: entries
+                .first()
+                .map(|entry| entry.updated)
+                .unwrap_or_else(now),
+            entries,
+        };
+
+        let output = match format {
+            FeedFormat::Atom => feed::render_atom(&feed),
+            FeedFormat::Rss => feed::render_rss(&feed),
+        };
+
+        Ok(output)
+    }
+
+    fn feed_entry(
+        config: &Config,
+        site: &SiteModel,
+        page: &PageModel,
+        author: String,
+        change: RecentChange,
+    ) -> FeedEntry {
+        let domain = DomainService::domain_for_site(config, site);
+        let source = match change.source {
+            RecentChangeSource::Page => "page",
+            RecentChangeSource::File => "file",
+        };
+
+        // Fall back to the page's current slug if the revision's fields are hidden
+        let slug = change.slug.as_deref().unwrap_or(&page.slug);
+        let mut title = match (change.source, &change.file_name) {
+            (RecentChangeSource::File, Some(name)) => format!("{name} ({slug})"),
+            (RecentChangeSource::File, None) => format!("File ({slug})"),
+            (RecentChangeSource::Page, _) => {
+                change.title.clone().unwrap_or_else(|| str!(slug))
+            }
+        };
+
+        // Infallible, this is writing to a String
+        let _ = write!(title, " [{}]", change.change_type.name());
+
+        FeedEntry {
+            id: format!(
+                "tag:{},2019:{}-revision/{}",
+                config.main_domain_no_dot, source, change.revision_id,
+            ),
+            title,
+            link: format!("https://{domain}/{slug}"),
+            author,
+            summary: change.comments.unwrap_or_default(),
+            category: change.change_type.name(),
+            updated: change.created_at,
+        }
+    }
+
+    fn from_page_revision(model: PageRevisionModel) -> RecentChange {
+        let PageRevisionModel {
+            revision_id,
+            revision_type,
+            created_at,
+            revision_number,
+            page_id,
+            site_id,
+            user_id,
+            changes,
+            comments,
+            hidden,
+            title,
+            slug,
+            ..
+        } = model;
+
+        let mut comments = Some(comments);
+        let mut title = Some(title);
+        let mut slug = Some(slug);
+
+        for field in &hidden {
+            match field.as_str() {
+                "comments" => comments = None,
+                "title" => title = None,
+                "slug" => slug = None,
+                _ => (),
+            }
+        }
+
+        RecentChange {
+            source: RecentChangeSource::Page,
+            change_type: revision_type.into(),
+            revision_id,
+            revision_number,
+            created_at,
+            site_id,
+            page_id,
+            file_id: None,
+            user_id,
+            changes,
+            comments,
+            title,
+            slug,
+            file_name: None,
+        }
+    }
+
+    fn from_file_revision(model: FileRevisionModel) -> RecentChange {
+        let FileRevisionModel {
+            revision_id,
+            revision_type,
+            created_at,
+            revision_number,
+            file_id,
+            page_id,
+            site_id,
+            user_id,
+            name,
+            changes,
+            comments,
+            hidden,
+            ..
+        } = model;
+
+        let mut comments = Some(comments);
+        let mut name = Some(name);
+
+        for field in &hidden {
+            match field.as_str() {
+                "comments" => comments = None,
+                "name" => name = None,
+                _ => (),
+            }
+        }
+
+        RecentChange {
+            source: RecentChangeSource::File,
+            change_type: revision_type.into(),
+            revision_id,
+            revision_number,
+            created_at,
+            site_id,
+            page_id,
+            file_id: Some(file_id),
+            user_id,
+            changes,
+            comments,
+            title: None,
+            slug: None,
+            file_name: name,
+        }
+    }
+}
+
+fn cursor_for(change: &RecentChange) -> RecentChangesCursor {
+    RecentChangesCursor {
+        created_at: change.created_at,
+        source: change.source,
+        revision_id: change.revision_id,
+    }
+}
+
+/// Sort key for changes, in the order described on `RecentChangesCursor`.
+fn change_order(
+    cursor: &RecentChangesCursor,
+) -> (Reverse<OffsetDateTime>, u8, Reverse<i64>) {
+    let source = match cursor.source {
+        RecentChangeSource::Page => 0,
+        RecentChangeSource::File => 1,
+    };
+
+    (
+        Reverse(cursor.created_at),
+        source,
+        Reverse(cursor.revision_id),
+    )
+}
+
+/// Builds the condition for changes from one source which come after the cursor.
+fn cursor_condition<C: ColumnTrait>(
+    cursor: RecentChangesCursor,
+    source: RecentChangeSource,
+    created_at: C,
+    revision_id: C,
+) -> Condition {
+    let same_time = Condition::all().add(created_at.eq(cursor.created_at));
+    let same_time = match (cursor.source, source) {
+        // Same table, continue by revision ID
+        (RecentChangeSource::Page, RecentChangeSource::Page)
+        | (RecentChangeSource::File, RecentChangeSource::File) => {
+            Some(same_time.add(revision_id.lt(cursor.revision_id)))
+        }
+
+        // File revisions at this time come after all page revisions
+        (RecentChangeSource::Page, RecentChangeSource::File) => Some(same_time),
+
+        // Page revisions at this time all came before this file revision
+        (RecentChangeSource::File, RecentChangeSource::Page) => None,
+    };
+
+    Condition::any()
+        .add(created_at.lt(cursor.created_at))
+        .add_option(same_time)
+}
+
+#[test]
+fn recent_changes_cursor() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let timestamp = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+    let cursor = |source, revision_id| RecentChangesCursor {
+        created_at: timestamp,
+        source,
+        revision_id,
+    };
+
+    // Changes sharing a timestamp still have a consistent order
+    let mut cursors = [
+        cursor(RecentChangeSource::File, 9),
+        cursor(RecentChangeSource::Page, 3),
+        cursor(RecentChangeSource::Page, 5),
+        RecentChangesCursor {
+            created_at: timestamp + time::Duration::days(1),
+            source: RecentChangeSource::File,
+            revision_id: 1,
+        },
+    ];
+
+    cursors.sort_by_key(change_order);
+    let order: Vec<_> = cursors.iter().map(|c| (c.source, c.revision_id)).collect();
+    assert_eq!(
+        order,
+        [
+            (RecentChangeSource::File, 1),
+            (RecentChangeSource::Page, 5),
+            (RecentChangeSource::Page, 3),
+            (RecentChangeSource::File, 9),
+        ],
+    );
+
+    // Rows at the cursor's timestamp are continued from, not skipped
+    let sql = |cursor, source| {
+        PageRevision::find()
+            .filter(cursor_condition(
+                cursor,
+                source,
+                page_revision::Column::CreatedAt,
+                page_revision::Column::RevisionId,
+            ))
+            .build(DbBackend::Postgres)
+            .to_string()
+    };
+
+    let same_source = sql(
+        cursor(RecentChangeSource::Page, 5),
+        RecentChangeSource::Page,
+    );
+    assert!(
+        same_source.contains(r#""revision_id" < 5"#),
+        "Same-timestamp rows not continued: {same_source}",
+    );
+
+    let after_pages = sql(
+        cursor(RecentChangeSource::Page, 5),
+        RecentChangeSource::File,
+    );
+    assert!(
+        after_pages.contains(r#""created_at" = "#),
+        "Same-timestamp rows from other source skipped: {after_pages}",
+    );
+
+    let after_files = sql(
+        cursor(RecentChangeSource::File, 9),
+        RecentChangeSource::Page,
+    );
+    assert!(
+        !after_files.contains(r#""created_at" = "#),
+        "Same-timestamp rows from other source repeated: {after_files}",
+    );
+}
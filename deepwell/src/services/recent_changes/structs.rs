@@ -0,0 +1,210 @@
+/*
+ * services/recent_changes/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::{FileRevisionType, PageRevisionType};
+use time::OffsetDateTime;
+
+/// The broad kinds of changes which can be filtered on.
+///
+/// Some of these cover several revision types, for instance
+/// `edit` includes regular edits as well as rollbacks and undos.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecentChangeType {
+    Create,
+    Edit,
+    Move,
+    Delete,
+    Restore,
+}
+
+impl RecentChangeType {
+    pub fn page_revision_types(self) -> &'static [PageRevisionType] {
+        match self {
+            RecentChangeType::Create => &[PageRevisionType::Create],
+            RecentChangeType::Edit => &[
+                PageRevisionType::Regular,
+                PageRevisionType::Rollback,
+                PageRevisionType::Undo,
+            ],
+            RecentChangeType::Move => &[PageRevisionType::Move],
+            RecentChangeType::Delete => &[PageRevisionType::Delete],
+            RecentChangeType::Restore => &[PageRevisionType::Undelete],
+        }
+    }
+
+    pub fn file_revision_types(self) -> &'static [FileRevisionType] {
+        match self {
+            RecentChangeType::Create => &[FileRevisionType::Create],
+            RecentChangeType::Edit => {
+                &[FileRevisionType::Regular, FileRevisionType::Rollback]
+            }
+            RecentChangeType::Move => &[FileRevisionType::Move],
+            RecentChangeType::Delete => &[FileRevisionType::Delete],
+            RecentChangeType::Restore => &[FileRevisionType::Undelete],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RecentChangeType::Create => "create",
+            RecentChangeType::Edit => "edit",
+            RecentChangeType::Move => "move",
+            RecentChangeType::Delete => "delete",
+            RecentChangeType::Restore => "restore",
+        }
+    }
+}
+
+impl From<PageRevisionType> for RecentChangeType {
+    fn from(revision_type: PageRevisionType) -> Self {
+        match revision_type {
+            PageRevisionType::Create => RecentChangeType::Create,
+            PageRevisionType::Regular
+            | PageRevisionType::Rollback
+            | PageRevisionType::Undo => RecentChangeType::Edit,
+            PageRevisionType::Move => RecentChangeType::Move,
+            PageRevisionType::Delete => RecentChangeType::Delete,
+            PageRevisionType::Undelete => RecentChangeType::Restore,
+        }
+    }
+}
+
+impl From<FileRevisionType> for RecentChangeType {
+    fn from(revision_type: FileRevisionType) -> Self {
+        match revision_type {
+            FileRevisionType::Create => RecentChangeType::Create,
+            FileRevisionType::Regular | FileRevisionType::Rollback => {
+                RecentChangeType::Edit
+            }
+            FileRevisionType::Move => RecentChangeType::Move,
+            FileRevisionType::Delete => RecentChangeType::Delete,
+            FileRevisionType::Undelete => RecentChangeType::Restore,
+        }
+    }
+}
+
+/// Which revision table a recent change was sourced from.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecentChangeSource {
+    Page,
+    File,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetRecentChanges<'a> {
+    /// The site to get changes for. If `None`, then changes across all sites are returned.
+    pub site_id: Option<i64>,
+
+    /// Only include changes to pages in this category. Requires `site_id`.
+    #[serde(default)]
+    pub category: Option<Reference<'a>>,
+
+    /// Only include these kinds of changes. If empty, then all are included.
+    #[serde(default)]
+    pub change_types: Vec<RecentChangeType>,
+
+    /// Only include changes from one source. If `None`, then both pages and files are included.
+    #[serde(default)]
+    pub source: Option<RecentChangeSource>,
+
+    /// Only include changes made by this user.
+    #[serde(default)]
+    pub user_id: Option<i64>,
+
+    /// Only include changes made at or after this time.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub after: Option<OffsetDateTime>,
+
+    /// Only include changes made strictly before this time.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub before: Option<OffsetDateTime>,
+
+    /// Only include changes which come after this one, for pagination.
+    ///
+    /// See `GetRecentChangesOutput::next_cursor`.
+    #[serde(default)]
+    pub cursor: Option<RecentChangesCursor>,
+
+    pub limit: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RecentChange {
+    pub source: RecentChangeSource,
+    pub change_type: RecentChangeType,
+    pub revision_id: i64,
+    pub revision_number: i32,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: Option<i64>,
+    pub user_id: i64,
+    pub changes: Vec<String>,
+
+    // These fields are None if they have been hidden
+    pub comments: Option<String>,
+    pub title: Option<String>,
+    pub slug: Option<String>,
+    pub file_name: Option<String>,
+}
+
+/// The position of a change in the recent changes listing.
+///
+/// Many revisions can share a creation timestamp (for instance, those made
+/// in one transaction by a bulk operation or import), so the revision is
+/// identified as well. Changes are ordered newest first, then page revisions
+/// before file revisions, then by descending revision ID.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RecentChangesCursor {
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub source: RecentChangeSource,
+    pub revision_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetRecentChangesOutput {
+    pub changes: Vec<RecentChange>,
+
+    /// The value to pass as `cursor` to get the next page of changes.
+    ///
+    /// If `None`, then there are no more changes to retrieve.
+    pub next_cursor: Option<RecentChangesCursor>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetRecentChangesFeed<'a> {
+    #[serde(flatten)]
+    pub input: GetRecentChanges<'a>,
+    pub format: FeedFormat,
+}
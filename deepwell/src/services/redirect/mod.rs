@@ -0,0 +1,41 @@
+/*
+ * services/redirect/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The redirect service, for handling page slugs left behind by moves.
+//!
+//! When a page is moved, a redirect may be left at its old slug so that
+//! existing links continue to work. Links to a redirected slug are recorded
+//! as `redirect` connections to the target page. Optionally, the pages
+//! linking to the old slug can have their wikitext updated to point to
+//! the new one instead.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod rewrite;
+mod service;
+mod structs;
+
+pub use self::rewrite::{rewrite_links, RewrittenLinks};
+pub use self::service::RedirectService;
+pub use self::structs::*;
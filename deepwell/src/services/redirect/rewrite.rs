@@ -0,0 +1,184 @@
+/*
+ * services/redirect/rewrite.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Rewriting of links in wikitext, for updating backlinks to a moved page.
+//!
+//! This handles triple-bracket links (`[[[page]]]`, `[[[page|label]]]`) and
+//! single-bracket local links (`[/page label]`). Link targets are normalized
+//! before being compared, so `[[[Old Page]]]` matches the slug `old-page`.
+//!
+//! Links without a label are given their original text as one, so that the
+//! rendered page reads the same as it did before the rewrite.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use wikidot_normalize::normalize;
+
+static LINK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\[\[\[(?P<triple>[^\[\]]+?)\]\]\]",
+        r"|",
+        r"\[(?P<star>\*?)/(?P<path>[^\s\]#]+)(?P<anchor>#[^\s\]]*)?(?P<label>\s[^\]]*)?\]",
+    ))
+    .unwrap()
+});
+
+/// The result of rewriting links in a piece of wikitext.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewrittenLinks {
+    pub wikitext: String,
+
+    /// The original markup of each link which was changed.
+    pub links: Vec<String>,
+}
+
+/// Replaces all links to `old_slug` in the wikitext with links to `new_slug`.
+///
+/// Returns `None` if no links were changed.
+pub fn rewrite_links(
+    wikitext: &str,
+    old_slug: &str,
+    new_slug: &str,
+) -> Option<RewrittenLinks> {
+    let mut links = Vec::new();
+    let wikitext = LINK_REGEX.replace_all(wikitext, |captures: &Captures| {
+        let original = &captures[0];
+        let replacement = match captures.name("triple") {
+            Some(inner) => rewrite_triple(inner.as_str(), old_slug, new_slug),
+            None => rewrite_single(captures, old_slug, new_slug),
+        };
+
+        match replacement {
+            Some(replacement) => {
+                links.push(str!(original));
+                replacement
+            }
+            None => str!(original),
+        }
+    });
+
+    if links.is_empty() {
+        None
+    } else {
+        Some(RewrittenLinks {
+            wikitext: wikitext.into_owned(),
+            links,
+        })
+    }
+}
+
+fn rewrite_triple(inner: &str, old_slug: &str, new_slug: &str) -> Option<String> {
+    let (target, label) = match inner.split_once('|') {
+        Some((target, label)) => (target, Some(label)),
+        None => (inner, None),
+    };
+
+    let (star, target) = match target.strip_prefix('*') {
+        Some(target) => ("*", target),
+        None => ("", target),
+    };
+
+    let (path, anchor) = match target.split_once('#') {
+        Some((path, anchor)) => (path, Some(anchor)),
+        None => (target, None),
+    };
+
+    let path = path.trim().trim_start_matches('/');
+    if !slug_matches(path, old_slug) {
+        return None;
+    }
+
+    let anchor = anchor
+        .map(|anchor| format!("#{anchor}"))
+        .unwrap_or_default();
+    let label = label.unwrap_or(path);
+    Some(format!("[[[{star}{new_slug}{anchor}|{label}]]]"))
+}
+
+fn rewrite_single(captures: &Captures, old_slug: &str, new_slug: &str) -> Option<String> {
+    let path = &captures["path"];
+    if !slug_matches(path, old_slug) {
+        return None;
+    }
+
+    let star = &captures["star"];
+    let anchor = captures.name("anchor").map_or("", |m| m.as_str());
+    let label = match captures.name("label") {
+        Some(label) => label.as_str().to_owned(),
+        None => format!(" {path}"),
+    };
+
+    Some(format!("[{star}/{new_slug}{anchor}{label}]"))
+}
+
+fn slug_matches(path: &str, slug: &str) -> bool {
+    // Skip external and cross-site links
+    if path.is_empty() || path.contains("://") || path.starts_with(':') {
+        return false;
+    }
+
+    let mut path = str!(path);
+    normalize(&mut path);
+    path == slug
+}
+
+#[test]
+fn rewrite() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {{
+            let output = rewrite_links($input, "old-page", "archive:new-page")
+                .map(|output| output.wikitext);
+            let expected: Option<&str> = $expected;
+
+            assert_eq!(
+                output.as_deref(),
+                expected,
+                "Rewritten wikitext does not match expected",
+            );
+        }};
+    }
+
+    check!("No links here.", None);
+    check!("[[[other-page]]] and [/other-page]", None);
+    check!(
+        "See [[[old-page]]].",
+        Some("See [[[archive:new-page|old-page]]]."),
+    );
+    check!(
+        "See [[[Old Page | the page]]].",
+        Some("See [[[archive:new-page| the page]]]."),
+    );
+    check!(
+        "[[[*/old-page#section|Section]]]",
+        Some("[[[*archive:new-page#section|Section]]]"),
+    );
+    check!(
+        "[/old-page Link text] [*/old-page]",
+        Some("[/archive:new-page Link text] [*/archive:new-page old-page]"),
+    );
+    check!(
+        "[[[:other-site:old-page]]] [https://example.com/old-page]",
+        None
+    );
+
+    let output = rewrite_links("[[[old-page]]] [[[x]]] [/old-page y]", "old-page", "z")
+        .expect("No links rewritten");
+    assert_eq!(output.links, vec!["[[[old-page]]]", "[/old-page y]"]);
+}
@@ -0,0 +1,372 @@
+/*
+ * services/redirect/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use super::rewrite::{rewrite_links, RewrittenLinks};
+use crate::constants::MAINTENANCE_BOT_USER_ID;
+use crate::models::page::Model as PageModel;
+use crate::models::page_redirect::{
+    self, Entity as PageRedirect, Model as PageRedirectModel,
+};
+use crate::services::job::{Job, JobService};
+use crate::services::page::{EditPage, EditPageBody};
+use crate::services::{
+    LinkService, OutdateService, PageRevisionService, PageService, TextService,
+};
+use crate::types::{ConnectionType, Maybe};
+use std::collections::BTreeSet;
+use wikidot_normalize::normalize;
+
+#[derive(Debug)]
+pub struct RedirectService;
+
+impl RedirectService {
+    /// Creates a redirect from the given slug to a page.
+    ///
+    /// Any existing redirect at this slug is replaced.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<PageRedirectModel> {
+        info!(
+            "Creating redirect from '{slug}' to page ID {page_id} in site ID {site_id}"
+        );
+
+        let txn = ctx.transaction();
+        Self::remove_at(ctx, site_id, slug).await?;
+
+        let model = page_redirect::ActiveModel {
+            site_id: Set(site_id),
+            slug: Set(str!(slug)),
+            page_id: Set(page_id),
+            created_by: Set(user_id),
+            ..Default::default()
+        };
+
+        let redirect = model.insert(txn).await?;
+        Ok(redirect)
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<Option<PageRedirectModel>> {
+        let txn = ctx.transaction();
+        let redirect = PageRedirect::find_by_id((site_id, str!(slug)))
+            .one(txn)
+            .await?;
+
+        Ok(redirect)
+    }
+
+    /// Gets the page a slug redirects to, if any.
+    ///
+    /// Redirects to deleted pages are ignored.
+    pub async fn get_target(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<Option<PageModel>> {
+        match Self::get_optional(ctx, site_id, slug).await? {
+            Some(redirect) => {
+                PageService::get_direct_optional(ctx, redirect.page_id, false).await
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Gets all redirects pointing to a page, newest first.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetPageRedirects { site_id, page_id }: GetPageRedirects,
+    ) -> Result<Vec<PageRedirectModel>> {
+        let txn = ctx.transaction();
+        let redirects = PageRedirect::find()
+            .filter(
+                Condition::all()
+                    .add(page_redirect::Column::SiteId.eq(site_id))
+                    .add(page_redirect::Column::PageId.eq(page_id)),
+            )
+            .order_by_desc(page_redirect::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(redirects)
+    }
+
+    /// Deletes the redirect at the given slug.
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        GetPageRedirect { site_id, slug }: GetPageRedirect,
+    ) -> Result<()> {
+        info!("Deleting redirect at '{slug}' in site ID {site_id}");
+
+        if Self::remove_at(ctx, site_id, &slug).await? {
+            Ok(())
+        } else {
+            Err(Error::PageRedirectNotFound)
+        }
+    }
+
+    /// Removes any redirect at the given slug.
+    ///
+    /// This is called whenever a page comes to occupy a slug, since an
+    /// extant page always takes precedence over a redirect.
+    /// Returns whether a redirect was removed.
+    pub async fn remove_at(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+        let Some(redirect) = Self::get_optional(ctx, site_id, slug).await? else {
+            return Ok(false);
+        };
+
+        redirect.clone().delete(txn).await?;
+
+        // Pages linking via this redirect need to be re-rendered,
+        // since their links no longer go to the same place.
+        OutdateService::outdate_incoming_links(ctx, redirect.page_id, 0).await?;
+        Ok(true)
+    }
+
+    /// Queues a job to update links to a page's old slug.
+    pub async fn queue_rewrite_backlinks(
+        ctx: &ServiceContext<'_>,
+        input: RewriteBacklinks,
+    ) -> Result<()> {
+        // Ensure the page is in this site before queueing
+        PageService::get(ctx, input.site_id, Reference::Id(input.page_id)).await?;
+        let job = Job::RewriteBacklinks {
+            input,
+            after_page_id: 0,
+        };
+
+        JobService::queue_job(ctx, &job, None).await
+    }
+
+    /// Lists the changes which rewriting backlinks would make, without editing anything.
+    pub async fn preview_backlinks(
+        ctx: &ServiceContext<'_>,
+        input: RewriteBacklinks,
+    ) -> Result<Vec<BacklinkRewrite>> {
+        let Some(backlinks) = Self::get_backlinks(ctx, input).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut rewrites = Vec::new();
+        for &from_page_id in &backlinks.from_page_ids {
+            if let Some((page, RewrittenLinks { links, .. })) =
+                Self::rewrite_page(ctx, &backlinks, from_page_id).await?
+            {
+                rewrites.push(BacklinkRewrite {
+                    page_id: page.page_id,
+                    slug: page.slug,
+                    links,
+                });
+            }
+        }
+
+        Ok(rewrites)
+    }
+
+    /// Edits the next batch of pages linking to a page's old slug to use its current slug.
+    ///
+    /// Pages are processed in order of ID, starting after `after_page_id`.
+    /// The edits are made by the maintenance bot, with the requesting user
+    /// named in the revision comments. Returns the last page ID checked
+    /// if there are more pages left to go.
+    pub async fn rewrite_backlinks(
+        ctx: &ServiceContext<'_>,
+        input: RewriteBacklinks,
+        after_page_id: i64,
+    ) -> Result<Option<i64>> {
+        let site_id = input.site_id;
+        let user_id = input.user_id;
+        let Some(backlinks) = Self::get_backlinks(ctx, input).await? else {
+            return Ok(None);
+        };
+
+        let batch_size = usize::from(ctx.config().job_backlink_rewrite_batch_size.get());
+        let mut from_page_ids = backlinks.from_page_ids.range(after_page_id + 1..);
+        let mut last_page_id = None;
+
+        for &from_page_id in from_page_ids.by_ref().take(batch_size) {
+            last_page_id = Some(from_page_id);
+
+            let Some((page, RewrittenLinks { wikitext, .. })) =
+                Self::rewrite_page(ctx, &backlinks, from_page_id).await?
+            else {
+                continue;
+            };
+
+            let last_revision =
+                PageRevisionService::get_latest(ctx, site_id, page.page_id).await?;
+
+            PageService::edit(
+                ctx,
+                EditPage {
+                    site_id,
+                    page: Reference::Id(page.page_id),
+                    last_revision_id: last_revision.revision_id,
+                    revision_comments: format!(
+                        "Updating links to moved page '{}' → '{}' (requested by user ID {user_id})",
+                        backlinks.old_slug, backlinks.new_slug,
+                    ),
+                    user_id: MAINTENANCE_BOT_USER_ID,
+                    body: EditPageBody {
+                        wikitext: Maybe::Set(wikitext),
+                        ..Default::default()
+                    },
+                    bypass_filter: false,
+                    bypass_tag_check: false,
+                },
+            )
+            .await?;
+        }
+
+        if from_page_ids.next().is_some() {
+            Ok(last_page_id)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Finds all pages which link to the old slug.
+    ///
+    /// Returns `None` if nothing should be rewritten.
+    async fn get_backlinks(
+        ctx: &ServiceContext<'_>,
+        RewriteBacklinks {
+            site_id,
+            page_id,
+            mut old_slug,
+            ..
+        }: RewriteBacklinks,
+    ) -> Result<Option<Backlinks>> {
+        info!(
+            "Finding backlinks to '{old_slug}' for page ID {page_id} in site ID {site_id}",
+        );
+
+        normalize(&mut old_slug);
+        let page = PageService::get(ctx, site_id, Reference::Id(page_id)).await?;
+        let new_slug = page.slug;
+
+        if new_slug == old_slug {
+            warn!("Page has moved back to '{old_slug}', nothing to rewrite");
+            return Ok(None);
+        }
+
+        if PageService::get_optional(ctx, site_id, Reference::Slug(cow!(&old_slug)))
+            .await?
+            .is_some()
+        {
+            warn!("Another page now exists at '{old_slug}', not rewriting its links");
+            return Ok(None);
+        }
+
+        // Links to the old slug are either redirects (if one was left),
+        // links to the page (if they were rendered before the move),
+        // or missing links (if there is no redirect).
+        let mut from_page_ids = BTreeSet::new();
+        let connections = LinkService::get_to(
+            ctx,
+            page_id,
+            Some(&[ConnectionType::Link, ConnectionType::Redirect]),
+        )
+        .await?
+        .connections;
+
+        for connection in connections {
+            from_page_ids.insert(connection.from_page_id);
+        }
+
+        let connections = LinkService::get_to_missing(
+            ctx,
+            site_id,
+            &old_slug,
+            Some(&[ConnectionType::Link]),
+        )
+        .await?
+        .connections;
+
+        for connection in connections {
+            from_page_ids.insert(connection.from_page_id);
+        }
+
+        Ok(Some(Backlinks {
+            site_id,
+            old_slug,
+            new_slug,
+            from_page_ids,
+        }))
+    }
+
+    /// Rewrites the links to the old slug in a single linking page.
+    ///
+    /// Returns `None` if the page has no links which need changes.
+    async fn rewrite_page(
+        ctx: &ServiceContext<'_>,
+        backlinks: &Backlinks,
+        from_page_id: i64,
+    ) -> Result<Option<(PageModel, RewrittenLinks)>> {
+        let Some(page) =
+            PageService::get_direct_optional(ctx, from_page_id, false).await?
+        else {
+            return Ok(None);
+        };
+
+        // Cross-site links are not rewritten
+        if page.site_id != backlinks.site_id {
+            return Ok(None);
+        }
+
+        let revision =
+            PageRevisionService::get_latest(ctx, page.site_id, page.page_id).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+
+        match rewrite_links(&wikitext, &backlinks.old_slug, &backlinks.new_slug) {
+            Some(rewritten) => {
+                debug!(
+                    "Rewriting {} link(s) in page ID {}",
+                    rewritten.links.len(),
+                    page.page_id,
+                );
+
+                Ok(Some((page, rewritten)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// The pages linking to a moved page's old slug.
+#[derive(Debug)]
+struct Backlinks {
+    site_id: i64,
+    old_slug: String,
+    new_slug: String,
+    from_page_ids: BTreeSet<i64>,
+}
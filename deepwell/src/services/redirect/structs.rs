@@ -0,0 +1,48 @@
+/*
+ * services/redirect/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageRedirect {
+    pub site_id: i64,
+    pub slug: String,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetPageRedirects {
+    pub site_id: i64,
+    pub page_id: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RewriteBacklinks {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub old_slug: String,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BacklinkRewrite {
+    pub page_id: i64,
+    pub slug: String,
+
+    /// The original markup of each link which would be changed.
+    pub links: Vec<String>,
+}
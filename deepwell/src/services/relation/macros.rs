@@ -20,7 +20,7 @@
 
 /// Implements the types and all non-add methods for a relation.
 macro_rules! impl_relation {
-    // Don't add create() method impl
+    // Don't add create() or remove() method impls
     (
         $relation_type:ident,
         $dest_type:ident,
@@ -28,7 +28,7 @@ macro_rules! impl_relation {
         $from_type:ident,
         $from_name:ident,
         $data_type:ty,
-        NO_CREATE_IMPL $(,)?
+        NO_CREATE_REMOVE_IMPL $(,)?
     ) => {
         paste! {
             // Methods
@@ -90,27 +90,6 @@ macro_rules! impl_relation {
                     .await
                 }
 
-                #[allow(dead_code)] // TEMP
-                pub async fn [<remove_ $relation_type:snake>](
-                    ctx: &ServiceContext<'_>,
-                    [<Remove $relation_type>] {
-                        $dest_name,
-                        $from_name,
-                        removed_by,
-                    }: [<Remove $relation_type>],
-                ) -> Result<RelationModel> {
-                    Self::remove(
-                        ctx,
-                        RelationReference::Relationship {
-                            relation_type: RelationType::$relation_type,
-                            dest: RelationObject::$dest_type($dest_name),
-                            from: RelationObject::$from_type($from_name),
-                        },
-                        removed_by,
-                    ).await
-                }
-
-                // TODO paginate
                 #[allow(dead_code)] // TEMP
                 pub async fn [<get_ $relation_type:snake _history>](
                     ctx: &ServiceContext<'_>,
@@ -118,28 +97,50 @@ macro_rules! impl_relation {
                         $dest_name,
                         $from_name,
                     }: [<Get $relation_type>],
+                    start_id: i64,
+                    limit: u64,
                 ) -> Result<Vec<RelationModel>> {
                     Self::get_history(
                         ctx,
                         RelationType::$relation_type,
                         RelationObject::$dest_type($dest_name),
                         RelationObject::$from_type($from_name),
+                        start_id,
+                        limit,
                     )
                     .await
                 }
 
-                // TODO paginate
                 #[allow(dead_code)] // TEMP
                 pub async fn [<get_ $relation_type:snake _entries>](
                     ctx: &ServiceContext<'_>,
                     object: RelationObject,
                     direction: RelationDirection,
+                    start_id: i64,
+                    limit: u64,
                 ) -> Result<Vec<RelationModel>> {
                     Self::get_entries(
                         ctx,
                         RelationType::$relation_type,
                         object,
                         direction,
+                        start_id,
+                        limit,
+                    )
+                    .await
+                }
+
+                #[allow(dead_code)] // TEMP
+                pub async fn [<count_ $relation_type:snake _entries>](
+                    ctx: &ServiceContext<'_>,
+                    object: RelationObject,
+                    direction: RelationDirection,
+                ) -> Result<u64> {
+                    Self::count_entries(
+                        ctx,
+                        RelationType::$relation_type,
+                        object,
+                        direction,
                     )
                     .await
                 }
@@ -175,6 +176,51 @@ macro_rules! impl_relation {
         }
     };
 
+    // Don't add create() method impl
+    (
+        $relation_type:ident,
+        $dest_type:ident,
+        $dest_name:ident,
+        $from_type:ident,
+        $from_name:ident,
+        $data_type:ty,
+        NO_CREATE_IMPL $(,)?
+    ) => {
+        impl_relation!(
+            $relation_type,
+            $dest_type,
+            $dest_name,
+            $from_type,
+            $from_name,
+            $data_type,
+            NO_CREATE_REMOVE_IMPL,
+        );
+
+        paste! {
+            impl RelationService {
+                #[allow(dead_code)] // TEMP
+                pub async fn [<remove_ $relation_type:snake>](
+                    ctx: &ServiceContext<'_>,
+                    [<Remove $relation_type>] {
+                        $dest_name,
+                        $from_name,
+                        removed_by,
+                    }: [<Remove $relation_type>],
+                ) -> Result<RelationModel> {
+                    Self::remove(
+                        ctx,
+                        RelationReference::Relationship {
+                            relation_type: RelationType::$relation_type,
+                            dest: RelationObject::$dest_type($dest_name),
+                            from: RelationObject::$from_type($from_name),
+                        },
+                        removed_by,
+                    ).await
+                }
+            }
+        }
+    };
+
     // Add create() method impl
     (
         $relation_type:ident,
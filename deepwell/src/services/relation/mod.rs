@@ -28,6 +28,8 @@
 //!
 //! For example:
 //! * `site` / `member` / `user` &mdash; User is a site member
+//! * `site` / `application` / `user` &mdash; User has applied to join a site
+//! * `site` / `invitation` / `user` &mdash; User has been invited to join a site
 //! * `user` / `block` / `user` &mdash; User has blocked another user
 
 #[allow(unused_imports)]
@@ -44,7 +46,9 @@ mod macros;
 
 mod page_star;
 mod page_watch;
+mod site_application;
 mod site_ban;
+mod site_invitation;
 mod site_member;
 mod site_user;
 mod structs;
@@ -54,7 +58,9 @@ mod user_follow;
 
 pub use self::page_star::*;
 pub use self::page_watch::*;
+pub use self::site_application::*;
 pub use self::site_ban::*;
+pub use self::site_invitation::*;
 pub use self::site_member::*;
 pub use self::site_user::*;
 pub use self::structs::*;
@@ -200,64 +206,138 @@ impl RelationService {
             .map(|relation| relation.is_some())
     }
 
-    // TODO paginate
     /// Gets the history of this `dest` / `from` relation.
     ///
     /// This includes all all edits of the relation (`overwritten_at`)
     /// and deleted / remade versions of the relation (`deleted_at`).
+    ///
+    /// The `start_id` argument gives the relation ID to search from, exclusive.
+    /// If `0`, then means "everything".
     pub async fn get_history(
         ctx: &ServiceContext<'_>,
         relation_type: RelationType,
         dest: RelationObject,
         from: RelationObject,
+        start_id: i64,
+        limit: u64,
     ) -> Result<Vec<RelationModel>> {
         info!("Getting history of relations for {dest:?} / {relation_type:?} / {from:?}");
 
         let txn = ctx.transaction();
         let relations = Relation::find()
-            .filter(relation_condition(relation_type, dest, from))
-            .order_by_asc(relation::Column::CreatedAt)
+            .filter(
+                Condition::all()
+                    .add(relation_condition(relation_type, dest, from))
+                    .add(relation::Column::RelationId.gt(start_id)),
+            )
+            .order_by_asc(relation::Column::RelationId)
+            .limit(limit)
             .all(txn)
             .await?;
 
         Ok(relations)
     }
 
-    // TODO paginate
-    /// Gets all relations from the starting object in the given direction.
+    /// Gets all active relations from the starting object in the given direction.
     ///
     /// For instance, this can be used to get all blocked users, or all users who are blocking
     /// someone depending on the `RelationDirection`.
+    ///
+    /// Only the current version of each relation is returned, so overwritten and
+    /// deleted rows are excluded. Use `get_history()` to see those as well.
+    ///
+    /// The `start_id` argument gives the relation ID to search from, exclusive.
+    /// If `0`, then means "everything".
     pub async fn get_entries(
         ctx: &ServiceContext<'_>,
         relation_type: RelationType,
         object: RelationObject,
         direction: RelationDirection,
+        start_id: i64,
+        limit: u64,
     ) -> Result<Vec<RelationModel>> {
         info!("Getting {direction:?} relations for {object:?} / {relation_type:?}",);
 
-        let (object_type, object_id) = object.into();
-        let (object_type_column, object_id_column) = match direction {
-            RelationDirection::Dest => {
-                (relation::Column::DestType, relation::Column::DestId)
-            }
-            RelationDirection::From => {
-                (relation::Column::FromType, relation::Column::FromId)
-            }
-        };
-
         let txn = ctx.transaction();
         let relations = Relation::find()
             .filter(
-                Condition::all()
-                    .add(relation::Column::RelationType.eq(relation_type.value()))
-                    .add(object_type_column.eq(object_type))
-                    .add(object_id_column.eq(object_id)),
+                entries_condition(relation_type, object, direction)
+                    .add(relation::Column::RelationId.gt(start_id)),
             )
-            .order_by_asc(relation::Column::CreatedAt)
+            .order_by_asc(relation::Column::RelationId)
+            .limit(limit)
             .all(txn)
             .await?;
 
         Ok(relations)
     }
+
+    /// Counts all active relations from the starting object in the given direction.
+    ///
+    /// See `get_entries()` for more information.
+    pub async fn count_entries(
+        ctx: &ServiceContext<'_>,
+        relation_type: RelationType,
+        object: RelationObject,
+        direction: RelationDirection,
+    ) -> Result<u64> {
+        info!("Counting {direction:?} relations for {object:?} / {relation_type:?}",);
+
+        let txn = ctx.transaction();
+        let count = Relation::find()
+            .filter(entries_condition(relation_type, object, direction))
+            .count(txn)
+            .await?;
+
+        Ok(count)
+    }
+}
+
+/// Builds the condition for active relations of an object in the given direction.
+fn entries_condition(
+    relation_type: RelationType,
+    object: RelationObject,
+    direction: RelationDirection,
+) -> Condition {
+    let (object_type, object_id) = object.into();
+    let (object_type_column, object_id_column) = match direction {
+        RelationDirection::Dest => (relation::Column::DestType, relation::Column::DestId),
+        RelationDirection::From => (relation::Column::FromType, relation::Column::FromId),
+    };
+
+    Condition::all()
+        .add(relation::Column::RelationType.eq(relation_type.value()))
+        .add(object_type_column.eq(object_type))
+        .add(object_id_column.eq(object_id))
+        .add(relation::Column::OverwrittenAt.is_null())
+        .add(relation::Column::DeletedAt.is_null())
+}
+
+/// Ensure entry queries only select the current version of each relation.
+#[test]
+fn entries_condition_active() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let sql = Relation::find()
+        .filter(entries_condition(
+            RelationType::SiteMember,
+            RelationObject::Site(1),
+            RelationDirection::Dest,
+        ))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""dest_type" = (CAST('site' AS "#),
+        "Missing type check: {sql}",
+    );
+    assert!(sql.contains(r#""dest_id" = 1"#), "Missing ID check: {sql}");
+    assert!(
+        sql.contains(r#""overwritten_at" IS NULL"#),
+        "Overwritten relations not excluded: {sql}",
+    );
+    assert!(
+        sql.contains(r#""deleted_at" IS NULL"#),
+        "Deleted relations not excluded: {sql}",
+    );
 }
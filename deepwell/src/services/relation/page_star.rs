@@ -21,3 +21,56 @@
 use super::prelude::*;
 
 impl_relation!(PageStar, Page, page_id, User, user_id, ());
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetStarredPages {
+    pub user_id: i64,
+    pub start_id: i64,
+    pub limit: u64,
+}
+
+impl RelationService {
+    /// Gets the pages this user has starred.
+    ///
+    /// The `start_id` argument gives the relation ID to search from, exclusive.
+    /// If `0`, then means "everything".
+    pub async fn get_starred_pages(
+        ctx: &ServiceContext<'_>,
+        GetStarredPages {
+            user_id,
+            start_id,
+            limit,
+        }: GetStarredPages,
+    ) -> Result<Vec<RelationModel>> {
+        Self::get_page_star_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+            start_id,
+            limit,
+        )
+        .await
+    }
+
+    pub async fn count_starred_pages(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<u64> {
+        Self::count_page_star_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+        )
+        .await
+    }
+
+    /// Gets the number of users who have starred this page.
+    pub async fn count_page_stars(ctx: &ServiceContext<'_>, page_id: i64) -> Result<u64> {
+        Self::count_page_star_entries(
+            ctx,
+            RelationObject::Page(page_id),
+            RelationDirection::Dest,
+        )
+        .await
+    }
+}
@@ -21,3 +21,46 @@
 use super::prelude::*;
 
 impl_relation!(PageWatch, Page, page_id, User, user_id, ());
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetWatchedPages {
+    pub user_id: i64,
+    pub start_id: i64,
+    pub limit: u64,
+}
+
+impl RelationService {
+    /// Gets the pages this user is watching.
+    ///
+    /// The `start_id` argument gives the relation ID to search from, exclusive.
+    /// If `0`, then means "everything".
+    pub async fn get_watched_pages(
+        ctx: &ServiceContext<'_>,
+        GetWatchedPages {
+            user_id,
+            start_id,
+            limit,
+        }: GetWatchedPages,
+    ) -> Result<Vec<RelationModel>> {
+        Self::get_page_watch_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+            start_id,
+            limit,
+        )
+        .await
+    }
+
+    pub async fn count_watched_pages(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<u64> {
+        Self::count_page_watch_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+        )
+        .await
+    }
+}
@@ -0,0 +1,274 @@
+/*
+ * services/relation/site_application.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::SiteJoinPolicy;
+use crate::services::audit::{AuditEvent, AuditService};
+use crate::services::notification::NotificationEvent;
+use crate::services::{NotificationService, SiteService};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SiteApplicationData {
+    pub message: String,
+}
+
+impl_relation!(
+    SiteApplication,
+    Site,
+    site_id,
+    User,
+    user_id,
+    SiteApplicationData,
+    NO_CREATE_IMPL,
+);
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RespondSiteApplication {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub responded_by: i64,
+
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetSiteApplications {
+    pub site_id: i64,
+    pub start_id: i64,
+    pub limit: u64,
+}
+
+impl RelationService {
+    /// Submits an application by a user to join a site.
+    ///
+    /// Only sites with the `application` join policy accept these.
+    pub async fn create_site_application(
+        ctx: &ServiceContext<'_>,
+        CreateSiteApplication {
+            site_id,
+            user_id,
+            metadata,
+            created_by,
+        }: CreateSiteApplication,
+    ) -> Result<()> {
+        info!("User ID {user_id} is applying to join site ID {site_id}");
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        if site.join_policy != SiteJoinPolicy::Application {
+            error!(
+                "Site ID {site_id} has join policy {:?}, cannot apply",
+                site.join_policy,
+            );
+            return Err(Error::SiteJoinNotAllowed);
+        }
+
+        // Cannot apply if banned
+        Self::check_site_ban(ctx, GetSiteBan { site_id, user_id }, "apply to").await?;
+
+        if Self::site_member_exists(ctx, GetSiteMember { site_id, user_id }).await? {
+            error!("User ID {user_id} is already a member of site ID {site_id}");
+            return Err(Error::SiteMemberExists);
+        }
+
+        if Self::site_application_exists(ctx, GetSiteApplication { site_id, user_id })
+            .await?
+        {
+            error!("User ID {user_id} already has an application to site ID {site_id}");
+            return Err(Error::SiteApplicationExists);
+        }
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            created_by,
+            Some(user_id),
+            AuditEvent::ApplicationSubmit,
+            &metadata,
+        )
+        .await?;
+
+        create_operation!(
+            ctx,
+            SiteApplication,
+            Site,
+            site_id,
+            User,
+            user_id,
+            created_by,
+            &metadata,
+        )
+    }
+
+    /// Withdraws a user's own pending application to a site.
+    pub async fn withdraw_site_application(
+        ctx: &ServiceContext<'_>,
+        GetSiteApplication { site_id, user_id }: GetSiteApplication,
+    ) -> Result<RelationModel> {
+        info!("User ID {user_id} is withdrawing their application to site ID {site_id}");
+
+        let relation = Self::remove_site_application(
+            ctx,
+            RemoveSiteApplication {
+                site_id,
+                user_id,
+                removed_by: user_id,
+            },
+        )
+        .await?;
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            user_id,
+            Some(user_id),
+            AuditEvent::ApplicationWithdraw,
+            &(),
+        )
+        .await?;
+
+        Ok(relation)
+    }
+
+    /// Accepts a pending application, making the applicant a site member.
+    pub async fn accept_site_application(
+        ctx: &ServiceContext<'_>,
+        RespondSiteApplication {
+            site_id,
+            user_id,
+            responded_by,
+            reason,
+        }: RespondSiteApplication,
+    ) -> Result<()> {
+        info!("User ID {responded_by} is accepting application by user ID {user_id} to site ID {site_id}");
+
+        Self::remove_site_application(
+            ctx,
+            RemoveSiteApplication {
+                site_id,
+                user_id,
+                removed_by: responded_by,
+            },
+        )
+        .await?;
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            responded_by,
+            Some(user_id),
+            AuditEvent::ApplicationAccept,
+            &serde_json::json!({ "reason": reason }),
+        )
+        .await?;
+
+        Self::create_site_member(
+            ctx,
+            CreateSiteMember {
+                site_id,
+                user_id,
+                metadata: SiteMemberData {
+                    accepted: SiteMemberAccepted::Accepted(responded_by),
+                },
+                created_by: responded_by,
+            },
+        )
+        .await?;
+
+        NotificationService::queue(
+            ctx,
+            NotificationEvent::SiteApplicationResponse {
+                site_id,
+                applicant_id: user_id,
+                accepted: true,
+                user_id: responded_by,
+            },
+        )
+        .await
+    }
+
+    /// Declines a pending application, with an optional reason given to the applicant.
+    pub async fn decline_site_application(
+        ctx: &ServiceContext<'_>,
+        RespondSiteApplication {
+            site_id,
+            user_id,
+            responded_by,
+            reason,
+        }: RespondSiteApplication,
+    ) -> Result<RelationModel> {
+        info!("User ID {responded_by} is declining application by user ID {user_id} to site ID {site_id}");
+
+        let relation = Self::remove_site_application(
+            ctx,
+            RemoveSiteApplication {
+                site_id,
+                user_id,
+                removed_by: responded_by,
+            },
+        )
+        .await?;
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            responded_by,
+            Some(user_id),
+            AuditEvent::ApplicationDecline,
+            &serde_json::json!({ "reason": reason }),
+        )
+        .await?;
+
+        NotificationService::queue(
+            ctx,
+            NotificationEvent::SiteApplicationResponse {
+                site_id,
+                applicant_id: user_id,
+                accepted: false,
+                user_id: responded_by,
+            },
+        )
+        .await?;
+
+        Ok(relation)
+    }
+
+    /// Gets the pending applications to join a site.
+    ///
+    /// The `start_id` argument gives the relation ID to search from, exclusive.
+    /// If `0`, then means "everything".
+    pub async fn get_site_applications(
+        ctx: &ServiceContext<'_>,
+        GetSiteApplications {
+            site_id,
+            start_id,
+            limit,
+        }: GetSiteApplications,
+    ) -> Result<Vec<RelationModel>> {
+        Self::get_site_application_entries(
+            ctx,
+            RelationObject::Site(site_id),
+            RelationDirection::Dest,
+            start_id,
+            limit,
+        )
+        .await
+    }
+}
@@ -58,7 +58,7 @@ impl RelationService {
             },
         )
         .await?;
-        // TODO: remove site member applications
+        Self::clear_site_join_requests(ctx, site_id, user_id, created_by).await?;
         // TODO: remove site roles
 
         create_operation!(
@@ -0,0 +1,345 @@
+/*
+ * services/relation/site_invitation.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::audit::{AuditEvent, AuditService};
+use crate::services::notification::NotificationEvent;
+use crate::services::{NotificationService, UserService};
+use wikidot_normalize::normalize;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SiteInvitationData {
+    pub message: String,
+}
+
+impl_relation!(
+    SiteInvitation,
+    Site,
+    site_id,
+    User,
+    user_id,
+    SiteInvitationData,
+    NO_CREATE_IMPL,
+);
+
+/// Which user is being invited, either by their username or by their email.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteInvitee {
+    Username(String),
+    Email(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SendSiteInvitation {
+    pub site_id: i64,
+    pub invitee: SiteInvitee,
+    pub message: String,
+    pub created_by: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetSiteInvitations {
+    pub site_id: i64,
+    pub start_id: i64,
+    pub limit: u64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserSiteInvitations {
+    pub user_id: i64,
+    pub start_id: i64,
+    pub limit: u64,
+}
+
+impl RelationService {
+    /// Invites a user to join a site.
+    ///
+    /// Invitations may be sent regardless of the site's join policy.
+    ///
+    /// Invitations by email respond the same whether or not any user has that
+    /// address, or is able to be invited, so they cannot be used to find out
+    /// which addresses have accounts.
+    ///
+    /// # Returns
+    /// The invited user's ID if they were invited by username, otherwise `None`.
+    pub async fn send_site_invitation(
+        ctx: &ServiceContext<'_>,
+        SendSiteInvitation {
+            site_id,
+            invitee,
+            message,
+            created_by,
+        }: SendSiteInvitation,
+    ) -> Result<Option<i64>> {
+        info!("User ID {created_by} is inviting {invitee:?} to site ID {site_id}");
+
+        let metadata = SiteInvitationData { message };
+        match invitee {
+            SiteInvitee::Username(mut name) => {
+                normalize(&mut name);
+                let user_id = UserService::get(ctx, Reference::Slug(cow!(name)))
+                    .await?
+                    .user_id;
+
+                Self::create_site_invitation(
+                    ctx,
+                    CreateSiteInvitation {
+                        site_id,
+                        user_id,
+                        metadata,
+                        created_by,
+                    },
+                )
+                .await?;
+
+                Ok(Some(user_id))
+            }
+            SiteInvitee::Email(email) => {
+                let user_id =
+                    match UserService::get_optional_by_email(ctx, &email).await? {
+                        Some(user) => user.user_id,
+                        None => {
+                            debug!("No user with invited email, not sending invitation");
+                            return Ok(None);
+                        }
+                    };
+
+                let result = Self::create_site_invitation(
+                    ctx,
+                    CreateSiteInvitation {
+                        site_id,
+                        user_id,
+                        metadata,
+                        created_by,
+                    },
+                )
+                .await;
+
+                // These are checked before anything is changed,
+                // so they can be hidden without leaving partial changes.
+                match result {
+                    Ok(())
+                    | Err(
+                        Error::SiteBlockedUser
+                        | Error::SiteMemberExists
+                        | Error::SiteInvitationExists,
+                    ) => Ok(None),
+                    Err(error) => Err(error),
+                }
+            }
+        }
+    }
+
+    pub async fn create_site_invitation(
+        ctx: &ServiceContext<'_>,
+        CreateSiteInvitation {
+            site_id,
+            user_id,
+            metadata,
+            created_by,
+        }: CreateSiteInvitation,
+    ) -> Result<()> {
+        // Cannot invite if banned
+        Self::check_site_ban(ctx, GetSiteBan { site_id, user_id }, "be invited to")
+            .await?;
+
+        if Self::site_member_exists(ctx, GetSiteMember { site_id, user_id }).await? {
+            error!("User ID {user_id} is already a member of site ID {site_id}");
+            return Err(Error::SiteMemberExists);
+        }
+
+        if Self::site_invitation_exists(ctx, GetSiteInvitation { site_id, user_id })
+            .await?
+        {
+            error!("User ID {user_id} already has an invitation to site ID {site_id}");
+            return Err(Error::SiteInvitationExists);
+        }
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            created_by,
+            Some(user_id),
+            AuditEvent::InvitationSend,
+            &metadata,
+        )
+        .await?;
+
+        Self::create(
+            ctx,
+            RelationType::SiteInvitation,
+            RelationObject::Site(site_id),
+            RelationObject::User(user_id),
+            created_by,
+            &metadata,
+        )
+        .await?;
+
+        NotificationService::queue(
+            ctx,
+            NotificationEvent::SiteInvitation {
+                site_id,
+                invitee_id: user_id,
+                user_id: created_by,
+            },
+        )
+        .await
+    }
+
+    /// Accepts a user's invitation, making them a site member.
+    pub async fn accept_site_invitation(
+        ctx: &ServiceContext<'_>,
+        GetSiteInvitation { site_id, user_id }: GetSiteInvitation,
+    ) -> Result<()> {
+        info!("User ID {user_id} is accepting invitation to site ID {site_id}");
+
+        let invitation = Self::remove_site_invitation(
+            ctx,
+            RemoveSiteInvitation {
+                site_id,
+                user_id,
+                removed_by: user_id,
+            },
+        )
+        .await?;
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            user_id,
+            Some(user_id),
+            AuditEvent::InvitationAccept,
+            &(),
+        )
+        .await?;
+
+        Self::create_site_member(
+            ctx,
+            CreateSiteMember {
+                site_id,
+                user_id,
+                metadata: SiteMemberData {
+                    accepted: SiteMemberAccepted::Invitation(invitation.created_by),
+                },
+                created_by: user_id,
+            },
+        )
+        .await
+    }
+
+    /// Declines a user's invitation.
+    pub async fn decline_site_invitation(
+        ctx: &ServiceContext<'_>,
+        GetSiteInvitation { site_id, user_id }: GetSiteInvitation,
+    ) -> Result<RelationModel> {
+        info!("User ID {user_id} is declining invitation to site ID {site_id}");
+
+        let relation = Self::remove_site_invitation(
+            ctx,
+            RemoveSiteInvitation {
+                site_id,
+                user_id,
+                removed_by: user_id,
+            },
+        )
+        .await?;
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            user_id,
+            Some(user_id),
+            AuditEvent::InvitationDecline,
+            &(),
+        )
+        .await?;
+
+        Ok(relation)
+    }
+
+    /// Revokes a pending invitation, as done by site staff.
+    pub async fn revoke_site_invitation(
+        ctx: &ServiceContext<'_>,
+        input: RemoveSiteInvitation,
+    ) -> Result<RelationModel> {
+        info!(
+            "User ID {} is revoking invitation for user ID {} to site ID {}",
+            input.removed_by, input.user_id, input.site_id,
+        );
+
+        let relation = Self::remove_site_invitation(ctx, input).await?;
+
+        AuditService::record(
+            ctx,
+            Some(input.site_id),
+            input.removed_by,
+            Some(input.user_id),
+            AuditEvent::InvitationRevoke,
+            &(),
+        )
+        .await?;
+
+        Ok(relation)
+    }
+
+    /// Gets the pending invitations sent for a site.
+    ///
+    /// The `start_id` argument gives the relation ID to search from, exclusive.
+    /// If `0`, then means "everything".
+    pub async fn get_site_invitations(
+        ctx: &ServiceContext<'_>,
+        GetSiteInvitations {
+            site_id,
+            start_id,
+            limit,
+        }: GetSiteInvitations,
+    ) -> Result<Vec<RelationModel>> {
+        Self::get_site_invitation_entries(
+            ctx,
+            RelationObject::Site(site_id),
+            RelationDirection::Dest,
+            start_id,
+            limit,
+        )
+        .await
+    }
+
+    /// Gets the pending invitations a user has received.
+    ///
+    /// See `get_site_invitations()` for more information.
+    pub async fn get_user_site_invitations(
+        ctx: &ServiceContext<'_>,
+        GetUserSiteInvitations {
+            user_id,
+            start_id,
+            limit,
+        }: GetUserSiteInvitations,
+    ) -> Result<Vec<RelationModel>> {
+        Self::get_site_invitation_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+            start_id,
+            limit,
+        )
+        .await
+    }
+}
@@ -19,6 +19,9 @@
  */
 
 use super::prelude::*;
+use crate::models::sea_orm_active_enums::SiteJoinPolicy;
+use crate::services::audit::{AuditEvent, AuditService};
+use crate::services::{PasswordService, SiteService};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case", tag = "cause", content = "user_id")]
@@ -42,9 +45,18 @@ impl_relation!(
     User,
     user_id,
     SiteMemberData,
-    NO_CREATE_IMPL,
+    NO_CREATE_REMOVE_IMPL,
 );
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct JoinSite {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
 impl RelationService {
     pub async fn create_site_member(
         ctx: &ServiceContext<'_>,
@@ -58,8 +70,142 @@ impl RelationService {
         // Cannot join if banned
         Self::check_site_ban(ctx, GetSiteBan { site_id, user_id }, "join").await?;
 
+        // Now a member, nothing left to apply or be invited for
+        Self::clear_site_join_requests(ctx, site_id, user_id, created_by).await?;
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            created_by,
+            Some(user_id),
+            AuditEvent::MemberJoin,
+            &metadata,
+        )
+        .await?;
+
         create_operation!(
             ctx, SiteMember, Site, site_id, User, user_id, created_by, &metadata,
         )
     }
+
+    /// Removes a user from a site.
+    ///
+    /// This is recorded in the audit log, whether the user was removed
+    /// directly or as part of being banned.
+    pub async fn remove_site_member(
+        ctx: &ServiceContext<'_>,
+        RemoveSiteMember {
+            site_id,
+            user_id,
+            removed_by,
+        }: RemoveSiteMember,
+    ) -> Result<RelationModel> {
+        let relation = Self::remove(
+            ctx,
+            RelationReference::Relationship {
+                relation_type: RelationType::SiteMember,
+                dest: RelationObject::Site(site_id),
+                from: RelationObject::User(user_id),
+            },
+            removed_by,
+        )
+        .await?;
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            removed_by,
+            Some(user_id),
+            AuditEvent::MemberRemove,
+            &(),
+        )
+        .await?;
+
+        Ok(relation)
+    }
+
+    /// Has a user join a site on their own, as permitted by its join policy.
+    ///
+    /// Sites which take applications or are invite-only cannot be joined
+    /// this way, see `create_site_application()` and `accept_site_invitation()`.
+    pub async fn join_site(
+        ctx: &ServiceContext<'_>,
+        JoinSite {
+            site_id,
+            user_id,
+            password,
+        }: JoinSite,
+    ) -> Result<()> {
+        info!("User ID {user_id} is joining site ID {site_id}");
+
+        if Self::site_member_exists(ctx, GetSiteMember { site_id, user_id }).await? {
+            error!("User ID {user_id} is already a member of site ID {site_id}");
+            return Err(Error::SiteMemberExists);
+        }
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let accepted = match site.join_policy {
+            SiteJoinPolicy::Open => SiteMemberAccepted::SelfJoined,
+            SiteJoinPolicy::Password => {
+                let password = password.ok_or(Error::EmptyPassword)?;
+                let hash = site.join_password.ok_or(Error::SiteJoinPasswordMissing)?;
+                PasswordService::verify(ctx, &password, &hash).await?;
+                SiteMemberAccepted::Password
+            }
+            SiteJoinPolicy::Application | SiteJoinPolicy::Invite => {
+                error!(
+                    "Site ID {site_id} has join policy {:?}, cannot join directly",
+                    site.join_policy,
+                );
+                return Err(Error::SiteJoinNotAllowed);
+            }
+        };
+
+        Self::create_site_member(
+            ctx,
+            CreateSiteMember {
+                site_id,
+                user_id,
+                metadata: SiteMemberData { accepted },
+                created_by: user_id,
+            },
+        )
+        .await
+    }
+
+    /// Removes any pending application or invitation for this user to join a site.
+    pub async fn clear_site_join_requests(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+        removed_by: i64,
+    ) -> Result<()> {
+        let get_application = GetSiteApplication { site_id, user_id };
+        if Self::site_application_exists(ctx, get_application).await? {
+            Self::remove_site_application(
+                ctx,
+                RemoveSiteApplication {
+                    site_id,
+                    user_id,
+                    removed_by,
+                },
+            )
+            .await?;
+        }
+
+        let get_invitation = GetSiteInvitation { site_id, user_id };
+        if Self::site_invitation_exists(ctx, get_invitation).await? {
+            Self::remove_site_invitation(
+                ctx,
+                RemoveSiteInvitation {
+                    site_id,
+                    user_id,
+                    removed_by,
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
 }
@@ -58,7 +58,7 @@ impl RelationService {
         // This means there should be no results for both
         // this site_id -> anything and this user_id -> anything.
 
-        let sites = RelationService::get_entries(
+        let sites = RelationService::count_entries(
             ctx,
             RelationType::SiteUser,
             RelationObject::Site(site_id),
@@ -66,12 +66,12 @@ impl RelationService {
         )
         .await?;
 
-        if !sites.is_empty() {
-            error!("Found a different relation with this site, cannot create relation: {sites:?}");
+        if sites > 0 {
+            error!("Found a different relation with this site, cannot create relation");
             return Err(Error::BadRequest);
         }
 
-        let users = RelationService::get_entries(
+        let users = RelationService::count_entries(
             ctx,
             RelationType::SiteUser,
             RelationObject::User(user_id),
@@ -79,8 +79,8 @@ impl RelationService {
         )
         .await?;
 
-        if !users.is_empty() {
-            error!("Found a different relation with this user, cannot create relation: {users:?}");
+        if users > 0 {
+            error!("Found a different relation with this user, cannot create relation");
             return Err(Error::BadRequest);
         }
 
@@ -127,7 +127,7 @@ async fn get_relation(
 ) -> Result<RelationModel> {
     // We implement our own query since it's 1:1 and we
     // don't have to worry about multiple results like
-    // for get_entries().
+    // for count_entries().
 
     let txn = ctx.transaction();
     let model = Relation::find()
@@ -128,8 +128,8 @@ pub enum RelationDirection {
 pub enum RelationType {
     SiteUser,
     SiteBan,
-    #[allow(dead_code)] // TEMP
     SiteApplication,
+    SiteInvitation,
     SiteMember,
     PageStar,
     PageWatch,
@@ -151,6 +151,7 @@ impl RelationType {
             RelationType::SiteUser => "site-user", // for the 'site' user_type
             RelationType::SiteBan => "ban",
             RelationType::SiteApplication => "application",
+            RelationType::SiteInvitation => "invitation",
             RelationType::SiteMember => "member",
             RelationType::PageStar => "star",
             RelationType::PageWatch => "watch",
@@ -175,6 +176,7 @@ impl RelationType {
             RelationType::SiteUser => t!(Site, User),
             RelationType::SiteBan => t!(Site, User),
             RelationType::SiteApplication => t!(Site, User),
+            RelationType::SiteInvitation => t!(Site, User),
             RelationType::SiteMember => t!(Site, User),
             RelationType::PageStar => t!(Page, User),
             RelationType::PageWatch => t!(Page, User),
@@ -30,8 +30,14 @@ impl_relation!(
     NO_CREATE_IMPL,
 );
 
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserFollows {
+    pub user_id: i64,
+    pub start_id: i64,
+    pub limit: u64,
+}
+
 impl RelationService {
-    #[allow(dead_code)] // TEMP
     pub async fn create_user_follow(
         ctx: &ServiceContext<'_>,
         CreateUserFollow {
@@ -41,6 +47,12 @@ impl RelationService {
             metadata: (),
         }: CreateUserFollow,
     ) -> Result<()> {
+        // Cannot follow yourself
+        if followed_user == following_user {
+            error!("User ID {following_user} cannot follow themselves");
+            return Err(Error::BadRequest);
+        }
+
         // Cannot follow if blocked
         Self::check_user_block(ctx, followed_user, following_user, "follow").await?;
 
@@ -55,3 +67,48 @@ impl RelationService {
         )
     }
 }
+
+impl RelationService {
+    /// Gets the users who are following this user.
+    ///
+    /// The `start_id` argument gives the relation ID to search from, exclusive.
+    /// If `0`, then means "everything".
+    pub async fn get_user_followers(
+        ctx: &ServiceContext<'_>,
+        GetUserFollows {
+            user_id,
+            start_id,
+            limit,
+        }: GetUserFollows,
+    ) -> Result<Vec<RelationModel>> {
+        Self::get_user_follow_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::Dest,
+            start_id,
+            limit,
+        )
+        .await
+    }
+
+    /// Gets the users this user is following.
+    ///
+    /// See `get_user_followers()` for more information.
+    pub async fn get_user_following(
+        ctx: &ServiceContext<'_>,
+        GetUserFollows {
+            user_id,
+            start_id,
+            limit,
+        }: GetUserFollows,
+    ) -> Result<Vec<RelationModel>> {
+        Self::get_user_follow_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+            start_id,
+            limit,
+        )
+        .await
+    }
+}
@@ -20,6 +20,7 @@
 
 use super::prelude::*;
 use crate::services::TextService;
+use std::time::Instant;
 use tokio::time::timeout;
 
 #[derive(Debug)]
@@ -38,7 +39,8 @@ impl RenderService {
         // This way we can cut it off if it times out.
 
         let config = ctx.config();
-        let (html_output, errors) = timeout(config.render_timeout, async {
+        let start = Instant::now();
+        let result = timeout(config.render_timeout, async {
             // Run ftml to parse and render
             // TODO include
             ftml::preprocess(&mut wikitext);
@@ -48,10 +50,20 @@ impl RenderService {
             let html_output = HtmlRender.render(&tree, page_info, settings);
             (html_output, errors)
         })
-        .await
+        .await;
+
         // Not using Error::from() because timeouts could occur in other places,
         // and this error variant is not specific to all timeouts.
-        .map_err(|_| Error::RenderTimeout)?;
+        let (html_output, errors) = match result {
+            Ok(output) => {
+                ctx.metrics().record_render(start.elapsed());
+                output
+            }
+            Err(_) => {
+                ctx.metrics().record_render_timeout();
+                return Err(Error::RenderTimeout);
+            }
+        };
 
         // Insert compiled HTML into text table
         let compiled_hash = TextService::create(ctx, html_output.body.clone()).await?;
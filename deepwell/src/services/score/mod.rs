@@ -37,4 +37,7 @@ mod structs;
 pub use self::impls::*;
 pub use self::scorer::Scorer;
 pub use self::service::ScoreService;
+pub use self::structs::{
+    DailyVoteCount, GetDailyVotes, GetScoreDrops, GetScoreTimeline, ScoreDrop, ScoreType,
+};
 pub use ftml::data::ScoreValue;
@@ -20,6 +20,14 @@
 
 use super::impls::*;
 use super::prelude::*;
+use crate::models::page_score_snapshot::{
+    self, Entity as PageScoreSnapshot, Model as PageScoreSnapshotModel,
+};
+use crate::services::{CategoryService, PageService};
+use sea_orm::{DatabaseBackend, Statement, Value};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use time::{Duration, OffsetDateTime, UtcOffset};
 
 #[derive(Debug)]
 pub struct ScoreService;
@@ -33,15 +41,315 @@ impl ScoreService {
         Ok(score)
     }
 
+    /// Records the current score of a page, for tracking it over time.
+    pub async fn snapshot(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<PageScoreSnapshotModel> {
+        debug!("Recording score snapshot for page ID {page_id}");
+
+        let page = PageService::get_direct(ctx, page_id, true).await?;
+        let state = Self::current_state(ctx, page_id).await?;
+        Self::insert_snapshot(ctx, page.site_id, page_id, state).await
+    }
+
+    /// Records score snapshots for a batch of pages which haven't had one recently.
+    ///
+    /// Snapshots are already taken whenever votes change, so this only catches
+    /// pages whose scorer output may have changed some other way, such as their
+    /// category switching scorers. Pages whose latest snapshot still matches
+    /// their current votes and score are skipped, so unchanged pages do not
+    /// accumulate a row every period.
+    ///
+    /// Pages are processed in ID order, starting after `after_page_id`.
+    /// Returns the last page ID processed if there are more pages remaining.
+    pub async fn snapshot_stale(
+        ctx: &ServiceContext<'_>,
+        after_page_id: i64,
+    ) -> Result<Option<i64>> {
+        #[derive(FromQueryResult, Debug)]
+        struct StalePage {
+            page_id: i64,
+            site_id: i64,
+            score: Option<f64>,
+            vote_count: Option<i64>,
+            upvotes: Option<i64>,
+            downvotes: Option<i64>,
+        }
+
+        let txn = ctx.transaction();
+        let batch_size = ctx.config().job_score_snapshot_batch_size.get();
+        let since = now() - ctx.config().job_score_snapshot;
+        info!(
+            "Recording score snapshots for pages without one since {since} after page ID {after_page_id}",
+        );
+
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                SELECT
+                    page.page_id,
+                    page.site_id,
+                    latest.score,
+                    latest.vote_count,
+                    latest.upvotes,
+                    latest.downvotes
+                FROM page
+                LEFT JOIN LATERAL (
+                    SELECT created_at, score, vote_count, upvotes, downvotes
+                    FROM page_score_snapshot
+                    WHERE page_score_snapshot.page_id = page.page_id
+                    ORDER BY snapshot_id DESC
+                    LIMIT 1
+                ) AS latest ON true
+                WHERE page.deleted_at IS NULL
+                    AND page.page_id > $2
+                    AND (latest.created_at IS NULL OR latest.created_at <= $1)
+                ORDER BY page.page_id
+                LIMIT $3
+                ",
+            ),
+            [
+                Value::from(since),
+                Value::from(after_page_id),
+                Value::from(i64::from(batch_size) + 1),
+            ],
+        );
+
+        // One extra page is fetched to know whether there is another batch
+        let mut pages = StalePage::find_by_statement(query).all(txn).await?;
+        let more = pages.len() > usize::from(batch_size);
+        pages.truncate(usize::from(batch_size));
+
+        for page in &pages {
+            let latest = match (page.score, page.vote_count, page.upvotes, page.downvotes)
+            {
+                (Some(score), Some(vote_count), Some(upvotes), Some(downvotes)) => {
+                    Some(ScoreState {
+                        score,
+                        vote_count,
+                        upvotes,
+                        downvotes,
+                    })
+                }
+                _ => None,
+            };
+
+            let state = Self::current_state(ctx, page.page_id).await?;
+            if latest == Some(state) {
+                trace!("Score for page ID {} is unchanged, skipping", page.page_id);
+                continue;
+            }
+
+            Self::insert_snapshot(ctx, page.site_id, page.page_id, state).await?;
+        }
+
+        if more {
+            Ok(pages.last().map(|page| page.page_id))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Computes the current score and vote tallies for a page.
+    async fn current_state(ctx: &ServiceContext<'_>, page_id: i64) -> Result<ScoreState> {
+        let txn = ctx.transaction();
+        let condition = Self::build_condition(page_id);
+        let scorer = Self::get_scorer(ctx, page_id).await?;
+        let score = scorer.score(txn, condition.clone()).await?;
+        let votes = Self::collect_votes(txn, condition).await?;
+
+        let count_votes = |positive: bool| {
+            votes
+                .iter()
+                .filter(|&(value, _)| if positive { value > 0 } else { value < 0 })
+                .fold(0, |sum, (_, count)| sum + count as i64)
+        };
+
+        Ok(ScoreState {
+            score: score.to_f64(),
+            vote_count: votes.count() as i64,
+            upvotes: count_votes(true),
+            downvotes: count_votes(false),
+        })
+    }
+
+    async fn insert_snapshot(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        ScoreState {
+            score,
+            vote_count,
+            upvotes,
+            downvotes,
+        }: ScoreState,
+    ) -> Result<PageScoreSnapshotModel> {
+        let txn = ctx.transaction();
+        let model = page_score_snapshot::ActiveModel {
+            site_id: Set(site_id),
+            page_id: Set(page_id),
+            score: Set(score),
+            vote_count: Set(vote_count),
+            upvotes: Set(upvotes),
+            downvotes: Set(downvotes),
+            ..Default::default()
+        };
+
+        let snapshot = model.insert(txn).await?;
+        Ok(snapshot)
+    }
+
+    /// Gets the score snapshots for a page, oldest first.
+    pub async fn get_timeline(
+        ctx: &ServiceContext<'_>,
+        GetScoreTimeline { page_id, days }: GetScoreTimeline,
+    ) -> Result<Vec<PageScoreSnapshotModel>> {
+        let txn = ctx.transaction();
+        let since_condition = days.map(|days| {
+            page_score_snapshot::Column::CreatedAt
+                .gt(now() - Duration::days(i64::from(days)))
+        });
+
+        let snapshots = PageScoreSnapshot::find()
+            .filter(
+                Condition::all()
+                    .add(page_score_snapshot::Column::PageId.eq(page_id))
+                    .add_option(since_condition),
+            )
+            .order_by_asc(page_score_snapshot::Column::SnapshotId)
+            .all(txn)
+            .await?;
+
+        Ok(snapshots)
+    }
+
+    /// Counts the upvotes and downvotes cast on a page each day.
+    ///
+    /// Only current votes are counted, on the day they were cast. A changed vote
+    /// counts once, on the day of the change, and disabled votes are not counted.
+    /// Days with no votes are omitted.
+    pub async fn get_daily_votes(
+        ctx: &ServiceContext<'_>,
+        GetDailyVotes { page_id, days }: GetDailyVotes,
+    ) -> Result<Vec<DailyVoteCount>> {
+        #[derive(FromQueryResult, Debug)]
+        struct VoteRow {
+            created_at: OffsetDateTime,
+            value: VoteValue,
+        }
+
+        let txn = ctx.transaction();
+        let votes = PageVote::find()
+            .select_only()
+            .column(page_vote::Column::CreatedAt)
+            .column(page_vote::Column::Value)
+            .filter(Self::build_condition(page_id).add(
+                page_vote::Column::CreatedAt.gt(now() - Duration::days(i64::from(days))),
+            ))
+            .order_by_asc(page_vote::Column::CreatedAt)
+            .into_model::<VoteRow>()
+            .all(txn)
+            .await?;
+
+        let votes = votes
+            .into_iter()
+            .map(|VoteRow { created_at, value }| (created_at, value));
+
+        Ok(count_daily_votes(votes))
+    }
+
+    /// Finds pages whose score fell below the threshold in the last few days.
+    ///
+    /// This looks for consecutive snapshots going from at or above the threshold
+    /// to below it, returning the most recent such drop for each extant page.
+    /// Pages which have since recovered are still included, see `current_score`.
+    pub async fn get_drops(
+        ctx: &ServiceContext<'_>,
+        GetScoreDrops {
+            site_id,
+            threshold,
+            days,
+        }: GetScoreDrops,
+    ) -> Result<Vec<ScoreDrop>> {
+        let txn = ctx.transaction();
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                SELECT
+                    snapshots.page_id,
+                    page.slug,
+                    snapshots.previous_score,
+                    snapshots.score,
+                    snapshots.created_at
+                FROM (
+                    SELECT
+                        page_id,
+                        snapshot_id,
+                        score,
+                        created_at,
+                        LAG(score) OVER (
+                            PARTITION BY page_id
+                            ORDER BY snapshot_id
+                        ) AS previous_score
+                    FROM page_score_snapshot
+                    WHERE site_id = $1
+                ) AS snapshots
+                JOIN page
+                    ON page.page_id = snapshots.page_id
+                WHERE snapshots.created_at > $2
+                    AND page.deleted_at IS NULL
+                ORDER BY snapshots.page_id, snapshots.snapshot_id
+                ",
+            ),
+            [
+                Value::from(site_id),
+                Value::from(now() - Duration::days(i64::from(days))),
+            ],
+        );
+
+        let snapshots = ScoreSnapshotRow::find_by_statement(query).all(txn).await?;
+        Ok(find_drops(snapshots, threshold))
+    }
+
     /// Gets the correct `Scorer` implementation for this page.
     ///
-    /// Currently stubbed, will be implemented when relevant settings are added.
+    /// This is determined by the page's category settings,
+    /// falling back to the test scorer if none is configured.
     pub async fn get_scorer(
-        _ctx: &ServiceContext<'_>,
-        _page_id: i64,
-    ) -> Result<&'static impl Scorer> {
-        // TODO
-        Ok(&TestScorer)
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<&'static (dyn Scorer + Send + Sync)> {
+        let page = PageService::get_direct(ctx, page_id, true).await?;
+        // Deleted pages may still be in a deleted category
+        let category =
+            CategoryService::get_direct(ctx, page.page_category_id, true).await?;
+
+        match category.scorer {
+            None => Ok(&TestScorer),
+            Some(ref scorer) => Self::get_scorer_for_type(scorer.parse()?),
+        }
+    }
+
+    /// Gets the `Scorer` implementation for the given score type.
+    ///
+    /// Fails if there is no implementation available for this type yet.
+    pub fn get_scorer_for_type(
+        score_type: ScoreType,
+    ) -> Result<&'static (dyn Scorer + Send + Sync)> {
+        match score_type {
+            ScoreType::Null => Ok(&NullScorer),
+            ScoreType::Test => Ok(&TestScorer),
+            ScoreType::Sum => Ok(&SumScorer),
+            ScoreType::Mean => Ok(&MeanScorer),
+            ScoreType::Percent => Ok(&PercentScorer),
+            ScoreType::Median => {
+                error!("No scorer implementation for score type {score_type:?}");
+                Err(Error::InvalidEnumValue)
+            }
+        }
     }
 
     /// Helper method for retrieving a `VoteMap` for a page.
@@ -95,3 +403,171 @@ impl ScoreService {
             .add(page_vote::Column::DisabledAt.is_null())
     }
 }
+
+/// Counts upvotes and downvotes by the day they were cast, in UTC.
+fn count_daily_votes<I>(votes: I) -> Vec<DailyVoteCount>
+where
+    I: IntoIterator<Item = (OffsetDateTime, VoteValue)>,
+{
+    let mut days = BTreeMap::new();
+    for (created_at, value) in votes {
+        let day = created_at.to_offset(UtcOffset::UTC).date();
+        let (upvotes, downvotes) = days.entry(day).or_insert((0, 0));
+        match value.cmp(&0) {
+            Ordering::Greater => *upvotes += 1,
+            Ordering::Less => *downvotes += 1,
+            Ordering::Equal => (),
+        }
+    }
+
+    days.into_iter()
+        .filter(|&(_, counts)| counts != (0, 0))
+        .map(|(day, (upvotes, downvotes))| DailyVoteCount {
+            day: day.midnight().assume_utc(),
+            upvotes,
+            downvotes,
+        })
+        .collect()
+}
+
+/// Finds the most recent drop below the threshold for each page.
+///
+/// The snapshots must be ordered by page, then oldest first. The current score
+/// is taken from the last snapshot of each page.
+fn find_drops(snapshots: Vec<ScoreSnapshotRow>, threshold: f64) -> Vec<ScoreDrop> {
+    let mut drops: Vec<ScoreDrop> = Vec::new();
+
+    for snapshot in snapshots {
+        let dropped = matches!(
+            snapshot.previous_score,
+            Some(previous_score) if previous_score >= threshold && snapshot.score < threshold,
+        );
+
+        if dropped {
+            let drop = ScoreDrop {
+                page_id: snapshot.page_id,
+                slug: snapshot.slug,
+                previous_score: snapshot.previous_score.unwrap_or_default(),
+                score: snapshot.score,
+                current_score: snapshot.score,
+                dropped_at: snapshot.created_at,
+            };
+
+            // Replace any earlier drop for this page
+            match drops.last_mut() {
+                Some(last) if last.page_id == drop.page_id => *last = drop,
+                _ => drops.push(drop),
+            }
+        } else if let Some(last) = drops.last_mut() {
+            // Later snapshot of a page which already dropped
+            if last.page_id == snapshot.page_id {
+                last.current_score = snapshot.score;
+            }
+        }
+    }
+
+    drops
+}
+
+#[cfg(test)]
+fn march(day: u8, hour: u8, minute: u8) -> OffsetDateTime {
+    use time::{Date, Month};
+
+    Date::from_calendar_date(2024, Month::March, day)
+        .unwrap()
+        .with_hms(hour, minute, 0)
+        .unwrap()
+        .assume_utc()
+}
+
+#[test]
+fn daily_votes() {
+    let plus_four = UtcOffset::from_hms(4, 0, 0).unwrap();
+    let votes = [
+        (march(1, 9, 0), 1),
+        (march(1, 23, 59), -1),
+        (march(1, 12, 0), 1),
+        (march(1, 21, 0).to_offset(plus_four), 1), // March 2nd locally
+        (march(3, 0, 0), 0),
+        (march(4, 8, 30), -1),
+    ];
+
+    let counts = count_daily_votes(votes);
+    assert_eq!(
+        counts,
+        [
+            DailyVoteCount {
+                day: march(1, 0, 0),
+                upvotes: 3,
+                downvotes: 1,
+            },
+            DailyVoteCount {
+                day: march(4, 0, 0),
+                upvotes: 0,
+                downvotes: 1,
+            },
+        ],
+    );
+
+    assert!(count_daily_votes([]).is_empty());
+}
+
+#[test]
+fn score_drops() {
+    fn snapshot(
+        page_id: i64,
+        previous_score: Option<f64>,
+        score: f64,
+        day: u8,
+    ) -> ScoreSnapshotRow {
+        ScoreSnapshotRow {
+            page_id,
+            slug: format!("page-{page_id}"),
+            previous_score,
+            score,
+            created_at: march(day, 0, 0),
+        }
+    }
+
+    let snapshots = vec![
+        // Drops twice, the latest is reported, then recovers
+        snapshot(1, Some(5.0), -2.0, 1),
+        snapshot(1, Some(-2.0), 1.0, 2),
+        snapshot(1, Some(1.0), -1.0, 3),
+        snapshot(1, Some(-1.0), 4.0, 4),
+        // Already below the threshold, never crossed it
+        snapshot(2, None, -3.0, 1),
+        snapshot(2, Some(-3.0), -4.0, 2),
+        // Crossing happened before the first snapshot in range
+        snapshot(3, Some(-1.0), -5.0, 1),
+        // Lands exactly on the threshold, not a drop
+        snapshot(4, Some(3.0), 0.0, 1),
+        // Drops and stays down
+        snapshot(5, Some(0.0), -1.0, 2),
+    ];
+
+    let drops = find_drops(snapshots, 0.0);
+    assert_eq!(
+        drops,
+        [
+            ScoreDrop {
+                page_id: 1,
+                slug: str!("page-1"),
+                previous_score: 1.0,
+                score: -1.0,
+                current_score: 4.0,
+                dropped_at: march(3, 0, 0),
+            },
+            ScoreDrop {
+                page_id: 5,
+                slug: str!("page-5"),
+                previous_score: 0.0,
+                score: -1.0,
+                current_score: -1.0,
+                dropped_at: march(2, 0, 0),
+            },
+        ],
+    );
+
+    assert!(find_drops(vec![], 0.0).is_empty());
+}
@@ -18,7 +18,12 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::services::Error as ServiceError;
+use sea_orm::FromQueryResult;
 use std::collections::BTreeMap;
+use std::str::FromStr;
+use strum_macros::EnumIter;
+use time::OffsetDateTime;
 
 pub use crate::services::vote::VoteValue;
 
@@ -29,7 +34,8 @@ pub enum VoteType {
     FiveStar,
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(EnumIter, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
 pub enum ScoreType {
     Null,
     Test,
@@ -39,6 +45,35 @@ pub enum ScoreType {
     Percent,
 }
 
+impl ScoreType {
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoreType::Null => "null",
+            ScoreType::Test => "test",
+            ScoreType::Sum => "sum",
+            ScoreType::Mean => "mean",
+            ScoreType::Median => "median",
+            ScoreType::Percent => "percent",
+        }
+    }
+}
+
+impl FromStr for ScoreType {
+    type Err = ServiceError;
+
+    fn from_str(value: &str) -> Result<ScoreType, ServiceError> {
+        match value {
+            "null" => Ok(ScoreType::Null),
+            "test" => Ok(ScoreType::Test),
+            "sum" => Ok(ScoreType::Sum),
+            "mean" => Ok(ScoreType::Mean),
+            "median" => Ok(ScoreType::Median),
+            "percent" => Ok(ScoreType::Percent),
+            _ => Err(ServiceError::InvalidEnumValue),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct VoteMap {
     inner: BTreeMap<VoteValue, u64>,
@@ -88,3 +123,87 @@ impl VoteMap {
         self.inner.iter().map(|(&value, &count)| (value, count))
     }
 }
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetScoreTimeline {
+    pub page_id: i64,
+
+    /// Only include snapshots from the last this many days, if set.
+    pub days: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetDailyVotes {
+    pub page_id: i64,
+    pub days: u32,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DailyVoteCount {
+    #[serde(with = "time::serde::rfc3339")]
+    pub day: OffsetDateTime,
+    pub upvotes: i64,
+    pub downvotes: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetScoreDrops {
+    pub site_id: i64,
+    pub threshold: f64,
+    pub days: u32,
+}
+
+/// A page whose score fell below a threshold.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ScoreDrop {
+    pub page_id: i64,
+    pub slug: String,
+    pub previous_score: f64,
+    pub score: f64,
+    pub current_score: f64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub dropped_at: OffsetDateTime,
+}
+
+/// The score and vote tallies of a page at some point in time.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ScoreState {
+    pub score: f64,
+    pub vote_count: i64,
+    pub upvotes: i64,
+    pub downvotes: i64,
+}
+
+/// A score snapshot paired with the score of the one before it.
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct ScoreSnapshotRow {
+    pub page_id: i64,
+    pub slug: String,
+    pub previous_score: Option<f64>,
+    pub score: f64,
+    pub created_at: OffsetDateTime,
+}
+
+/// Ensure `ScoreType::name()` produces the same output as serde.
+#[test]
+fn score_type_name_serde() {
+    use strum::IntoEnumIterator;
+
+    for variant in ScoreType::iter() {
+        let output = serde_json::to_string(&variant).expect("Unable to serialize JSON");
+        let serde_name: String =
+            serde_json::from_str(&output).expect("Unable to deserialize JSON");
+
+        assert_eq!(
+            &serde_name,
+            variant.name(),
+            "Serde name does not match variant name",
+        );
+
+        let converted: ScoreType =
+            serde_name.as_str().parse().expect("Could not convert item");
+
+        assert_eq!(converted, variant, "Converted item does not match variant");
+    }
+}
@@ -0,0 +1,106 @@
+/*
+ * services/session/device.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Summarizing user agent strings into something readable.
+//!
+//! This is used to show users which devices their sessions and
+//! login attempts came from, for instance "Firefox on Windows 10".
+
+use woothee::parser::Parser;
+
+/// The value `woothee` uses for fields it could not determine.
+const UNKNOWN: &str = "UNKNOWN";
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceCategory {
+    Desktop,
+    Mobile,
+    Crawler,
+    Appliance,
+    Unknown,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeviceSummary {
+    pub category: DeviceCategory,
+    pub browser: Option<String>,
+    pub browser_version: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+}
+
+impl DeviceSummary {
+    pub fn parse(user_agent: &str) -> Self {
+        fn known(value: &str) -> Option<String> {
+            if value.is_empty() || value == UNKNOWN {
+                None
+            } else {
+                Some(str!(value))
+            }
+        }
+
+        match Parser::new().parse(user_agent) {
+            Some(result) => DeviceSummary {
+                category: match result.category {
+                    "pc" => DeviceCategory::Desktop,
+                    "smartphone" | "mobilephone" => DeviceCategory::Mobile,
+                    "crawler" => DeviceCategory::Crawler,
+                    "appliance" => DeviceCategory::Appliance,
+                    _ => DeviceCategory::Unknown,
+                },
+                browser: known(result.name),
+                browser_version: known(result.version),
+                os: known(result.os),
+                os_version: known(&result.os_version),
+            },
+            None => DeviceSummary {
+                category: DeviceCategory::Unknown,
+                browser: None,
+                browser_version: None,
+                os: None,
+                os_version: None,
+            },
+        }
+    }
+}
+
+#[test]
+fn parse_device() {
+    let device = DeviceSummary::parse(
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:128.0) Gecko/20100101 Firefox/128.0",
+    );
+    assert_eq!(device.category, DeviceCategory::Desktop);
+    assert_eq!(device.browser.as_deref(), Some("Firefox"));
+    assert_eq!(device.browser_version.as_deref(), Some("128.0"));
+    assert_eq!(device.os.as_deref(), Some("Windows 10"));
+
+    let device = DeviceSummary::parse(
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+    );
+    assert_eq!(device.category, DeviceCategory::Mobile);
+    assert_eq!(device.browser.as_deref(), Some("Safari"));
+    assert_eq!(device.os.as_deref(), Some("iPhone"));
+
+    let device = DeviceSummary::parse("");
+    assert_eq!(device.category, DeviceCategory::Unknown);
+    assert_eq!(device.browser, None);
+    assert_eq!(device.os, None);
+}
@@ -24,8 +24,10 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod device;
 mod service;
 mod structs;
 
+pub use self::device::{DeviceCategory, DeviceSummary};
 pub use self::service::SessionService;
 pub use self::structs::*;
@@ -74,6 +74,7 @@ impl SessionService {
             ip_address: Set(str!(ip_address)), // TODO inet type?
             user_agent: Set(user_agent),
             restricted: Set(restricted),
+            ..Default::default()
         };
 
         let SessionModel { session_token, .. } = model.insert(txn).await?;
@@ -285,6 +286,52 @@ impl SessionService {
         Ok(rows_affected)
     }
 
+    /// Invalidates one of a user's sessions, referred to by its ID.
+    ///
+    /// The passed session token must belong to the same user,
+    /// but does not need to be the session being invalidated.
+    pub async fn invalidate_by_id(
+        ctx: &ServiceContext<'_>,
+        InvalidateSession {
+            session_token,
+            user_id,
+            session_id,
+        }: InvalidateSession,
+    ) -> Result<()> {
+        info!("Invalidating session ID {session_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let session = Self::get(ctx, &session_token).await?;
+        if session.user_id != user_id {
+            error!(
+                "Requested invalidation of session, user IDs do not match! (current: {}, request: {})",
+                session.user_id,
+                user_id,
+            );
+
+            return Err(Error::SessionUserId {
+                active_user_id: user_id,
+                session_user_id: session.user_id,
+            });
+        }
+
+        let DeleteResult { rows_affected } = Session::delete_many()
+            .filter(
+                Condition::all()
+                    .add(session::Column::SessionId.eq(session_id))
+                    .add(session::Column::UserId.eq(user_id)),
+            )
+            .exec(txn)
+            .await?;
+
+        if rows_affected != 1 {
+            error!("Session ID {session_id} does not exist for user ID {user_id}");
+            return Err(Error::SessionNotFound);
+        }
+
+        Ok(())
+    }
+
     /// Prunes all expired sessions from the database.
     ///
     /// # Returns
@@ -18,8 +18,10 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::device::DeviceSummary;
 use crate::models::session::Model as SessionModel;
 use std::net::IpAddr;
+use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct CreateSession {
@@ -41,8 +43,43 @@ pub type GetOtherSessions = InvalidateOtherSessions;
 
 #[derive(Serialize, Debug, Clone)]
 pub struct GetOtherSessionsOutput {
-    pub current: SessionModel,
-    pub others: Vec<SessionModel>,
+    pub current: SessionInfo,
+    pub others: Vec<SessionInfo>,
+}
+
+/// Information about a session, for display to its user.
+///
+/// This omits the session token, since only the session's own
+/// client should ever have it. Sessions are instead referred
+/// to by their ID.
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionInfo {
+    pub session_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+
+    pub ip_address: String,
+    pub user_agent: String,
+    pub device: DeviceSummary,
+    pub restricted: bool,
+}
+
+impl From<SessionModel> for SessionInfo {
+    fn from(model: SessionModel) -> Self {
+        SessionInfo {
+            session_id: model.session_id,
+            created_at: model.created_at,
+            expires_at: model.expires_at,
+            device: DeviceSummary::parse(&model.user_agent),
+            ip_address: model.ip_address,
+            user_agent: model.user_agent,
+            restricted: model.restricted,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -50,3 +87,10 @@ pub struct InvalidateOtherSessions {
     pub session_token: String,
     pub user_id: i64,
 }
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct InvalidateSession {
+    pub session_token: String,
+    pub user_id: i64,
+    pub session_id: i64,
+}
@@ -22,17 +22,27 @@ use wikidot_normalize::normalize;
 
 use super::prelude::*;
 use crate::constants::SYSTEM_USER_ID;
-use crate::models::sea_orm_active_enums::{AliasType, UserType};
+use crate::models::sea_orm_active_enums::{AliasType, SiteJoinPolicy, UserType};
 use crate::models::site::{self, Entity as Site, Model as SiteModel};
 use crate::services::alias::CreateAlias;
+use crate::services::audit::AuditEvent;
+use crate::services::category::CategorySettings;
+use crate::services::file::License;
+use crate::services::page::{CreatePage, EditPage, EditPageBody};
 use crate::services::relation::CreateSiteUser;
+use crate::services::tag::{CreateSiteTag, CreateSiteTagGroup};
 use crate::services::user::{CreateUser, UpdateUserBody};
-use crate::services::{AliasService, Error, RelationService, UserService};
+use crate::services::{
+    AliasService, AuditService, CategoryService, Error, PageRevisionService, PageService,
+    PasswordService, RelationService, TagService, TextService, UserService,
+};
 use crate::utils::validate_locale;
 use ftml::layout::Layout;
 use ref_map::*;
-use sea_orm::NotSet;
+use sea_orm::{DatabaseBackend, NotSet, Statement, Value};
+use serde_json::Value as JsonValue;
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct SiteService;
@@ -48,6 +58,7 @@ impl SiteService {
             default_page,
             layout,
             locale,
+            default_license,
         }: CreateSite,
     ) -> Result<CreateSiteOutput> {
         let txn = ctx.transaction();
@@ -61,6 +72,9 @@ impl SiteService {
         // Validate locale.
         validate_locale(&locale)?;
 
+        // Validate default file license, if any.
+        let default_license = validate_default_license(default_license)?;
+
         // Insert into database
         let model = site::ActiveModel {
             slug: Set(slug.clone()),
@@ -73,20 +87,43 @@ impl SiteService {
             },
             layout: Set(layout.map(|l| str!(l.value()))),
             locale: Set(locale.clone()),
+            default_license: Set(default_license),
             ..Default::default()
         };
         let site = model.insert(txn).await?;
 
         // Create site user, and add relation
+        let site_user_id =
+            Self::create_site_user(ctx, site.site_id, &slug, locale, description).await?;
 
+        // Return
+        Ok(CreateSiteOutput {
+            site_id: site.site_id,
+            site_user_id,
+            slug,
+        })
+    }
+
+    /// Creates the user which represents a site, and the relation linking the two.
+    ///
+    /// Every site has one of these, so anything inserting site rows directly
+    /// (such as imports) needs to call this afterwards.
+    pub(crate) async fn create_site_user(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        locale: String,
+        description: String,
+    ) -> Result<i64> {
         let user = UserService::create(
             ctx,
             CreateUser {
                 user_type: UserType::Site,
-                name: format!("site:{slug}"),
+                name: site_user_name(slug),
                 email: String::new(),
                 locales: vec![locale],
                 password: String::new(),
+                ip_address: None,
                 bypass_filter: false,
                 bypass_email_verification: false,
             },
@@ -107,7 +144,7 @@ impl SiteService {
         RelationService::create_site_user(
             ctx,
             CreateSiteUser {
-                site_id: site.site_id,
+                site_id,
                 user_id: user.user_id,
                 metadata: (),
                 created_by: SYSTEM_USER_ID,
@@ -115,12 +152,7 @@ impl SiteService {
         )
         .await?;
 
-        // Return
-        Ok(CreateSiteOutput {
-            site_id: site.site_id,
-            site_user_id: user.user_id,
-            slug,
-        })
+        Ok(user.user_id)
     }
 
     /// Update site information.
@@ -148,7 +180,7 @@ impl SiteService {
 
         if let Maybe::Set(new_slug) = input.slug {
             Self::update_slug(ctx, &site, &new_slug, updating_user_id).await?;
-            site_user_body.name = Maybe::Set(format!("site:{new_slug}"));
+            site_user_body.name = Maybe::Set(site_user_name(&new_slug));
             model.slug = Set(new_slug);
         }
 
@@ -171,6 +203,37 @@ impl SiteService {
             model.layout = Set(layout.map(|l| str!(l.value())));
         }
 
+        if let Maybe::Set(default_license) = input.default_license {
+            model.default_license = Set(validate_default_license(default_license)?);
+        }
+
+        // Join policy and password are checked together,
+        // since the password policy requires that one be set.
+        let join_policy = match input.join_policy {
+            Maybe::Set(join_policy) => {
+                model.join_policy = Set(join_policy);
+                join_policy
+            }
+            Maybe::Unset => site.join_policy,
+        };
+
+        let has_join_password = match input.join_password {
+            Maybe::Set(Some(password)) => {
+                model.join_password = Set(Some(PasswordService::new_hash(&password)?));
+                true
+            }
+            Maybe::Set(None) => {
+                model.join_password = Set(None);
+                false
+            }
+            Maybe::Unset => site.join_password.is_some(),
+        };
+
+        if join_policy == SiteJoinPolicy::Password && !has_join_password {
+            error!("Cannot set site join policy to password without a password");
+            return Err(Error::SiteJoinPasswordMissing);
+        }
+
         // Update site
         model.updated_at = Set(Some(now()));
         let new_site = model.update(txn).await?;
@@ -240,6 +303,341 @@ impl SiteService {
         Ok(())
     }
 
+    /// Deletes a site, which can be restored until the grace period has passed.
+    ///
+    /// After that point, the site and all its data are purged
+    /// by the `PurgeDeletedSites` job.
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        reference: Reference<'_>,
+        user_id: i64,
+    ) -> Result<SiteModel> {
+        let txn = ctx.transaction();
+        let site = Self::get(ctx, reference).await?;
+        info!("Deleting site ID {}", site.site_id);
+
+        if site.deleted_at.is_some() {
+            error!("Site ID {} is already deleted", site.site_id);
+            return Err(Error::SiteNotFound);
+        }
+
+        let model = site::ActiveModel {
+            site_id: Set(site.site_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+        let site = model.update(txn).await?;
+
+        // Delete the site user too, so a new site can take this slug
+        let site_user_id =
+            RelationService::get_site_user_id_for_site(ctx, site.site_id).await?;
+        UserService::delete(ctx, Reference::Id(site_user_id)).await?;
+
+        AuditService::record(
+            ctx,
+            Some(site.site_id),
+            user_id,
+            None,
+            AuditEvent::SiteDelete,
+            &site.slug,
+        )
+        .await?;
+
+        Ok(site)
+    }
+
+    /// Restores a deleted site, optionally under a new slug.
+    ///
+    /// A new slug is needed if another site has since taken the old one.
+    pub async fn restore(
+        ctx: &ServiceContext<'_>,
+        RestoreSite {
+            site_id,
+            user_id,
+            slug,
+        }: RestoreSite,
+    ) -> Result<SiteModel> {
+        let txn = ctx.transaction();
+        let site = Self::get(ctx, Reference::Id(site_id)).await?;
+        info!("Restoring site ID {site_id}");
+
+        let deleted_at = match site.deleted_at {
+            Some(deleted_at) => deleted_at,
+            None => {
+                warn!("Site requested to be restored is not currently deleted");
+                return Err(Error::SiteNotDeleted);
+            }
+        };
+
+        if deleted_at + ctx.config().site_deletion_grace_period < now() {
+            warn!("Site ID {site_id} is past its deletion grace period");
+            return Err(Error::SiteRestoreExpired);
+        }
+
+        let slug = match slug {
+            None => site.slug.clone(),
+            Some(mut slug) => {
+                normalize(&mut slug);
+                slug
+            }
+        };
+
+        Self::check_conflicts(ctx, &slug, "restore").await?;
+
+        // Restore the site user, renaming it if the slug changed
+        let site_user_id =
+            RelationService::get_site_user_id_for_site(ctx, site_id).await?;
+        UserService::restore(ctx, site_user_id, site_user_name(&slug)).await?;
+
+        let model = site::ActiveModel {
+            site_id: Set(site_id),
+            slug: Set(slug),
+            updated_at: Set(Some(now())),
+            deleted_at: Set(None),
+            ..Default::default()
+        };
+        let site = model.update(txn).await?;
+
+        AuditService::record(
+            ctx,
+            Some(site_id),
+            user_id,
+            None,
+            AuditEvent::SiteRestore,
+            &site.slug,
+        )
+        .await?;
+
+        Ok(site)
+    }
+
+    /// Permanently removes the next site whose deletion grace period has passed.
+    ///
+    /// Only one site is purged at a time, since each can have a lot of data.
+    /// Returns `true` if a site was purged, meaning there may be more.
+    pub async fn purge_deleted(ctx: &ServiceContext<'_>) -> Result<bool> {
+        let txn = ctx.transaction();
+        let cutoff = now() - ctx.config().site_deletion_grace_period;
+        info!("Purging next site deleted before {cutoff}");
+
+        let site = Site::find()
+            .filter(site::Column::DeletedAt.lt(cutoff))
+            .order_by_asc(site::Column::DeletedAt)
+            .one(txn)
+            .await?;
+
+        match site {
+            Some(site) => {
+                Self::purge(ctx, site.site_id).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Removes a site and everything in it from the database.
+    ///
+    /// The site user is only soft-deleted, since other rows
+    /// (such as revisions on other sites) may still refer to it.
+    /// Audit log entries are kept, but no longer linked to the site.
+    async fn purge(ctx: &ServiceContext<'_>, site_id: i64) -> Result<()> {
+        let txn = ctx.transaction();
+        info!("Purging site ID {site_id}");
+
+        // Normally deleted with the site, but not for sites deleted before that
+        let site_user_id =
+            RelationService::get_site_user_id_for_site(ctx, site_id).await?;
+        let site_user = UserService::get(ctx, Reference::Id(site_user_id)).await?;
+        if site_user.deleted_at.is_none() {
+            UserService::delete(ctx, Reference::Id(site_user_id)).await?;
+        }
+
+        AliasService::remove_all(ctx, AliasType::Site, site_id).await?;
+
+        for sql in PURGE_SITE_QUERIES {
+            trace!("Running purge query: {sql}");
+            let statement = Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                sql,
+                [Value::from(site_id)],
+            );
+
+            txn.execute(statement).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new site from an existing one.
+    ///
+    /// This copies the site settings, categories, tag definitions,
+    /// and the latest revision of each of the specified pages.
+    pub async fn clone_from_template(
+        ctx: &ServiceContext<'_>,
+        CloneSite {
+            template,
+            slug,
+            name,
+            tagline,
+            description,
+            locale,
+            user_id,
+            pages,
+        }: CloneSite<'_>,
+    ) -> Result<CloneSiteOutput> {
+        let template = Self::get(ctx, template).await?;
+        info!(
+            "Creating site '{slug}' from template site ID {}",
+            template.site_id,
+        );
+
+        let default_license = match template.default_license {
+            None => None,
+            Some(value) => Some(serde_json::from_value(value)?),
+        };
+
+        let output = Self::create(
+            ctx,
+            CreateSite {
+                slug,
+                name,
+                tagline,
+                description,
+                default_page: Some(template.default_page),
+                layout: template.layout.ref_map(|l| parse_layout(l)).transpose()?,
+                locale: locale.unwrap_or(template.locale),
+                default_license,
+            },
+        )
+        .await?;
+
+        let site_id = output.site_id;
+
+        // Copy categories
+        let mut categories = Vec::new();
+        for category in CategoryService::get_all(ctx, template.site_id).await? {
+            debug!("Copying category '{}'", category.slug);
+
+            let settings = CategorySettings {
+                layout: category.layout.ref_map(|l| parse_layout(l)).transpose()?,
+                default_license: match category.default_license {
+                    None => None,
+                    Some(value) => Some(serde_json::from_value(value)?),
+                },
+                scorer: category
+                    .scorer
+                    .ref_map(|s| s.parse().map_err(|_| Error::InvalidEnumValue))
+                    .transpose()?,
+                template_slug: category.template_slug,
+                use_templates: category.use_templates,
+                permissions: match category.permissions {
+                    None => None,
+                    Some(value) => Some(serde_json::from_value(value)?),
+                },
+            };
+
+            let category =
+                CategoryService::create(ctx, site_id, category.slug, settings).await?;
+
+            categories.push(category.category_id);
+        }
+
+        // Copy pages
+        //
+        // This happens before tag definitions are copied, so
+        // that tag rules don't apply part-way through cloning.
+        let mut page_ids = Vec::new();
+        for page_slug in pages {
+            debug!("Copying page '{page_slug}'");
+
+            let page =
+                PageService::get(ctx, template.site_id, Reference::Slug(cow!(page_slug)))
+                    .await?;
+
+            let revision =
+                PageRevisionService::get_latest(ctx, template.site_id, page.page_id)
+                    .await?;
+
+            let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+            let created = PageService::create(
+                ctx,
+                CreatePage {
+                    site_id,
+                    wikitext,
+                    title: revision.title,
+                    alt_title: revision.alt_title,
+                    slug: page.slug,
+                    layout: page.layout.ref_map(|l| parse_layout(l)).transpose()?,
+                    revision_comments: format!("Copied from site {}", template.slug),
+                    user_id,
+                    bypass_filter: false,
+                },
+            )
+            .await?;
+
+            if !revision.tags.is_empty() {
+                PageService::edit(
+                    ctx,
+                    EditPage {
+                        site_id,
+                        page: Reference::Id(created.page_id),
+                        last_revision_id: created.revision_id,
+                        revision_comments: str!("Copy page tags"),
+                        user_id,
+                        body: EditPageBody {
+                            tags: Maybe::Set(revision.tags),
+                            ..Default::default()
+                        },
+                        bypass_filter: false,
+                        bypass_tag_check: false,
+                    },
+                )
+                .await?;
+            }
+
+            page_ids.push(created.page_id);
+        }
+
+        // Copy tag definitions, mapping old group IDs to new ones
+        let mut group_ids = HashMap::new();
+        for group in TagService::get_all_groups(ctx, template.site_id).await? {
+            let new_group = TagService::create_group(
+                ctx,
+                CreateSiteTagGroup {
+                    site_id,
+                    name: group.name,
+                    max_tags: group.max_tags,
+                    description: group.description,
+                    min_tags: group.min_tags,
+                },
+            )
+            .await?;
+
+            group_ids.insert(group.tag_group_id, new_group.tag_group_id);
+        }
+
+        for tag in TagService::get_all(ctx, template.site_id).await? {
+            TagService::create(
+                ctx,
+                CreateSiteTag {
+                    site_id,
+                    name: tag.name,
+                    tag_group_id: tag
+                        .tag_group_id
+                        .and_then(|id| group_ids.get(&id).copied()),
+                    description: tag.description,
+                },
+            )
+            .await?;
+        }
+
+        Ok(CloneSiteOutput {
+            site: output,
+            categories,
+            pages: page_ids,
+        })
+    }
+
     #[inline]
     pub async fn exists(
         ctx: &ServiceContext<'_>,
@@ -255,6 +653,7 @@ impl SiteService {
         mut reference: Reference<'_>,
     ) -> Result<Option<SiteModel>> {
         let txn = ctx.transaction();
+        let mut from_alias = false;
 
         // If slug, determine if this is a site alias.
         //
@@ -268,6 +667,7 @@ impl SiteService {
                 // Rewrite reference so in the "real" site search
                 // we locate directly via site ID.
                 reference = Reference::Id(alias.target_id);
+                from_alias = true;
             }
         }
 
@@ -285,6 +685,11 @@ impl SiteService {
             }
         };
 
+        // Slugs shouldn't resolve to deleted sites, even through an alias.
+        if from_alias {
+            return Ok(site.filter(|site| site.deleted_at.is_none()));
+        }
+
         Ok(site)
     }
 
@@ -317,10 +722,22 @@ impl SiteService {
         }
     }
 
+    /// Gets the default license for files uploaded to this site, if one is set.
+    pub async fn get_default_license(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Option<License>> {
+        let site = Self::get(ctx, Reference::Id(site_id)).await?;
+        match site.default_license {
+            None => Ok(None),
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+        }
+    }
+
     /// Checks to see if a site already exists at the slug specified.
     ///
     /// If so, this method fails with `Error::SiteExists`. Otherwise it returns nothing.
-    async fn check_conflicts(
+    pub(crate) async fn check_conflicts(
         ctx: &ServiceContext<'_>,
         slug: &str,
         action: &str,
@@ -354,3 +771,99 @@ impl SiteService {
         }
     }
 }
+
+/// Queries to remove all of a site's data, in foreign key order.
+///
+/// Each takes the site ID as its only parameter.
+const PURGE_SITE_QUERIES: [&str; 30] = [
+    "UPDATE site SET custom_domain = NULL WHERE site_id = $1",
+    "UPDATE page SET latest_revision_id = NULL WHERE site_id = $1",
+    "
+    DELETE FROM relation
+    WHERE (from_type = 'site' AND from_id = $1)
+        OR (dest_type = 'site' AND dest_id = $1)
+        OR (from_type = 'page' AND from_id IN (SELECT page_id FROM page WHERE site_id = $1))
+        OR (dest_type = 'page' AND dest_id IN (SELECT page_id FROM page WHERE site_id = $1))
+        OR (from_type = 'file' AND from_id IN (SELECT file_id FROM file WHERE site_id = $1))
+        OR (dest_type = 'file' AND dest_id IN (SELECT file_id FROM file WHERE site_id = $1))
+    ",
+    "
+    DELETE FROM notification
+    WHERE site_id = $1
+        OR page_id IN (SELECT page_id FROM page WHERE site_id = $1)
+        OR revision_id IN (SELECT revision_id FROM page_revision WHERE site_id = $1)
+    ",
+    "DELETE FROM message_report WHERE reported_to_site_id = $1",
+    "
+    DELETE FROM page_connection_missing
+    WHERE to_site_id = $1
+        OR from_page_id IN (SELECT page_id FROM page WHERE site_id = $1)
+    ",
+    "
+    DELETE FROM page_connection
+    WHERE from_page_id IN (SELECT page_id FROM page WHERE site_id = $1)
+        OR to_page_id IN (SELECT page_id FROM page WHERE site_id = $1)
+    ",
+    "
+    DELETE FROM page_parent
+    WHERE parent_page_id IN (SELECT page_id FROM page WHERE site_id = $1)
+        OR child_page_id IN (SELECT page_id FROM page WHERE site_id = $1)
+    ",
+    "DELETE FROM page_link WHERE page_id IN (SELECT page_id FROM page WHERE site_id = $1)",
+    "DELETE FROM page_attribution WHERE page_id IN (SELECT page_id FROM page WHERE site_id = $1)",
+    "DELETE FROM page_lock WHERE page_id IN (SELECT page_id FROM page WHERE site_id = $1)",
+    "DELETE FROM page_draft WHERE page_id IN (SELECT page_id FROM page WHERE site_id = $1)",
+    "DELETE FROM page_vote WHERE page_id IN (SELECT page_id FROM page WHERE site_id = $1)",
+    "DELETE FROM vote_fraud_report WHERE site_id = $1",
+    "DELETE FROM page_score_snapshot WHERE site_id = $1",
+    "DELETE FROM page_redirect WHERE site_id = $1",
+    "DELETE FROM file_revision WHERE site_id = $1",
+    "DELETE FROM file WHERE site_id = $1",
+    "DELETE FROM page_revision WHERE site_id = $1",
+    "DELETE FROM page WHERE site_id = $1",
+    "DELETE FROM page_category WHERE site_id = $1",
+    "DELETE FROM site_tag WHERE site_id = $1",
+    "DELETE FROM site_tag_group WHERE site_id = $1",
+    "DELETE FROM page_bulk_operation WHERE site_id = $1",
+    "DELETE FROM site_export WHERE site_id = $1",
+    "DELETE FROM site_import WHERE site_id = $1",
+    "DELETE FROM filter WHERE site_id = $1",
+    "UPDATE audit_log SET site_id = NULL WHERE site_id = $1",
+    "DELETE FROM site_domain WHERE site_id = $1",
+    "DELETE FROM site WHERE site_id = $1",
+];
+
+fn parse_layout(value: &str) -> Result<Layout> {
+    value.parse().map_err(|_| Error::InvalidEnumValue)
+}
+
+/// Validates a site's default license, converting it for storage.
+fn validate_default_license(license: Option<License>) -> Result<Option<JsonValue>> {
+    match license {
+        None => Ok(None),
+        Some(mut license) => {
+            license.validate()?;
+            Ok(Some(serde_json::to_value(license)?))
+        }
+    }
+}
+
+/// Gets the name of the user which represents the site with this slug.
+fn site_user_name(slug: &str) -> String {
+    format!("site:{slug}")
+}
+
+#[test]
+fn site_user_names() {
+    use crate::utils::get_slug;
+
+    // A deleted site's slug is taken by a new site,
+    // then the old site is restored under another slug.
+    // Their site users must not have the same name or slug.
+    let new_site_user = site_user_name("scp-wiki");
+    let restored_site_user = site_user_name("scp-wiki-archive");
+
+    assert_eq!(new_site_user, "site:scp-wiki");
+    assert_eq!(restored_site_user, "site:scp-wiki-archive");
+    assert_ne!(get_slug(&new_site_user), get_slug(&restored_site_user));
+}
@@ -19,8 +19,10 @@
  */
 
 use crate::models::alias::Model as AliasModel;
+use crate::models::sea_orm_active_enums::SiteJoinPolicy;
 use crate::models::site::Model as SiteModel;
 use crate::models::site_domain::Model as SiteDomainModel;
+use crate::services::file::License;
 use crate::types::{Maybe, Reference};
 use ftml::layout::Layout;
 
@@ -33,6 +35,9 @@ pub struct CreateSite {
     pub default_page: Option<String>,
     pub layout: Option<Layout>,
     pub locale: String,
+
+    #[serde(default)]
+    pub default_license: Option<License>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -73,4 +78,43 @@ pub struct UpdateSiteBody {
     pub description: Maybe<String>,
     pub locale: Maybe<String>,
     pub layout: Maybe<Option<Layout>>,
+    pub default_license: Maybe<Option<License>>,
+    pub join_policy: Maybe<SiteJoinPolicy>,
+    pub join_password: Maybe<Option<String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeleteSite<'a> {
+    pub site: Reference<'a>,
+    pub user_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RestoreSite {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub slug: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CloneSite<'a> {
+    pub template: Reference<'a>,
+    pub slug: String,
+    pub name: String,
+    pub tagline: String,
+    pub description: String,
+    pub locale: Option<String>,
+    pub user_id: i64,
+
+    /// Slugs of the pages in the template site to copy over.
+    #[serde(default)]
+    pub pages: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CloneSiteOutput {
+    #[serde(flatten)]
+    pub site: CreateSiteOutput,
+    pub categories: Vec<i64>,
+    pub pages: Vec<i64>,
 }
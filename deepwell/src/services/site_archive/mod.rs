@@ -0,0 +1,43 @@
+/*
+ * services/site_archive/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Exporting and importing a whole site as a portable archive.
+//!
+//! An archive is a gzipped tar file. The first entry is `site.json`, a versioned
+//! manifest containing the database rows for the site. After it come the text
+//! entries referenced by page revisions (`text/<hash>`), then the blobs referenced
+//! by file revisions (`blobs/<hash>`), each named by the hex form of its hash.
+//!
+//! Exports run in the background as a job, tracked in the `site_export` table,
+//! with the archive streamed to a temporary file and then to blob storage.
+//! Imports run in the background as batched jobs, with their progress
+//! tracked in the `site_import` table.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::SiteArchiveService;
+pub use self::structs::*;
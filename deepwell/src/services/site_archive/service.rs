@@ -0,0 +1,1269 @@
+/*
+ * services/site_archive/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::config::Config;
+use crate::constants::SYSTEM_USER_ID;
+use crate::hash::{
+    blob_hash_to_hex, k12_hash, sha512_hash, slice_to_blob_hash, BlobHash, TextHash,
+};
+use crate::models::file::{self, Entity as File};
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::page::{self, Entity as Page};
+use crate::models::page_attribution::{self, Entity as PageAttribution};
+use crate::models::page_category::{
+    self, Entity as PageCategory, Model as PageCategoryModel,
+};
+use crate::models::page_parent::{self, Entity as PageParent};
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::page_vote::{self, Entity as PageVote};
+use crate::models::sea_orm_active_enums::AliasType;
+use crate::models::site_export::{self, Model as SiteExportModel};
+use crate::models::site_import::{self, Model as SiteImportModel};
+use crate::models::user::{self, Entity as User};
+use crate::services::alias::CreateAlias;
+use crate::services::blob::{FinalizeBlobUploadOutput, EMPTY_BLOB_HASH};
+use crate::services::category::CategoryPermissions;
+use crate::services::file::License;
+use crate::services::import::{
+    ImportCategory, ImportFile, ImportFileRevision, ImportPage, ImportPageAttribution,
+    ImportPageParent, ImportPageRevision, ImportPageVote, ImportService, ImportSite,
+};
+use crate::services::job::{Job, JobService};
+use crate::services::score::ScoreType;
+use crate::services::{AliasService, BlobService, SiteService, TextService, UserService};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sea_orm::TransactionTrait;
+use sea_query::{Query, SelectStatement};
+use sha2::{Digest, Sha512};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::env;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use tar::{Archive, Builder, EntryType, Header};
+use tokio::fs;
+use wikidot_normalize::normalize;
+
+/// The path of the manifest, which is always the first entry.
+const MANIFEST_PATH: &str = "site.json";
+
+/// The directory containing text entries, named by hex K12 hash.
+const TEXT_DIRECTORY: &str = "text/";
+
+/// The directory containing blob entries, named by hex SHA-512 hash.
+const BLOB_DIRECTORY: &str = "blobs/";
+
+/// The MIME type archives are stored with.
+const ARCHIVE_MIME: &str = "application/gzip";
+
+/// How many user IDs to look up at once, to stay under the bind parameter limit.
+const USER_CHUNK_SIZE: usize = 10_000;
+
+#[derive(Debug)]
+pub struct SiteArchiveService;
+
+impl SiteArchiveService {
+    /// Starts exporting a site, with all its pages, files, and history, into an archive.
+    ///
+    /// The export runs in the background as a job, see `run_export()`.
+    /// Deleted pages and files are included. Once complete, the archive
+    /// is stored as a blob, which can be downloaded via `get_export_optional()`.
+    pub async fn export(
+        ctx: &ServiceContext<'_>,
+        SiteExport { site_id }: SiteExport,
+    ) -> Result<SiteExportModel> {
+        info!("Starting export of site ID {site_id}");
+
+        let txn = ctx.transaction();
+        SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        let model = site_export::ActiveModel {
+            site_id: Set(site_id),
+            ..Default::default()
+        };
+        let export = model.insert(txn).await?;
+
+        let job = Job::ExportSiteArchive {
+            export_id: export.export_id,
+        };
+        JobService::queue_job(ctx, &job, None).await?;
+        Ok(export)
+    }
+
+    /// Gets a site export, with a presigned URL to download it if it is complete.
+    pub async fn get_export_optional(
+        ctx: &ServiceContext<'_>,
+        export_id: i64,
+    ) -> Result<Option<SiteExportOutput>> {
+        let txn = ctx.transaction();
+        let export = match site_export::Entity::find_by_id(export_id).one(txn).await? {
+            Some(export) => export,
+            None => return Ok(None),
+        };
+
+        let url = match export.s3_hash {
+            Some(ref s3_hash) => {
+                let url = ctx
+                    .blob_storage()
+                    .presign_download(
+                        &blob_hash_to_hex(s3_hash),
+                        ctx.config().presigned_expiry_secs,
+                    )
+                    .await?;
+
+                Some(url)
+            }
+            None => None,
+        };
+
+        Ok(Some(SiteExportOutput { export, url }))
+    }
+
+    /// Builds the archive for a site export, and stores it as a blob.
+    ///
+    /// The archive is written to a temporary file as each entry is fetched,
+    /// then streamed to blob storage, so only the manifest and a single
+    /// text or blob entry are held in memory at a time.
+    ///
+    /// If this fails, the export is marked as failed with the error,
+    /// since retrying would likely fail in the same way.
+    pub async fn run_export(ctx: &ServiceContext<'_>, export_id: i64) -> Result<()> {
+        let txn = ctx.transaction();
+
+        // Lock the row, so the same export is not run twice at once
+        let export = site_export::Entity::find_by_id(export_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::SiteExportNotFound)?;
+
+        if export.completed_at.is_some() || export.failed_at.is_some() {
+            info!("Site export ID {export_id} is finished, stopping");
+            return Ok(());
+        }
+
+        let path = env::temp_dir().join(format!("deepwell-export-{export_id}.tar.gz"));
+        let result = match Self::write_archive(ctx, export.site_id, &path).await {
+            Ok((s3_hash, size)) => {
+                BlobService::upload_file(ctx, &path, s3_hash, size, ARCHIVE_MIME).await
+            }
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = fs::remove_file(&path).await {
+            warn!(
+                "Unable to remove temporary site archive {}: {error}",
+                path.display()
+            );
+        }
+
+        let mut model = site_export::ActiveModel {
+            export_id: Set(export_id),
+            ..Default::default()
+        };
+
+        match result {
+            Ok(FinalizeBlobUploadOutput { s3_hash, size, .. }) => {
+                info!(
+                    "Exported site ID {} to archive ({size} bytes)",
+                    export.site_id
+                );
+                model.completed_at = Set(Some(now()));
+                model.s3_hash = Set(Some(s3_hash.to_vec()));
+                model.size = Set(Some(size));
+            }
+            Err(error) => {
+                error!("Site export ID {export_id} failed: {error}");
+                model.failed_at = Set(Some(now()));
+                model.error_code = Set(Some(error.code()));
+                model.error_message = Set(Some(error.to_string()));
+            }
+        }
+
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    /// Writes the archive for a site to the given path.
+    ///
+    /// # Returns
+    /// The hash and size of the archive written.
+    async fn write_archive(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        path: &Path,
+    ) -> Result<(BlobHash, i64)> {
+        info!("Writing archive for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        // Gather all rows belonging to this site
+        let aliases = AliasService::get_all(ctx, AliasType::Site, site_id).await?;
+
+        let categories = PageCategory::find()
+            .filter(page_category::Column::SiteId.eq(site_id))
+            .order_by_asc(page_category::Column::CategoryId)
+            .all(txn)
+            .await?;
+
+        let pages = Page::find()
+            .filter(page::Column::SiteId.eq(site_id))
+            .order_by_asc(page::Column::PageId)
+            .all(txn)
+            .await?;
+
+        let page_revisions = PageRevision::find()
+            .filter(page_revision::Column::SiteId.eq(site_id))
+            .order_by_asc(page_revision::Column::PageId)
+            .order_by_asc(page_revision::Column::RevisionNumber)
+            .all(txn)
+            .await?;
+
+        let page_parents = PageParent::find()
+            .filter(page_parent::Column::ChildPageId.in_subquery(site_pages(site_id)))
+            .all(txn)
+            .await?;
+
+        let page_votes = PageVote::find()
+            .filter(page_vote::Column::PageId.in_subquery(site_pages(site_id)))
+            .order_by_asc(page_vote::Column::PageVoteId)
+            .all(txn)
+            .await?;
+
+        let page_attributions = PageAttribution::find()
+            .filter(page_attribution::Column::PageId.in_subquery(site_pages(site_id)))
+            .all(txn)
+            .await?;
+
+        let files = File::find()
+            .filter(file::Column::SiteId.eq(site_id))
+            .order_by_asc(file::Column::FileId)
+            .all(txn)
+            .await?;
+
+        let file_revisions = FileRevision::find()
+            .filter(file_revision::Column::SiteId.eq(site_id))
+            .order_by_asc(file_revision::Column::FileId)
+            .order_by_asc(file_revision::Column::RevisionNumber)
+            .all(txn)
+            .await?;
+
+        // Record every user referenced, so they can be matched up on import
+        let user_ids = {
+            let mut user_ids = BTreeSet::new();
+            user_ids.extend(aliases.iter().map(|alias| alias.created_by));
+            user_ids.extend(page_revisions.iter().map(|revision| revision.user_id));
+            user_ids.extend(page_votes.iter().map(|vote| vote.user_id));
+            user_ids.extend(page_votes.iter().filter_map(|vote| vote.disabled_by));
+            user_ids.extend(page_attributions.iter().map(|attr| attr.user_id));
+            user_ids.extend(file_revisions.iter().map(|revision| revision.user_id));
+            user_ids.into_iter().collect::<Vec<_>>()
+        };
+
+        let mut users = Vec::with_capacity(user_ids.len());
+        for chunk in user_ids.chunks(USER_CHUNK_SIZE) {
+            let models = User::find()
+                .filter(user::Column::UserId.is_in(chunk.iter().copied()))
+                .all(txn)
+                .await?;
+
+            users.extend(models.into_iter().map(|user| ArchiveUser {
+                user_id: user.user_id,
+                slug: user.slug,
+            }));
+        }
+
+        // Collect referenced text and blobs
+        let text_hashes = page_revisions
+            .iter()
+            .flat_map(|revision| [&revision.wikitext_hash, &revision.compiled_hash])
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        let blob_hashes = file_revisions
+            .iter()
+            .map(|revision| &revision.s3_hash)
+            .filter(|hash| hash.as_slice() != EMPTY_BLOB_HASH)
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        // Build manifest
+        let exported_at = now();
+        let manifest = SiteArchive {
+            version: ARCHIVE_VERSION,
+            exported_at,
+            site,
+            users,
+            aliases,
+            categories,
+            pages,
+            page_revisions,
+            page_parents,
+            page_votes,
+            page_attributions,
+            files,
+            file_revisions,
+        };
+
+        debug!(
+            "Writing site archive with {} pages, {} revisions, {} files, {} texts, and {} blobs",
+            manifest.pages.len(),
+            manifest.page_revisions.len(),
+            manifest.files.len(),
+            text_hashes.len(),
+            blob_hashes.len(),
+        );
+
+        // Write archive, streaming each entry to the file
+        let mtime = u64::try_from(exported_at.unix_timestamp()).unwrap_or(0);
+        let file = BufWriter::new(std::fs::File::create(path)?);
+        let mut builder = Builder::new(GzEncoder::new(
+            HashWriter::new(file),
+            Compression::default(),
+        ));
+
+        add_entry(
+            &mut builder,
+            MANIFEST_PATH,
+            &serde_json::to_vec(&manifest)?,
+            mtime,
+        )?;
+        drop(manifest);
+
+        for hash in text_hashes {
+            let contents = TextService::get(ctx, &hash).await?;
+            let path = format!("{TEXT_DIRECTORY}{}", hex::encode(&hash));
+            add_entry(&mut builder, &path, contents.as_bytes(), mtime)?;
+        }
+
+        for hash in blob_hashes {
+            let data = BlobService::get(ctx, &hash).await?;
+            let path = format!("{BLOB_DIRECTORY}{}", blob_hash_to_hex(&hash));
+            add_entry(&mut builder, &path, &data, mtime)?;
+        }
+
+        let (s3_hash, size) = builder.into_inner()?.finish()?.finish()?;
+        Ok((s3_hash, size))
+    }
+
+    /// Starts importing a site archive as a new site, under the given slug.
+    ///
+    /// The import runs in the background as a series of jobs, see `run_import_step()`.
+    /// All IDs are newly assigned. Users are not part of the archive, so they
+    /// are matched against existing users by ID and slug, or by slug alone.
+    /// Revisions by archived users which do not exist on this instance are
+    /// attributed to the system user, and their votes and attributions are dropped.
+    ///
+    /// The archive must already be uploaded as a blob.
+    pub async fn import(
+        ctx: &ServiceContext<'_>,
+        SiteImport {
+            s3_hash,
+            mut slug,
+            name,
+        }: SiteImport,
+    ) -> Result<SiteImportModel> {
+        info!("Starting import of site archive to new site '{slug}'");
+
+        let txn = ctx.transaction();
+        normalize(&mut slug);
+        SiteService::check_conflicts(ctx, &slug, "import").await?;
+
+        let s3_hash = BlobHash::try_from(s3_hash.as_ref())
+            .map_err(|_| Error::SiteArchiveInvalid("s3_hash"))?;
+
+        if !BlobService::exists(ctx, &s3_hash).await? {
+            error!("Site archive blob to import does not exist");
+            return Err(Error::BlobNotFound);
+        }
+
+        let model = site_import::ActiveModel {
+            s3_hash: Set(s3_hash.to_vec()),
+            slug: Set(slug),
+            name: Set(name),
+            ..Default::default()
+        };
+        let import = model.insert(txn).await?;
+
+        let job = Job::ImportSiteArchive {
+            import_id: import.import_id,
+        };
+        JobService::queue_job(ctx, &job, None).await?;
+        Ok(import)
+    }
+
+    pub async fn get_import_optional(
+        ctx: &ServiceContext<'_>,
+        import_id: i64,
+    ) -> Result<Option<SiteImportModel>> {
+        let txn = ctx.transaction();
+        let import = site_import::Entity::find_by_id(import_id).one(txn).await?;
+        Ok(import)
+    }
+
+    /// Performs the next step of a site archive import.
+    ///
+    /// The first step checks the whole archive against the configured limits.
+    /// Then each step stores a batch of the text and blob entries, and once all
+    /// are stored, a final step creates the site with its pages and files.
+    ///
+    /// Each step runs in a savepoint. If it fails, its changes are rolled back
+    /// and the import is marked as failed with the error, since retrying the
+    /// same archive would not produce any different result.
+    ///
+    /// # Returns
+    /// Whether there are more steps to perform.
+    pub async fn run_import_step(
+        ctx: &ServiceContext<'_>,
+        import_id: i64,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+
+        // Lock the row, so that steps of the same import run one at a time
+        let import = site_import::Entity::find_by_id(import_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::SiteImportNotFound)?;
+
+        if import.completed_at.is_some() || import.failed_at.is_some() {
+            info!("Site import ID {import_id} is finished, stopping");
+            return Ok(false);
+        }
+
+        let savepoint = txn.begin().await?;
+        let step_ctx = ctx.savepoint(&savepoint);
+        let result = match import.extracted_at {
+            None => Self::extract_entries(&step_ctx, &import).await,
+            Some(_) => Self::create_site(&step_ctx, &import).await,
+        };
+
+        let mut model = site_import::ActiveModel {
+            import_id: Set(import_id),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        let more = match result {
+            Ok(ImportStep::Extracted { position, finished }) => {
+                savepoint.commit().await?;
+                model.position = Set(position);
+                if finished {
+                    model.extracted_at = Set(Some(now()));
+                }
+
+                true
+            }
+            Ok(ImportStep::Created {
+                site_id,
+                unmapped_users,
+                skipped_aliases,
+            }) => {
+                savepoint.commit().await?;
+                model.completed_at = Set(Some(now()));
+                model.site_id = Set(Some(site_id));
+                model.unmapped_users = Set(unmapped_users);
+                model.skipped_aliases = Set(skipped_aliases);
+                false
+            }
+            Err(error) => {
+                error!("Site import ID {import_id} failed: {error}");
+                savepoint.rollback().await?;
+                model.failed_at = Set(Some(now()));
+                model.error_code = Set(Some(error.code()));
+                model.error_message = Set(Some(error.to_string()));
+                false
+            }
+        };
+
+        model.update(txn).await?;
+        Ok(more)
+    }
+
+    /// Stores the next batch of text and blob entries from the archive.
+    async fn extract_entries(
+        ctx: &ServiceContext<'_>,
+        import: &SiteImportModel,
+    ) -> Result<ImportStep> {
+        let config = ctx.config();
+        let limits = ArchiveLimits::new(config);
+        let import_id = import.import_id;
+        let data = BlobService::get(ctx, &import.s3_hash).await?;
+
+        // Check the archive before storing anything
+        if import.position == 0 {
+            info!("Checking site archive for import ID {import_id}");
+            read_manifest(&data, limits)?;
+            read_archive(&data, limits, |_, _, _| Ok(true))?;
+        }
+
+        let batch_size = usize::from(config.job_site_import_batch_size.get());
+        let mut entries = Vec::with_capacity(batch_size);
+        let mut position = import.position as usize;
+        let mut finished = true;
+
+        read_archive(&data, limits, |index, path, entry| {
+            if index < position {
+                // Stored by an earlier step
+                return Ok(true);
+            }
+
+            if entries.len() >= batch_size {
+                finished = false;
+                return Ok(false);
+            }
+
+            position = index + 1;
+            if path != MANIFEST_PATH {
+                entries.push((str!(path), read_entry(entry)?));
+            }
+
+            Ok(true)
+        })?;
+
+        info!(
+            "Storing {} site archive entries, up to entry {position} (import ID {import_id})",
+            entries.len(),
+        );
+
+        for (path, data) in entries {
+            Self::store_entry(ctx, &path, data).await?;
+        }
+
+        Ok(ImportStep::Extracted {
+            position: position
+                .try_into()
+                .map_err(|_| Error::SiteArchiveInvalid("entries"))?,
+            finished,
+        })
+    }
+
+    /// Stores a single text or blob entry, checking that it matches its hash.
+    async fn store_entry(
+        ctx: &ServiceContext<'_>,
+        path: &str,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        if let Some(hex_hash) = path.strip_prefix(TEXT_DIRECTORY) {
+            let contents =
+                String::from_utf8(data).map_err(|_| Error::SiteArchiveInvalid("text"))?;
+
+            if hex::encode(k12_hash(contents.as_bytes())) != hex_hash {
+                error!("Site archive text entry {path} does not match its hash");
+                return Err(Error::SiteArchiveInvalid("text"));
+            }
+
+            TextService::create(ctx, contents).await?;
+        } else if let Some(hex_hash) = path.strip_prefix(BLOB_DIRECTORY) {
+            if blob_hash_to_hex(&sha512_hash(&data)).as_str() != hex_hash {
+                error!("Site archive blob entry {path} does not match its hash");
+                return Err(Error::SiteArchiveInvalid("blob"));
+            }
+
+            BlobService::direct_upload(ctx, data).await?;
+        } else {
+            warn!("Ignoring unknown site archive entry {path}");
+        }
+
+        Ok(())
+    }
+
+    /// Creates the site and all its rows from the manifest.
+    ///
+    /// All the text and blob entries must have already been stored.
+    async fn create_site(
+        ctx: &ServiceContext<'_>,
+        import: &SiteImportModel,
+    ) -> Result<ImportStep> {
+        let limits = ArchiveLimits::new(ctx.config());
+        let slug = import.slug.clone();
+        info!(
+            "Creating site '{slug}' from site archive (import ID {})",
+            import.import_id,
+        );
+
+        // The slug may have been taken since the import started
+        SiteService::check_conflicts(ctx, &slug, "import").await?;
+
+        let data = BlobService::get(ctx, &import.s3_hash).await?;
+        let mut manifest = read_manifest(&data, limits)?;
+
+        // Entries were checked against their hashes when stored,
+        // so their names say which texts and blobs the archive had.
+        let mut text_hashes = HashSet::new();
+        let mut blob_hashes = HashSet::new();
+        blob_hashes.insert(EMPTY_BLOB_HASH);
+
+        read_archive(&data, limits, |_, path, _| {
+            if let Some(hex_hash) = path.strip_prefix(TEXT_DIRECTORY) {
+                let hash = hex::decode(hex_hash)
+                    .ok()
+                    .and_then(|hash| TextHash::try_from(hash.as_slice()).ok())
+                    .ok_or(Error::SiteArchiveInvalid("text"))?;
+
+                text_hashes.insert(hash);
+            } else if let Some(hex_hash) = path.strip_prefix(BLOB_DIRECTORY) {
+                let hash = hex::decode(hex_hash)
+                    .ok()
+                    .and_then(|hash| BlobHash::try_from(hash.as_slice()).ok())
+                    .ok_or(Error::SiteArchiveInvalid("blob"))?;
+
+                blob_hashes.insert(hash);
+            }
+
+            Ok(true)
+        })?;
+
+        // Match up users
+        let mut users = HashMap::new();
+        let mut unmapped_users = Vec::new();
+
+        for ArchiveUser { user_id, slug } in manifest.users {
+            let user = match UserService::get_optional(ctx, Reference::Id(user_id))
+                .await?
+            {
+                Some(user) if user.slug == slug => Some(user),
+                _ => UserService::get_optional(ctx, Reference::Slug(cow!(slug))).await?,
+            };
+
+            match user {
+                Some(user) => {
+                    users.insert(user_id, user.user_id);
+                }
+                None => {
+                    debug!("No user matches archived user '{slug}' (ID {user_id})");
+                    unmapped_users.push(slug);
+                }
+            }
+        }
+
+        let author = |user_id| users.get(&user_id).copied().unwrap_or(SYSTEM_USER_ID);
+
+        // Create site
+        let site = manifest.site;
+        let site_id = ImportService::add_site(
+            ctx,
+            ImportSite {
+                site_id: None,
+                created_at: site.created_at,
+                updated_at: site.updated_at,
+                from_wikidot: site.from_wikidot,
+                name: import.name.clone().unwrap_or(site.name),
+                slug: slug.clone(),
+                tagline: site.tagline,
+                description: site.description,
+                default_page: Some(site.default_page),
+                layout: site.layout,
+                locale: site.locale,
+                default_license: site.default_license,
+            },
+        )
+        .await?;
+
+        // Pages are matched to their categories by slug,
+        // so those don't need to be remapped.
+        for category in manifest.categories {
+            check_category(&category)?;
+            ImportService::add_category(
+                ctx,
+                ImportCategory {
+                    site_id,
+                    created_at: category.created_at,
+                    deleted_at: category.deleted_at,
+                    slug: category.slug,
+                    layout: category.layout,
+                    default_license: category.default_license,
+                    scorer: category.scorer,
+                    template_slug: category.template_slug,
+                    use_templates: category.use_templates,
+                    permissions: category.permissions,
+                },
+            )
+            .await?;
+        }
+
+        let mut pages = HashMap::new();
+        for page in &manifest.pages {
+            let page_id = ImportService::add_page(
+                ctx,
+                ImportPage {
+                    page_id: None,
+                    site_id,
+                    created_at: page.created_at,
+                    updated_at: page.updated_at,
+                    deleted_at: page.deleted_at,
+                    from_wikidot: page.from_wikidot,
+                    slug: page.slug.clone(),
+                    layout: page.layout.clone(),
+                    locked: false,
+                    discussion_thread_id: None,
+                },
+            )
+            .await?;
+
+            pages.insert(page.page_id, page_id);
+        }
+
+        let page_id = |page_id| {
+            pages
+                .get(&page_id)
+                .copied()
+                .ok_or(Error::SiteArchiveInvalid("page_id"))
+        };
+
+        // Revisions must be added in order, see ImportService::add_page_revision()
+        manifest
+            .page_revisions
+            .sort_by_key(|revision| (revision.page_id, revision.revision_number));
+
+        for revision in manifest.page_revisions {
+            let wikitext_hash = text_hash(&revision.wikitext_hash, &text_hashes)?;
+            let compiled_hash = text_hash(&revision.compiled_hash, &text_hashes)?;
+
+            ImportService::add_page_revision(
+                ctx,
+                ImportPageRevision {
+                    page_id: page_id(revision.page_id)?,
+                    site_id,
+                    user_id: author(revision.user_id),
+                    created_at: revision.created_at,
+                    updated_at: revision.updated_at,
+                    from_wikidot: revision.from_wikidot,
+                    revision_type: revision.revision_type,
+                    revision_number: revision.revision_number,
+                    changes: revision.changes,
+                    wikitext_hash,
+                    compiled_hash,
+                    compiled_at: revision.compiled_at,
+                    compiled_generator: revision.compiled_generator,
+                    comments: revision.comments,
+                    hidden: revision.hidden,
+                    title: revision.title,
+                    alt_title: revision.alt_title,
+                    slug: revision.slug,
+                    tags: revision.tags,
+                },
+            )
+            .await?;
+        }
+
+        for parent in manifest.page_parents {
+            ImportService::add_page_parent(
+                ctx,
+                ImportPageParent {
+                    parent_page_id: page_id(parent.parent_page_id)?,
+                    child_page_id: page_id(parent.child_page_id)?,
+                    created_at: parent.created_at,
+                },
+            )
+            .await?;
+        }
+
+        // Votes and attributions only make sense for actual users,
+        // so those for unmatched users are dropped.
+        for vote in manifest.page_votes {
+            let user_id = match users.get(&vote.user_id) {
+                Some(&user_id) => user_id,
+                None => continue,
+            };
+
+            ImportService::add_page_vote(
+                ctx,
+                ImportPageVote {
+                    page_id: page_id(vote.page_id)?,
+                    user_id,
+                    created_at: vote.created_at,
+                    deleted_at: vote.deleted_at,
+                    disabled_at: vote.disabled_at,
+                    disabled_by: vote.disabled_by.map(author),
+                    from_wikidot: vote.from_wikidot,
+                    value: vote.value,
+                },
+            )
+            .await?;
+        }
+
+        for attribution in manifest.page_attributions {
+            let user_id = match users.get(&attribution.user_id) {
+                Some(&user_id) => user_id,
+                None => continue,
+            };
+
+            ImportService::add_page_attribution(
+                ctx,
+                ImportPageAttribution {
+                    page_id: page_id(attribution.page_id)?,
+                    user_id,
+                    attribution_type: attribution.attribution_type,
+                    attribution_date: attribution.attribution_date,
+                    created_at: attribution.created_at,
+                },
+            )
+            .await?;
+        }
+
+        // Files
+        let mut files = HashMap::new();
+        for file in &manifest.files {
+            let file_id = ImportService::add_file(
+                ctx,
+                ImportFile {
+                    file_id: None,
+                    page_id: page_id(file.page_id)?,
+                    site_id,
+                    created_at: file.created_at,
+                    updated_at: file.updated_at,
+                    deleted_at: file.deleted_at,
+                    from_wikidot: file.from_wikidot,
+                    name: file.name.clone(),
+                },
+            )
+            .await?;
+
+            files.insert(file.file_id, file_id);
+        }
+
+        manifest
+            .file_revisions
+            .sort_by_key(|revision| (revision.file_id, revision.revision_number));
+
+        for revision in manifest.file_revisions {
+            let s3_hash = BlobHash::try_from(revision.s3_hash.as_slice())
+                .map_err(|_| Error::SiteArchiveInvalid("s3_hash"))?;
+
+            if !blob_hashes.contains(&s3_hash) {
+                error!(
+                    "Site archive is missing blob {}",
+                    blob_hash_to_hex(&s3_hash),
+                );
+                return Err(Error::SiteArchiveInvalid("blob"));
+            }
+
+            let file_id = files
+                .get(&revision.file_id)
+                .copied()
+                .ok_or(Error::SiteArchiveInvalid("file_id"))?;
+
+            ImportService::add_file_revision(
+                ctx,
+                ImportFileRevision {
+                    file_id,
+                    page_id: page_id(revision.page_id)?,
+                    site_id,
+                    user_id: author(revision.user_id),
+                    created_at: revision.created_at,
+                    revision_type: revision.revision_type,
+                    revision_number: revision.revision_number,
+                    name: revision.name,
+                    s3_hash,
+                    mime: revision.mime,
+                    size: revision.size,
+                    licensing: revision.licensing,
+                    changes: revision.changes,
+                    comments: revision.comments,
+                    hidden: revision.hidden,
+                },
+            )
+            .await?;
+        }
+
+        // Site aliases are global, so the old ones may still be in use
+        let mut skipped_aliases = Vec::new();
+        for alias in manifest.aliases {
+            if AliasService::exists(ctx, AliasType::Site, &alias.slug).await?
+                || SiteService::exists(ctx, Reference::Slug(cow!(alias.slug))).await?
+            {
+                debug!("Skipping site alias '{}', slug is taken", alias.slug);
+                skipped_aliases.push(alias.slug);
+                continue;
+            }
+
+            AliasService::create(
+                ctx,
+                CreateAlias {
+                    slug: alias.slug,
+                    alias_type: AliasType::Site,
+                    target_id: site_id,
+                    created_by: author(alias.created_by),
+                    bypass_filter: true,
+                },
+            )
+            .await?;
+        }
+
+        // Rerender pages, which also fills in links and other derived data
+        for page in &manifest.pages {
+            if page.deleted_at.is_none() {
+                let job = Job::RerenderPage {
+                    site_id,
+                    page_id: page_id(page.page_id)?,
+                    depth: 0,
+                };
+
+                JobService::queue_job(ctx, &job, None).await?;
+            }
+        }
+
+        info!(
+            "Imported site archive as site ID {site_id} ({} pages, {} files, {} unmapped users)",
+            manifest.pages.len(),
+            manifest.files.len(),
+            unmapped_users.len(),
+        );
+
+        Ok(ImportStep::Created {
+            site_id,
+            unmapped_users,
+            skipped_aliases,
+        })
+    }
+}
+
+/// The result of a single step of a site import.
+#[derive(Debug)]
+enum ImportStep {
+    /// A batch of entries was stored.
+    Extracted { position: i32, finished: bool },
+
+    /// The site was created, and the import is complete.
+    Created {
+        site_id: i64,
+        unmapped_users: Vec<String>,
+        skipped_aliases: Vec<String>,
+    },
+}
+
+/// Subquery for the IDs of all pages in a site, including deleted ones.
+fn site_pages(site_id: i64) -> SelectStatement {
+    Query::select()
+        .column(page::Column::PageId)
+        .from(page::Entity)
+        .and_where(page::Column::SiteId.eq(site_id))
+        .to_owned()
+}
+
+/// The limits on what a site archive may contain.
+#[derive(Debug, Copy, Clone)]
+struct ArchiveLimits {
+    maximum_entry_size: u64,
+    maximum_size: u64,
+    maximum_entries: usize,
+}
+
+impl ArchiveLimits {
+    fn new(config: &Config) -> Self {
+        ArchiveLimits {
+            maximum_entry_size: u64::try_from(config.maximum_blob_size).unwrap_or(0),
+            maximum_size: config.site_maximum_archive_size,
+            maximum_entries: config.site_maximum_archive_entries as usize,
+        }
+    }
+}
+
+/// Writer which hashes and counts everything written through it.
+///
+/// This is used to get the blob hash of an archive as it is written.
+struct HashWriter<W> {
+    inner: W,
+    hasher: Sha512,
+    size: u64,
+}
+
+impl<W: Write> HashWriter<W> {
+    fn new(inner: W) -> Self {
+        HashWriter {
+            inner,
+            hasher: Sha512::new(),
+            size: 0,
+        }
+    }
+
+    /// Flushes the inner writer, returning the hash and size of what was written.
+    fn finish(mut self) -> io::Result<(BlobHash, i64)> {
+        self.inner.flush()?;
+        let hash = slice_to_blob_hash(&self.hasher.finalize());
+        let size = i64::try_from(self.size).expect("Archive size exceeds i64");
+        Ok((hash, size))
+    }
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Appends a regular file to the archive being built.
+fn add_entry<W: Write>(
+    builder: &mut Builder<W>,
+    path: &str,
+    data: &[u8],
+    mtime: u64,
+) -> io::Result<()> {
+    let mut header = Header::new_gnu();
+    header.set_entry_type(EntryType::Regular);
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    builder.append_data(&mut header, path, data)
+}
+
+/// Goes through the regular files in a site archive, enforcing the configured limits.
+///
+/// Each file is passed to `visit` with its index in the archive and its path.
+/// Its contents are only read if `visit` reads them, and iteration stops
+/// early if it returns `false`.
+fn read_archive<F>(data: &[u8], limits: ArchiveLimits, mut visit: F) -> Result<()>
+where
+    F: FnMut(usize, &str, &mut dyn Read) -> Result<bool>,
+{
+    let ArchiveLimits {
+        maximum_entry_size,
+        maximum_size,
+        maximum_entries,
+    } = limits;
+    let mut total_size = 0;
+
+    let mut archive = Archive::new(GzDecoder::new(data));
+    for (index, entry) in archive.entries().map_err(archive_error)?.enumerate() {
+        let mut entry = entry.map_err(archive_error)?;
+
+        if index >= maximum_entries {
+            error!("Site archive has more than {maximum_entries} entries");
+            return Err(Error::SiteArchiveInvalid("entries"));
+        }
+
+        let size = entry.size();
+        total_size += size;
+        if size > maximum_entry_size || total_size > maximum_size {
+            error!("Site archive exceeds the maximum size (entry {index})");
+            return Err(Error::SiteArchiveInvalid("size"));
+        }
+
+        // Skip directories and anything else
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path().map_err(archive_error)?;
+        let path = path
+            .to_str()
+            .ok_or(Error::SiteArchiveInvalid("path"))?
+            .to_owned();
+
+        if !visit(index, &path, &mut entry)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads and parses the manifest, which must be the first file in the archive.
+fn read_manifest(data: &[u8], limits: ArchiveLimits) -> Result<SiteArchive> {
+    let mut manifest = None;
+    read_archive(data, limits, |_, path, entry| {
+        if path != MANIFEST_PATH {
+            error!("Site archive does not begin with {MANIFEST_PATH}");
+            return Err(Error::SiteArchiveInvalid("manifest"));
+        }
+
+        let data = read_entry(entry)?;
+        let SiteArchiveVersion { version } = serde_json::from_slice(&data)
+            .map_err(|_| Error::SiteArchiveInvalid("manifest"))?;
+
+        if version != ARCHIVE_VERSION {
+            error!(
+                "Site archive has version {version}, only {ARCHIVE_VERSION} is supported",
+            );
+            return Err(Error::SiteArchiveInvalid("version"));
+        }
+
+        let parsed = serde_json::from_slice(&data).map_err(|error| {
+            error!("Unable to parse site archive manifest: {error}");
+            Error::SiteArchiveInvalid("manifest")
+        })?;
+
+        manifest = Some(parsed);
+        Ok(false)
+    })?;
+
+    manifest.ok_or(Error::SiteArchiveInvalid("manifest"))
+}
+
+fn read_entry(entry: &mut dyn Read) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data).map_err(archive_error)?;
+    Ok(data)
+}
+
+/// Checks that a category's settings from the manifest are valid.
+fn check_category(category: &PageCategoryModel) -> Result<()> {
+    let valid = || -> Option<()> {
+        if let Some(ref value) = category.default_license {
+            let mut license: License = serde_json::from_value(value.clone()).ok()?;
+            license.validate().ok()?;
+        }
+
+        if let Some(ref scorer) = category.scorer {
+            scorer.parse::<ScoreType>().ok()?;
+        }
+
+        if let Some(ref value) = category.permissions {
+            serde_json::from_value::<CategoryPermissions>(value.clone()).ok()?;
+        }
+
+        Some(())
+    };
+
+    match valid() {
+        Some(()) => Ok(()),
+        None => {
+            error!(
+                "Site archive has invalid settings for category '{}'",
+                category.slug
+            );
+            Err(Error::SiteArchiveInvalid("categories"))
+        }
+    }
+}
+
+fn archive_error(error: io::Error) -> Error {
+    error!("Unable to read site archive: {error}");
+    Error::SiteArchiveInvalid("archive")
+}
+
+/// Converts a text hash from the manifest, checking that its text was in the archive.
+fn text_hash(hash: &[u8], text_hashes: &HashSet<TextHash>) -> Result<TextHash> {
+    let hash =
+        TextHash::try_from(hash).map_err(|_| Error::SiteArchiveInvalid("text_hash"))?;
+
+    if !text_hashes.contains(&hash) {
+        error!("Site archive is missing text {}", hex::encode(hash));
+        return Err(Error::SiteArchiveInvalid("text"));
+    }
+
+    Ok(hash)
+}
+
+#[test]
+fn archive_entries() {
+    let long_path = format!("{BLOB_DIRECTORY}{}", "ab".repeat(64));
+    let files: [(&str, &[u8]); 4] = [
+        (MANIFEST_PATH, b"{}"),
+        ("text/00112233", b"apple banana"),
+        (&long_path, &[0xff; 1000]),
+        ("empty", b""),
+    ];
+
+    let mut builder = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (path, data) in files {
+        add_entry(&mut builder, path, data, 1_700_000_000).unwrap();
+    }
+    let archive = builder.into_inner().unwrap().finish().unwrap();
+
+    let limits = ArchiveLimits {
+        maximum_entry_size: 4096,
+        maximum_size: 8192,
+        maximum_entries: 10,
+    };
+
+    // Round trip, including a path too long for the basic header
+    let mut entries = Vec::new();
+    read_archive(&archive, limits, |index, path, entry| {
+        entries.push((index, str!(path), read_entry(entry)?));
+        Ok(true)
+    })
+    .expect("Unable to read archive");
+
+    let expected = files
+        .iter()
+        .enumerate()
+        .map(|(index, (path, data))| (index, str!(path), data.to_vec()))
+        .collect::<Vec<_>>();
+    assert_eq!(entries, expected);
+
+    // Stopping early, without reading the contents
+    let mut paths = Vec::new();
+    read_archive(&archive, limits, |_, path, _| {
+        paths.push(str!(path));
+        Ok(paths.len() < 2)
+    })
+    .expect("Unable to read archive");
+    assert_eq!(paths, [MANIFEST_PATH, "text/00112233"]);
+
+    // Limits are enforced even for entries which aren't read
+    let check = |limits, expected| {
+        let result = read_archive(&archive, limits, |_, _, _| Ok(true));
+        assert!(
+            matches!(result, Err(Error::SiteArchiveInvalid(part)) if part == expected),
+            "Limits not enforced: {result:?}",
+        );
+    };
+
+    check(
+        ArchiveLimits {
+            maximum_entries: 3,
+            ..limits
+        },
+        "entries",
+    );
+    check(
+        ArchiveLimits {
+            maximum_entry_size: 999,
+            ..limits
+        },
+        "size",
+    );
+    check(
+        ArchiveLimits {
+            maximum_size: 1010,
+            ..limits
+        },
+        "size",
+    );
+
+    // Corrupted archives are rejected
+    let mut corrupted = archive.clone();
+    corrupted.truncate(corrupted.len() / 2);
+    assert!(read_archive(&corrupted, limits, |_, _, _| Ok(true)).is_err());
+}
+
+#[test]
+fn hash_writer() {
+    let data = b"apple banana cherry";
+    let mut writer = HashWriter::new(Vec::new());
+    writer.write_all(&data[..5]).unwrap();
+    writer.write_all(&data[5..]).unwrap();
+
+    let (hash, size) = writer.finish().unwrap();
+    assert_eq!(hash, sha512_hash(data));
+    assert_eq!(size, data.len() as i64);
+}
@@ -0,0 +1,108 @@
+/*
+ * services/site_archive/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::alias::Model as AliasModel;
+use crate::models::file::Model as FileModel;
+use crate::models::file_revision::Model as FileRevisionModel;
+use crate::models::page::Model as PageModel;
+use crate::models::page_attribution::Model as PageAttributionModel;
+use crate::models::page_category::Model as PageCategoryModel;
+use crate::models::page_parent::Model as PageParentModel;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::page_vote::Model as PageVoteModel;
+use crate::models::site::Model as SiteModel;
+use crate::models::site_export::Model as SiteExportModel;
+use crate::types::Bytes;
+use time::OffsetDateTime;
+
+/// The current version of the site archive format.
+///
+/// This must be bumped whenever the manifest changes in an incompatible way.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SiteExport {
+    pub site_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSiteExport {
+    pub export_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SiteExportOutput {
+    pub export: SiteExportModel,
+
+    /// A presigned URL to download the archive from, once it is complete.
+    pub url: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SiteImport {
+    /// The hash of the archive, which must already be uploaded as a blob.
+    pub s3_hash: Bytes<'static>,
+    pub slug: String,
+
+    /// The name of the new site, or the archived name if not given.
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSiteImport {
+    pub import_id: i64,
+}
+
+/// The manifest of a site archive, stored as `site.json`.
+///
+/// All IDs are those of the exporting instance, and are remapped on import.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SiteArchive {
+    pub version: u32,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    pub site: SiteModel,
+    pub users: Vec<ArchiveUser>,
+    pub aliases: Vec<AliasModel>,
+    pub categories: Vec<PageCategoryModel>,
+    pub pages: Vec<PageModel>,
+    pub page_revisions: Vec<PageRevisionModel>,
+    pub page_parents: Vec<PageParentModel>,
+    pub page_votes: Vec<PageVoteModel>,
+    pub page_attributions: Vec<PageAttributionModel>,
+    pub files: Vec<FileModel>,
+    pub file_revisions: Vec<FileRevisionModel>,
+}
+
+/// Used to check the version before parsing the rest of the manifest.
+#[derive(Deserialize, Debug)]
+pub struct SiteArchiveVersion {
+    pub version: u32,
+}
+
+/// A user referenced by the archive.
+///
+/// On import, users are matched by ID and slug, or else by slug alone.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArchiveUser {
+    pub user_id: i64,
+    pub slug: String,
+}
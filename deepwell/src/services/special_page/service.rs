@@ -19,8 +19,11 @@
  */
 
 use super::prelude::*;
+use crate::models::page_category::Model as PageCategoryModel;
 use crate::models::site::Model as SiteModel;
-use crate::services::{PageRevisionService, PageService, RenderService, TextService};
+use crate::services::{
+    CategoryService, PageRevisionService, PageService, RenderService, TextService,
+};
 use crate::types::Reference;
 use crate::utils::split_category;
 use fluent::{FluentArgs, FluentValue};
@@ -58,7 +61,16 @@ impl SpecialPageService {
         let (slugs, translate_key) = match sp_page_type {
             // TODO: Figure out exact template ordering (e.g. _template vs cat:_template)
             //       See https://scuttle.atlassian.net/browse/WJ-1201
-            SpecialPageType::Template => (vec![cow!(config.special_page_template)], ""),
+            SpecialPageType::Template => {
+                let slugs = Self::template_slugs(
+                    ctx,
+                    site.site_id,
+                    page_info.category.ref_map(|s| s.as_ref()),
+                )
+                .await?;
+
+                (slugs, "")
+            }
             SpecialPageType::Missing => {
                 let slugs = Self::slugs_with_category(
                     &config.special_page_missing,
@@ -100,6 +112,31 @@ impl SpecialPageService {
         })
     }
 
+    /// Gets the template page slugs for a category, based on its settings.
+    ///
+    /// If the category has disabled templates, then this list is empty.
+    async fn template_slugs(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_category: Option<&str>,
+    ) -> Result<Vec<Cow<'static, str>>> {
+        let category_slug = page_category.unwrap_or("_default");
+        let category =
+            CategoryService::get_optional(ctx, site_id, Reference::from(category_slug))
+                .await?;
+
+        let slugs = match category {
+            Some(category) if !category.use_templates => vec![],
+            Some(PageCategoryModel {
+                template_slug: Some(slug),
+                ..
+            }) => vec![Cow::Owned(slug)],
+            _ => vec![Cow::Owned(ctx.config().special_page_template.clone())],
+        };
+
+        Ok(slugs)
+    }
+
     fn slugs_with_category<'a>(
         base_slug: &'a str,
         page_category: Option<&'a str>,
@@ -0,0 +1,40 @@
+/*
+ * services/tag/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The tag service, for governing which tags pages in a site may use.
+//!
+//! Sites may define a list of tags, each with a description and optionally
+//! a group. Once any tags are defined, page edits may only use those tags,
+//! and each group's minimum and maximum tag counts are enforced.
+//!
+//! Tags can also be renamed or merged across a whole site, which is done
+//! in a job that creates a new revision for each affected page.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::TagService;
+pub use self::structs::*;
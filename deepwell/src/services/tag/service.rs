@@ -0,0 +1,664 @@
+/*
+ * services/tag/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::site_tag::{self, Entity as SiteTag, Model as SiteTagModel};
+use crate::models::site_tag_group::{
+    self, Entity as SiteTagGroup, Model as SiteTagGroupModel,
+};
+use crate::services::job::{Job, JobService};
+use crate::services::page::{EditPage, EditPageBody};
+use crate::services::PageService;
+use sea_orm::{DatabaseBackend, FromQueryResult, Statement, Value};
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug)]
+pub struct TagService;
+
+impl TagService {
+    // Tags
+
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateSiteTag {
+            site_id,
+            name,
+            tag_group_id,
+            description,
+        }: CreateSiteTag,
+    ) -> Result<SiteTagModel> {
+        let txn = ctx.transaction();
+        let name = str!(name.trim());
+        info!("Creating tag '{name}' in site ID {site_id}");
+
+        if Self::get_optional(ctx, site_id, &name).await?.is_some() {
+            error!("Tag '{name}' already exists in site ID {site_id}");
+            return Err(Error::SiteTagExists);
+        }
+
+        if let Some(tag_group_id) = tag_group_id {
+            Self::get_group(ctx, site_id, tag_group_id).await?;
+        }
+
+        let model = site_tag::ActiveModel {
+            site_id: Set(site_id),
+            name: Set(name),
+            description: Set(description),
+            tag_group_id: Set(tag_group_id),
+            ..Default::default()
+        };
+
+        let tag = model.insert(txn).await?;
+        Ok(tag)
+    }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_id: i64,
+        input: UpdateSiteTagBody,
+    ) -> Result<SiteTagModel> {
+        let txn = ctx.transaction();
+        Self::get(ctx, site_id, tag_id).await?;
+
+        let mut model = site_tag::ActiveModel {
+            tag_id: Set(tag_id),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        if let Maybe::Set(description) = input.description {
+            model.description = Set(description);
+        }
+
+        if let Maybe::Set(tag_group_id) = input.tag_group_id {
+            if let Some(tag_group_id) = tag_group_id {
+                Self::get_group(ctx, site_id, tag_group_id).await?;
+            }
+
+            model.tag_group_id = Set(tag_group_id);
+        }
+
+        let tag = model.update(txn).await?;
+        Ok(tag)
+    }
+
+    /// Removes a tag definition.
+    ///
+    /// Pages which already have this tag keep it, but it can
+    /// no longer be added to pages if the site has other tags defined.
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_id: i64,
+    ) -> Result<SiteTagModel> {
+        let txn = ctx.transaction();
+        let tag = Self::get(ctx, site_id, tag_id).await?;
+        SiteTag::delete_by_id(tag_id).exec(txn).await?;
+        Ok(tag)
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        name: &str,
+    ) -> Result<Option<SiteTagModel>> {
+        let txn = ctx.transaction();
+        let tag = SiteTag::find()
+            .filter(
+                Condition::all()
+                    .add(site_tag::Column::SiteId.eq(site_id))
+                    .add(site_tag::Column::Name.eq(name)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(tag)
+    }
+
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_id: i64,
+    ) -> Result<SiteTagModel> {
+        let txn = ctx.transaction();
+        let tag = SiteTag::find()
+            .filter(
+                Condition::all()
+                    .add(site_tag::Column::SiteId.eq(site_id))
+                    .add(site_tag::Column::TagId.eq(tag_id)),
+            )
+            .one(txn)
+            .await?;
+
+        tag.ok_or(Error::SiteTagNotFound)
+    }
+
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<SiteTagModel>> {
+        let txn = ctx.transaction();
+        let tags = SiteTag::find()
+            .filter(site_tag::Column::SiteId.eq(site_id))
+            .order_by_asc(site_tag::Column::Name)
+            .all(txn)
+            .await?;
+
+        Ok(tags)
+    }
+
+    // Tag groups
+
+    pub async fn create_group(
+        ctx: &ServiceContext<'_>,
+        CreateSiteTagGroup {
+            site_id,
+            name,
+            max_tags,
+            description,
+            min_tags,
+        }: CreateSiteTagGroup,
+    ) -> Result<SiteTagGroupModel> {
+        let txn = ctx.transaction();
+        info!("Creating tag group '{name}' in site ID {site_id}");
+        check_group_limits(min_tags, max_tags)?;
+
+        let existing = SiteTagGroup::find()
+            .filter(
+                Condition::all()
+                    .add(site_tag_group::Column::SiteId.eq(site_id))
+                    .add(site_tag_group::Column::Name.eq(name.as_str())),
+            )
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            error!("Tag group '{name}' already exists in site ID {site_id}");
+            return Err(Error::SiteTagGroupExists);
+        }
+
+        let model = site_tag_group::ActiveModel {
+            site_id: Set(site_id),
+            name: Set(name),
+            description: Set(description),
+            min_tags: Set(min_tags),
+            max_tags: Set(max_tags),
+            ..Default::default()
+        };
+
+        let group = model.insert(txn).await?;
+        Ok(group)
+    }
+
+    pub async fn update_group(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_group_id: i64,
+        input: UpdateSiteTagGroupBody,
+    ) -> Result<SiteTagGroupModel> {
+        let txn = ctx.transaction();
+        let group = Self::get_group(ctx, site_id, tag_group_id).await?;
+
+        let mut model = site_tag_group::ActiveModel {
+            tag_group_id: Set(tag_group_id),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        if let Maybe::Set(name) = input.name {
+            model.name = Set(name);
+        }
+
+        if let Maybe::Set(description) = input.description {
+            model.description = Set(description);
+        }
+
+        // Limits are checked together, since they depend on each other
+        let min_tags = match input.min_tags {
+            Maybe::Set(min_tags) => {
+                model.min_tags = Set(min_tags);
+                min_tags
+            }
+            Maybe::Unset => group.min_tags,
+        };
+
+        let max_tags = match input.max_tags {
+            Maybe::Set(max_tags) => {
+                model.max_tags = Set(max_tags);
+                max_tags
+            }
+            Maybe::Unset => group.max_tags,
+        };
+
+        check_group_limits(min_tags, max_tags)?;
+
+        let group = model.update(txn).await?;
+        Ok(group)
+    }
+
+    /// Removes a tag group.
+    ///
+    /// The tags in the group are kept, but no longer belong to any group.
+    pub async fn delete_group(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_group_id: i64,
+    ) -> Result<SiteTagGroupModel> {
+        let txn = ctx.transaction();
+        let group = Self::get_group(ctx, site_id, tag_group_id).await?;
+
+        SiteTag::update_many()
+            .col_expr(
+                site_tag::Column::TagGroupId,
+                sea_orm::sea_query::Expr::value(Option::<i64>::None),
+            )
+            .filter(site_tag::Column::TagGroupId.eq(tag_group_id))
+            .exec(txn)
+            .await?;
+
+        SiteTagGroup::delete_by_id(tag_group_id).exec(txn).await?;
+        Ok(group)
+    }
+
+    pub async fn get_group(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_group_id: i64,
+    ) -> Result<SiteTagGroupModel> {
+        let txn = ctx.transaction();
+        let group = SiteTagGroup::find()
+            .filter(
+                Condition::all()
+                    .add(site_tag_group::Column::SiteId.eq(site_id))
+                    .add(site_tag_group::Column::TagGroupId.eq(tag_group_id)),
+            )
+            .one(txn)
+            .await?;
+
+        group.ok_or(Error::SiteTagGroupNotFound)
+    }
+
+    pub async fn get_all_groups(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<SiteTagGroupModel>> {
+        let txn = ctx.transaction();
+        let groups = SiteTagGroup::find()
+            .filter(site_tag_group::Column::SiteId.eq(site_id))
+            .order_by_asc(site_tag_group::Column::Name)
+            .all(txn)
+            .await?;
+
+        Ok(groups)
+    }
+
+    // Validation
+
+    /// Checks that a page's tags are allowed by the site's tag rules.
+    ///
+    /// If the site has no tags defined, then any tags are permitted.
+    pub async fn validate_page_tags(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tags: &[String],
+    ) -> Result<()> {
+        let defined = Self::get_all(ctx, site_id).await?;
+        if defined.is_empty() {
+            debug!("No tags defined for site ID {site_id}, allowing any");
+            return Ok(());
+        }
+
+        let groups = Self::get_all_groups(ctx, site_id).await?;
+        let violations = check_tags(tags, &defined, &groups);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            warn!(
+                "Page tags violate {} rules for site ID {site_id}",
+                violations.len(),
+            );
+            Err(Error::PageTagsInvalid(violations))
+        }
+    }
+
+    // Usage
+
+    /// Counts how many pages use each tag in a site.
+    ///
+    /// Only the latest revision of non-deleted pages is considered.
+    pub async fn get_usage(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<TagUsage>> {
+        let txn = ctx.transaction();
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                SELECT tag, COUNT(*) AS count
+                FROM page
+                JOIN page_revision
+                    ON page.latest_revision_id = page_revision.revision_id
+                CROSS JOIN LATERAL unnest(page_revision.tags) AS tag
+                WHERE page.site_id = $1
+                    AND page.deleted_at IS NULL
+                GROUP BY tag
+                ORDER BY count DESC, tag
+                ",
+            ),
+            [Value::from(site_id)],
+        );
+
+        let usage = TagUsage::find_by_statement(query).all(txn).await?;
+        Ok(usage)
+    }
+
+    // Renaming
+
+    /// Renames or merges a tag, by queueing a job to update the pages using it.
+    ///
+    /// If the new tag is already defined, the old definition is removed (a merge).
+    /// Otherwise the old definition, if any, is renamed. Tag definitions are only
+    /// changed once every page has been updated, see `rename_in_pages()`.
+    pub async fn rename(
+        ctx: &ServiceContext<'_>,
+        mut input: RenameSiteTag,
+    ) -> Result<RenameSiteTagOutput> {
+        let site_id = input.site_id;
+        input.from = str!(input.from.trim());
+        input.to = str!(input.to.trim());
+        info!(
+            "Renaming tag '{}' to '{}' in site ID {site_id}",
+            input.from, input.to,
+        );
+
+        if input.from == input.to {
+            error!("Source and destination tags are the same: {}", input.to);
+            return Err(Error::SiteTagExists);
+        }
+
+        let merged = Self::get_optional(ctx, site_id, &input.to).await?.is_some();
+        let job = Job::RenameTag {
+            input,
+            after_page_id: 0,
+        };
+
+        JobService::queue_job(ctx, &job, None).await?;
+        Ok(RenameSiteTagOutput { merged })
+    }
+
+    /// Replaces a tag on the next batch of pages in a site, creating a revision for each.
+    ///
+    /// Pages are processed in order of ID, starting after `after_page_id`.
+    /// Returns the last page ID updated if there are more pages left to go.
+    /// After the last batch, the tag definitions are renamed or merged.
+    ///
+    /// Run from a job, see `rename()`.
+    pub async fn rename_in_pages(
+        ctx: &ServiceContext<'_>,
+        RenameSiteTag {
+            site_id,
+            from,
+            to,
+            user_id,
+        }: RenameSiteTag,
+        after_page_id: i64,
+    ) -> Result<Option<i64>> {
+        #[derive(FromQueryResult, Debug)]
+        struct TaggedPage {
+            page_id: i64,
+            revision_id: i64,
+            tags: Vec<String>,
+        }
+
+        let txn = ctx.transaction();
+        let batch_size = ctx.config().job_tag_rename_batch_size.get();
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                SELECT page.page_id, page_revision.revision_id, page_revision.tags
+                FROM page
+                JOIN page_revision
+                    ON page.latest_revision_id = page_revision.revision_id
+                WHERE page.site_id = $1
+                    AND page.deleted_at IS NULL
+                    AND page.page_id > $3
+                    AND $2 = ANY(page_revision.tags)
+                ORDER BY page.page_id
+                LIMIT $4
+                ",
+            ),
+            [
+                Value::from(site_id),
+                Value::from(from.as_str()),
+                Value::from(after_page_id),
+                Value::from(i64::from(batch_size) + 1),
+            ],
+        );
+
+        // One extra page is fetched to know whether there is another batch
+        let mut pages = TaggedPage::find_by_statement(query).all(txn).await?;
+        let more = pages.len() > usize::from(batch_size);
+        pages.truncate(usize::from(batch_size));
+        info!(
+            "Renaming tag '{from}' to '{to}' on {} pages in site ID {site_id}",
+            pages.len(),
+        );
+
+        for TaggedPage {
+            page_id,
+            revision_id,
+            tags,
+        } in &pages
+        {
+            PageService::edit(
+                ctx,
+                EditPage {
+                    site_id,
+                    page: Reference::Id(*page_id),
+                    last_revision_id: *revision_id,
+                    revision_comments: format!(
+                        "Renaming tag '{from}' → '{to}' (requested by user ID {user_id})",
+                    ),
+                    user_id: SYSTEM_USER_ID,
+                    body: EditPageBody {
+                        tags: Maybe::Set(replace_tag(tags, &from, &to)),
+                        ..Default::default()
+                    },
+                    bypass_filter: true,
+                    bypass_tag_check: true,
+                },
+            )
+            .await?;
+        }
+
+        if more {
+            return Ok(pages.last().map(|page| page.page_id));
+        }
+
+        // All pages are updated, now switch over the definitions
+        let from_tag = Self::get_optional(ctx, site_id, &from).await?;
+        let to_tag = Self::get_optional(ctx, site_id, &to).await?;
+        if let Some(from_tag) = from_tag {
+            if to_tag.is_some() {
+                debug!("Merging tag definition '{from}' into '{to}'");
+                SiteTag::delete_by_id(from_tag.tag_id).exec(txn).await?;
+            } else {
+                debug!("Renaming tag definition '{from}' to '{to}'");
+                let model = site_tag::ActiveModel {
+                    tag_id: Set(from_tag.tag_id),
+                    name: Set(to),
+                    updated_at: Set(Some(now())),
+                    ..Default::default()
+                };
+
+                model.update(txn).await?;
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+fn check_group_limits(min_tags: i32, max_tags: Option<i32>) -> Result<()> {
+    let valid = match max_tags {
+        Some(max_tags) => min_tags >= 0 && max_tags >= min_tags,
+        None => min_tags >= 0,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        error!("Invalid tag group limits: min {min_tags}, max {max_tags:?}");
+        Err(Error::SiteTagGroupLimitsInvalid)
+    }
+}
+
+/// Checks a list of tags against a site's tag definitions and groups.
+fn check_tags(
+    tags: &[String],
+    defined: &[SiteTagModel],
+    groups: &[SiteTagGroupModel],
+) -> Vec<TagViolation> {
+    let defined = defined
+        .iter()
+        .map(|tag| (tag.name.as_str(), tag))
+        .collect::<HashMap<_, _>>();
+
+    let mut violations = Vec::new();
+    let mut group_counts = HashMap::new();
+
+    for tag in tags.iter().collect::<BTreeSet<_>>() {
+        match defined.get(tag.as_str()) {
+            None => violations.push(TagViolation::Unknown { tag: tag.clone() }),
+            Some(SiteTagModel {
+                tag_group_id: Some(tag_group_id),
+                ..
+            }) => *group_counts.entry(*tag_group_id).or_insert(0) += 1,
+            Some(_) => (),
+        }
+    }
+
+    for group in groups {
+        let count = group_counts.get(&group.tag_group_id).copied().unwrap_or(0);
+        let too_many = matches!(group.max_tags, Some(max_tags) if count > max_tags);
+        if count < group.min_tags || too_many {
+            violations.push(TagViolation::GroupLimit {
+                group: group.name.clone(),
+                count,
+                min_tags: group.min_tags,
+                max_tags: group.max_tags,
+            });
+        }
+    }
+
+    violations
+}
+
+/// Replaces one tag with another, without introducing duplicates.
+fn replace_tag(tags: &[String], from: &str, to: &str) -> Vec<String> {
+    let mut new_tags = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = if tag == from { to } else { tag };
+        if !new_tags.iter().any(|t| t == tag) {
+            new_tags.push(str!(tag));
+        }
+    }
+
+    new_tags
+}
+
+#[test]
+fn tag_rules() {
+    fn tag(tag_id: i64, name: &str, tag_group_id: Option<i64>) -> SiteTagModel {
+        SiteTagModel {
+            tag_id,
+            created_at: now(),
+            updated_at: None,
+            site_id: 1,
+            name: str!(name),
+            description: String::new(),
+            tag_group_id,
+        }
+    }
+
+    fn group(tag_group_id: i64, name: &str, max_tags: Option<i32>) -> SiteTagGroupModel {
+        SiteTagGroupModel {
+            tag_group_id,
+            created_at: now(),
+            updated_at: None,
+            site_id: 1,
+            name: str!(name),
+            description: String::new(),
+            min_tags: 1,
+            max_tags,
+        }
+    }
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| str!(s)).collect()
+    }
+
+    let defined = [
+        tag(1, "safe", Some(1)),
+        tag(2, "euclid", Some(1)),
+        tag(3, "scp", None),
+    ];
+    let groups = [group(1, "object-class", Some(1))];
+
+    assert_eq!(
+        check_tags(&tags(&["scp", "safe"]), &defined, &groups),
+        vec![]
+    );
+    assert_eq!(
+        check_tags(&tags(&["scp", "keter", "safe"]), &defined, &groups),
+        vec![TagViolation::Unknown { tag: str!("keter") }],
+    );
+    assert_eq!(
+        check_tags(&tags(&["scp"]), &defined, &groups),
+        vec![TagViolation::GroupLimit {
+            group: str!("object-class"),
+            count: 0,
+            min_tags: 1,
+            max_tags: Some(1),
+        }],
+    );
+    assert_eq!(
+        check_tags(&tags(&["safe", "euclid"]), &defined, &groups),
+        vec![TagViolation::GroupLimit {
+            group: str!("object-class"),
+            count: 2,
+            min_tags: 1,
+            max_tags: Some(1),
+        }],
+    );
+
+    assert_eq!(
+        replace_tag(&tags(&["a", "b", "c"]), "b", "c"),
+        tags(&["a", "c"]),
+    );
+    assert_eq!(
+        replace_tag(&tags(&["a", "b", "c"]), "b", "d"),
+        tags(&["a", "d", "c"]),
+    );
+}
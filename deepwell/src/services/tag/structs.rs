@@ -0,0 +1,132 @@
+/*
+ * services/tag/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::types::Maybe;
+use sea_orm::FromQueryResult;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateSiteTag {
+    pub site_id: i64,
+    pub name: String,
+    pub tag_group_id: Option<i64>,
+
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSiteTag {
+    pub site_id: i64,
+    pub tag_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateSiteTag {
+    pub site_id: i64,
+    pub tag_id: i64,
+
+    #[serde(flatten)]
+    pub body: UpdateSiteTagBody,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct UpdateSiteTagBody {
+    pub description: Maybe<String>,
+    pub tag_group_id: Maybe<Option<i64>>,
+    // NOTE: renaming is done through RenameSiteTag,
+    //       since it also needs to update pages using the tag.
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateSiteTagGroup {
+    pub site_id: i64,
+    pub name: String,
+    pub max_tags: Option<i32>,
+
+    #[serde(default)]
+    pub description: String,
+
+    #[serde(default)]
+    pub min_tags: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSiteTagGroup {
+    pub site_id: i64,
+    pub tag_group_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateSiteTagGroup {
+    pub site_id: i64,
+    pub tag_group_id: i64,
+
+    #[serde(flatten)]
+    pub body: UpdateSiteTagGroupBody,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct UpdateSiteTagGroupBody {
+    pub name: Maybe<String>,
+    pub description: Maybe<String>,
+    pub min_tags: Maybe<i32>,
+    pub max_tags: Maybe<Option<i32>>,
+}
+
+/// Renames a tag on every page in a site.
+///
+/// If a tag with the new name is already defined, then
+/// this merges the old tag into it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RenameSiteTag {
+    pub site_id: i64,
+    pub from: String,
+    pub to: String,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Copy, Clone)]
+pub struct RenameSiteTagOutput {
+    pub merged: bool,
+}
+
+#[derive(Serialize, FromQueryResult, Debug, Clone)]
+pub struct TagUsage {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// A reason why a page's tags were rejected.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum TagViolation {
+    /// The tag is not defined for this site.
+    Unknown { tag: String },
+
+    /// The page has too few or too many tags from this group.
+    GroupLimit {
+        group: String,
+        count: i32,
+        min_tags: i32,
+        max_tags: Option<i32>,
+    },
+}
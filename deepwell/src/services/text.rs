@@ -28,6 +28,7 @@ use super::prelude::*;
 use crate::hash::{k12_hash, TextHash, TEXT_HASH_LENGTH};
 use crate::models::message_draft::{self, Entity as MessageDraft};
 use crate::models::message_record::{self, Entity as MessageRecord};
+use crate::models::page_draft::{self, Entity as PageDraft};
 use crate::models::page_revision::{self, Entity as PageRevision};
 use crate::models::text::{self, Entity as Text};
 use sea_query::Query;
@@ -135,6 +136,7 @@ impl TextService {
                         PageRevision,
                         page_revision::Column::CompiledHash,
                     ))
+                    .add(not_in_column!(PageDraft, page_draft::Column::WikitextHash))
                     .add(not_in_column!(
                         MessageDraft,
                         message_draft::Column::WikitextHash,
@@ -47,6 +47,7 @@ impl UserService {
             email,
             locales,
             password,
+            ip_address,
             bypass_filter,
             bypass_email_verification,
         }: CreateUser,
@@ -57,7 +58,12 @@ impl UserService {
         debug!("Normalizing user data (name '{name}', slug '{slug}')");
         regex_replace_in_place(&mut name, &LEADING_TRAILING_CHARS, "");
 
-        info!("Attempting to create user '{name}' ('{slug}')");
+        match ip_address {
+            Some(ip_address) => {
+                info!("Attempting to create user '{name}' ('{slug}') from {ip_address}");
+            }
+            None => info!("Attempting to create user '{name}' ('{slug}')"),
+        }
 
         // Empty slug check
         if slug.is_empty() {
@@ -307,6 +313,28 @@ impl UserService {
         find_or_error!(Self::get_optional(ctx, reference), User)
     }
 
+    /// Gets a regular user by their email address.
+    ///
+    /// Only regular users are required to have unique emails,
+    /// so other kinds of accounts are not searched.
+    pub async fn get_optional_by_email(
+        ctx: &ServiceContext<'_>,
+        email: &str,
+    ) -> Result<Option<UserModel>> {
+        let txn = ctx.transaction();
+        let user = User::find()
+            .filter(
+                Condition::all()
+                    .add(user::Column::Email.eq(email))
+                    .add(user::Column::UserType.eq(UserType::Regular))
+                    .add(user::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(user)
+    }
+
     /// Gets the user ID from a reference, looking up if necessary.
     ///
     /// Convenience method since this is much more common than the optional
@@ -702,6 +730,36 @@ impl UserService {
         Ok(user)
     }
 
+    /// Restores a deleted user under the given name.
+    ///
+    /// This is used for site users, which are deleted along with their site.
+    /// The name is set directly, since the site may be restored under a new slug.
+    pub(crate) async fn restore(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        name: String,
+    ) -> Result<UserModel> {
+        let txn = ctx.transaction();
+        let user = Self::get(ctx, Reference::Id(user_id)).await?;
+        info!("Restoring user with ID {user_id} as '{name}'");
+
+        if user.deleted_at.is_none() {
+            warn!("User ID {user_id} is not deleted");
+        }
+
+        let model = user::ActiveModel {
+            user_id: Set(user_id),
+            slug: Set(get_user_slug(&name, user.user_type)),
+            name: Set(name),
+            deleted_at: Set(None),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        let user = model.update(txn).await?;
+        Ok(user)
+    }
+
     async fn run_name_filter(
         ctx: &ServiceContext<'_>,
         name: &str,
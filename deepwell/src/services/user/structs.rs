@@ -23,6 +23,7 @@ use crate::models::alias::Model as AliasModel;
 use crate::models::sea_orm_active_enums::UserType;
 use crate::models::user::Model as UserModel;
 use crate::types::Bytes;
+use std::net::IpAddr;
 use time::Date;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -33,6 +34,10 @@ pub struct CreateUser {
     pub locales: Vec<String>,
     pub password: String,
 
+    /// Where the request to create this user came from, if anywhere.
+    #[serde(default)]
+    pub ip_address: Option<IpAddr>,
+
     #[serde(default)]
     pub bypass_filter: bool,
     #[serde(default)]
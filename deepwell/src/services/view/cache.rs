@@ -0,0 +1,123 @@
+/*
+ * services/view/cache.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Caching of page view data in Redis.
+//!
+//! Assembling the data for a page view requires fetching the page, its latest
+//! revision, and both of its text fields. Since most views are of unchanged pages,
+//! this data is cached, keyed by site and slug, with a field per site layout.
+//!
+//! Entries are removed by `invalidate()` whenever the page is changed or
+//! re-rendered, which the `OutdateService` and page services do as part of
+//! their work. The removal is deferred until the changing transaction commits,
+//! so a view running concurrently cannot store the old data after it. Entries
+//! also expire after a configured time, in case a removal fails.
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::utils::trim_default;
+use redis::AsyncCommands;
+
+/// The data for an existing page, as stored in the cache.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedPageView {
+    pub page: PageModel,
+    pub page_revision: PageRevisionModel,
+    pub wikitext: String,
+    pub compiled_html: String,
+}
+
+pub async fn get(
+    ctx: &ServiceContext<'_>,
+    site_id: i64,
+    slug: &str,
+    layout: &str,
+) -> Result<Option<CachedPageView>> {
+    if ctx.config().page_view_cache_ttl.is_none() {
+        return Ok(None);
+    }
+
+    let mut redis = ctx.redis_connect().await?;
+    let value: Option<String> = redis.hget(cache_key(site_id, slug), layout).await?;
+    ctx.metrics().record_page_view_cache(value.is_some());
+
+    match value {
+        Some(value) => {
+            debug!("Found cached page view for site ID {site_id} slug '{slug}'");
+            let data = serde_json::from_str(&value)?;
+            Ok(Some(data))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn set(
+    ctx: &ServiceContext<'_>,
+    site_id: i64,
+    slug: &str,
+    layout: &str,
+    data: &CachedPageView,
+) -> Result<()> {
+    let Some(ttl) = ctx.config().page_view_cache_ttl else {
+        return Ok(());
+    };
+
+    debug!("Caching page view for site ID {site_id} slug '{slug}'");
+    let key = cache_key(site_id, slug);
+    let value = serde_json::to_string(data)?;
+    let mut redis = ctx.redis_connect().await?;
+    redis::pipe()
+        .atomic()
+        .hset(&key, layout, value)
+        .ignore()
+        .expire(&key, ttl.as_secs() as i64)
+        .ignore()
+        .query_async::<_, ()>(&mut redis)
+        .await?;
+
+    Ok(())
+}
+
+/// Removes all cached view data for a page, for every layout.
+///
+/// This happens once the current transaction commits.
+pub fn invalidate(ctx: &ServiceContext<'_>, site_id: i64, slug: &str) {
+    if ctx.config().page_view_cache_ttl.is_none() {
+        return;
+    }
+
+    debug!("Invalidating cached page view for site ID {site_id} slug '{slug}'");
+    ctx.invalidate_after_commit(cache_key(site_id, slug));
+}
+
+fn cache_key(site_id: i64, slug: &str) -> String {
+    format!("page-view:{site_id}:{}", trim_default(slug))
+}
+
+#[test]
+fn page_view_cache_keys() {
+    assert_eq!(cache_key(1, "scp-001"), "page-view:1:scp-001");
+    assert_eq!(cache_key(1, "_default:scp-001"), "page-view:1:scp-001");
+    assert_eq!(
+        cache_key(20, "fragment:scp-001-1"),
+        "page-view:20:fragment:scp-001-1"
+    );
+}
@@ -29,16 +29,21 @@
 //! The service also contains the core method `ViewService::get_viewer()`, which converts the
 //! requesting domain and session token into a site and user, respectively.
 
+use super::cache::{self, CachedPageView};
 use super::prelude::*;
 use crate::models::page::Model as PageModel;
+use crate::models::page_attribution::Model as PageAttributionModel;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::site::Model as SiteModel;
 use crate::services::domain::SiteDomainResult;
+use crate::services::edit_session::EditSession;
+use crate::services::parent::PageAncestor;
 use crate::services::render::RenderOutput;
 use crate::services::special_page::{GetSpecialPageOutput, SpecialPageType};
 use crate::services::{
-    DomainService, PageRevisionService, PageService, SessionService, SpecialPageService,
-    TextService, UserService,
+    DomainService, EditSessionService, PageAttributionService, PageRevisionService,
+    PageService, ParentService, RedirectService, RelationService, SessionService,
+    SpecialPageService, TextService, UserService,
 };
 use crate::utils::split_category;
 use fluent::{FluentArgs, FluentValue};
@@ -98,7 +103,7 @@ impl ViewService {
             Some(PageRoute { slug, extra }) => (slug, extra),
         };
 
-        let redirect_page = Self::should_redirect_page(page_full_slug);
+        let mut redirect_page = Self::should_redirect_page(page_full_slug);
         let options = PageOptions::parse(page_extra);
 
         // Get page, revision, and text fields
@@ -132,6 +137,10 @@ impl ViewService {
             Found {
                 page: PageModel,
                 page_revision: PageRevisionModel,
+                star_count: u64,
+                attributions: Vec<PageAttributionModel>,
+                edit_session: Option<EditSession>,
+                breadcrumbs: Vec<PageAncestor>,
             },
             Missing,
             Private,
@@ -139,20 +148,30 @@ impl ViewService {
         }
 
         // Get wikitext and HTML to return for this page.
-        let (status, wikitext, compiled_html) = match PageService::get_optional(
+        //
+        // Page and category layout overrides are part of the page's data,
+        // so only the site's layout needs to distinguish cache entries.
+        let site_layout = site
+            .layout
+            .as_deref()
+            .unwrap_or(config.default_page_layout.value());
+
+        let (status, wikitext, compiled_html) = match Self::get_page_data(
             ctx,
             site.site_id,
-            Reference::Slug(cow!(page_full_slug)),
+            page_full_slug,
+            site_layout,
+            !options.rerender,
         )
         .await?
         {
             // This page exists, return its data directly.
-            Some(page) => {
-                // Get associated revision
-                let page_revision =
-                    PageRevisionService::get_latest(ctx, site.site_id, page.page_id)
-                        .await?;
-
+            Some(CachedPageView {
+                page,
+                page_revision,
+                wikitext,
+                compiled_html,
+            }) => {
                 // Check user access to page
                 let user_permissions = match user_session {
                     Some(ref session) => session.user_permissions,
@@ -183,15 +202,23 @@ impl ViewService {
                             .await?;
                     };
 
-                    let (wikitext, compiled_html) = try_join!(
-                        TextService::get(ctx, &page_revision.wikitext_hash),
-                        TextService::get(ctx, &page_revision.compiled_hash),
-                    )?;
+                    let star_count =
+                        RelationService::count_page_stars(ctx, page.page_id).await?;
+                    let attributions =
+                        PageAttributionService::get_all_direct(ctx, page.page_id).await?;
+                    let edit_session =
+                        EditSessionService::get_direct(ctx, page.page_id).await?;
+                    let breadcrumbs =
+                        ParentService::get_breadcrumbs(ctx, page.page_id).await?;
 
                     (
                         PageStatus::Found {
                             page,
                             page_revision,
+                            star_count,
+                            attributions,
+                            edit_session,
+                            breadcrumbs,
                         },
                         wikitext,
                         compiled_html,
@@ -232,6 +259,14 @@ impl ViewService {
             }
             // The page is missing, fetch the "missing page" data (_404).
             None => {
+                // If the page was moved and left a redirect, send the user there
+                if redirect_page.is_none() {
+                    redirect_page =
+                        RedirectService::get_target(ctx, site.site_id, page_full_slug)
+                            .await?
+                            .map(|page| page.slug);
+                }
+
                 let GetSpecialPageOutput {
                     wikitext,
                     render_output,
@@ -270,6 +305,10 @@ impl ViewService {
             PageStatus::Found {
                 page,
                 page_revision,
+                star_count,
+                attributions,
+                edit_session,
+                breadcrumbs,
             } => GetPageViewOutput::PageFound {
                 viewer,
                 options,
@@ -278,6 +317,10 @@ impl ViewService {
                 redirect_page,
                 wikitext,
                 compiled_html,
+                star_count,
+                attributions,
+                edit_session,
+                breadcrumbs,
             },
             PageStatus::Missing => GetPageViewOutput::PageMissing {
                 viewer,
@@ -305,6 +348,67 @@ impl ViewService {
         Ok(output)
     }
 
+    /// Gets the data for an existing page, either from the cache or the database.
+    ///
+    /// If `use_cache` is false, the cache is neither read nor filled.
+    async fn get_page_data(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        layout: &str,
+        use_cache: bool,
+    ) -> Result<Option<CachedPageView>> {
+        if use_cache {
+            if let Some(data) = cache::get(ctx, site_id, slug, layout).await? {
+                return Ok(Some(data));
+            }
+        }
+
+        let page =
+            match PageService::get_optional(ctx, site_id, Reference::Slug(cow!(slug)))
+                .await?
+            {
+                Some(page) => page,
+                None => return Ok(None),
+            };
+
+        let page_revision =
+            PageRevisionService::get_latest(ctx, site_id, page.page_id).await?;
+
+        let (wikitext, compiled_html) = try_join!(
+            TextService::get(ctx, &page_revision.wikitext_hash),
+            TextService::get(ctx, &page_revision.compiled_hash),
+        )?;
+
+        let data = CachedPageView {
+            page,
+            page_revision,
+            wikitext,
+            compiled_html,
+        };
+
+        if use_cache {
+            cache::set(ctx, site_id, slug, layout, &data).await?;
+        }
+
+        Ok(Some(data))
+    }
+
+    /// Removes any cached view data for a page.
+    ///
+    /// This must be called whenever anything shown in a page view changes,
+    /// or the page at that slug is created or removed. The data is removed
+    /// once the transaction commits.
+    #[inline]
+    pub async fn invalidate_page(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<()> {
+        cache::invalidate(ctx, site_id, slug);
+        Ok(())
+    }
+
     pub async fn user(
         ctx: &ServiceContext<'_>,
         GetUserView {
@@ -21,10 +21,13 @@
 use super::options::PageOptions;
 use super::prelude::*;
 use crate::models::page::Model as PageModel;
+use crate::models::page_attribution::Model as PageAttributionModel;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::session::Model as SessionModel;
 use crate::models::site::Model as SiteModel;
 use crate::models::user::Model as UserModel;
+use crate::services::edit_session::EditSession;
+use crate::services::parent::PageAncestor;
 
 // TODO replace with actual user permissions type
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
@@ -63,6 +66,10 @@ pub enum GetPageViewOutput {
         redirect_page: Option<String>,
         wikitext: String,
         compiled_html: String,
+        star_count: u64,
+        attributions: Vec<PageAttributionModel>,
+        edit_session: Option<EditSession>,
+        breadcrumbs: Vec<PageAncestor>,
     },
 
     PageMissing {
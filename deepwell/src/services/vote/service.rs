@@ -21,6 +21,7 @@
 
 use super::prelude::*;
 use crate::models::page_vote::{self, Entity as PageVote, Model as PageVoteModel};
+use crate::services::ScoreService;
 use sea_orm::IntoActiveModel;
 
 #[derive(Debug)]
@@ -38,12 +39,13 @@ impl VoteService {
             page_id,
             user_id,
             value,
+            ip_address,
         }: CreateVote,
     ) -> Result<Option<PageVoteModel>> {
         let txn = ctx.transaction();
         info!(
-            "Casting new vote by user ID {} on page ID {} (value {})",
-            user_id, page_id, value,
+            "Casting new vote by user ID {} ({}) on page ID {} (value {})",
+            user_id, ip_address, page_id, value,
         );
 
         // Get previous vote, if any
@@ -69,6 +71,7 @@ impl VoteService {
         };
 
         let vote = model.insert(txn).await?;
+        ScoreService::snapshot(ctx, page_id).await?;
         Ok(Some(vote))
     }
 
@@ -123,6 +126,7 @@ impl VoteService {
         }
 
         let model = vote.update(txn).await?;
+        ScoreService::snapshot(ctx, model.page_id).await?;
         Ok(model)
     }
 
@@ -135,6 +139,7 @@ impl VoteService {
         vote.deleted_at = Set(Some(now()));
 
         let model = vote.update(txn).await?;
+        ScoreService::snapshot(ctx, model.page_id).await?;
         Ok(model)
     }
 
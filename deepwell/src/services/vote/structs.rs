@@ -18,6 +18,8 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::net::IpAddr;
+
 pub type VoteValue = i16;
 
 #[derive(Deserialize, Debug, Copy, Clone)]
@@ -25,6 +27,7 @@ pub struct CreateVote {
     pub page_id: i64,
     pub user_id: i64,
     pub value: VoteValue,
+    pub ip_address: IpAddr,
 }
 
 #[derive(Deserialize, Debug, Copy, Clone)]
@@ -0,0 +1,42 @@
+/*
+ * services/vote_fraud/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The vote fraud service, for finding suspected sockpuppet voting.
+//!
+//! A periodic job looks at recent votes cast by recently-created accounts,
+//! and links those accounts together if they have shared an IP address in a
+//! session or successful login. Shared user agents are noted in reports, but
+//! are too common to link accounts by. Linked accounts which voted the same
+//! way on enough of the same pages are recorded as a report.
+//!
+//! Reports are reviewed by moderators. Confirming a report disables all the
+//! votes it lists, in the same way as disabling them one at a time would.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::VoteFraudService;
+pub use self::structs::*;
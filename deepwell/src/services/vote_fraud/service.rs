@@ -0,0 +1,580 @@
+/*
+ * services/vote_fraud/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_vote::{self, Entity as PageVote};
+use crate::models::vote_fraud_report::{
+    self, Entity as VoteFraudReport, Model as VoteFraudReportModel,
+};
+use crate::services::vote::GetVote;
+use crate::services::VoteService;
+use sea_orm::{DatabaseBackend, FromQueryResult, Statement, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use time::{Duration, OffsetDateTime};
+
+#[derive(Debug)]
+pub struct VoteFraudService;
+
+impl VoteFraudService {
+    /// Looks for suspected sockpuppet voting, creating a report for each cluster found.
+    ///
+    /// Votes which are already part of a report are not considered again.
+    pub async fn detect(ctx: &ServiceContext<'_>) -> Result<Vec<VoteFraudReportModel>> {
+        let txn = ctx.transaction();
+        let config = ctx.config();
+        let now = now();
+        info!("Looking for suspected vote fraud");
+
+        // Get recent votes by new accounts
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                SELECT
+                    page_vote.page_vote_id,
+                    page.site_id,
+                    page_vote.page_id,
+                    page_vote.user_id,
+                    page_vote.value
+                FROM page_vote
+                JOIN page
+                    ON page.page_id = page_vote.page_id
+                JOIN \"user\"
+                    ON \"user\".user_id = page_vote.user_id
+                WHERE page_vote.created_at > $1
+                    AND page_vote.deleted_at IS NULL
+                    AND page_vote.disabled_at IS NULL
+                    AND \"user\".created_at > $2
+                    AND \"user\".user_type = 'regular'
+                    AND NOT EXISTS (
+                        SELECT 1 FROM vote_fraud_report
+                        WHERE page_vote.page_vote_id = ANY(vote_fraud_report.vote_ids)
+                    )
+                ",
+            ),
+            [
+                Value::from(now - config.vote_fraud_lookback),
+                Value::from(now - config.vote_fraud_account_age),
+            ],
+        );
+
+        let votes = CandidateVote::find_by_statement(query).all(txn).await?;
+        if votes.is_empty() {
+            debug!("No recent votes by new accounts, nothing to check");
+            return Ok(Vec::new());
+        }
+
+        // Get the network details for each of those accounts.
+        //
+        // Sessions are pruned once they expire, so successful
+        // logins are used as well to get a longer history.
+        let user_ids: BTreeSet<i64> = votes.iter().map(|vote| vote.user_id).collect();
+        let query = Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            str!(
+                "
+                SELECT
+                    session.user_id,
+                    session.ip_address,
+                    session.user_agent,
+                    \"user\".created_at AS user_created_at
+                FROM session
+                JOIN \"user\"
+                    ON \"user\".user_id = session.user_id
+                WHERE session.user_id = ANY($1)
+                UNION
+                SELECT
+                    login_attempt.user_id,
+                    host(login_attempt.ip_address),
+                    login_attempt.user_agent,
+                    \"user\".created_at AS user_created_at
+                FROM login_attempt
+                JOIN \"user\"
+                    ON \"user\".user_id = login_attempt.user_id
+                WHERE login_attempt.user_id = ANY($1)
+                    AND login_attempt.result = 'success'
+                ",
+            ),
+            [Value::from(user_ids.into_iter().collect::<Vec<_>>())],
+        );
+
+        let fingerprints = UserFingerprint::find_by_statement(query).all(txn).await?;
+        let clusters = find_clusters(
+            &votes,
+            &fingerprints,
+            config.vote_fraud_creation_window,
+            config.vote_fraud_minimum_cluster_size,
+            config.vote_fraud_minimum_shared_pages,
+        );
+
+        // Save each cluster as a report
+        let mut reports = Vec::with_capacity(clusters.len());
+        for cluster in clusters {
+            info!(
+                "Found suspected vote fraud by user IDs {:?} in site ID {} ({} votes)",
+                cluster.user_ids,
+                cluster.site_id,
+                cluster.vote_ids.len(),
+            );
+
+            let model = vote_fraud_report::ActiveModel {
+                site_id: Set(cluster.site_id),
+                user_ids: Set(cluster.user_ids),
+                page_ids: Set(cluster.page_ids),
+                vote_ids: Set(cluster.vote_ids),
+                ip_addresses: Set(cluster.ip_addresses),
+                user_agents: Set(cluster.user_agents),
+                ..Default::default()
+            };
+
+            reports.push(model.insert(txn).await?);
+        }
+
+        Ok(reports)
+    }
+
+    /// Marks a report as reviewed by a moderator.
+    ///
+    /// If the report is confirmed, then all of its votes
+    /// which are still active are disabled.
+    pub async fn review(
+        ctx: &ServiceContext<'_>,
+        ReviewVoteFraudReport {
+            site_id,
+            report_id,
+            user_id,
+            confirmed,
+        }: ReviewVoteFraudReport,
+    ) -> Result<VoteFraudReportModel> {
+        let txn = ctx.transaction();
+        let report = Self::get(ctx, site_id, report_id).await?;
+        info!(
+            "Reviewing vote fraud report ID {report_id} by user ID {user_id} (confirmed: {confirmed})",
+        );
+
+        if report.reviewed_at.is_some() {
+            error!("Vote fraud report ID {report_id} has already been reviewed");
+            return Err(Error::VoteFraudReportReviewed);
+        }
+
+        if confirmed {
+            let votes = PageVote::find()
+                .filter(
+                    Condition::all()
+                        .add(page_vote::Column::PageVoteId.is_in(report.vote_ids))
+                        .add(page_vote::Column::DeletedAt.is_null())
+                        .add(page_vote::Column::DisabledAt.is_null()),
+                )
+                .all(txn)
+                .await?;
+
+            debug!("Disabling {} votes from confirmed report", votes.len());
+            for vote in votes {
+                let key = GetVote {
+                    page_id: vote.page_id,
+                    user_id: vote.user_id,
+                };
+
+                VoteService::action(ctx, key, false, user_id).await?;
+            }
+        }
+
+        let model = vote_fraud_report::ActiveModel {
+            report_id: Set(report_id),
+            reviewed_at: Set(Some(now())),
+            reviewed_by: Set(Some(user_id)),
+            confirmed: Set(Some(confirmed)),
+            ..Default::default()
+        };
+
+        let report = model.update(txn).await?;
+        Ok(report)
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        report_id: i64,
+    ) -> Result<Option<VoteFraudReportModel>> {
+        let txn = ctx.transaction();
+        let report = VoteFraudReport::find()
+            .filter(
+                Condition::all()
+                    .add(vote_fraud_report::Column::ReportId.eq(report_id))
+                    .add(vote_fraud_report::Column::SiteId.eq(site_id)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(report)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        report_id: i64,
+    ) -> Result<VoteFraudReportModel> {
+        find_or_error!(Self::get_optional(ctx, site_id, report_id), VoteFraudReport)
+    }
+
+    /// Gets the vote fraud reports for a site, newest first.
+    ///
+    /// The `reviewed` argument:
+    /// * If it is `Some(true)`, then it only returns reports which have been reviewed.
+    /// * If it is `Some(false)`, then it only returns reports awaiting review.
+    /// * If it is `None`, then it returns all reports.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetVoteFraudReports { site_id, reviewed }: GetVoteFraudReports,
+    ) -> Result<Vec<VoteFraudReportModel>> {
+        let txn = ctx.transaction();
+        let reviewed_condition = match reviewed {
+            Some(true) => Some(vote_fraud_report::Column::ReviewedAt.is_not_null()),
+            Some(false) => Some(vote_fraud_report::Column::ReviewedAt.is_null()),
+            None => None,
+        };
+
+        let reports = VoteFraudReport::find()
+            .filter(
+                Condition::all()
+                    .add(vote_fraud_report::Column::SiteId.eq(site_id))
+                    .add_option(reviewed_condition),
+            )
+            .order_by_desc(vote_fraud_report::Column::ReportId)
+            .all(txn)
+            .await?;
+
+        Ok(reports)
+    }
+}
+
+/// Groups votes into clusters of linked accounts voting together.
+///
+/// Accounts are linked if they share an IP address, either directly or
+/// through other accounts. User agents are too common to link accounts on
+/// their own, so accounts sharing one are only linked if they also share a
+/// subnet (see `subnet()`), or were created within `creation_window` of
+/// each other. Within each site, a group of linked accounts is flagged if
+/// at least two of them cast the same vote on each of enough pages.
+/// Only those votes and accounts are included.
+fn find_clusters(
+    votes: &[CandidateVote],
+    fingerprints: &[UserFingerprint],
+    creation_window: Duration,
+    minimum_cluster_size: usize,
+    minimum_shared_pages: usize,
+) -> Vec<VoteCluster> {
+    // Link accounts which share an IP address
+    let mut parents = HashMap::new();
+    let mut owners = HashMap::new();
+
+    for fingerprint in fingerprints {
+        let ip_address = fingerprint.ip_address.as_str();
+
+        find_root(&mut parents, fingerprint.user_id);
+        if ip_address.is_empty() {
+            continue;
+        }
+
+        match owners.get(ip_address) {
+            Some(&user_id) => link(&mut parents, fingerprint.user_id, user_id),
+            None => {
+                owners.insert(ip_address, fingerprint.user_id);
+            }
+        }
+    }
+
+    // Link accounts which share a user agent and either a subnet or creation time
+    let mut user_agents = HashMap::new();
+    for fingerprint in fingerprints {
+        if !fingerprint.user_agent.is_empty() {
+            user_agents
+                .entry(fingerprint.user_agent.as_str())
+                .or_insert_with(Vec::new)
+                .push(fingerprint);
+        }
+    }
+
+    for fingerprints in user_agents.values_mut() {
+        let mut subnet_owners = HashMap::new();
+        for fingerprint in fingerprints.iter() {
+            if let Some(subnet) = subnet(&fingerprint.ip_address) {
+                match subnet_owners.get(&subnet) {
+                    Some(&user_id) => link(&mut parents, fingerprint.user_id, user_id),
+                    None => {
+                        subnet_owners.insert(subnet, fingerprint.user_id);
+                    }
+                }
+            }
+        }
+
+        // Chain together accounts created close to one another
+        fingerprints.sort_by_key(|fingerprint| fingerprint.user_created_at);
+        for pair in fingerprints.windows(2) {
+            if pair[1].user_created_at - pair[0].user_created_at <= creation_window {
+                link(&mut parents, pair[0].user_id, pair[1].user_id);
+            }
+        }
+    }
+
+    // Group votes by site and linked accounts, then by page and value
+    let mut groups = BTreeMap::new();
+    for vote in votes {
+        if !parents.contains_key(&vote.user_id) {
+            continue;
+        }
+
+        let root = find_root(&mut parents, vote.user_id);
+        groups
+            .entry((vote.site_id, root))
+            .or_insert_with(BTreeMap::new)
+            .entry((vote.page_id, vote.value))
+            .or_insert_with(Vec::new)
+            .push(vote);
+    }
+
+    let mut clusters = Vec::new();
+    for ((site_id, _), page_votes) in groups {
+        let mut user_ids = BTreeSet::new();
+        let mut page_ids = BTreeSet::new();
+        let mut vote_ids = Vec::new();
+
+        for ((page_id, _), votes) in page_votes {
+            let voters: BTreeSet<i64> = votes.iter().map(|vote| vote.user_id).collect();
+            if voters.len() < 2 {
+                continue;
+            }
+
+            page_ids.insert(page_id);
+            user_ids.extend(voters);
+            vote_ids.extend(votes.iter().map(|vote| vote.page_vote_id));
+        }
+
+        if user_ids.len() < minimum_cluster_size || page_ids.len() < minimum_shared_pages
+        {
+            continue;
+        }
+
+        // Find which details are shared between the flagged accounts
+        let mut ip_addresses = BTreeMap::new();
+        let mut user_agents = BTreeMap::new();
+        for fingerprint in fingerprints {
+            if !user_ids.contains(&fingerprint.user_id) {
+                continue;
+            }
+
+            ip_addresses
+                .entry(fingerprint.ip_address.as_str())
+                .or_insert_with(BTreeSet::new)
+                .insert(fingerprint.user_id);
+
+            user_agents
+                .entry(fingerprint.user_agent.as_str())
+                .or_insert_with(BTreeSet::new)
+                .insert(fingerprint.user_id);
+        }
+
+        fn shared(values: BTreeMap<&str, BTreeSet<i64>>) -> Vec<String> {
+            values
+                .into_iter()
+                .filter(|(value, users)| !value.is_empty() && users.len() >= 2)
+                .map(|(value, _)| str!(value))
+                .collect()
+        }
+
+        vote_ids.sort_unstable();
+        clusters.push(VoteCluster {
+            site_id,
+            user_ids: user_ids.into_iter().collect(),
+            page_ids: page_ids.into_iter().collect(),
+            vote_ids,
+            ip_addresses: shared(ip_addresses),
+            user_agents: shared(user_agents),
+        });
+    }
+
+    clusters
+}
+
+/// Gets the network an IP address belongs to, for linking accounts.
+///
+/// This is the /24 for IPv4 addresses, and the /64 for IPv6 addresses,
+/// which is usually what is assigned to a single connection.
+fn subnet(ip_address: &str) -> Option<IpAddr> {
+    match ip_address.parse().ok()? {
+        IpAddr::V4(address) => {
+            let [a, b, c, _] = address.octets();
+            Some(IpAddr::V4(Ipv4Addr::new(a, b, c, 0)))
+        }
+        IpAddr::V6(address) => {
+            let prefix = u128::from(address) & !(u128::MAX >> 64);
+            Some(IpAddr::V6(Ipv6Addr::from(prefix)))
+        }
+    }
+}
+
+fn find_root(parents: &mut HashMap<i64, i64>, user_id: i64) -> i64 {
+    let parent = *parents.entry(user_id).or_insert(user_id);
+    if parent == user_id {
+        return user_id;
+    }
+
+    let root = find_root(parents, parent);
+    parents.insert(user_id, root);
+    root
+}
+
+fn link(parents: &mut HashMap<i64, i64>, first: i64, second: i64) {
+    let first = find_root(parents, first);
+    let second = find_root(parents, second);
+    if first != second {
+        parents.insert(first.max(second), first.min(second));
+    }
+}
+
+#[test]
+fn vote_clusters() {
+    fn vote(page_vote_id: i64, page_id: i64, user_id: i64, value: i16) -> CandidateVote {
+        CandidateVote {
+            page_vote_id,
+            site_id: 1,
+            page_id,
+            user_id,
+            value,
+        }
+    }
+
+    fn fingerprint(user_id: i64, ip_address: &str, user_agent: &str) -> UserFingerprint {
+        UserFingerprint {
+            user_id,
+            ip_address: str!(ip_address),
+            user_agent: str!(user_agent),
+            user_created_at: OffsetDateTime::UNIX_EPOCH + Duration::days(user_id),
+        }
+    }
+
+    // Users 1 and 2 share an IP, and users 2 and 3 share another.
+    // User 4 is unrelated, but votes the same way.
+    let fingerprints = [
+        fingerprint(1, "10.0.0.1", "Firefox"),
+        fingerprint(2, "10.0.0.1", "Chrome"),
+        fingerprint(2, "10.0.0.2", "Chrome"),
+        fingerprint(3, "10.0.0.2", "Chrome"),
+        fingerprint(4, "10.0.0.3", "Safari"),
+    ];
+    let window = Duration::hours(1);
+
+    let votes = [
+        vote(1, 100, 1, 1),
+        vote(2, 100, 2, 1),
+        vote(3, 100, 4, 1),
+        vote(4, 101, 1, 1),
+        vote(5, 101, 3, 1),
+        vote(6, 102, 2, -1),
+        vote(7, 102, 3, -1),
+        vote(8, 103, 1, 1),
+        vote(9, 103, 2, -1),
+    ];
+
+    assert_eq!(
+        find_clusters(&votes, &fingerprints, window, 2, 3),
+        vec![VoteCluster {
+            site_id: 1,
+            user_ids: vec![1, 2, 3],
+            page_ids: vec![100, 101, 102],
+            vote_ids: vec![1, 2, 4, 5, 6, 7],
+            ip_addresses: vec![str!("10.0.0.1"), str!("10.0.0.2")],
+            user_agents: vec![str!("Chrome")],
+        }],
+    );
+
+    assert!(find_clusters(&votes, &fingerprints, window, 2, 4).is_empty());
+    assert!(find_clusters(&votes, &fingerprints, window, 4, 3).is_empty());
+    assert!(find_clusters(&votes, &[], window, 2, 3).is_empty());
+}
+
+#[test]
+fn vote_clusters_user_agent() {
+    const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/120.0";
+
+    fn fingerprint(
+        user_id: i64,
+        ip_address: &str,
+        created_hours: i64,
+    ) -> UserFingerprint {
+        UserFingerprint {
+            user_id,
+            ip_address: str!(ip_address),
+            user_agent: str!(USER_AGENT),
+            user_created_at: OffsetDateTime::UNIX_EPOCH + Duration::hours(created_hours),
+        }
+    }
+
+    let mut votes = Vec::new();
+    for page_id in 100..105 {
+        for user_id in 1..=5 {
+            votes.push(CandidateVote {
+                page_vote_id: page_id * 10 + user_id,
+                site_id: 1,
+                page_id,
+                user_id,
+                value: 1,
+            });
+        }
+    }
+
+    let clustered_users = |fingerprints: &[UserFingerprint]| {
+        find_clusters(&votes, fingerprints, Duration::hours(1), 2, 3)
+            .into_iter()
+            .map(|cluster| cluster.user_ids)
+            .collect::<Vec<_>>()
+    };
+
+    // Unrelated users with the most common browser all vote the same way
+    let fingerprints = (1..=5)
+        .map(|user_id| fingerprint(user_id, &format!("10.0.{user_id}.1"), user_id * 24))
+        .collect::<Vec<_>>();
+
+    assert!(clustered_users(&fingerprints).is_empty());
+
+    // Users 1 and 2 share a subnet, users 4 and 5 were created together
+    let fingerprints = [
+        fingerprint(1, "10.0.0.1", 0),
+        fingerprint(2, "10.0.0.200", 24),
+        fingerprint(3, "10.0.3.1", 48),
+        fingerprint(4, "2001:db8:4::1", 72),
+        fingerprint(5, "2001:db8:5::1", 72),
+    ];
+
+    assert_eq!(clustered_users(&fingerprints), vec![vec![1, 2], vec![4, 5]]);
+}
+
+#[test]
+fn subnets() {
+    assert_eq!(subnet("192.168.1.77"), Some("192.168.1.0".parse().unwrap()));
+    assert_eq!(
+        subnet("2001:db8:1:2:3:4:5:6"),
+        Some("2001:db8:1:2::".parse().unwrap()),
+    );
+    assert_eq!(subnet(""), None);
+    assert_eq!(subnet("not an address"), None);
+}
@@ -0,0 +1,74 @@
+/*
+ * services/vote_fraud/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::services::vote::VoteValue;
+use sea_orm::FromQueryResult;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetVoteFraudReport {
+    pub site_id: i64,
+    pub report_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetVoteFraudReports {
+    pub site_id: i64,
+    pub reviewed: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct ReviewVoteFraudReport {
+    pub site_id: i64,
+    pub report_id: i64,
+    pub user_id: i64,
+    pub confirmed: bool,
+}
+
+/// A recent vote by a recently-created account.
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct CandidateVote {
+    pub page_vote_id: i64,
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+    pub value: VoteValue,
+}
+
+/// An IP address and user agent pair a user has been seen with,
+/// along with when that user's account was created.
+#[derive(FromQueryResult, Debug, Clone)]
+pub struct UserFingerprint {
+    pub user_id: i64,
+    pub ip_address: String,
+    pub user_agent: String,
+    pub user_created_at: OffsetDateTime,
+}
+
+/// A group of linked accounts which voted together, before it is saved as a report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteCluster {
+    pub site_id: i64,
+    pub user_ids: Vec<i64>,
+    pub page_ids: Vec<i64>,
+    pub vote_ids: Vec<i64>,
+    pub ip_addresses: Vec<String>,
+    pub user_agents: Vec<String>,
+}
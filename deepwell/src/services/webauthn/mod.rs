@@ -0,0 +1,40 @@
+/*
+ * services/webauthn/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The WebAuthn service, which manages passkeys and other public-key credentials.
+//!
+//! Credentials can be used as a second factor in place of a TOTP, via the same
+//! restricted session flow, or on their own for passwordless login.
+//!
+//! Verification of authenticator responses is done by `webauthn-rs`. We store
+//! its serialized passkeys, and keep its challenge state in Redis with a short
+//! expiry, where it is single-use.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::WebauthnService;
+pub use self::structs::*;
@@ -0,0 +1,462 @@
+/*
+ * services/webauthn/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::UserType;
+use crate::models::user::Model as UserModel;
+use crate::models::user_webauthn_credential::{
+    self, Entity as UserWebauthnCredential, Model as WebauthnCredentialModel,
+};
+use data_encoding::BASE64URL_NOPAD;
+use redis::AsyncCommands;
+use webauthn_rs::prelude::{
+    DiscoverableKey, Passkey, Url, Uuid, Webauthn, WebauthnBuilder, WebauthnError,
+};
+
+#[derive(Debug)]
+pub struct WebauthnService;
+
+impl WebauthnService {
+    /// Begins registering a new credential for the given user.
+    ///
+    /// The output is passed to the browser to create the credential.
+    pub async fn register_start(
+        ctx: &ServiceContext<'_>,
+        user: &UserModel,
+    ) -> Result<WebauthnRegisterStartOutput> {
+        info!(
+            "Beginning WebAuthn registration for user ID {}",
+            user.user_id
+        );
+
+        // Only regular accounts can have WebAuthn credentials, same as MFA
+        if user.user_type != UserType::Regular {
+            error!("Only regular users may have WebAuthn credentials");
+            return Err(Error::BadRequest);
+        }
+
+        // Prevent registering the same authenticator twice
+        let exclude_credentials = Self::get_passkeys(ctx, user.user_id)
+            .await?
+            .iter()
+            .map(|(_, passkey)| passkey.cred_id().clone())
+            .collect();
+
+        let (options, state) = Self::webauthn(ctx.config())?.start_passkey_registration(
+            user_handle(user.user_id),
+            &user.name,
+            &user.name,
+            Some(exclude_credentials),
+        )?;
+
+        let challenge = BASE64URL_NOPAD.encode(options.public_key.challenge.as_ref());
+        Self::store_challenge(
+            ctx,
+            &challenge,
+            &WebauthnChallenge::Register {
+                user_id: user.user_id,
+                state,
+            },
+        )
+        .await?;
+
+        Ok(WebauthnRegisterStartOutput { challenge, options })
+    }
+
+    /// Finishes registering a credential, verifying the authenticator's response.
+    pub async fn register_finish(
+        ctx: &ServiceContext<'_>,
+        user: &UserModel,
+        WebauthnRegisterFinish {
+            name,
+            challenge,
+            credential,
+        }: WebauthnRegisterFinish,
+    ) -> Result<WebauthnCredentialOutput> {
+        info!(
+            "Finishing WebAuthn registration of '{name}' for user ID {}",
+            user.user_id,
+        );
+
+        let txn = ctx.transaction();
+
+        if name.is_empty() {
+            error!("WebAuthn credential name is empty");
+            return Err(Error::BadRequest);
+        }
+
+        let state = match Self::take_challenge(ctx, &challenge).await? {
+            WebauthnChallenge::Register { user_id, state } if user_id == user.user_id => {
+                state
+            }
+            state => {
+                error!(
+                    "WebAuthn challenge was issued for {state:?}, not registration by user ID {}",
+                    user.user_id,
+                );
+                return Err(Error::InvalidAuthentication);
+            }
+        };
+
+        let passkey = Self::webauthn(ctx.config())?
+            .finish_passkey_registration(&credential, &state)
+            .map_err(verification_error)?;
+
+        // Check for conflicts
+        let external_id = passkey.cred_id().to_vec();
+        if Self::get_by_name(ctx, user.user_id, &name).await?.is_some()
+            || Self::get_by_external_id(ctx, &external_id).await?.is_some()
+        {
+            error!("WebAuthn credential already exists");
+            return Err(Error::WebauthnCredentialExists);
+        }
+
+        let model = user_webauthn_credential::ActiveModel {
+            user_id: Set(user.user_id),
+            name: Set(name),
+            external_id: Set(external_id),
+            passkey: Set(serde_json::to_value(&passkey)?),
+            ..Default::default()
+        };
+
+        let credential = model.insert(txn).await?;
+        Ok(credential.into())
+    }
+
+    /// Begins authenticating with a credential.
+    ///
+    /// If a user is passed, this is for a second factor, and only their
+    /// credentials are permitted. Otherwise this is a passwordless login,
+    /// and the browser is expected to offer the user's discoverable credentials.
+    ///
+    /// Either way, user verification (such as a PIN or biometric) is required.
+    pub async fn auth_start(
+        ctx: &ServiceContext<'_>,
+        user: Option<&UserModel>,
+    ) -> Result<WebauthnAuthStartOutput> {
+        let user_id = user.map(|user| user.user_id);
+        info!("Beginning WebAuthn authentication for user ID {user_id:?}");
+
+        let webauthn = Self::webauthn(ctx.config())?;
+        let (options, state) = match user_id {
+            Some(user_id) => {
+                let passkeys = Self::get_passkeys(ctx, user_id)
+                    .await?
+                    .into_iter()
+                    .map(|(_, passkey)| passkey)
+                    .collect::<Vec<_>>();
+
+                let (options, state) =
+                    webauthn.start_passkey_authentication(&passkeys)?;
+                (options, WebauthnChallenge::Authenticate { user_id, state })
+            }
+            None => {
+                let (options, state) = webauthn.start_discoverable_authentication()?;
+                (options, WebauthnChallenge::Login { state })
+            }
+        };
+
+        let challenge = BASE64URL_NOPAD.encode(options.public_key.challenge.as_ref());
+        Self::store_challenge(ctx, &challenge, &state).await?;
+        Ok(WebauthnAuthStartOutput { challenge, options })
+    }
+
+    /// Verifies an assertion from an authenticator.
+    ///
+    /// If `user_id` is passed, the credential must belong to that user.
+    /// Otherwise this is a passwordless login, and the owner of the
+    /// credential is the one being authenticated.
+    ///
+    /// # Returns
+    /// The ID of the user who owns the credential.
+    pub async fn auth_finish(
+        ctx: &ServiceContext<'_>,
+        user_id: Option<i64>,
+        WebauthnAssertion {
+            challenge,
+            credential,
+        }: WebauthnAssertion,
+    ) -> Result<i64> {
+        info!("Finishing WebAuthn authentication for user ID {user_id:?}");
+
+        let txn = ctx.transaction();
+        let webauthn = Self::webauthn(ctx.config())?;
+        let state = Self::take_challenge(ctx, &challenge).await?;
+
+        // Find credential
+        let model =
+            match Self::get_by_external_id(ctx, credential.get_credential_id()).await? {
+                Some(model) => model,
+                None => {
+                    error!("No WebAuthn credential with this ID exists");
+                    return Err(Error::InvalidAuthentication);
+                }
+            };
+
+        let mut passkey: Passkey = serde_json::from_value(model.passkey.clone())?;
+
+        // Verify the assertion against the challenge state
+        let result = match (state, user_id) {
+            (
+                WebauthnChallenge::Authenticate {
+                    user_id: challenge_user_id,
+                    state,
+                },
+                Some(user_id),
+            ) if challenge_user_id == user_id => {
+                if model.user_id != user_id {
+                    error!(
+                        "WebAuthn credential belongs to user ID {}, not {}",
+                        model.user_id, user_id,
+                    );
+                    return Err(Error::InvalidAuthentication);
+                }
+
+                webauthn.finish_passkey_authentication(&credential, &state)
+            }
+            (WebauthnChallenge::Login { state }, None) => {
+                let (handle, _) = webauthn
+                    .identify_discoverable_authentication(&credential)
+                    .map_err(verification_error)?;
+
+                if handle != user_handle(model.user_id) {
+                    error!(
+                        "WebAuthn user handle does not match credential owner user ID {}",
+                        model.user_id,
+                    );
+                    return Err(Error::InvalidAuthentication);
+                }
+
+                webauthn.finish_discoverable_authentication(
+                    &credential,
+                    state,
+                    &[DiscoverableKey::from(&passkey)],
+                )
+            }
+            (state, _) => {
+                error!(
+                    "WebAuthn challenge was issued for {state:?}, not authentication by user ID {user_id:?}",
+                );
+                return Err(Error::InvalidAuthentication);
+            }
+        }
+        .map_err(verification_error)?;
+
+        // Update usage information, including the signature counter
+        passkey.update_credential(&result);
+        let owner_id = model.user_id;
+        let model = user_webauthn_credential::ActiveModel {
+            credential_id: Set(model.credential_id),
+            passkey: Set(serde_json::to_value(&passkey)?),
+            last_used_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Ok(owner_id)
+    }
+
+    /// Gets all of a user's credentials, in order of registration.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<Vec<WebauthnCredentialModel>> {
+        let txn = ctx.transaction();
+        let credentials = UserWebauthnCredential::find()
+            .filter(user_webauthn_credential::Column::UserId.eq(user_id))
+            .order_by_asc(user_webauthn_credential::Column::CredentialId)
+            .all(txn)
+            .await?;
+
+        Ok(credentials)
+    }
+
+    /// Counts how many credentials a user has.
+    pub async fn count(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        let txn = ctx.transaction();
+        let count = UserWebauthnCredential::find()
+            .filter(user_webauthn_credential::Column::UserId.eq(user_id))
+            .count(txn)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Revokes one of a user's credentials.
+    pub async fn remove(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        credential_id: i64,
+    ) -> Result<()> {
+        info!("Removing WebAuthn credential ID {credential_id} for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected, .. } = UserWebauthnCredential::delete_many()
+            .filter(
+                Condition::all()
+                    .add(user_webauthn_credential::Column::CredentialId.eq(credential_id))
+                    .add(user_webauthn_credential::Column::UserId.eq(user_id)),
+            )
+            .exec(txn)
+            .await?;
+
+        if rows_affected == 1 {
+            Ok(())
+        } else {
+            Err(Error::WebauthnCredentialNotFound)
+        }
+    }
+
+    /// Gets all of a user's credentials, with their deserialized passkeys.
+    async fn get_passkeys(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<Vec<(WebauthnCredentialModel, Passkey)>> {
+        let mut passkeys = Vec::new();
+        for credential in Self::get_all(ctx, user_id).await? {
+            let passkey = serde_json::from_value(credential.passkey.clone())?;
+            passkeys.push((credential, passkey));
+        }
+
+        Ok(passkeys)
+    }
+
+    async fn get_by_name(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        name: &str,
+    ) -> Result<Option<WebauthnCredentialModel>> {
+        let txn = ctx.transaction();
+        let credential = UserWebauthnCredential::find()
+            .filter(
+                Condition::all()
+                    .add(user_webauthn_credential::Column::UserId.eq(user_id))
+                    .add(user_webauthn_credential::Column::Name.eq(name)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(credential)
+    }
+
+    async fn get_by_external_id(
+        ctx: &ServiceContext<'_>,
+        external_id: &[u8],
+    ) -> Result<Option<WebauthnCredentialModel>> {
+        let txn = ctx.transaction();
+        let credential = UserWebauthnCredential::find()
+            .filter(user_webauthn_credential::Column::ExternalId.eq(external_id))
+            .one(txn)
+            .await?;
+
+        Ok(credential)
+    }
+
+    // Challenges
+
+    /// Stores the state for a challenge in Redis until it expires.
+    async fn store_challenge(
+        ctx: &ServiceContext<'_>,
+        challenge: &str,
+        state: &WebauthnChallenge,
+    ) -> Result<()> {
+        debug!("Issuing WebAuthn challenge {challenge}");
+        let value = serde_json::to_string(state)?;
+        let lifetime = ctx.config().webauthn_challenge_lifetime.as_secs();
+        let mut redis = ctx.redis_connect().await?;
+        redis
+            .set_ex::<_, _, ()>(challenge_key(challenge), value, lifetime)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Consumes a challenge, returning the state it was issued with.
+    ///
+    /// The challenge is removed even if the response does not verify,
+    /// since either way it cannot be used again.
+    async fn take_challenge(
+        ctx: &ServiceContext<'_>,
+        challenge: &str,
+    ) -> Result<WebauthnChallenge> {
+        let mut redis = ctx.redis_connect().await?;
+        let value: Option<String> = redis.get_del(challenge_key(challenge)).await?;
+        match value {
+            Some(value) => Ok(serde_json::from_str(&value)?),
+            None => {
+                error!("WebAuthn challenge does not exist or has expired");
+                Err(Error::InvalidAuthentication)
+            }
+        }
+    }
+
+    // Relying party
+
+    /// Builds the `webauthn-rs` instance for our relying party.
+    ///
+    /// The relying party ID is the main domain. Since sites are subdomains
+    /// of this, credentials are usable on all of them.
+    fn webauthn(config: &Config) -> Result<Webauthn> {
+        let rp_id = &config.main_domain_no_dot;
+        let origin = match Url::parse(&format!("https://{rp_id}")) {
+            Ok(origin) => origin,
+            Err(error) => {
+                error!("Main domain is not a valid WebAuthn origin: {error}");
+                return Err(Error::Webauthn(WebauthnError::Configuration));
+            }
+        };
+
+        let webauthn = WebauthnBuilder::new(rp_id, &origin)?
+            .rp_name(&config.webauthn_relying_party_name)
+            .allow_subdomains(true)
+            .timeout(config.webauthn_challenge_lifetime)
+            .build()?;
+
+        Ok(webauthn)
+    }
+}
+
+/// Gets the WebAuthn user handle for a user, which is derived from their ID.
+fn user_handle(user_id: i64) -> Uuid {
+    Uuid::from_u64_pair(0, user_id as u64)
+}
+
+fn challenge_key(challenge: &str) -> String {
+    format!("webauthn-challenge:{challenge}")
+}
+
+/// Converts a failure to verify an authenticator response.
+///
+/// These are all treated as authentication failures,
+/// the details are only logged.
+fn verification_error(error: WebauthnError) -> Error {
+    error!("WebAuthn verification failed: {error}");
+    Error::InvalidAuthentication
+}
+
+#[test]
+fn user_handles() {
+    assert_eq!(user_handle(1), user_handle(1));
+    assert_ne!(user_handle(1), user_handle(2));
+    assert_eq!(
+        user_handle(1).to_string(),
+        "00000000-0000-0000-0000-000000000001",
+    );
+}
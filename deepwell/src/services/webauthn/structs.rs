@@ -0,0 +1,125 @@
+/*
+ * services/webauthn/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2025 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::user_webauthn_credential::Model as WebauthnCredentialModel;
+use time::OffsetDateTime;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, DiscoverableAuthentication, PasskeyAuthentication,
+    PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential,
+    RequestChallengeResponse,
+};
+
+/// The state stored in Redis for an outstanding challenge.
+///
+/// This is kept server-side and paired with the challenge sent
+/// to the browser, as `webauthn-rs` requires.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case", tag = "purpose")]
+pub enum WebauthnChallenge {
+    /// Registering a new credential for a user.
+    Register {
+        user_id: i64,
+        state: PasskeyRegistration,
+    },
+
+    /// Using one of a user's credentials as a second factor.
+    Authenticate {
+        user_id: i64,
+        state: PasskeyAuthentication,
+    },
+
+    /// Passwordless login, where any user's credential may answer it.
+    Login { state: DiscoverableAuthentication },
+}
+
+/// Options for `navigator.credentials.create()`.
+#[derive(Serialize, Debug, Clone)]
+pub struct WebauthnRegisterStartOutput {
+    /// The challenge, to be passed back when finishing registration.
+    pub challenge: String,
+    pub options: CreationChallengeResponse,
+}
+
+/// The response from the authenticator after `navigator.credentials.create()`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebauthnRegisterFinish {
+    /// The user-chosen name to refer to this credential by.
+    pub name: String,
+    pub challenge: String,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebauthnRegister {
+    pub user_id: i64,
+    pub session_token: String,
+
+    #[serde(flatten)]
+    pub input: WebauthnRegisterFinish,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebauthnRemove {
+    pub user_id: i64,
+    pub session_token: String,
+    pub credential_id: i64,
+}
+
+/// Options for `navigator.credentials.get()`.
+#[derive(Serialize, Debug, Clone)]
+pub struct WebauthnAuthStartOutput {
+    /// The challenge, to be passed back with the assertion.
+    pub challenge: String,
+    pub options: RequestChallengeResponse,
+}
+
+/// The response from the authenticator after `navigator.credentials.get()`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebauthnAssertion {
+    pub challenge: String,
+    pub credential: PublicKeyCredential,
+}
+
+/// Information about a registered credential.
+///
+/// This omits the public key, which is not useful to clients.
+#[derive(Serialize, Debug, Clone)]
+pub struct WebauthnCredentialOutput {
+    pub credential_id: i64,
+    pub name: String,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_used_at: Option<OffsetDateTime>,
+}
+
+impl From<WebauthnCredentialModel> for WebauthnCredentialOutput {
+    fn from(model: WebauthnCredentialModel) -> Self {
+        WebauthnCredentialOutput {
+            credential_id: model.credential_id,
+            name: model.name,
+            created_at: model.created_at,
+            last_used_at: model.last_used_at,
+        }
+    }
+}
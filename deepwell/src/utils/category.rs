@@ -58,6 +58,16 @@ pub fn get_category_name(slug: &str) -> &str {
     split_category_name(slug).0
 }
 
+/// Builds a full slug from a category and page slug.
+///
+/// Pages in `_default` do not have a category prefix.
+pub fn join_category(category: &str, page: &str) -> String {
+    match category {
+        "_default" => str!(page),
+        _ => format!("{category}:{page}"),
+    }
+}
+
 /// Trims off the `_default:` category if present.
 pub fn trim_default(slug: &str) -> &str {
     // We cannot simply use str::strip_prefix() here,
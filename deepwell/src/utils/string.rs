@@ -50,3 +50,42 @@ pub fn trim_end_matches_in_place(string: &mut String, pattern: &str) {
         string.drain(pattern.len() - 1..);
     }
 }
+
+/// Escapes text for inclusion in XML or HTML content or attribute values.
+pub fn escape_xml(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for ch in input.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&apos;"),
+            _ => output.push(ch),
+        }
+    }
+
+    output
+}
+
+#[test]
+fn xml_escape() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                escape_xml($input),
+                $expected,
+                "Escaped XML text doesn't match expected",
+            );
+        };
+    }
+
+    check!("", "");
+    check!("Main Page", "Main Page");
+    check!("SCP-173 & SCP-682", "SCP-173 &amp; SCP-682");
+    check!(
+        "<b>\"it's\"</b>",
+        "&lt;b&gt;&quot;it&apos;s&quot;&lt;/b&gt;"
+    );
+}